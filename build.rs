@@ -0,0 +1,37 @@
+//! 构建脚本：记录git提交、rustc版本和目标平台，供 `ssh-conn version --verbose` 输出
+
+use std::path::Path;
+use std::process::Command;
+
+fn main() {
+    let commit = command_output("git", &["rev-parse", "--short=12", "HEAD"]);
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let rustc_version = command_output(&rustc, &["--version"]);
+    let target = std::env::var("TARGET").unwrap_or_default();
+
+    println!(
+        "cargo:rustc-env=SSH_CONN_GIT_COMMIT={}",
+        commit.as_deref().unwrap_or("unknown")
+    );
+    println!(
+        "cargo:rustc-env=SSH_CONN_RUSTC_VERSION={}",
+        rustc_version.as_deref().unwrap_or("unknown")
+    );
+    println!("cargo:rustc-env=SSH_CONN_TARGET={}", target);
+
+    println!("cargo:rerun-if-changed=build.rs");
+    // 提交变化时重新运行；不是git仓库（如从crates.io构建）时这些文件不存在
+    for path in [".git/HEAD", ".git/index"] {
+        if Path::new(path).exists() {
+            println!("cargo:rerun-if-changed={}", path);
+        }
+    }
+}
+
+/// 运行命令并返回去掉首尾空白的标准输出，失败或输出为空时返回None
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    let stdout = String::from_utf8(output.stdout).ok()?;
+    let stdout = stdout.trim();
+    (output.status.success() && !stdout.is_empty()).then(|| stdout.to_string())
+}
@@ -0,0 +1,341 @@
+//! ssh-agent 集成：连接前把主机的私钥载入agent（设置 `add_keys_to_agent` 或 `connect --add-key`）
+//!
+//! 用 `ssh-keygen -lf` 取私钥的指纹，与 `ssh-add -l` 列出的指纹比较，
+//! 不在agent中时运行 `ssh-add <私钥>`，口令提示直接显示在当前终端。
+//! agent不可用（未设置 `SSH_AUTH_SOCK` 或无法连接）时跳过，只记录调试日志。
+//! 存储了私钥口令时（`password set-keyphrase`），把本程序设为 `SSH_ASKPASS` 运行 `ssh-add`，
+//! 口令经环境变量交给作为askpass运行的子进程（见 [`askpass_response`]），无需手动输入。
+//! `doctor` 和TUI的主机详情通过 [`agent_status`] 与 [`key_status`] 显示agent状态和私钥是否已载入。
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use zeroize::Zeroizing;
+
+use crate::utils::expand_path;
+
+/// 作为 `SSH_ASKPASS` 运行时，从该环境变量读取要输出的私钥口令
+pub const ASKPASS_PASSPHRASE_ENV: &str = "SSH_CONN_ASKPASS_PASSPHRASE";
+
+/// `ssh-add -l` 在agent中没有密钥时的退出码
+const NO_IDENTITIES_EXIT_CODE: i32 = 1;
+
+/// ssh-agent 的状态
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AgentStatus {
+    /// 未设置 `SSH_AUTH_SOCK`
+    NotConfigured,
+    /// 设置了 `SSH_AUTH_SOCK`，但无法连接agent或无法运行 `ssh-add`
+    Unreachable,
+    /// agent正在运行，附带已载入密钥的指纹
+    Running(Vec<String>),
+}
+
+/// 主机的私钥是否已载入agent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyStatus {
+    Loaded,
+    NotLoaded,
+    /// agent未运行或不可用
+    NoAgent,
+}
+
+/// 解析 `ssh-keygen -lf` 或 `ssh-add -l` 的一行输出，取出指纹
+///
+/// 格式为 `<位数> <指纹> <注释> (<类型>)`，如
+/// `256 SHA256:nThbg6kXUpJWGl7E1IGOCspRomTxdCARLviKw6E5SY8 me@laptop (ED25519)`，
+/// 注释可能为空或含空格
+pub fn parse_fingerprint_line(line: &str) -> Option<&str> {
+    let mut parts = line.split_whitespace();
+    parts.next()?.parse::<u32>().ok()?;
+    let fingerprint = parts.next()?;
+    // 指纹带哈希算法前缀（SHA256:、MD5:）
+    fingerprint
+        .split_once(':')
+        .filter(|(algorithm, digest)| !algorithm.is_empty() && !digest.is_empty())
+        .map(|_| fingerprint)
+}
+
+/// 解析 `ssh-add -l` 的输出，返回agent中所有密钥的指纹
+///
+/// agent中没有密钥时输出 `The agent has no identities.`，解析结果为空
+pub fn parse_agent_fingerprints(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(parse_fingerprint_line)
+        .map(str::to_string)
+        .collect()
+}
+
+/// 根据 `ssh-add -l` 的退出码和输出判断agent状态
+///
+/// 退出码0为有密钥，1为agent中没有密钥（输出 `The agent has no identities.`），其他为无法连接
+pub fn parse_agent_status(exit_code: Option<i32>, stdout: &str) -> AgentStatus {
+    match exit_code {
+        Some(0) | Some(NO_IDENTITIES_EXIT_CODE) => {
+            AgentStatus::Running(parse_agent_fingerprints(stdout))
+        }
+        _ => AgentStatus::Unreachable,
+    }
+}
+
+/// 私钥的指纹，`ssh-keygen` 不可用或无法读取时返回None
+///
+/// 存在同名的 `.pub` 公钥时读取公钥，避免对有口令的私钥提示输入口令
+fn key_fingerprint(path: &Path) -> Option<String> {
+    let mut public = path.as_os_str().to_owned();
+    public.push(".pub");
+    let public = PathBuf::from(public);
+    let path = if public.is_file() { &public } else { path };
+    let output = Command::new("ssh-keygen")
+        .arg("-lf")
+        .arg(path)
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        log::debug!(
+            "ssh-keygen -lf {} failed: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(parse_fingerprint_line)
+        .map(str::to_string)
+}
+
+/// 检查 `SSH_AUTH_SOCK` 并运行 `ssh-add -l`，得到agent的状态
+pub fn agent_status() -> AgentStatus {
+    if std::env::var_os("SSH_AUTH_SOCK").is_none_or(|sock| sock.is_empty()) {
+        log::debug!("SSH_AUTH_SOCK is not set, skipping ssh-agent");
+        return AgentStatus::NotConfigured;
+    }
+    let output = match Command::new("ssh-add")
+        .arg("-l")
+        .stdin(Stdio::null())
+        .output()
+    {
+        Ok(output) => output,
+        Err(e) => {
+            log::debug!("Cannot run ssh-add: {}", e);
+            return AgentStatus::Unreachable;
+        }
+    };
+    let status = parse_agent_status(
+        output.status.code(),
+        &String::from_utf8_lossy(&output.stdout),
+    );
+    if status == AgentStatus::Unreachable {
+        log::debug!(
+            "ssh-agent is not reachable: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    status
+}
+
+/// agent中已有密钥的指纹，agent不可用时返回None
+pub fn agent_fingerprints() -> Option<Vec<String>> {
+    match agent_status() {
+        AgentStatus::Running(fingerprints) => Some(fingerprints),
+        AgentStatus::NotConfigured | AgentStatus::Unreachable => None,
+    }
+}
+
+/// 根据私钥的指纹（无法读取时为None）判断是否已在agent中
+pub fn match_key_status(fingerprint: Option<&str>, agent: &AgentStatus) -> KeyStatus {
+    match agent {
+        AgentStatus::Running(loaded) => match fingerprint {
+            Some(fingerprint) if loaded.iter().any(|f| f == fingerprint) => KeyStatus::Loaded,
+            _ => KeyStatus::NotLoaded,
+        },
+        AgentStatus::NotConfigured | AgentStatus::Unreachable => KeyStatus::NoAgent,
+    }
+}
+
+/// `identity_file` 对应的私钥是否已在agent中，私钥不存在或无法读取时视为未载入
+pub fn key_status(identity_file: &str, agent: &AgentStatus) -> KeyStatus {
+    if !matches!(agent, AgentStatus::Running(_)) {
+        return KeyStatus::NoAgent;
+    }
+    let fingerprint = key_fingerprint(&expand_path(identity_file));
+    match_key_status(fingerprint.as_deref(), agent)
+}
+
+/// 本程序被 `ssh-add` 当作 `SSH_ASKPASS` 调用时要输出的口令，普通运行时返回None
+pub fn askpass_response() -> Option<Zeroizing<String>> {
+    std::env::var(ASKPASS_PASSPHRASE_ENV)
+        .ok()
+        .map(Zeroizing::new)
+}
+
+/// 用存储的口令运行 `ssh-add`：本程序作为askpass输出口令，不读取终端；成功时返回true
+fn add_with_passphrase(path: &Path, passphrase: &str) -> bool {
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => {
+            log::warn!("Cannot locate ssh-conn for SSH_ASKPASS: {}", e);
+            return false;
+        }
+    };
+    let mut command = Command::new("ssh-add");
+    command
+        .arg(path)
+        .env("SSH_ASKPASS", exe)
+        .env("SSH_ASKPASS_REQUIRE", "force")
+        .env(ASKPASS_PASSPHRASE_ENV, passphrase)
+        .stdin(Stdio::null());
+    // OpenSSH 8.4之前不支持SSH_ASKPASS_REQUIRE，只在设置了DISPLAY时使用askpass
+    if std::env::var_os("DISPLAY").is_none() {
+        command.env("DISPLAY", ":0");
+    }
+    match command.status() {
+        Ok(status) if status.success() => true,
+        Ok(status) => {
+            log::warn!(
+                "ssh-add {} with the stored passphrase exited with {}",
+                path.display(),
+                status
+            );
+            false
+        }
+        Err(e) => {
+            log::warn!("Cannot run ssh-add: {}", e);
+            false
+        }
+    }
+}
+
+/// 确保 `identity_file` 对应的私钥已在agent中，不在时运行 `ssh-add` 载入
+///
+/// 提供了 `passphrase` 时先用它非交互地载入，失败（如口令已过期）再回退为在终端输入；
+/// 任何一步失败都不影响之后的连接：agent不可用时只记录调试日志，`ssh-add` 失败时记录警告
+pub fn ensure_key_loaded(identity_file: &str, passphrase: Option<&str>) {
+    let path = expand_path(identity_file);
+    if !path.is_file() {
+        log::debug!(
+            "Identity file {} not found, skipping ssh-add",
+            path.display()
+        );
+        return;
+    }
+    let Some(loaded) = agent_fingerprints() else {
+        return;
+    };
+    let Some(fingerprint) = key_fingerprint(&path) else {
+        return;
+    };
+    if loaded.contains(&fingerprint) {
+        log::debug!(
+            "{} is already in ssh-agent ({})",
+            path.display(),
+            fingerprint
+        );
+        return;
+    }
+
+    log::info!("ssh-add {}", path.display());
+    if let Some(passphrase) = passphrase
+        && add_with_passphrase(&path, passphrase)
+    {
+        return;
+    }
+    // 继承终端，ssh-add 的口令提示直接显示给用户
+    match Command::new("ssh-add").arg(&path).status() {
+        Ok(status) if status.success() => {}
+        Ok(status) => log::warn!("ssh-add {} exited with {}", path.display(), status),
+        Err(e) => log::warn!("Cannot run ssh-add: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fingerprint_line() {
+        assert_eq!(
+            parse_fingerprint_line(
+                "256 SHA256:nThbg6kXUpJWGl7E1IGOCspRomTxdCARLviKw6E5SY8 me@laptop (ED25519)"
+            ),
+            Some("SHA256:nThbg6kXUpJWGl7E1IGOCspRomTxdCARLviKw6E5SY8")
+        );
+        // 注释含空格、没有注释以及MD5指纹
+        assert_eq!(
+            parse_fingerprint_line("3072 SHA256:abc/+def work laptop key (RSA)"),
+            Some("SHA256:abc/+def")
+        );
+        assert_eq!(
+            parse_fingerprint_line("256 SHA256:abc no comment (ECDSA)"),
+            Some("SHA256:abc")
+        );
+        assert_eq!(
+            parse_fingerprint_line(
+                "2048 MD5:12:f8:7e:78:61:b4:bf:e2:de:24:15:96:4e:d4:72:53 (RSA)"
+            ),
+            Some("MD5:12:f8:7e:78:61:b4:bf:e2:de:24:15:96:4e:d4:72:53")
+        );
+
+        for line in [
+            "",
+            "The agent has no identities.",
+            "Could not open a connection to your authentication agent.",
+            "/home/me/.ssh/id_rsa is not a key file.",
+            "256 nocolon comment (ED25519)",
+            "256 SHA256: (ED25519)",
+        ] {
+            assert_eq!(parse_fingerprint_line(line), None, "{line}");
+        }
+    }
+
+    #[test]
+    fn test_parse_agent_fingerprints() {
+        let output = "256 SHA256:aaa me@laptop (ED25519)\n\
+                      3072 SHA256:bbb deploy key (RSA)\n";
+        assert_eq!(
+            parse_agent_fingerprints(output),
+            ["SHA256:aaa", "SHA256:bbb"]
+        );
+        assert!(parse_agent_fingerprints("The agent has no identities.\n").is_empty());
+    }
+
+    #[test]
+    fn test_parse_agent_status() {
+        assert_eq!(
+            parse_agent_status(Some(0), "256 SHA256:aaa me@laptop (ED25519)\n"),
+            AgentStatus::Running(vec!["SHA256:aaa".to_string()])
+        );
+        assert_eq!(
+            parse_agent_status(Some(1), "The agent has no identities.\n"),
+            AgentStatus::Running(Vec::new())
+        );
+        // 无法连接agent时退出码为2，被信号终止时没有退出码
+        assert_eq!(parse_agent_status(Some(2), ""), AgentStatus::Unreachable);
+        assert_eq!(parse_agent_status(None, ""), AgentStatus::Unreachable);
+    }
+
+    #[test]
+    fn test_match_key_status() {
+        let agent = AgentStatus::Running(vec!["SHA256:aaa".to_string()]);
+        assert_eq!(
+            match_key_status(Some("SHA256:aaa"), &agent),
+            KeyStatus::Loaded
+        );
+        assert_eq!(
+            match_key_status(Some("SHA256:bbb"), &agent),
+            KeyStatus::NotLoaded
+        );
+        assert_eq!(match_key_status(None, &agent), KeyStatus::NotLoaded);
+        assert_eq!(
+            match_key_status(Some("SHA256:aaa"), &AgentStatus::Unreachable),
+            KeyStatus::NoAgent
+        );
+        assert_eq!(
+            key_status("~/.ssh/id_ed25519", &AgentStatus::NotConfigured),
+            KeyStatus::NoAgent
+        );
+    }
+}
@@ -0,0 +1,159 @@
+//! 配置修改的审计日志
+//!
+//! 每次新增、编辑、删除主机后向审计日志追加一行：时间、操作者、操作、主机和变化的字段，如
+//! `2026-10-16T09:30:00+08:00 user=alice op=edit host=web User: deploy -> admin`。
+//! 默认写入SSH配置所在目录下的 `ssh_conn_audit.log`，可用设置 `audit_log_path` 指定，
+//! `audit_log = false` 关闭。写入失败只记录警告，不影响配置修改本身。
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+
+use crate::models::SshHost;
+
+/// 默认审计日志文件名
+pub const AUDIT_LOG_FILE_NAME: &str = "ssh_conn_audit.log";
+
+/// 记录的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditOperation {
+    Add,
+    Edit,
+    Delete,
+}
+
+impl AuditOperation {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditOperation::Add => "add",
+            AuditOperation::Edit => "edit",
+            AuditOperation::Delete => "delete",
+        }
+    }
+}
+
+/// 默认的审计日志路径：与SSH配置文件位于同一目录
+pub fn default_audit_log_path(config_path: &Path) -> PathBuf {
+    match config_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.join(AUDIT_LOG_FILE_NAME),
+        _ => PathBuf::from(AUDIT_LOG_FILE_NAME),
+    }
+}
+
+/// 当前操作者：取自环境变量 `USER`（Windows为 `USERNAME`），都没有时为 `unknown`
+pub fn current_user() -> String {
+    ["USER", "USERNAME"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|user| !user.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// 新增主机的字段摘要，如 `HostName=10.0.0.5, User=deploy`
+pub fn describe_host(host: &SshHost) -> String {
+    host.config_options()
+        .iter()
+        .map(|(keyword, value)| format!("{}={}", keyword, value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// 编辑前后变化的字段摘要，如 `User: deploy -> admin, Port: (unset) -> 2222`
+pub fn describe_changes(old: &SshHost, new: &SshHost) -> String {
+    let show = |values: &[String]| {
+        if values.is_empty() {
+            "(unset)".to_string()
+        } else {
+            values.join(" ")
+        }
+    };
+    old.changed_options(new)
+        .iter()
+        .map(|(keyword, old, new)| format!("{}: {} -> {}", keyword, show(old), show(new)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// 审计日志的一行（不含换行），换行符替换为空格以保证一条记录一行
+pub fn format_entry(
+    time: DateTime<Local>,
+    user: &str,
+    operation: AuditOperation,
+    host: &str,
+    details: &str,
+) -> String {
+    let mut line = format!(
+        "{} user={} op={} host={}",
+        time.to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        user,
+        operation.as_str(),
+        host
+    );
+    if !details.is_empty() {
+        line.push(' ');
+        line.push_str(details);
+    }
+    line.replace(['\n', '\r'], " ")
+}
+
+/// 向审计日志追加一行，目录不存在时自动创建
+pub fn append_entry(path: &Path, line: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_format_entry() {
+        let time = Local.with_ymd_and_hms(2026, 10, 16, 9, 30, 0).unwrap();
+        let line = format_entry(
+            time,
+            "alice",
+            AuditOperation::Edit,
+            "web",
+            "User: deploy -> admin",
+        );
+        assert!(line.starts_with("2026-10-16T09:30:00"), "{line}");
+        assert!(line.ends_with(" user=alice op=edit host=web User: deploy -> admin"));
+
+        let line = format_entry(time, "alice", AuditOperation::Delete, "web", "");
+        assert!(line.ends_with("op=delete host=web"));
+        // 一条记录始终只占一行
+        let line = format_entry(time, "alice", AuditOperation::Add, "web", "a\nb");
+        assert!(!line.contains('\n'));
+    }
+
+    #[test]
+    fn test_describe_changes() {
+        let mut old = SshHost::new("web".to_string());
+        old.hostname = Some("10.0.0.5".to_string());
+        old.user = Some("deploy".to_string());
+        let mut new = old.clone();
+        new.user = Some("admin".to_string());
+        new.port = Some("2222".to_string());
+
+        assert_eq!(describe_host(&old), "HostName=10.0.0.5, User=deploy");
+        assert_eq!(
+            describe_changes(&old, &new),
+            "User: deploy -> admin, Port: (unset) -> 2222"
+        );
+        assert_eq!(describe_changes(&old, &old), "");
+    }
+
+    #[test]
+    fn test_default_audit_log_path() {
+        assert_eq!(
+            default_audit_log_path(Path::new("/home/me/.ssh/config")),
+            PathBuf::from("/home/me/.ssh").join(AUDIT_LOG_FILE_NAME)
+        );
+    }
+}
@@ -0,0 +1,344 @@
+//! 连接性能测试（`bench` 子命令）
+//!
+//! 每轮分别计时DNS解析、TCP连接以及（可选的）完整的 `ssh -o BatchMode=yes <host> exit`，
+//! 多轮结果汇总为最小/平均/最大/p95。
+
+use std::time::Duration;
+
+use serde::{Serialize, Serializer};
+
+use crate::i18n::t;
+use crate::models::SshHost;
+use crate::network::{NetworkProbe, ssh_probe};
+
+/// 计时的阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BenchPhase {
+    Dns,
+    Tcp,
+    Ssh,
+}
+
+impl BenchPhase {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BenchPhase::Dns => "DNS",
+            BenchPhase::Tcp => "TCP",
+            BenchPhase::Ssh => "SSH",
+        }
+    }
+}
+
+fn as_millis<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64((duration.as_secs_f64() * 1000.0 * 100.0).round() / 100.0)
+}
+
+/// 一组耗时的统计
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LatencyStats {
+    #[serde(rename = "min_ms", serialize_with = "as_millis")]
+    pub min: Duration,
+    #[serde(rename = "avg_ms", serialize_with = "as_millis")]
+    pub avg: Duration,
+    #[serde(rename = "max_ms", serialize_with = "as_millis")]
+    pub max: Duration,
+    /// 按最近秩法取的第95百分位
+    #[serde(rename = "p95_ms", serialize_with = "as_millis")]
+    pub p95: Duration,
+}
+
+impl LatencyStats {
+    /// 由样本计算统计，没有样本时返回None
+    pub fn from_samples(samples: &[Duration]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+        let total: Duration = sorted.iter().sum();
+        let rank = (sorted.len() * 95).div_ceil(100);
+        Some(Self {
+            min: sorted[0],
+            avg: total / sorted.len() as u32,
+            max: sorted[sorted.len() - 1],
+            p95: sorted[rank.max(1) - 1],
+        })
+    }
+}
+
+/// 单个阶段的结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PhaseResult {
+    pub phase: BenchPhase,
+    /// 失败的轮数
+    pub failures: u32,
+    #[serde(flatten)]
+    pub stats: Option<LatencyStats>,
+    #[serde(skip)]
+    pub samples: Vec<Duration>,
+}
+
+impl PhaseResult {
+    fn new(phase: BenchPhase) -> Self {
+        Self {
+            phase,
+            failures: 0,
+            stats: None,
+            samples: Vec::new(),
+        }
+    }
+
+    fn record(&mut self, result: &crate::error::Result<Duration>) {
+        match result {
+            Ok(duration) => self.samples.push(*duration),
+            Err(e) => {
+                log::debug!("{} phase failed: {}", self.phase.as_str(), e);
+                self.failures += 1;
+            }
+        }
+    }
+
+    fn finish(mut self) -> Self {
+        self.stats = LatencyStats::from_samples(&self.samples);
+        self
+    }
+}
+
+/// 单台主机的测试结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HostBench {
+    pub host: String,
+    pub rounds: u32,
+    pub phases: Vec<PhaseResult>,
+}
+
+/// 对主机进行多轮测试；`ssh_argv` 为探测命令（见 [`ssh_probe`]），为None时跳过SSH阶段
+pub async fn bench_host(host: &SshHost, rounds: u32, ssh_argv: Option<&[String]>) -> HostBench {
+    let (hostname, port) = host.get_host_and_port();
+    let timeout_secs = host
+        .connect_timeout
+        .as_ref()
+        .and_then(|t| t.parse().ok())
+        .unwrap_or(5);
+    let probe = NetworkProbe::new().with_timeout(timeout_secs);
+    let family = host.address_family.as_deref();
+
+    let mut dns = PhaseResult::new(BenchPhase::Dns);
+    let mut tcp = PhaseResult::new(BenchPhase::Tcp);
+    let mut ssh = PhaseResult::new(BenchPhase::Ssh);
+    for _ in 0..rounds {
+        // 解析失败时本轮无法连接，TCP阶段同样记为失败
+        match probe.timed_resolve(&hostname, port, family).await {
+            Ok((duration, addrs)) => {
+                dns.record(&Ok(duration));
+                tcp.record(&probe.timed_connect(addrs[0]).await);
+            }
+            Err(e) => {
+                dns.record(&Err(e));
+                tcp.failures += 1;
+            }
+        }
+
+        if let Some(argv) = ssh_argv {
+            let argv = argv.to_vec();
+            let result = tokio::task::spawn_blocking(move || ssh_probe(&argv))
+                .await
+                .unwrap_or_else(|e| Err(crate::error::SshConnError::Connection(e.to_string())));
+            ssh.record(&result);
+        }
+    }
+
+    let mut phases = vec![dns.finish(), tcp.finish()];
+    if ssh_argv.is_some() {
+        phases.push(ssh.finish());
+    }
+    HostBench {
+        host: host.host.clone(),
+        rounds,
+        phases,
+    }
+}
+
+fn format_ms(duration: Duration) -> String {
+    format!("{:.1}ms", duration.as_secs_f64() * 1000.0)
+}
+
+/// 单元格的统计值，全部失败时为 `-`
+fn stat_cells(phase: &PhaseResult) -> [String; 4] {
+    match &phase.stats {
+        Some(s) => [s.min, s.avg, s.max, s.p95].map(format_ms),
+        None => std::array::from_fn(|_| "-".to_string()),
+    }
+}
+
+/// 按列宽对齐输出表格，第一列左对齐，其余右对齐
+fn render_rows(rows: &[Vec<String>]) -> String {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..columns)
+        .map(|i| {
+            rows.iter()
+                .filter_map(|row| row.get(i))
+                .map(|cell| cell.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .enumerate()
+                .map(|(i, cell)| {
+                    if i == 0 {
+                        format!("{:<width$}", cell, width = widths[i])
+                    } else {
+                        format!("{:>width$}", cell, width = widths[i])
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 输出结果表格：单台主机时每个阶段一行；比较多台主机时每项统计一行，各主机并列
+pub fn format_table(results: &[HostBench]) -> String {
+    const STATS: [&str; 4] = ["min", "avg", "max", "p95"];
+
+    let mut rows = Vec::new();
+    if let [result] = results {
+        let mut header = vec![t("bench.phase")];
+        header.extend(STATS.map(str::to_string));
+        header.push(t("bench.failed"));
+        rows.push(header);
+        for phase in &result.phases {
+            let mut row = vec![phase.phase.as_str().to_string()];
+            row.extend(stat_cells(phase));
+            row.push(format!("{}/{}", phase.failures, result.rounds));
+            rows.push(row);
+        }
+        return render_rows(&rows);
+    }
+
+    let mut header = vec![t("bench.phase")];
+    header.extend(results.iter().map(|r| r.host.clone()));
+    rows.push(header);
+    let phases = results.first().map_or(&[][..], |r| &r.phases[..]);
+    for (index, phase) in phases.iter().enumerate() {
+        let cells: Vec<[String; 4]> = results
+            .iter()
+            .map(|r| stat_cells(&r.phases[index]))
+            .collect();
+        for (stat_index, stat) in STATS.iter().enumerate() {
+            let mut row = vec![format!("{} {}", phase.phase.as_str(), stat)];
+            row.extend(cells.iter().map(|c| c[stat_index].clone()));
+            rows.push(row);
+        }
+        let mut row = vec![format!("{} {}", phase.phase.as_str(), t("bench.failed"))];
+        row.extend(
+            results
+                .iter()
+                .map(|r| format!("{}/{}", r.phases[index].failures, r.rounds)),
+        );
+        rows.push(row);
+    }
+    render_rows(&rows)
+}
+
+/// 输出JSON：`{"hosts": [{"host", "rounds", "phases": [...]}]}`
+pub fn format_json(results: &[HostBench]) -> String {
+    serde_json::to_string_pretty(&serde_json::json!({ "hosts": results }))
+        .expect("bench results are always serializable")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(values: &[u64]) -> Vec<Duration> {
+        values.iter().copied().map(Duration::from_millis).collect()
+    }
+
+    fn phase(phase: BenchPhase, samples: &[u64], failures: u32) -> PhaseResult {
+        PhaseResult {
+            phase,
+            failures,
+            stats: None,
+            samples: ms(samples),
+        }
+        .finish()
+    }
+
+    #[test]
+    fn test_latency_stats() {
+        assert_eq!(LatencyStats::from_samples(&[]), None);
+
+        let stats = LatencyStats::from_samples(&ms(&[30, 10, 20])).unwrap();
+        assert_eq!(stats.min, Duration::from_millis(10));
+        assert_eq!(stats.avg, Duration::from_millis(20));
+        assert_eq!(stats.max, Duration::from_millis(30));
+        assert_eq!(stats.p95, Duration::from_millis(30));
+
+        // 20个样本时p95为第19个
+        let samples: Vec<u64> = (1..=20).collect();
+        let stats = LatencyStats::from_samples(&ms(&samples)).unwrap();
+        assert_eq!(stats.p95, Duration::from_millis(19));
+    }
+
+    #[test]
+    fn test_format_table_single_and_comparison() {
+        let a = HostBench {
+            host: "web1".to_string(),
+            rounds: 2,
+            phases: vec![
+                phase(BenchPhase::Dns, &[1, 3], 0),
+                phase(BenchPhase::Tcp, &[], 2),
+            ],
+        };
+        let table = format_table(std::slice::from_ref(&a));
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("DNS"));
+        assert!(lines[1].contains("1.0ms") && lines[1].contains("2.0ms"));
+        assert!(lines[1].ends_with("0/2"));
+        assert!(lines[2].contains('-') && lines[2].ends_with("2/2"));
+
+        let mut b = a.clone();
+        b.host = "web2".to_string();
+        let table = format_table(&[a, b]);
+        let lines: Vec<&str> = table.lines().collect();
+        assert!(lines[0].contains("web1") && lines[0].contains("web2"));
+        // 每个阶段4项统计加失败次数
+        assert_eq!(lines.len(), 1 + 2 * 5);
+        assert!(lines[2].starts_with("DNS avg") && lines[2].ends_with("2.0ms"));
+    }
+
+    #[test]
+    fn test_format_json() {
+        let result = HostBench {
+            host: "web1".to_string(),
+            rounds: 2,
+            phases: vec![
+                phase(BenchPhase::Dns, &[1, 3], 0),
+                phase(BenchPhase::Tcp, &[], 2),
+            ],
+        };
+        let value: serde_json::Value = serde_json::from_str(&format_json(&[result])).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "hosts": [{
+                    "host": "web1",
+                    "rounds": 2,
+                    "phases": [
+                        { "phase": "dns", "failures": 0, "min_ms": 1.0, "avg_ms": 2.0, "max_ms": 3.0, "p95_ms": 3.0 },
+                        { "phase": "tcp", "failures": 2 },
+                    ]
+                }]
+            })
+        );
+    }
+}
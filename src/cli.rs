@@ -1,10 +1,20 @@
 //! 命令行接口模块
 
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::Serialize;
 
-use crate::config::ConfigManager;
-use crate::error::Result;
+use crate::agent::AgentStatus;
+use crate::config::{BackupOptions, CommandMode, ConfigManager, RetryPolicy};
+use crate::error::{Result, SshConnError};
 use crate::i18n::t;
+use crate::metrics::MetricsFormat;
+use crate::models::{
+    ConnectionStatus, EditableField, FieldAssignment, OptionalField, SearchFields, SshHost,
+};
+use crate::network::AuthMethods;
+use crate::output;
+use crate::prompt::{Prompter, TerminalPrompter, edit_wizard, host_wizard};
+use crate::settings::Settings;
 use crate::ui::UiManager;
 
 /// Command line interface
@@ -15,6 +25,30 @@ use crate::ui::UiManager;
     version
 )]
 pub struct Cli {
+    /// SSH config file to manage (overrides ssh_config_path in settings)
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<std::path::PathBuf>,
+    /// Named profile from settings.toml selecting the SSH config file
+    #[arg(long, global = true, value_name = "NAME", conflicts_with = "config")]
+    pub profile: Option<String>,
+    /// Print failures as JSON objects on stderr instead of localized text
+    #[arg(long, global = true)]
+    pub json_errors: bool,
+    /// Only print results and errors, no informational messages
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+    /// Show log output on stderr (-v info, -vv debug)
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+    /// Write logs to a rotating file instead of stderr (default: ~/.local/share/ssh-conn/logs/ssh-conn.log)
+    #[arg(long, global = true, value_name = "PATH", num_args = 0..=1)]
+    pub log_file: Option<Option<std::path::PathBuf>>,
+    /// Start the TUI in read-only mode (no add/edit/delete)
+    #[arg(long, env = "SSH_CONN_READ_ONLY")]
+    pub read_only: bool,
+    /// Start the TUI with a compact host table (overrides ui.compact; toggle with 'c')
+    #[arg(long)]
+    pub compact: bool,
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
@@ -23,18 +57,96 @@ pub struct Cli {
 #[derive(Subcommand)]
 pub enum Commands {
     /// List all SSH servers configured in ssh config
-    List,
+    List {
+        /// Sort the output (default: config file order)
+        #[arg(long, value_enum)]
+        sort: Option<SortKey>,
+        /// Group the output by HostName domain or user
+        #[arg(long, value_enum)]
+        group_by: Option<GroupBy>,
+    },
     /// Connect to specified server
     Connect {
         /// Host name in ssh config
         host: String,
+        /// RemoteCommand for this connection only (overrides the host's RemoteCommand)
+        #[arg(long)]
+        remote_command: Option<String>,
+        /// Log in as this user for this connection only (overrides the host's User, like `ssh -l`)
+        #[arg(long, conflicts_with = "print_only")]
+        user: Option<String>,
+        /// Print the connection string instead of connecting
+        /// (the complete ssh command when --remote-command is given)
+        #[arg(long)]
+        print_only: bool,
+        /// With --print-only, print the complete ssh command (identity file, port, jump host)
+        #[arg(long, requires = "print_only")]
+        full: bool,
+        /// Validate and print the resolved command and auth method without connecting
+        #[arg(long, conflicts_with = "print_only")]
+        dry_run: bool,
+        /// Load the host's IdentityFile into ssh-agent first (runs ssh-add if it is not loaded)
+        #[arg(long, conflicts_with_all = ["print_only", "dry_run"])]
+        add_key: bool,
+        /// Retry up to N times when ssh fails quickly with a network error (exit code 255)
+        #[arg(long, value_name = "N", conflicts_with_all = ["print_only", "dry_run"])]
+        retry: Option<u32>,
+        /// Seconds to wait before the first retry; each further retry waits this much longer
+        #[arg(long, value_name = "SECS", default_value_t = 2, requires = "retry")]
+        retry_delay: u64,
+    },
+    /// Print the exact command connect would run, without running it
+    Cmd {
+        /// Host name in ssh config
+        host: String,
+        /// RemoteCommand for this connection only (overrides the host's RemoteCommand)
+        #[arg(long)]
+        remote_command: Option<String>,
+        /// Print the sftp command instead of the ssh command
+        #[arg(long)]
+        sftp: bool,
+        /// Copy the command to the clipboard
+        #[arg(long)]
+        copy: bool,
+    },
+    /// Print one host's config block, ready to paste into another ssh config
+    ///
+    /// The block is printed in ssh config format unless --json or --yaml is given
+    /// (`--config` already selects the SSH config file to read).
+    Show {
+        /// Host name in ssh config
+        host: String,
+        /// Print the host as JSON
+        #[arg(long, conflicts_with = "yaml")]
+        json: bool,
+        /// Print the host as YAML
+        #[arg(long)]
+        yaml: bool,
+    },
+    /// Open an SFTP session to the specified server
+    Sftp {
+        /// Host name in ssh config
+        host: String,
+    },
+    /// Copy files with scp using the stored host config, e.g. `scp web:/etc/hosts .`
+    Scp {
+        /// Source (`<host>:<path>` or a local path)
+        source: String,
+        /// Destination (`<host>:<path>` or a local path)
+        destination: String,
+        /// Copy directories recursively
+        #[arg(short, long)]
+        recursive: bool,
     },
     /// Add server to ssh config
     Add {
-        /// Host name
-        host: String,
-        /// Server address (HostName)
-        hostname: String,
+        /// Host name (omit both host and hostname to be prompted for each field)
+        host: Option<String>,
+        /// Server address (HostName), required unless --interactive
+        hostname: Option<String>,
+        /// Prompt for each field, using the given arguments as defaults
+        #[arg(short, long)]
+        interactive: bool,
         /// Username (optional)
         #[arg(short, long)]
         user: Option<String>,
@@ -47,6 +159,22 @@ pub enum Commands {
         /// IdentityFile (optional)
         #[arg(long)]
         identity_file: Option<String>,
+        /// RemoteCommand (optional), e.g. 'tmux new -A -s main'
+        #[arg(long)]
+        remote_command: Option<String>,
+        /// RequestTTY (optional): auto, yes, force or no
+        #[arg(long, value_parser = parse_request_tty_arg)]
+        request_tty: Option<String>,
+        /// SetEnv entry KEY=VALUE (repeatable)
+        #[arg(long = "set-env", value_name = "KEY=VALUE", value_parser = parse_env_arg)]
+        set_env: Vec<(String, String)>,
+        #[command(flatten)]
+        network: NetworkArgs,
+        #[command(flatten)]
+        crypto: CryptoArgs,
+        /// Fill unspecified fields from a template defined in settings.toml
+        #[arg(long)]
+        template: Option<String>,
     },
     /// Edit server configuration
     Edit {
@@ -67,19 +195,986 @@ pub enum Commands {
         /// IdentityFile (optional)
         #[arg(long)]
         identity_file: Option<String>,
+        /// RemoteCommand (optional), e.g. 'tmux new -A -s main'
+        #[arg(long)]
+        remote_command: Option<String>,
+        /// RequestTTY (optional): auto, yes, force or no
+        #[arg(long, value_parser = parse_request_tty_arg)]
+        request_tty: Option<String>,
+        /// SetEnv entry KEY=VALUE (repeatable)
+        #[arg(long = "set-env", value_name = "KEY=VALUE", value_parser = parse_env_arg)]
+        set_env: Vec<(String, String)>,
+        #[command(flatten)]
+        network: NetworkArgs,
+        #[command(flatten)]
+        crypto: CryptoArgs,
+        /// Remove a directive (repeatable): user, port, proxy_command, identity_file,
+        /// remote_command, request_tty, set_env, address_family, bind_address,
+        /// permit_local_command, local_command
+        #[arg(long, value_name = "FIELD", value_parser = parse_clear_arg)]
+        clear: Vec<OptionalField>,
+        /// Save without showing the diff and asking for confirmation (never asked when stdin is not a terminal)
+        #[arg(short, long)]
+        yes: bool,
+        /// Prompt for each field with the current value as default (Enter keeps, '-' clears)
+        #[arg(short, long, conflicts_with_all = [
+            "hostname", "user", "port", "proxy_command", "identity_file", "remote_command",
+            "request_tty", "set_env", "clear", "address_family", "bind_address", "ciphers",
+            "macs", "host_key_algorithms", "pubkey_accepted_algorithms",
+        ])]
+        interactive: bool,
+        /// Open the TUI edit form for just this host, save and exit
+        #[arg(long, conflicts_with_all = [
+            "interactive", "hostname", "user", "port", "proxy_command", "identity_file",
+            "remote_command", "request_tty", "set_env", "clear", "address_family",
+            "bind_address", "ciphers", "macs", "host_key_algorithms",
+            "pubkey_accepted_algorithms",
+        ])]
+        form: bool,
     },
     /// Delete server configuration
     Delete {
-        /// Host name to delete
-        host: String,
+        /// Host name(s) to delete; with --glob, patterns such as 'staging-*' (quote them)
+        #[arg(required_unless_present = "query", conflicts_with = "query")]
+        hosts: Vec<String>,
+        /// Delete every host matching this search query (same syntax as `search`)
+        #[arg(long)]
+        query: Option<String>,
+        /// Treat the host arguments as `*`/`?` patterns matched against configured Host names
+        #[arg(long, conflicts_with = "query")]
+        glob: bool,
+        /// Delete without asking for confirmation (required when stdin is not a terminal)
+        #[arg(short, long)]
+        yes: bool,
+        /// Keep the stored password for this host
+        #[arg(long)]
+        keep_password: bool,
     },
     /// Search servers
     Search {
         /// Search query
         query: String,
+        /// Comma-separated fields to match: host,hostname,user,port (default: all)
+        #[arg(long, value_name = "FIELDS", value_parser = parse_search_fields)]
+        fields: Option<SearchFields>,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+    },
+    /// Import hosts from another tool
+    Import {
+        /// Source format
+        #[arg(long, value_enum, required_unless_present = "from_known_hosts")]
+        format: Option<ImportFormat>,
+        /// Import hosts found in known_hosts that are missing from the config
+        #[arg(long, conflicts_with = "format")]
+        from_known_hosts: bool,
+        /// Add every candidate without asking (with --from-known-hosts)
+        #[arg(long, requires = "from_known_hosts")]
+        all: bool,
+        /// Replace hosts that already exist instead of skipping them (asks once, backs up first)
+        #[arg(long, conflicts_with = "from_known_hosts")]
+        overwrite: bool,
+        /// Overwrite without confirmation (required when stdin is not a terminal)
+        #[arg(short, long, requires = "overwrite")]
+        yes: bool,
+        /// .reg export file or portable sessions folder; known_hosts file (default: ~/.ssh/known_hosts)
+        #[arg(required_unless_present = "from_known_hosts")]
+        path: Option<std::path::PathBuf>,
+    },
+    /// Change fields on every host matching a query, e.g. `--set user=svc-deploy`
+    BulkEdit {
+        /// Search query selecting the hosts (same syntax as `search`)
+        #[arg(long)]
+        query: String,
+        /// FIELD=VALUE to apply (repeatable); fields: user, port, identity_file, proxy_jump, connect_timeout
+        #[arg(long = "set", value_name = "FIELD=VALUE", required = true, value_parser = parse_field_assignment)]
+        set: Vec<FieldAssignment>,
+        /// Apply without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// List or inspect host templates from settings.toml
+    Template {
+        #[command(subcommand)]
+        action: TemplateAction,
+    },
+    /// Manage named profiles (profile name → SSH config file)
+    Profiles {
+        #[command(subcommand)]
+        action: ProfileAction,
+    },
+    /// Manage stored host passwords and private key passphrases
+    Password {
+        #[command(subcommand)]
+        action: PasswordAction,
+    },
+    /// Check hosts for known-weak crypto settings (Ciphers, MACs, HostKeyAlgorithms, ...)
+    Validate,
+    /// Check the environment: SSH config file, ssh-agent status and ~/.ssh permissions
+    Doctor {
+        /// Tighten loose permissions (~/.ssh to 700, config and password DB to 600)
+        #[arg(long)]
+        fix: bool,
+        /// Fix permissions without asking for confirmation
+        #[arg(short, long, requires = "fix")]
+        yes: bool,
+    },
+    /// Print the version; with --verbose also the git commit, rustc version, target and
+    /// where ssh/sshpass were found, for bug reports
+    Version,
+    /// Merge Host blocks that share the same name into one (later values win); backs up first
+    Dedupe {
+        /// Only report what would be merged, without changing the config
+        #[arg(long)]
+        dry_run: bool,
     },
     /// Backup configuration file
-    Backup,
+    Backup {
+        /// Write the backup to this path instead of next to the config
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<std::path::PathBuf>,
+        /// gzip-compress the backup (implied by a .gz extension)
+        #[arg(long)]
+        compress: bool,
+        /// Overwrite an existing file at the destination
+        #[arg(long)]
+        force: bool,
+        /// Also export stored passwords, encrypted with a passphrase
+        /// (prompted for, or taken from SSH_CONN_BACKUP_PASSPHRASE)
+        #[arg(long)]
+        include_passwords: bool,
+    },
+    /// Replace the SSH config with a backup
+    Restore {
+        /// Backup file to restore
+        #[arg(
+            required_unless_present = "latest_auto",
+            conflicts_with = "latest_auto"
+        )]
+        backup: Option<std::path::PathBuf>,
+        /// Restore the most recent automatic backup
+        #[arg(long)]
+        latest_auto: bool,
+        /// Show the changes against the current config before restoring
+        #[arg(long)]
+        diff: bool,
+        /// Restore without asking for confirmation (required when stdin is not a terminal)
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Show what changed in the config since a backup (default: the newest backup)
+    Diff {
+        /// Backup or other config file to compare against (.gz is decompressed)
+        file: Option<std::path::PathBuf>,
+        /// Print the changed hosts with per-option before/after values as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Keep testing hosts and print a line whenever one goes up or down (Ctrl-C to stop)
+    Watch {
+        /// Seconds between rounds
+        #[arg(long, default_value_t = 60, value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+        /// Only watch hosts matching this search query (same syntax as `search`)
+        #[arg(long)]
+        query: Option<String>,
+        /// Maximum number of hosts tested at the same time
+        #[arg(long, default_value_t = 16, value_parser = clap::value_parser!(u64).range(1..))]
+        concurrency: u64,
+    },
+    /// Test whether a host is reachable, or list the auth methods its server offers
+    Test {
+        /// Host name in ssh config
+        host: String,
+        /// List the auth methods the server offers (e.g. publickey, password) instead of a TCP test
+        #[arg(long)]
+        auth: bool,
+    },
+    /// Test all hosts once and print the results, optionally writing metrics for scraping
+    TestAll {
+        /// Only test hosts matching this search query (same syntax as `search`)
+        #[arg(long)]
+        query: Option<String>,
+        /// Maximum number of hosts tested at the same time
+        #[arg(long, default_value_t = 16, value_parser = clap::value_parser!(u64).range(1..))]
+        concurrency: u64,
+        /// Write per-host metrics to this file (atomically replaced)
+        #[arg(long, value_name = "PATH")]
+        metrics_out: Option<std::path::PathBuf>,
+        /// Format of the metrics file
+        #[arg(long, value_enum, default_value_t = MetricsFormat::Prometheus, requires = "metrics_out")]
+        metrics_format: MetricsFormat,
+    },
+    /// Measure DNS, TCP and optionally SSH connection time over several rounds
+    Bench {
+        /// Host to measure; give two hosts to compare them side by side
+        #[arg(required = true, num_args = 1..=2)]
+        hosts: Vec<String>,
+        /// Number of rounds
+        #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u32).range(1..))]
+        rounds: u32,
+        /// Also time a full `ssh -o BatchMode=yes <host> exit`
+        #[arg(long)]
+        ssh: bool,
+        /// Print the results as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Summarize the host inventory
+    Stats {
+        /// Print the summary as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show or change application settings (settings.toml)
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+/// 输出两份配置的差异：先按主机块汇总（含各主机变化的选项），再输出统一格式的逐行差异
+fn print_config_diff(old_label: &str, old: &str, new_label: &str, new: &str) {
+    let summary = crate::config::diff_config_hosts(old, new);
+    println!(
+        "{}",
+        t("diff_summary")
+            .replacen("{}", &summary.added.len().to_string(), 1)
+            .replacen("{}", &summary.removed.len().to_string(), 1)
+            .replacen("{}", &summary.modified.len().to_string(), 1)
+    );
+    for host in &summary.added {
+        println!("  + {}", host);
+    }
+    for host in &summary.removed {
+        println!("  - {}", host);
+    }
+    for modified in &summary.modified {
+        println!("  ~ {}", modified.host);
+        for change in &modified.changes {
+            println!(
+                "      {}: {} → {}",
+                change.option,
+                change.before.join(", "),
+                change.after.join(", ")
+            );
+        }
+    }
+
+    let diff = crate::utils::unified_diff(old, new, old_label, new_label, 3);
+    if diff.is_empty() {
+        println!("{}", t("diff_no_changes"));
+    } else {
+        println!();
+        print!("{}", diff);
+    }
+}
+
+/// 主机启用了弱加密算法时在标准错误输出提示
+fn warn_legacy_crypto(host: &SshHost) {
+    let warnings = host.legacy_crypto_warnings();
+    if !warnings.is_empty() {
+        eprintln!(
+            "⚠ {}: {}",
+            t("cli_labels.legacy_crypto"),
+            warnings.join(", ")
+        );
+    }
+}
+
+/// 输出版本，`verbose` 时附带构建信息和 `ssh`/`sshpass` 的位置
+///
+/// 不读取SSH配置和密码数据库，设置有误时也能输出，见 [`crate::version`]
+pub fn version_command(verbose: bool) -> Result<()> {
+    print!("{}", crate::version::VersionInfo::collect().render(verbose));
+    Ok(())
+}
+
+/// 在终端询问 y/N，只有输入 y/yes 时返回true
+fn confirm(prompt: &str) -> Result<bool> {
+    use std::io::Write;
+
+    print!("{} [y/N] ", prompt);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// 密码导出使用的口令：优先读取环境变量 `SSH_CONN_BACKUP_PASSPHRASE`，否则输入两次确认
+fn backup_passphrase(prompter: &mut dyn Prompter) -> Result<zeroize::Zeroizing<String>> {
+    new_passphrase(
+        prompter,
+        "SSH_CONN_BACKUP_PASSPHRASE",
+        ("backup_passphrase_prompt", "backup_passphrase_confirm"),
+        "error.backup_passphrase_empty",
+    )
+}
+
+/// 要保存的私钥口令：优先读取环境变量 `SSH_CONN_KEY_PASSPHRASE`，否则输入两次确认
+fn key_passphrase(prompter: &mut dyn Prompter) -> Result<zeroize::Zeroizing<String>> {
+    new_passphrase(
+        prompter,
+        "SSH_CONN_KEY_PASSPHRASE",
+        ("key_passphrase_prompt", "key_passphrase_confirm"),
+        "error.key_passphrase_empty",
+    )
+}
+
+/// 读取新口令：环境变量 `env` 优先，否则以 `prompts` 中的两个提示不回显地输入两次，
+/// 两次不一致或为空（提示 `empty_error`）时返回错误
+fn new_passphrase(
+    prompter: &mut dyn Prompter,
+    env: &str,
+    prompts: (&str, &str),
+    empty_error: &str,
+) -> Result<zeroize::Zeroizing<String>> {
+    let passphrase = match std::env::var(env) {
+        Ok(passphrase) => zeroize::Zeroizing::new(passphrase),
+        Err(_) => {
+            let cancelled = || SshConnError::InvalidArgument(t("bulk_edit_cancelled"));
+            let first = zeroize::Zeroizing::new(
+                prompter.read_hidden(&t(prompts.0))?.ok_or_else(cancelled)?,
+            );
+            let second = zeroize::Zeroizing::new(
+                prompter.read_hidden(&t(prompts.1))?.ok_or_else(cancelled)?,
+            );
+            if first != second {
+                return Err(SshConnError::InvalidArgument(t(
+                    "error.backup_passphrase_mismatch",
+                )));
+            }
+            first
+        }
+    };
+    if passphrase.is_empty() {
+        return Err(SshConnError::InvalidArgument(t(empty_error)));
+    }
+    Ok(passphrase)
+}
+
+/// 要求输入指定的单词确认，只有完全一致（忽略首尾空白）才算确认
+fn confirm_word(prompt: &str, word: &str) -> Result<bool> {
+    use std::io::Write;
+
+    print!("{} ", prompt);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(answer.trim() == word)
+}
+
+/// 解析 `--set-env KEY=VALUE` 参数
+fn parse_env_arg(value: &str) -> std::result::Result<(String, String), String> {
+    crate::utils::validate_env_var(value).map_err(|e| e.to_string())
+}
+
+/// 解析 `--request-tty` 参数
+fn parse_request_tty_arg(value: &str) -> std::result::Result<String, String> {
+    crate::utils::validate_request_tty(value).map_err(|e| e.to_string())
+}
+
+/// 解析 `edit --clear` 的字段名
+fn parse_clear_arg(value: &str) -> std::result::Result<OptionalField, String> {
+    OptionalField::parse(value).map_err(|e| e.to_string())
+}
+
+/// 解析 `--address-family` 参数
+fn parse_address_family_arg(value: &str) -> std::result::Result<String, String> {
+    crate::utils::validate_address_family(value).map_err(|e| e.to_string())
+}
+
+/// 解析 `--bind-address` 参数
+fn parse_bind_address_arg(value: &str) -> std::result::Result<String, String> {
+    crate::utils::validate_bind_address(value)
+        .map(|_| value.trim().to_string())
+        .map_err(|e| e.to_string())
+}
+
+/// 解析 `--ciphers` 等算法列表参数：非空且不含空白
+fn parse_algorithm_list(value: &str) -> std::result::Result<String, String> {
+    if value.is_empty() || value.chars().any(char::is_whitespace) {
+        return Err(t("error.algorithm_list_invalid").replace("{}", value));
+    }
+    Ok(value.to_string())
+}
+
+/// 解析 `bulk-edit --set FIELD=VALUE` 参数
+fn parse_field_assignment(value: &str) -> std::result::Result<FieldAssignment, String> {
+    FieldAssignment::parse(value).map_err(|e| e.to_string())
+}
+
+/// 解析 `--fields` 参数
+fn parse_search_fields(value: &str) -> std::result::Result<SearchFields, String> {
+    SearchFields::parse(value).map_err(|e| e.to_string())
+}
+
+/// Sort keys for host lists
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum SortKey {
+    /// Natural order of host names (web2 before web10)
+    Name,
+}
+
+/// Import source formats
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ImportFormat {
+    /// PuTTY sessions (.reg export or portable sessions folder)
+    Putty,
+}
+
+/// Output formats for host listings
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable text
+    Text,
+    /// JSON object with counts and the matching hosts
+    Json,
+    /// YAML document with counts and the matching hosts
+    Yaml,
+}
+
+/// 结构化输出的搜索结果
+#[derive(Debug, Serialize)]
+struct SearchOutput<'a> {
+    /// 匹配的主机数
+    count: usize,
+    /// 配置中的主机总数
+    total: usize,
+    hosts: &'a [SshHost],
+}
+
+/// 结构化输出的单台主机
+#[derive(Debug, Serialize)]
+struct HostExport<'a> {
+    #[serde(flatten)]
+    host: &'a SshHost,
+    /// 是否存储了密码（从不输出密码本身）
+    password_stored: bool,
+}
+
+/// 导出单台主机（`show` 子命令），`Text` 为可直接粘贴的ssh配置块
+///
+/// 只注明是否存储了密码，从不输出密码本身；不含主机所在配置文件的路径
+pub fn export_host(host: &SshHost, password_stored: bool, format: OutputFormat) -> Result<String> {
+    let host = SshHost {
+        source_file: None,
+        ..host.clone()
+    };
+    let document = HostExport {
+        host: &host,
+        password_stored,
+    };
+    let output = match format {
+        OutputFormat::Text => {
+            let note = if password_stored {
+                t("show_password_stored")
+            } else {
+                t("show_no_password")
+            };
+            return Ok(format!("# {}\n{}", note, host.to_config_format()));
+        }
+        OutputFormat::Json => serde_json::to_string_pretty(&document).map_err(|e| e.to_string()),
+        OutputFormat::Yaml => serde_yaml::to_string(&document).map_err(|e| e.to_string()),
+    };
+    output.map_err(|e| SshConnError::ConfigParse(format!("{}: {}", t("serialization_failed"), e)))
+}
+
+/// 将搜索结果序列化为JSON或YAML（含 `count`/`total` 字段），文本格式返回None（由调用方逐个格式化输出）
+pub fn serialize_hosts(
+    hosts: &[SshHost],
+    total: usize,
+    format: OutputFormat,
+) -> Result<Option<String>> {
+    let document = SearchOutput {
+        count: hosts.len(),
+        total,
+        hosts,
+    };
+    let output = match format {
+        OutputFormat::Text => return Ok(None),
+        OutputFormat::Json => serde_json::to_string_pretty(&document).map_err(|e| e.to_string()),
+        OutputFormat::Yaml => serde_yaml::to_string(&document).map_err(|e| e.to_string()),
+    };
+    output
+        .map(Some)
+        .map_err(|e| SshConnError::ConfigParse(format!("{}: {}", t("serialization_failed"), e)))
+}
+
+/// 列表末尾的数量汇总，如 "3 servers"；给出 `total` 时为搜索结果的 "2 of 5 matched"
+pub fn count_summary(count: usize, total: Option<usize>) -> String {
+    match total {
+        Some(total) => t("cli_labels.count_matched")
+            .replacen("{}", &count.to_string(), 1)
+            .replacen("{}", &total.to_string(), 1),
+        None => t("cli_labels.count_servers").replace("{}", &count.to_string()),
+    }
+}
+
+/// 单个取值的主机数量
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ValueCount {
+    pub value: String,
+    pub count: usize,
+}
+
+/// 主机清单统计
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct InventoryStats {
+    /// 主机总数
+    pub total: usize,
+    /// 按User统计（未设置的记为 `(default)`）
+    pub users: Vec<ValueCount>,
+    /// 按Port统计（未设置的记为 `(default)`）
+    pub ports: Vec<ValueCount>,
+    /// 按IdentityFile统计（未设置的记为 `(default)`）
+    pub identity_files: Vec<ValueCount>,
+    /// 使用ProxyCommand或ProxyJump的主机数
+    pub proxied: usize,
+    /// 存储了密码的主机数
+    pub stored_passwords: usize,
+    /// 没有HostName的主机数
+    pub missing_hostname: usize,
+}
+
+/// 统计主机清单，`password_hosts` 为存储了密码的主机名
+pub fn compute_stats(hosts: &[SshHost], password_hosts: &[String]) -> InventoryStats {
+    fn count_by(hosts: &[SshHost], value: impl Fn(&SshHost) -> Option<&str>) -> Vec<ValueCount> {
+        let mut counts: Vec<ValueCount> = Vec::new();
+        for host in hosts {
+            let value = value(host).filter(|v| !v.is_empty()).unwrap_or("(default)");
+            match counts.iter_mut().find(|c| c.value == value) {
+                Some(entry) => entry.count += 1,
+                None => counts.push(ValueCount {
+                    value: value.to_string(),
+                    count: 1,
+                }),
+            }
+        }
+        // 数量多的在前，数量相同时按自然顺序
+        counts.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| crate::utils::natural_cmp(&a.value, &b.value))
+        });
+        counts
+    }
+
+    InventoryStats {
+        total: hosts.len(),
+        users: count_by(hosts, |h| h.user.as_deref()),
+        ports: count_by(hosts, |h| h.port.as_deref()),
+        identity_files: count_by(hosts, |h| h.identity_file.as_deref()),
+        proxied: hosts
+            .iter()
+            .filter(|h| h.proxy_command.is_some() || h.get_custom_option("ProxyJump").is_some())
+            .count(),
+        stored_passwords: hosts
+            .iter()
+            .filter(|h| password_hosts.contains(&h.host))
+            .count(),
+        missing_hostname: hosts
+            .iter()
+            .filter(|h| h.hostname.as_deref().is_none_or(str::is_empty))
+            .count(),
+    }
+}
+
+/// 将统计结果格式化为对齐的文本表格
+pub fn format_stats_table(stats: &InventoryStats) -> String {
+    let summary = [
+        (t("cli_labels.stats_total"), stats.total),
+        (t("cli_labels.stats_proxied"), stats.proxied),
+        (
+            t("cli_labels.stats_stored_passwords"),
+            stats.stored_passwords,
+        ),
+        (
+            t("cli_labels.stats_missing_hostname"),
+            stats.missing_hostname,
+        ),
+    ];
+    let sections = [
+        (t("cli_labels.user"), &stats.users),
+        (t("cli_labels.port"), &stats.ports),
+        (t("cli_labels.identity_file"), &stats.identity_files),
+    ];
+
+    // 所有行共用同一列宽，按字符数对齐
+    let width = summary
+        .iter()
+        .map(|(label, _)| label.chars().count())
+        .chain(sections.iter().flat_map(|(label, counts)| {
+            std::iter::once(label.chars().count())
+                .chain(counts.iter().map(|c| c.value.chars().count() + 2))
+        }))
+        .max()
+        .unwrap_or(0);
+    let row = |label: &str, count: usize| {
+        let padding = width.saturating_sub(label.chars().count());
+        format!("{}{}  {:>5}", label, " ".repeat(padding), count)
+    };
+
+    let mut lines: Vec<String> = summary
+        .iter()
+        .map(|(label, count)| row(label, *count))
+        .collect();
+    for (label, counts) in sections {
+        lines.push(String::new());
+        lines.push(format!("{}:", label));
+        for entry in counts {
+            lines.push(row(&format!("  {}", entry.value), entry.count));
+        }
+    }
+    lines.join("\n")
+}
+
+/// 批量修改中单个字段的变化
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: EditableField,
+    pub before: Option<String>,
+    pub after: String,
+}
+
+/// 单台主机的批量修改计划
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BulkEditPlan {
+    pub host: String,
+    pub changes: Vec<FieldChange>,
+}
+
+/// 计算匹配 `query` 的主机需要做的修改
+///
+/// 同一字段多次赋值时以最后一次为准；取值已相同的字段不列出，没有任何变化的主机也不列出
+pub fn plan_bulk_edit(
+    hosts: &[SshHost],
+    query: &str,
+    assignments: &[FieldAssignment],
+) -> Vec<BulkEditPlan> {
+    let mut targets: Vec<&FieldAssignment> = Vec::new();
+    for assignment in assignments.iter().rev() {
+        if !targets.iter().any(|a| a.field == assignment.field) {
+            targets.insert(0, assignment);
+        }
+    }
+
+    hosts
+        .iter()
+        .filter(|host| host.matches_query(query))
+        .filter_map(|host| {
+            let changes: Vec<FieldChange> = targets
+                .iter()
+                .filter_map(|assignment| {
+                    let before = assignment.field.value(host);
+                    (before.as_deref() != Some(assignment.value.as_str())).then(|| FieldChange {
+                        field: assignment.field,
+                        before,
+                        after: assignment.value.clone(),
+                    })
+                })
+                .collect();
+            (!changes.is_empty()).then(|| BulkEditPlan {
+                host: host.host.clone(),
+                changes,
+            })
+        })
+        .collect()
+}
+
+/// 批量修改预览，每台主机一行，如 `web1: User deploy → svc-deploy`
+pub fn format_bulk_edit_preview(plans: &[BulkEditPlan]) -> String {
+    plans
+        .iter()
+        .map(|plan| {
+            let changes: Vec<String> = plan
+                .changes
+                .iter()
+                .map(|change| {
+                    format!(
+                        "{} {} → {}",
+                        change.field.keyword(),
+                        change
+                            .before
+                            .clone()
+                            .unwrap_or_else(|| t("bulk_edit_unset")),
+                        change.after
+                    )
+                })
+                .collect();
+            format!("  {}: {}", plan.host, changes.join(", "))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 逐台应用批量修改，一台失败不影响其余主机，返回每台主机的结果
+fn apply_bulk_edit(
+    manager: &mut ConfigManager,
+    plans: &[BulkEditPlan],
+) -> Vec<(String, Result<()>)> {
+    plans
+        .iter()
+        .map(|plan| {
+            let result = plan.changes.iter().try_for_each(|change| {
+                manager.set_host_option(&plan.host, change.field.keyword(), &change.after)
+            });
+            (plan.host.clone(), result)
+        })
+        .collect()
+}
+
+/// 逐台输出批量修改的结果和汇总
+///
+/// 与 `delete` 多台主机时一致，有主机失败时返回第一个错误，进程以对应的退出码结束
+fn report_bulk_edit(results: Vec<(String, Result<()>)>) -> Result<()> {
+    let total = results.len();
+    let mut first_error = None;
+    let mut failed = 0;
+    for (host, result) in results {
+        match result {
+            Ok(()) => println!("✓ {}", host),
+            Err(e) => {
+                failed += 1;
+                eprintln!("✗ {}: {}", host, e);
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    println!(
+        "{}",
+        t("bulk_edit_summary")
+            .replacen("{}", &(total - failed).to_string(), 1)
+            .replacen("{}", &failed.to_string(), 1)
+    );
+    first_error.map_or(Ok(()), Err)
+}
+
+/// 展开 `add` 的主机名和地址中的数字区间并一一配对，如 `node[01-20]` 与 `10.0.0.[1-20]`
+///
+/// 两者展开后的数量必须相同，且不能超过 [`crate::utils::MAX_RANGE_EXPANSION`]
+pub fn expand_add_targets(host: &str, hostname: &str) -> Result<Vec<(String, String)>> {
+    let expand = |pattern: &str| {
+        crate::utils::try_expand_range(pattern, crate::utils::MAX_RANGE_EXPANSION).ok_or_else(
+            || {
+                SshConnError::InvalidArgument(
+                    t("error.range_too_large")
+                        .replacen("{}", pattern, 1)
+                        .replacen("{}", &crate::utils::MAX_RANGE_EXPANSION.to_string(), 1),
+                )
+            },
+        )
+    };
+    let hosts = expand(host)?;
+    let hostnames = expand(hostname)?;
+    if hosts.len() != hostnames.len() {
+        return Err(SshConnError::InvalidArgument(
+            t("error.range_count_mismatch")
+                .replacen("{}", &hosts.len().to_string(), 1)
+                .replacen("{}", &hostnames.len().to_string(), 1),
+        ));
+    }
+    Ok(hosts.into_iter().zip(hostnames).collect())
+}
+
+/// 批量删除时选择目标主机的方式
+#[derive(Clone, Copy, Debug)]
+pub enum DeleteSelector<'a> {
+    /// 显式给出的主机名
+    Names(&'a [String]),
+    /// 与 `search` 相同语法的查询
+    Query(&'a str),
+    /// 匹配配置中Host名的 `*`/`?` 模式
+    Glob(&'a [String]),
+}
+
+/// 确定批量删除的目标主机
+///
+/// 显式给出的主机名去重后原样保留（不存在的主机在删除时报告失败）；
+/// 按查询或模式选择时按配置顺序返回，跳过通配符块，没有任何匹配时返回错误而不是静默成功
+pub fn select_delete_targets(hosts: &[SshHost], selector: DeleteSelector) -> Result<Vec<String>> {
+    let description = match selector {
+        DeleteSelector::Names(names) => {
+            let mut targets: Vec<String> = Vec::new();
+            for name in names {
+                if !targets.contains(name) {
+                    targets.push(name.clone());
+                }
+            }
+            return Ok(targets);
+        }
+        DeleteSelector::Query(query) => query.to_string(),
+        DeleteSelector::Glob(patterns) => patterns.join(" "),
+    };
+
+    let targets: Vec<String> = hosts
+        .iter()
+        .filter(|host| !host.host.contains(['*', '?']))
+        .filter(|host| match selector {
+            DeleteSelector::Names(_) => false,
+            DeleteSelector::Query(query) => host.matches_query(query),
+            DeleteSelector::Glob(patterns) => patterns
+                .iter()
+                .any(|pattern| crate::utils::glob_match(pattern, &host.host)),
+        })
+        .map(|host| host.host.clone())
+        .collect();
+    if targets.is_empty() {
+        return Err(SshConnError::InvalidArgument(
+            t("error.delete_nothing_matched").replace("{}", &description),
+        ));
+    }
+    Ok(targets)
+}
+
+/// Grouping keys for `list --group-by`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum GroupBy {
+    /// Registrable domain of HostName (IPs and single-label names go to "other")
+    Domain,
+    /// Login user
+    User,
+}
+
+impl GroupBy {
+    /// 主机所属的组名，无法分组时返回None
+    pub fn key(self, host: &crate::models::SshHost) -> Option<String> {
+        match self {
+            GroupBy::Domain => host.hostname.as_deref().and_then(crate::utils::host_domain),
+            GroupBy::User => host.user.clone().filter(|u| !u.is_empty()),
+        }
+    }
+}
+
+/// Settings subcommands
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Print the settings file path and the effective settings
+    Show,
+    /// Set a single key, e.g. `ui.auto_test_on_start false`
+    Set {
+        /// Dotted key path
+        key: String,
+        /// New value (TOML literal or plain string)
+        value: String,
+    },
+}
+
+/// Local network options shared by `add` and `edit`
+#[derive(Args, Debug, Clone, Default)]
+pub struct NetworkArgs {
+    /// AddressFamily (optional): any, inet (IPv4 only) or inet6 (IPv6 only)
+    #[arg(long, value_parser = parse_address_family_arg)]
+    pub address_family: Option<String>,
+    /// BindAddress (optional): local source IP address or interface name
+    #[arg(long, value_parser = parse_bind_address_arg)]
+    pub bind_address: Option<String>,
+}
+
+impl NetworkArgs {
+    /// 命令行中指定了的选项（配置关键字, 取值）
+    fn options(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("AddressFamily", &self.address_family),
+            ("BindAddress", &self.bind_address),
+        ]
+        .into_iter()
+        .filter_map(|(keyword, value)| value.as_deref().map(|v| (keyword, v)))
+        .collect()
+    }
+
+    /// 将指定的选项写入主机
+    fn apply(self, host: &mut SshHost) {
+        host.address_family = self.address_family.or(host.address_family.take());
+        host.bind_address = self.bind_address.or(host.bind_address.take());
+    }
+}
+
+/// Crypto algorithm options shared by `add` and `edit`
+#[derive(Args, Debug, Clone, Default)]
+pub struct CryptoArgs {
+    /// Ciphers (optional), e.g. 'aes256-gcm@openssh.com,aes128-ctr'
+    #[arg(long, value_parser = parse_algorithm_list)]
+    pub ciphers: Option<String>,
+    /// MACs (optional)
+    #[arg(long, value_parser = parse_algorithm_list)]
+    pub macs: Option<String>,
+    /// HostKeyAlgorithms (optional), e.g. '+ssh-rsa' for legacy devices
+    #[arg(long, value_parser = parse_algorithm_list)]
+    pub host_key_algorithms: Option<String>,
+    /// PubkeyAcceptedAlgorithms (optional), e.g. '+ssh-rsa'
+    #[arg(long, value_parser = parse_algorithm_list)]
+    pub pubkey_accepted_algorithms: Option<String>,
+}
+
+impl CryptoArgs {
+    /// 命令行中指定了的选项（配置关键字, 取值）
+    fn options(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("Ciphers", &self.ciphers),
+            ("MACs", &self.macs),
+            ("HostKeyAlgorithms", &self.host_key_algorithms),
+            ("PubkeyAcceptedAlgorithms", &self.pubkey_accepted_algorithms),
+        ]
+        .into_iter()
+        .filter_map(|(keyword, value)| value.as_deref().map(|v| (keyword, v)))
+        .collect()
+    }
+
+    /// 将指定的选项写入主机
+    fn apply(self, host: &mut SshHost) {
+        host.ciphers = self.ciphers.or(host.ciphers.take());
+        host.macs = self.macs.or(host.macs.take());
+        host.host_key_algorithms = self.host_key_algorithms.or(host.host_key_algorithms.take());
+        host.pubkey_accepted_algorithms = self
+            .pubkey_accepted_algorithms
+            .or(host.pubkey_accepted_algorithms.take());
+    }
+}
+
+/// Template subcommands
+#[derive(Subcommand)]
+pub enum TemplateAction {
+    /// List configured templates
+    List,
+    /// Print a template's fields
+    Show {
+        /// Template name
+        name: String,
+    },
+}
+
+/// Profile subcommands
+#[derive(Subcommand)]
+pub enum ProfileAction {
+    /// List configured profiles
+    List,
+    /// Add a profile or change its config file path
+    Add {
+        /// Profile name (letters, digits, '-' and '_')
+        name: String,
+        /// SSH config file used by the profile
+        path: std::path::PathBuf,
+    },
+}
+
+/// Password subcommands
+#[derive(Subcommand)]
+pub enum PasswordAction {
+    /// List hosts with a stored password and keys with a stored passphrase (secrets are not shown)
+    List,
+    /// Store the passphrase of a private key, used to load it into ssh-agent without prompting
+    /// (asked twice, or read from SSH_CONN_KEY_PASSPHRASE)
+    SetKeyphrase {
+        /// Private key file, e.g. ~/.ssh/id_ed25519
+        path: String,
+    },
+    /// Delete the stored passphrase of a private key
+    DeleteKeyphrase {
+        /// Private key file, e.g. ~/.ssh/id_ed25519
+        path: String,
+    },
 }
 
 /// 命令行应用
@@ -106,28 +1201,129 @@ impl CliApp {
         match cli.command {
             // 无参数时进入 TUI
             None => {
+                // 警告写入日志，避免输出打乱终端界面
+                for issue in self.config_manager.permission_issues() {
+                    log::warn!("{}", issue.message());
+                }
                 let mut ui_manager = UiManager::new(self.config_manager.clone());
+                ui_manager.set_read_only(cli.read_only);
+                if cli.compact {
+                    ui_manager.set_compact(true);
+                }
                 ui_manager
                     .start_tui()
                     .map_err(crate::error::SshConnError::Io)
             }
-            Some(cmd) => self.handle_command(cmd),
+            Some(Commands::Version) => version_command(cli.verbose > 0),
+            Some(cmd) => {
+                // doctor和validate自己报告权限问题；--json-errors时标准错误只输出JSON
+                if !matches!(cmd, Commands::Doctor { .. } | Commands::Validate)
+                    && !cli.json_errors
+                    && !output::is_quiet()
+                {
+                    for issue in self.config_manager.permission_issues() {
+                        eprintln!("⚠ {}", issue.message());
+                    }
+                }
+                self.handle_command(cmd)
+            }
         }
     }
 
     /// 处理具体命令
     fn handle_command(&mut self, cmd: Commands) -> Result<()> {
         match cmd {
-            Commands::List => self.list_hosts(),
-            Commands::Connect { host } => self.connect_host(host),
+            Commands::List { sort, group_by } => self.list_hosts(sort, group_by),
+            Commands::Connect {
+                host,
+                remote_command,
+                user,
+                print_only,
+                full,
+                dry_run,
+                add_key,
+                retry,
+                retry_delay,
+            } => {
+                if add_key {
+                    self.config_manager.set_add_keys_to_agent(true);
+                }
+                if dry_run {
+                    self.dry_run_connect(&host, remote_command.as_deref(), user.as_deref())
+                } else if print_only {
+                    self.print_connection(&host, remote_command.as_deref(), full)
+                } else if let Some(retries) = retry {
+                    let policy =
+                        RetryPolicy::new(retries, std::time::Duration::from_secs(retry_delay));
+                    self.config_manager.connect_host_with_retry(
+                        &host,
+                        remote_command.as_deref(),
+                        user.as_deref(),
+                        &policy,
+                    )
+                } else {
+                    self.connect_host(host, remote_command, user)
+                }
+            }
+            Commands::Cmd {
+                host,
+                remote_command,
+                sftp,
+                copy,
+            } => self.print_command(&host, remote_command.as_deref(), sftp, copy),
+            Commands::Show { host, json, yaml } => {
+                let format = if json {
+                    OutputFormat::Json
+                } else if yaml {
+                    OutputFormat::Yaml
+                } else {
+                    OutputFormat::Text
+                };
+                self.show_host(&host, format)
+            }
+            Commands::Sftp { host } => self.config_manager.sftp_host(&host),
+            Commands::Scp {
+                source,
+                destination,
+                recursive,
+            } => self.config_manager.scp(&source, &destination, recursive),
             Commands::Add {
                 host,
                 hostname,
+                interactive,
+                user,
+                port,
+                proxy_command,
+                identity_file,
+                remote_command,
+                request_tty,
+                set_env,
+                network,
+                crypto,
+                template,
+            } => self.add_host_command(
+                host,
+                hostname,
+                interactive,
                 user,
                 port,
                 proxy_command,
                 identity_file,
-            } => self.add_host_command(host, hostname, user, port, proxy_command, identity_file),
+                remote_command,
+                request_tty,
+                set_env,
+                network,
+                crypto,
+                template,
+            ),
+            Commands::Edit {
+                host, form: true, ..
+            } => self.edit_host_form(&host),
+            Commands::Edit {
+                host,
+                interactive: true,
+                ..
+            } => self.edit_host_interactive(&host, &mut TerminalPrompter),
             Commands::Edit {
                 host,
                 hostname,
@@ -135,50 +1331,426 @@ impl CliApp {
                 port,
                 proxy_command,
                 identity_file,
-            } => self.edit_host_command(host, hostname, user, port, proxy_command, identity_file),
-            Commands::Delete { host } => self.delete_host_command(host),
-            Commands::Search { query } => self.search_hosts(&query),
-            Commands::Backup => self.backup_config(),
+                remote_command,
+                request_tty,
+                set_env,
+                network,
+                crypto,
+                clear,
+                yes,
+                interactive: false,
+                form: false,
+            } => self.edit_host_command(
+                host,
+                hostname,
+                user,
+                port,
+                proxy_command,
+                identity_file,
+                remote_command,
+                request_tty,
+                set_env,
+                network,
+                crypto,
+                &clear,
+                yes,
+            ),
+            Commands::Delete {
+                mut hosts,
+                query,
+                glob,
+                yes,
+                keep_password,
+            } => {
+                let selector = match (&query, glob) {
+                    (Some(query), _) => DeleteSelector::Query(query),
+                    (None, true) => DeleteSelector::Glob(&hosts),
+                    (None, false) if hosts.len() == 1 => {
+                        return self.delete_host_command(hosts.remove(0), yes, keep_password);
+                    }
+                    (None, false) => DeleteSelector::Names(&hosts),
+                };
+                let targets = select_delete_targets(self.config_manager.get_hosts()?, selector)?;
+                // 按模式删除属于批量破坏性操作，需要输入完整的单词确认
+                self.delete_hosts_command(&targets, yes, keep_password, glob)
+            }
+            Commands::Search {
+                query,
+                fields,
+                output,
+            } => self.search_hosts(&query, fields.unwrap_or_default(), output),
+            Commands::Import {
+                format,
+                from_known_hosts,
+                all,
+                overwrite,
+                yes,
+                path,
+            } => {
+                if from_known_hosts {
+                    self.import_known_hosts(path, all)
+                } else {
+                    match (format, path) {
+                        (Some(format), Some(path)) => {
+                            self.import_hosts(format, &path, overwrite, yes)
+                        }
+                        // clap 已保证两者同时存在
+                        _ => Ok(()),
+                    }
+                }
+            }
+            Commands::BulkEdit { query, set, yes } => self.bulk_edit(&query, &set, yes),
+            Commands::Template { action } => self.template_command(action),
+            Commands::Profiles { action } => self.profiles_command(action),
+            Commands::Password { action } => self.password_command(action, &mut TerminalPrompter),
+            Commands::Validate => self.validate_hosts(),
+            Commands::Doctor { fix, yes } => self.doctor_command(fix, yes),
+            Commands::Version => version_command(false),
+            Commands::Dedupe { dry_run } => self.dedupe_command(dry_run),
+            Commands::Backup {
+                output,
+                compress,
+                force,
+                include_passwords,
+            } => self.backup_config(output, BackupOptions { compress, force }, include_passwords),
+            Commands::Restore {
+                backup,
+                latest_auto,
+                diff,
+                yes,
+            } => self.restore_config(backup, latest_auto, diff, yes),
+            Commands::Diff { file, json } => self.diff_command(file, json),
+            Commands::Watch {
+                interval,
+                query,
+                concurrency,
+            } => self.watch_command(interval, query.as_deref(), concurrency as usize),
+            Commands::Test { host, auth } => self.test_command(&host, auth),
+            Commands::TestAll {
+                query,
+                concurrency,
+                metrics_out,
+                metrics_format,
+            } => self.test_all_command(
+                query.as_deref(),
+                concurrency as usize,
+                metrics_out.as_deref(),
+                metrics_format,
+            ),
+            Commands::Bench {
+                hosts,
+                rounds,
+                ssh,
+                json,
+            } => self.bench_command(&hosts, rounds, ssh, json),
+            Commands::Stats { json } => self.print_stats(json),
+            Commands::Config { action } => self.config_command(action),
         }
     }
 
-    /// 连接到指定主机
-    fn connect_host(&mut self, host: String) -> Result<()> {
-        self.config_manager.connect_host(&host)?;
+    /// 列出或查看主机模板
+    fn template_command(&self, action: TemplateAction) -> Result<()> {
+        let settings = self.config_manager.settings();
+        match action {
+            TemplateAction::List => {
+                if settings.templates.is_empty() {
+                    println!("{}", t("template_none"));
+                }
+                for name in settings.templates.keys() {
+                    println!("{}", name);
+                }
+            }
+            TemplateAction::Show { name } => {
+                // 按设置文件中的写法输出，嵌套表的表头带上完整路径
+                let template = settings.template(&name)?;
+                let wrapped = std::collections::BTreeMap::from([(
+                    "templates",
+                    std::collections::BTreeMap::from([(name.as_str(), template)]),
+                )]);
+                let text = toml::to_string_pretty(&wrapped)
+                    .map_err(|e| SshConnError::ConfigParse(e.to_string()))?;
+                print!("{}", text);
+            }
+        }
         Ok(())
     }
 
-    /// 列出所有主机
-    fn list_hosts(&mut self) -> Result<()> {
-        let hosts = self.config_manager.get_hosts()?.clone();
-
-        if hosts.is_empty() {
-            println!("{}", t("no_ssh_config_found"));
-            return Ok(());
+    /// 列出或新增配置档案
+    fn profiles_command(&self, action: ProfileAction) -> Result<()> {
+        match action {
+            ProfileAction::List => {
+                let settings = self.config_manager.settings();
+                if settings.profiles.is_empty() {
+                    println!("{}", t("profile_none"));
+                }
+                for (name, path) in &settings.profiles {
+                    let marker = if settings.active_profile.as_deref() == Some(name) {
+                        "*"
+                    } else {
+                        " "
+                    };
+                    println!("{} {} → {}", marker, name, path.display());
+                }
+            }
+            ProfileAction::Add { name, path } => {
+                let settings_path = crate::utils::get_settings_path()?;
+                Settings::add_profile(&settings_path, &name, &path)?;
+                output::info(format_args!(
+                    "✓ {}: {} → {}",
+                    t("profile_saved"),
+                    name,
+                    path.display()
+                ));
+            }
         }
-
+        Ok(())
+    }
+
+    /// 列出已存储的密码和私钥口令，或管理私钥口令
+    ///
+    /// 私钥口令以展开后的路径保存，与主机密码分开存放，删除主机时不会删除
+    fn password_command(
+        &mut self,
+        action: PasswordAction,
+        prompter: &mut dyn Prompter,
+    ) -> Result<()> {
+        match action {
+            PasswordAction::List => {
+                let none = t("password_list_none");
+                let hosts = self.config_manager.stored_password_hosts();
+                println!("{}", t("password_list_hosts"));
+                if hosts.is_empty() {
+                    println!("  {}", none);
+                }
+                for host in hosts {
+                    println!("  {}", host);
+                }
+                let keys = self.config_manager.stored_key_passphrases();
+                println!("{}", t("password_list_keys"));
+                if keys.is_empty() {
+                    println!("  {}", none);
+                }
+                for key in keys {
+                    println!("  {}", key);
+                }
+            }
+            PasswordAction::SetKeyphrase { path } => {
+                let key_path = crate::password::key_passphrase_id(&path);
+                if !std::path::Path::new(&key_path).is_file() {
+                    return Err(SshConnError::IdentityFileNotFound {
+                        path: key_path.into(),
+                    });
+                }
+                let passphrase = key_passphrase(prompter)?;
+                self.config_manager
+                    .save_key_passphrase(&path, &passphrase)?;
+                output::info(format_args!(
+                    "✓ {}: {}",
+                    t("key_passphrase_saved"),
+                    key_path
+                ));
+            }
+            PasswordAction::DeleteKeyphrase { path } => {
+                let key_path = crate::password::key_passphrase_id(&path);
+                if !self.config_manager.delete_key_passphrase(&path)? {
+                    return Err(SshConnError::InvalidArgument(
+                        t("error.key_passphrase_not_found").replace("{}", &key_path),
+                    ));
+                }
+                output::info(format_args!(
+                    "✓ {}: {}",
+                    t("key_passphrase_deleted"),
+                    key_path
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// 查看或修改应用设置
+    fn config_command(&self, action: ConfigAction) -> Result<()> {
+        let path = crate::utils::get_settings_path()?;
+        match action {
+            ConfigAction::Show => {
+                println!("# {}", path.display());
+                print!("{}", self.config_manager.settings().to_toml_string()?);
+            }
+            ConfigAction::Set { key, value } => {
+                Settings::set_value(&path, &key, &value)?;
+                output::info(format_args!("✓ {} = {}", key, value));
+            }
+        }
+        Ok(())
+    }
+
+    /// 连接到指定主机
+    fn connect_host(
+        &mut self,
+        host: String,
+        remote_command: Option<String>,
+        user: Option<String>,
+    ) -> Result<()> {
+        self.config_manager
+            .connect_host(&host, remote_command.as_deref(), user.as_deref())?;
+        Ok(())
+    }
+
+    /// 打印连接信息而不实际连接
+    ///
+    /// `--full` 或指定了远程命令时打印连接时实际执行的命令（含 `--remote-command`），
+    /// 存储的密码只以 `sshpass -e`（读取 `SSHPASS` 环境变量）代替
+    fn print_connection(
+        &mut self,
+        host: &str,
+        remote_command: Option<&str>,
+        full: bool,
+    ) -> Result<()> {
+        let ssh_host = self.config_manager.get_host(host)?.ok_or_else(|| {
+            crate::error::SshConnError::HostNotFound {
+                host: host.to_string(),
+            }
+        })?;
+
+        if full || remote_command.is_some() {
+            let spec = self
+                .config_manager
+                .connect_command(host, remote_command, None)?;
+            println!("{}", spec.to_command_line());
+        } else {
+            println!("{}", ssh_host.get_connection_string());
+        }
+        Ok(())
+    }
+
+    /// 执行连接前的全部检查，打印将要执行的命令和认证方式后退出
+    fn dry_run_connect(
+        &mut self,
+        host: &str,
+        remote_command: Option<&str>,
+        user: Option<&str>,
+    ) -> Result<()> {
+        let plan = self
+            .config_manager
+            .plan_connect(host, remote_command, user)?;
+        if let Some(program) = plan.missing_programs().first() {
+            return Err(SshConnError::SshConnectionError(
+                t("error.program_not_found").replace("{}", program),
+            ));
+        }
+
+        print!("{}", plan.describe());
+        println!("{}", t("dry_run_done"));
+        Ok(())
+    }
+
+    /// 输出单台主机的配置（`show` 子命令）
+    fn show_host(&mut self, host: &str, format: OutputFormat) -> Result<()> {
+        let ssh_host =
+            self.config_manager
+                .get_host(host)?
+                .ok_or_else(|| SshConnError::HostNotFound {
+                    host: host.to_string(),
+                })?;
+        let password_stored = self.config_manager.has_stored_password(host);
+        println!(
+            "{}",
+            export_host(&ssh_host, password_stored, format)?.trim_end()
+        );
+        Ok(())
+    }
+
+    /// 打印连接时实际执行的命令，存储的密码只以 `SSHPASS` 环境变量的说明代替
+    fn print_command(
+        &mut self,
+        host: &str,
+        remote_command: Option<&str>,
+        sftp: bool,
+        copy: bool,
+    ) -> Result<()> {
+        let spec = if sftp {
+            let ssh_host =
+                self.config_manager
+                    .get_host(host)?
+                    .ok_or_else(|| SshConnError::HostNotFound {
+                        host: host.to_string(),
+                    })?;
+            self.config_manager
+                .build_ssh_command(&ssh_host, &CommandMode::Sftp)
+        } else {
+            self.config_manager
+                .connect_command(host, remote_command, None)?
+        };
+
+        let command = spec.to_command_line();
+        if spec.use_password {
+            println!("# {}", t("cmd_password_note"));
+        }
+        println!("{}", command);
+
+        if copy {
+            crate::utils::copy_to_clipboard(&command)?;
+            eprintln!("✓ {}", t("cmd_copied"));
+        }
+        Ok(())
+    }
+
+    /// 列出所有主机
+    fn list_hosts(&mut self, sort: Option<SortKey>, group_by: Option<GroupBy>) -> Result<()> {
+        let mut hosts = self.config_manager.get_hosts()?.clone();
+        if sort == Some(SortKey::Name) {
+            hosts.sort_by(|a, b| crate::utils::natural_cmp(&a.host, &b.host));
+        }
+
+        if hosts.is_empty() {
+            println!("{}", t("no_ssh_config_found"));
+            return Ok(());
+        }
+
         println!("{}:", t("server_list"));
         println!("{:-<80}", "");
 
-        for host in &hosts {
-            println!("{}", self.format_host_info(host));
-            println!();
+        match group_by {
+            None => {
+                for host in &hosts {
+                    println!("{}", self.format_host_info(host));
+                    println!();
+                }
+            }
+            Some(group_by) => {
+                for (name, members) in crate::utils::group_hosts(&hosts, |h| group_by.key(h)) {
+                    let name = name.unwrap_or_else(|| t("cli_labels.group_other"));
+                    println!("== {} ({}) ==", name, members.len());
+                    for host in members {
+                        println!("{}", self.format_host_info(host));
+                        println!();
+                    }
+                }
+            }
         }
 
+        println!("{}", count_summary(hosts.len(), None));
         Ok(())
     }
 
     /// 搜索主机
-    fn search_hosts(&mut self, query: &str) -> Result<()> {
-        let hosts = self.config_manager.get_hosts()?.clone();
+    fn search_hosts(
+        &mut self,
+        query: &str,
+        fields: SearchFields,
+        output: OutputFormat,
+    ) -> Result<()> {
+        let filtered_hosts = self.config_manager.search_hosts_in_fields(query, fields)?;
+        let total = self.config_manager.get_hosts()?.len();
 
-        let filtered_hosts: Vec<_> = hosts
-            .iter()
-            .filter(|host| host.matches_query(query))
-            .collect();
+        // 结构化输出即使没有结果也输出空列表，便于管道处理
+        if let Some(serialized) = serialize_hosts(&filtered_hosts, total, output)? {
+            println!("{}", serialized.trim_end());
+            return Ok(());
+        }
 
         if filtered_hosts.is_empty() {
             println!("{}", t("no_matching_servers").replace("{}", query));
+            println!("{}", count_summary(0, Some(total)));
             return Ok(());
         }
 
@@ -190,41 +1762,733 @@ impl CliApp {
             println!();
         }
 
+        println!("{}", count_summary(filtered_hosts.len(), Some(total)));
+        Ok(())
+    }
+
+    /// 导入主机，已存在的主机跳过
+    fn import_hosts(
+        &mut self,
+        format: ImportFormat,
+        path: &std::path::Path,
+        overwrite: bool,
+        yes: bool,
+    ) -> Result<()> {
+        let result = match format {
+            ImportFormat::Putty => crate::import::load_putty(path)?,
+        };
+
+        for note in &result.notes {
+            println!("⚠ {}", note);
+        }
+        self.add_imported_hosts(&result.hosts, overwrite, yes)
+    }
+
+    /// 从 known_hosts 导入配置中没有的主机，未指定 `all` 时逐个确认
+    fn import_known_hosts(&mut self, path: Option<std::path::PathBuf>, all: bool) -> Result<()> {
+        let path = match path {
+            Some(path) => path,
+            None => crate::utils::get_known_hosts_path()?,
+        };
+        let content = std::fs::read_to_string(&path)?;
+        let result = crate::import::parse_known_hosts(&content, self.config_manager.get_hosts()?);
+
+        for note in &result.notes {
+            println!("⚠ {}", note);
+        }
+        if result.hosts.is_empty() {
+            println!("{}", t("import_known_hosts_none"));
+            return Ok(());
+        }
+
+        let mut selected = Vec::new();
+        for host in result.hosts {
+            let target = format!(
+                "{} ({}{})",
+                host.host,
+                host.hostname.as_deref().unwrap_or_default(),
+                host.port
+                    .as_deref()
+                    .map(|p| format!(":{}", p))
+                    .unwrap_or_default()
+            );
+            if all || confirm(&t("import_known_hosts_prompt").replace("{}", &target))? {
+                selected.push(host);
+            }
+        }
+        self.add_imported_hosts(&selected, false, false)
+    }
+
+    /// 通过常规添加流程写入导入的主机
+    ///
+    /// 已存在的主机默认跳过；`overwrite` 时列出将被覆盖的主机并确认一次（`yes` 时不确认），
+    /// 覆盖前先备份配置，再用导入的字段更新这些主机，其他指令保持不变
+    fn add_imported_hosts(&mut self, hosts: &[SshHost], overwrite: bool, yes: bool) -> Result<()> {
+        let mut existing = Vec::new();
+        for host in hosts {
+            if self.config_manager.host_exists(&host.host)? {
+                existing.push(host.host.as_str());
+            }
+        }
+
+        let mut overwrite = overwrite && !existing.is_empty();
+        if overwrite {
+            if !yes {
+                if !std::io::IsTerminal::is_terminal(&std::io::stdin()) {
+                    return Err(SshConnError::InvalidArgument(t(
+                        "error.import_overwrite_requires_yes",
+                    )));
+                }
+                println!("{}", t("import_overwrite_list"));
+                for name in &existing {
+                    println!("  {}", name);
+                }
+                overwrite = confirm(
+                    &t("import_overwrite_confirm").replace("{}", &existing.len().to_string()),
+                )?;
+            }
+            // 备份失败时不覆盖任何主机
+            if overwrite {
+                let backup_path = self.config_manager.backup_config()?;
+                output::info(format_args!(
+                    "✓ {}: {}",
+                    t("config_backup_success"),
+                    backup_path
+                ));
+            }
+        }
+
+        let mut imported = 0;
+        let mut overwritten = 0;
+        let mut skipped = 0;
+        for host in hosts {
+            if existing.contains(&host.host.as_str()) {
+                if !overwrite {
+                    println!("- {}", t("import_skip_existing").replace("{}", &host.host));
+                    skipped += 1;
+                    continue;
+                }
+                // 只改写导入的字段，块内其他指令和注释保持不变
+                let updated = self
+                    .config_manager
+                    .get_host(&host.host)
+                    .and_then(|current| {
+                        let mut merged = current.ok_or_else(|| SshConnError::HostNotFound {
+                            host: host.host.clone(),
+                        })?;
+                        merged.hostname = host.hostname.clone().or(merged.hostname);
+                        merged.user = host.user.clone().or(merged.user);
+                        merged.port = host.port.clone().or(merged.port);
+                        merged.proxy_command = host.proxy_command.clone().or(merged.proxy_command);
+                        merged.identity_file = host.identity_file.clone().or(merged.identity_file);
+                        self.config_manager.update_host(&merged, None)
+                    });
+                match updated {
+                    Ok(()) => {
+                        println!("↻ {}", host.host);
+                        overwritten += 1;
+                    }
+                    Err(e) => {
+                        println!("✗ {}: {}", host.host, e);
+                        skipped += 1;
+                    }
+                }
+                continue;
+            }
+
+            // 通过常规的添加流程写入，复用其中的校验
+            let added = host
+                .port
+                .as_deref()
+                .map(crate::utils::validate_port)
+                .transpose()
+                .and_then(|port| {
+                    self.config_manager.add_host(
+                        &host.host,
+                        host.hostname.as_deref().unwrap_or_default(),
+                        host.user.as_deref(),
+                        port,
+                        host.proxy_command.as_deref(),
+                        host.identity_file.as_deref(),
+                        None,
+                        None,
+                        &[],
+                        None,
+                    )
+                });
+            match added {
+                Ok(()) => {
+                    println!("✓ {}", host.host);
+                    imported += 1;
+                }
+                Err(e) => {
+                    println!("✗ {}: {}", host.host, e);
+                    skipped += 1;
+                }
+            }
+        }
+
+        println!(
+            "{}",
+            t("import_summary")
+                .replacen("{}", &imported.to_string(), 1)
+                .replacen("{}", &skipped.to_string(), 1)
+        );
+        if overwritten > 0 {
+            println!(
+                "{}",
+                t("import_overwritten").replace("{}", &overwritten.to_string())
+            );
+        }
         Ok(())
     }
 
-    /// 备份配置
-    fn backup_config(&self) -> Result<()> {
+    /// 批量修改匹配查询的主机：预览、确认、备份一次后逐台应用
+    fn bulk_edit(&mut self, query: &str, assignments: &[FieldAssignment], yes: bool) -> Result<()> {
+        let plans = plan_bulk_edit(self.config_manager.get_hosts()?, query, assignments);
+        if plans.is_empty() {
+            println!("{}", t("bulk_edit_no_changes"));
+            return Ok(());
+        }
+
+        println!(
+            "{}",
+            t("bulk_edit_preview").replace("{}", &plans.len().to_string())
+        );
+        println!("{}", format_bulk_edit_preview(&plans));
+        if !yes && !confirm(&t("bulk_edit_confirm"))? {
+            println!("{}", t("bulk_edit_cancelled"));
+            return Ok(());
+        }
+
+        // 备份失败时整个操作中止，不修改任何主机
         let backup_path = self.config_manager.backup_config()?;
-        println!("✓ {}: {}", t("config_backup_success"), backup_path);
+        output::info(format_args!(
+            "✓ {}: {}",
+            t("config_backup_success"),
+            backup_path
+        ));
+
+        report_bulk_edit(apply_bulk_edit(&mut self.config_manager, &plans))
+    }
+
+    /// 持续监测主机可用性，只输出状态变化；Ctrl-C 结束后输出汇总
+    fn watch_command(
+        &mut self,
+        interval: u64,
+        query: Option<&str>,
+        concurrency: usize,
+    ) -> Result<()> {
+        use crate::watch::{StatusTracker, probe_round, run_hook, wait_interval};
+        use std::sync::Arc;
+        use std::sync::atomic::AtomicBool;
+
+        let hosts: Vec<SshHost> = self
+            .config_manager
+            .get_hosts()?
+            .iter()
+            .filter(|host| !host.host.contains(['*', '?']))
+            .filter(|host| query.is_none_or(|query| host.matches_query(query)))
+            .cloned()
+            .collect();
+        if hosts.is_empty() {
+            return Err(SshConnError::InvalidArgument(t("error.watch_no_hosts")));
+        }
+        let hosts: Vec<SshHost> = hosts
+            .iter()
+            .map(|host| self.config_manager.effective_host(host))
+            .collect();
+        let hook = self.config_manager.settings().watch_hook.clone();
+
+        // Ctrl-C 只置位标志，当前一轮结束后正常退出并输出汇总
+        let stop = Arc::new(AtomicBool::new(false));
+        #[cfg(unix)]
+        for signal in [signal_hook::consts::SIGINT, signal_hook::consts::SIGTERM] {
+            signal_hook::flag::register(signal, Arc::clone(&stop))?;
+        }
+
+        println!(
+            "{}",
+            t("watch_started")
+                .replacen("{}", &hosts.len().to_string(), 1)
+                .replacen("{}", &interval.to_string(), 1)
+        );
+        let runtime = tokio::runtime::Runtime::new()?;
+        let mut tracker = StatusTracker::default();
+        loop {
+            let statuses = runtime.block_on(probe_round(&hosts, concurrency));
+            let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+            for (host, status) in hosts.iter().zip(&statuses) {
+                let Some(change) = tracker.observe(&host.host, status) else {
+                    continue;
+                };
+                println!("{}", change.format_line(&timestamp));
+                if let Some(hook) = &hook
+                    && change.is_transition()
+                {
+                    run_hook(hook, &change);
+                }
+            }
+            if !wait_interval(std::time::Duration::from_secs(interval), &stop) {
+                break;
+            }
+        }
+
+        println!();
+        println!("{}", t("watch_summary"));
+        for line in tracker.summary_lines() {
+            println!("  {}", line);
+        }
+        Ok(())
+    }
+
+    /// 测试单个主机的连通性；`auth` 时改为列出服务器接受的认证方式
+    fn test_command(&mut self, name: &str, auth: bool) -> Result<()> {
+        let host =
+            self.config_manager
+                .get_host(name)?
+                .ok_or_else(|| SshConnError::HostNotFound {
+                    host: name.to_string(),
+                })?;
+        let host = self.config_manager.effective_host(&host);
+        let runtime = tokio::runtime::Runtime::new()?;
+
+        if !auth {
+            let statuses =
+                runtime.block_on(crate::watch::probe_round(std::slice::from_ref(&host), 1));
+            return match statuses.into_iter().next() {
+                Some(ConnectionStatus::Connected(d) | ConnectionStatus::SshReachable(d)) => {
+                    println!("✓ {}  {}ms", host.host, d.as_millis());
+                    Ok(())
+                }
+                Some(ConnectionStatus::Failed(reason)) => Err(SshConnError::Connection(reason)),
+                _ => Ok(()),
+            };
+        }
+
+        let argv = self
+            .config_manager
+            .build_ssh_command(&host, &CommandMode::AuthProbe)
+            .argv();
+        let probe = crate::network::NetworkProbe::for_auth_probe(&host);
+        let methods = runtime.block_on(probe.probe_auth_methods(&argv))?;
+        let AuthMethods::Offered(list) = &methods else {
+            println!("{}", t("test_auth_none_required").replace("{}", &host.host));
+            return Ok(());
+        };
+        println!(
+            "{}",
+            t("test_auth_methods")
+                .replacen("{}", &host.host, 1)
+                .replacen("{}", &list.join(", "), 1)
+        );
+        // 服务器只接受密钥等方式时，存储的密码或密码命令都派不上用场
+        let uses_password =
+            host.password_command.is_some() || self.config_manager.has_stored_password(&host.host);
+        if uses_password && !methods.offers_password() {
+            println!("⚠ {}", t("test_auth_no_password"));
+        }
+        Ok(())
+    }
+
+    /// 并发测试全部（或匹配的）主机一次，输出结果，并可写出指标文件
+    fn test_all_command(
+        &mut self,
+        query: Option<&str>,
+        concurrency: usize,
+        metrics_out: Option<&std::path::Path>,
+        metrics_format: MetricsFormat,
+    ) -> Result<()> {
+        use crate::metrics::{HostMetric, format_metrics, write_atomic};
+
+        let hosts: Vec<SshHost> = self
+            .config_manager
+            .get_hosts()?
+            .iter()
+            .filter(|host| !host.host.contains(['*', '?']))
+            .filter(|host| query.is_none_or(|query| host.matches_query(query)))
+            .cloned()
+            .collect();
+        if hosts.is_empty() {
+            return Err(SshConnError::InvalidArgument(t("error.test_no_hosts")));
+        }
+        let hosts: Vec<SshHost> = hosts
+            .iter()
+            .map(|host| self.config_manager.effective_host(host))
+            .collect();
+
+        let runtime = tokio::runtime::Runtime::new()?;
+        let statuses = runtime.block_on(crate::watch::probe_round(&hosts, concurrency));
+        let mut results = Vec::with_capacity(hosts.len());
+        for (host, status) in hosts.iter().zip(&statuses) {
+            match status {
+                ConnectionStatus::Connected(d) | ConnectionStatus::SshReachable(d) => {
+                    println!("✓ {}  {}ms", host.host, d.as_millis())
+                }
+                ConnectionStatus::Failed(reason) => println!("✗ {}  {}", host.host, reason),
+                ConnectionStatus::Unknown | ConnectionStatus::Connecting => {}
+            }
+            results.extend(HostMetric::from_status(&host.host, status));
+        }
+        let up = results.iter().filter(|r| r.up).count();
+        println!(
+            "{}",
+            t("test_all_summary")
+                .replacen("{}", &up.to_string(), 1)
+                .replacen("{}", &hosts.len().to_string(), 1)
+        );
+
+        if let Some(path) = metrics_out {
+            write_atomic(path, &format_metrics(&results, metrics_format))?;
+            output::info(t("metrics_written").replace("{}", &path.display().to_string()));
+        }
+        Ok(())
+    }
+
+    fn bench_command(
+        &mut self,
+        names: &[String],
+        rounds: u32,
+        ssh: bool,
+        json: bool,
+    ) -> Result<()> {
+        let mut hosts = Vec::with_capacity(names.len());
+        for name in names {
+            let host = self
+                .config_manager
+                .get_host(name)?
+                .ok_or_else(|| SshConnError::HostNotFound { host: name.clone() })?;
+            hosts.push(self.config_manager.effective_host(&host));
+        }
+
+        let runtime = tokio::runtime::Runtime::new()?;
+        let mut results = Vec::with_capacity(hosts.len());
+        for host in &hosts {
+            output::info(t("bench.running").replacen("{}", &host.host, 1).replacen(
+                "{}",
+                &rounds.to_string(),
+                1,
+            ));
+            let argv = ssh.then(|| {
+                self.config_manager
+                    .build_ssh_command(host, &CommandMode::Probe)
+                    .argv()
+            });
+            results.push(runtime.block_on(crate::bench::bench_host(host, rounds, argv.as_deref())));
+        }
+
+        if json {
+            println!("{}", crate::bench::format_json(&results));
+        } else {
+            println!("{}", crate::bench::format_table(&results));
+        }
+        Ok(())
+    }
+
+    /// 输出主机清单统计
+    fn print_stats(&mut self, json: bool) -> Result<()> {
+        let password_hosts = self.config_manager.stored_password_hosts();
+        let stats = compute_stats(self.config_manager.get_hosts()?, &password_hosts);
+
+        if json {
+            let output = serde_json::to_string_pretty(&stats).map_err(|e| {
+                SshConnError::ConfigParse(format!("{}: {}", t("serialization_failed"), e))
+            })?;
+            println!("{}", output);
+        } else {
+            println!("{}", format_stats_table(&stats));
+        }
+        Ok(())
+    }
+
+    /// 备份配置，可指定目标路径、压缩，并可附带加密的密码导出
+    ///
+    /// 密码导出写到 `<备份文件>.passwords.enc`
+    fn backup_config(
+        &self,
+        output: Option<std::path::PathBuf>,
+        options: BackupOptions,
+        include_passwords: bool,
+    ) -> Result<()> {
+        // 先取得口令，避免写了配置备份后才发现无法导出密码
+        let passphrase = if include_passwords {
+            Some(backup_passphrase(&mut TerminalPrompter)?)
+        } else {
+            None
+        };
+
+        // 路径加了引号时shell不会展开 ~ 和环境变量，这里统一展开
+        let dest = output
+            .map(|path| crate::utils::expand_path(&path.to_string_lossy()))
+            .unwrap_or_else(|| self.config_manager.default_backup_path());
+        let outcome = self.config_manager.backup_config_to(&dest, &options)?;
+        println!(
+            "✓ {}: {} ({})",
+            t("config_backup_success"),
+            outcome.path.display(),
+            t("backup_size").replace("{}", &outcome.size.to_string())
+        );
+
+        if let Some(passphrase) = passphrase {
+            let mut dest = outcome.path.into_os_string();
+            dest.push(crate::config::PASSWORD_EXPORT_SUFFIX);
+            let outcome = self.config_manager.backup_passwords_to(
+                std::path::Path::new(&dest),
+                &passphrase,
+                &options,
+            )?;
+            println!(
+                "✓ {}: {} ({})",
+                t("backup_passwords_success"),
+                outcome.path.display(),
+                t("backup_size").replace("{}", &outcome.size.to_string())
+            );
+        }
+        Ok(())
+    }
+
+    /// 用备份替换当前配置，替换前会自动备份当前配置
+    fn restore_config(
+        &mut self,
+        backup: Option<std::path::PathBuf>,
+        latest_auto: bool,
+        diff: bool,
+        yes: bool,
+    ) -> Result<()> {
+        let backup = match backup {
+            Some(path) => crate::utils::expand_path(&path.to_string_lossy()),
+            None if latest_auto => self
+                .config_manager
+                .latest_auto_backup()
+                .ok_or_else(|| SshConnError::InvalidArgument(t("error.no_auto_backup")))?,
+            None => return Err(SshConnError::InvalidArgument(t("error.restore_no_backup"))),
+        };
+        let content = crate::config::read_backup(&backup)?;
+
+        println!(
+            "{}",
+            t("restore_source").replace("{}", &backup.display().to_string())
+        );
+        let interactive = std::io::IsTerminal::is_terminal(&std::io::stdin());
+        // 交互式恢复默认先展示差异
+        if diff || (!yes && interactive) {
+            self.print_restore_diff(&backup, &content)?;
+        }
+        if !yes {
+            if !interactive {
+                return Err(SshConnError::InvalidArgument(t(
+                    "error.restore_requires_yes",
+                )));
+            }
+            if !confirm(&t("restore_confirm").replace("{}", self.config_manager.config_path()))? {
+                println!("{}", t("bulk_edit_cancelled"));
+                return Ok(());
+            }
+        }
+
+        self.config_manager.restore_config(&content)?;
+        output::info(format_args!("✓ {}", t("restore_success")));
+        Ok(())
+    }
+
+    /// 显示备份与当前配置的差异（恢复后的内容为新内容）
+    fn print_restore_diff(&self, backup: &std::path::Path, content: &str) -> Result<()> {
+        let current = self.config_manager.config_content()?;
+        print_config_diff(
+            self.config_manager.config_path(),
+            &current,
+            &backup.display().to_string(),
+            content,
+        );
+        Ok(())
+    }
+
+    /// 比较指定文件（默认最近一次备份）与当前配置，列出此后的变化
+    fn diff_command(&self, file: Option<std::path::PathBuf>, json: bool) -> Result<()> {
+        let file = match file {
+            Some(file) => file,
+            None => self
+                .config_manager
+                .latest_backup()
+                .ok_or_else(|| SshConnError::InvalidArgument(t("error.no_backup")))?,
+        };
+        let old = crate::config::read_backup(&file)?;
+        let current = self.config_manager.config_content()?;
+
+        if json {
+            let summary = crate::config::diff_config_hosts(&old, &current);
+            let value = serde_json::json!({
+                "from": file.display().to_string(),
+                "to": self.config_manager.config_path(),
+                "added": summary.added,
+                "removed": summary.removed,
+                "modified": summary.modified,
+            });
+            let output = serde_json::to_string_pretty(&value).map_err(|e| {
+                SshConnError::ConfigParse(format!("{}: {}", t("serialization_failed"), e))
+            })?;
+            println!("{}", output);
+        } else {
+            print_config_diff(
+                &file.display().to_string(),
+                &old,
+                self.config_manager.config_path(),
+                &current,
+            );
+        }
         Ok(())
     }
 
     /// 添加主机命令
+    #[allow(clippy::too_many_arguments)]
     fn add_host_command(
         &mut self,
-        host: String,
-        hostname: String,
+        host: Option<String>,
+        hostname: Option<String>,
+        interactive: bool,
         user: Option<String>,
         port: Option<u16>,
         proxy_command: Option<String>,
         identity_file: Option<String>,
+        remote_command: Option<String>,
+        request_tty: Option<String>,
+        set_env: Vec<(String, String)>,
+        network: NetworkArgs,
+        crypto: CryptoArgs,
+        template: Option<String>,
     ) -> Result<()> {
-        self.config_manager.add_host(
-            &host,
-            &hostname,
-            user.as_deref(),
-            port,
-            proxy_command.as_deref(),
-            identity_file.as_deref(),
-            None, // 命令行模式下不设置密码
-        )?;
+        let interactive = interactive || host.is_none();
+        if !interactive && hostname.is_none() {
+            return Err(SshConnError::InvalidArgument(t(
+                "error.add_hostname_required",
+            )));
+        }
+        let mut entry = SshHost::new(host.unwrap_or_default());
+        entry.hostname = hostname;
+        entry.user = user;
+        entry.port = port.map(|p| p.to_string());
+        entry.proxy_command = proxy_command;
+        entry.identity_file = identity_file;
+        entry.remote_command = remote_command;
+        entry.request_tty = request_tty;
+        entry.set_env = set_env;
+        network.apply(&mut entry);
+        crypto.apply(&mut entry);
+
+        // 命令行中明确指定的字段优先于模板
+        if let Some(name) = template {
+            self.config_manager
+                .settings()
+                .template(&name)?
+                .apply(&mut entry);
+        }
+
+        // 命令行模式下只有交互输入时设置密码
+        let mut password = None;
+        if interactive {
+            let existing: Vec<String> = self
+                .config_manager
+                .get_hosts()?
+                .iter()
+                .map(|h| h.host.clone())
+                .collect();
+            match host_wizard(&mut TerminalPrompter, &entry, |name| {
+                existing.iter().any(|h| h == name)
+            })? {
+                Some(answers) => {
+                    entry = answers.host;
+                    password = answers.password;
+                }
+                None => {
+                    println!("{}", t("bulk_edit_cancelled"));
+                    return Ok(());
+                }
+            }
+        }
+
+        let targets =
+            expand_add_targets(&entry.host, entry.hostname.as_deref().unwrap_or_default())?;
+        if targets.len() > 1 {
+            return self.add_host_range(&entry, &targets);
+        }
+
+        self.config_manager
+            .add_host_entry(&entry, password.as_deref())?;
+        warn_legacy_crypto(&entry);
+
+        output::info(format_args!(
+            "✓ {}: {}",
+            t("success_add_server"),
+            entry.host
+        ));
+        Ok(())
+    }
+
+    /// 按展开的区间添加多台设置相同的主机
+    ///
+    /// 先检查所有名称都不存在，避免只添加了一部分
+    fn add_host_range(&mut self, entry: &SshHost, targets: &[(String, String)]) -> Result<()> {
+        for (host, _) in targets {
+            if self.config_manager.host_exists(host)? {
+                return Err(SshConnError::HostAlreadyExists { host: host.clone() });
+            }
+        }
+
+        for (host, hostname) in targets {
+            let mut entry = entry.clone();
+            entry.host = host.clone();
+            entry.hostname = Some(hostname.clone());
+            self.config_manager.add_host_entry(&entry, None)?;
+            output::info(format_args!(
+                "✓ {}: {} ({})",
+                t("success_add_server"),
+                host,
+                hostname
+            ));
+        }
+        warn_legacy_crypto(entry);
+        Ok(())
+    }
+
+    /// 逐项交互编辑主机，确认后只改写有变化的指令
+    fn edit_host_interactive(&mut self, host: &str, prompter: &mut dyn Prompter) -> Result<()> {
+        let current =
+            self.config_manager
+                .get_host(host)?
+                .ok_or_else(|| SshConnError::HostNotFound {
+                    host: host.to_string(),
+                })?;
 
-        println!("✓ {}: {}", t("success_add_server"), host);
+        let Some(answers) = edit_wizard(prompter, &current)? else {
+            println!("{}", t("bulk_edit_cancelled"));
+            return Ok(());
+        };
+        if current.diff_lines(&answers.host).is_empty() && answers.password.is_none() {
+            println!("{}", t("wizard_no_changes"));
+            return Ok(());
+        }
+
+        self.config_manager
+            .update_host(&answers.host, answers.password.as_deref())?;
+        warn_legacy_crypto(&answers.host);
+
+        output::info(format_args!("✓ {}: {}", t("success_update_server"), host));
+        Ok(())
+    }
+
+    /// 在只包含编辑表单的TUI中编辑主机
+    fn edit_host_form(&mut self, host: &str) -> Result<()> {
+        let mut ui_manager = UiManager::new(self.config_manager.clone());
+        if ui_manager.run_edit_form(host)? {
+            output::info(format_args!("✓ {}: {}", t("success_update_server"), host));
+        } else {
+            println!("{}", t("bulk_edit_cancelled"));
+        }
         Ok(())
     }
 
     /// 编辑主机命令
+    #[allow(clippy::too_many_arguments)]
     fn edit_host_command(
         &mut self,
         host: String,
@@ -233,7 +2497,76 @@ impl CliApp {
         port: Option<u16>,
         proxy_command: Option<String>,
         identity_file: Option<String>,
+        remote_command: Option<String>,
+        request_tty: Option<String>,
+        set_env: Vec<(String, String)>,
+        network: NetworkArgs,
+        crypto: CryptoArgs,
+        clear: &[OptionalField],
+        yes: bool,
     ) -> Result<()> {
+        // 同一字段不能既设置新值又清除
+        let set_fields = [
+            (OptionalField::User, user.is_some()),
+            (OptionalField::Port, port.is_some()),
+            (OptionalField::ProxyCommand, proxy_command.is_some()),
+            (OptionalField::IdentityFile, identity_file.is_some()),
+            (OptionalField::RemoteCommand, remote_command.is_some()),
+            (OptionalField::RequestTty, request_tty.is_some()),
+            (OptionalField::SetEnv, !set_env.is_empty()),
+            (
+                OptionalField::AddressFamily,
+                network.address_family.is_some(),
+            ),
+            (OptionalField::BindAddress, network.bind_address.is_some()),
+        ];
+        if let Some((field, _)) = set_fields
+            .iter()
+            .find(|(field, is_set)| *is_set && clear.contains(field))
+        {
+            return Err(SshConnError::InvalidArgument(
+                t("error.field_set_and_cleared").replace("{}", field.name()),
+            ));
+        }
+        // 指定了 --set-env 时整体替换原有的SetEnv
+        let set_env = (!set_env.is_empty()).then_some(set_env.as_slice());
+        let options: Vec<(&str, &str)> = network
+            .options()
+            .into_iter()
+            .chain(crypto.options())
+            .collect();
+
+        // 在终端中先显示配置块的变化并确认；脚本中（标准输入不是终端）直接保存
+        if !yes && std::io::IsTerminal::is_terminal(&std::io::stdin()) {
+            let current = self
+                .config_manager
+                .get_host(&host)?
+                .ok_or_else(|| SshConnError::HostNotFound { host: host.clone() })?;
+            let updated = self.config_manager.preview_edit_host(
+                &host,
+                hostname.as_deref(),
+                user.as_deref(),
+                port,
+                proxy_command.as_deref(),
+                identity_file.as_deref(),
+                remote_command.as_deref(),
+                request_tty.as_deref(),
+                set_env,
+                clear,
+                &options,
+            )?;
+            let diff = current.config_diff(&updated);
+            if diff.is_empty() {
+                println!("{}", t("wizard_no_changes"));
+                return Ok(());
+            }
+            print!("{}", diff);
+            if !confirm(&t("edit_diff_confirm"))? {
+                println!("{}", t("bulk_edit_cancelled"));
+                return Ok(());
+            }
+        }
+
         self.config_manager.edit_host(
             &host,
             hostname.as_deref(),
@@ -241,22 +2574,284 @@ impl CliApp {
             port,
             proxy_command.as_deref(),
             identity_file.as_deref(),
+            remote_command.as_deref(),
+            request_tty.as_deref(),
+            set_env,
             None, // 命令行模式下不设置密码
+            clear,
         )?;
+        for (keyword, value) in options {
+            self.config_manager.set_host_option(&host, keyword, value)?;
+        }
+        if let Some(entry) = self.config_manager.get_host(&host)? {
+            warn_legacy_crypto(&entry);
+        }
 
-        println!("✓ {}: {}", t("success_update_server"), host);
+        output::info(format_args!("✓ {}: {}", t("success_update_server"), host));
+        Ok(())
+    }
+
+    /// 检查所有主机配置中启用的弱加密算法
+    fn validate_hosts(&mut self) -> Result<()> {
+        for issue in self.config_manager.permission_issues() {
+            println!("⚠ {}", issue.message());
+        }
+
+        let mut flagged = 0;
+        for host in self.config_manager.get_hosts()? {
+            let warnings = host.legacy_crypto_warnings();
+            if warnings.is_empty() {
+                continue;
+            }
+            flagged += 1;
+            println!("⚠ {} [{}]", host.host, t("cli_labels.legacy_crypto"));
+            for warning in warnings {
+                println!("    {}", warning);
+            }
+        }
+
+        if flagged == 0 {
+            println!("✓ {}", t("validate_no_issues"));
+        } else {
+            println!(
+                "{}",
+                t("validate_legacy_crypto_summary").replace("{}", &flagged.to_string())
+            );
+        }
+        Ok(())
+    }
+
+    /// 检查运行环境：SSH配置文件是否存在、ssh-agent是否可用及已载入的密钥数量、`~/.ssh` 中的权限
+    ///
+    /// `fix` 时确认后收紧过宽的权限；标准输入不是终端时必须指定 `--yes`
+    fn doctor_command(&mut self, fix: bool, yes: bool) -> Result<()> {
+        let config_path = self.config_manager.config_path().to_string();
+        if std::path::Path::new(&config_path).is_file() {
+            let count = self.config_manager.list_hosts()?.len();
+            println!(
+                "✓ {}",
+                t("doctor_config").replacen("{}", &config_path, 1).replacen(
+                    "{}",
+                    &count.to_string(),
+                    1
+                )
+            );
+        } else {
+            println!(
+                "⚠ {}",
+                t("doctor_config_missing").replace("{}", &config_path)
+            );
+        }
+
+        match crate::agent::agent_status() {
+            AgentStatus::Running(keys) => println!(
+                "✓ {}",
+                t("doctor_agent_running").replace("{}", &keys.len().to_string())
+            ),
+            AgentStatus::NotConfigured => println!("⚠ {}", t("doctor_agent_not_configured")),
+            AgentStatus::Unreachable => println!("✗ {}", t("doctor_agent_unreachable")),
+        }
+
+        let issues = self.config_manager.permission_issues();
+        if issues.is_empty() {
+            println!("✓ {}", t("doctor_permissions_ok"));
+            return Ok(());
+        }
+        for issue in &issues {
+            println!("⚠ {}", issue.message());
+        }
+        if !fix {
+            println!("{}", t("doctor_permissions_fix_hint"));
+            return Ok(());
+        }
+
+        if !yes {
+            if !std::io::IsTerminal::is_terminal(&std::io::stdin()) {
+                return Err(SshConnError::InvalidArgument(t(
+                    "error.doctor_fix_requires_yes",
+                )));
+            }
+            if !confirm(&t("doctor_permissions_confirm"))? {
+                println!("{}", t("bulk_edit_cancelled"));
+                return Ok(());
+            }
+        }
+        for issue in &issues {
+            issue
+                .fix()
+                .map_err(|e| SshConnError::file_access(&issue.path, e))?;
+            println!(
+                "✓ {}",
+                t("doctor_permissions_fixed")
+                    .replacen("{}", &issue.path.display().to_string(), 1)
+                    .replacen("{}", &format!("{:o}", issue.mode & issue.expected), 1)
+            );
+        }
+        Ok(())
+    }
+
+    /// 合并同名的Host块并报告取值冲突
+    fn dedupe_command(&mut self, dry_run: bool) -> Result<()> {
+        let result = if dry_run {
+            crate::config::merge_duplicate_blocks(&self.config_manager.config_content()?)
+        } else {
+            let (result, backup_path) = self.config_manager.dedupe_hosts()?;
+            if let Some(path) = backup_path {
+                output::info(format_args!("✓ {}: {}", t("config_backup_success"), path));
+            }
+            result
+        };
+
+        if result.merged.is_empty() {
+            println!("✓ {}", t("dedupe_none"));
+            return Ok(());
+        }
+        for (host, blocks) in &result.merged {
+            println!(
+                "↻ {}",
+                t("dedupe_merged")
+                    .replacen("{}", host, 1)
+                    .replacen("{}", &blocks.to_string(), 1)
+            );
+            for conflict in result.conflicts.iter().filter(|c| &c.host == host) {
+                println!(
+                    "    ! {}",
+                    t("dedupe_conflict")
+                        .replacen("{}", &conflict.option, 1)
+                        .replacen("{}", &conflict.discarded, 1)
+                        .replacen("{}", &conflict.kept, 1)
+                );
+            }
+        }
+        if dry_run {
+            println!("{}", t("dedupe_dry_run"));
+        }
         Ok(())
     }
 
     /// 删除主机命令
-    fn delete_host_command(&mut self, host: String) -> Result<()> {
-        self.config_manager.delete_host(&host)?;
-        println!("✓ {}: {}", t("success_delete_server"), host);
+    ///
+    /// 终端中先显示主机摘要并确认；标准输入不是终端时必须指定 `--yes`，避免管道中误删
+    fn delete_host_command(&mut self, host: String, yes: bool, keep_password: bool) -> Result<()> {
+        let ssh_host = self
+            .config_manager
+            .get_host(&host)?
+            .ok_or_else(|| SshConnError::HostNotFound { host: host.clone() })?;
+
+        if !yes {
+            if !std::io::IsTerminal::is_terminal(&std::io::stdin()) {
+                return Err(SshConnError::InvalidArgument(t(
+                    "error.delete_requires_yes",
+                )));
+            }
+
+            println!("{}", self.format_host_info(&ssh_host));
+            let password = match (
+                self.config_manager.has_stored_password(&host),
+                keep_password,
+            ) {
+                (false, _) => t("delete_password_none"),
+                (true, false) => t("delete_password_deleted"),
+                (true, true) => t("delete_password_kept"),
+            };
+            println!("  {}: {}", t("cli_labels.stored_password"), password);
+            if !confirm(&t("delete_confirm_prompt").replace("{}", &host))? {
+                println!("{}", t("bulk_edit_cancelled"));
+                return Ok(());
+            }
+        }
+
+        self.config_manager.remove_host(&host, keep_password)?;
+        output::info(format_args!("✓ {}: {}", t("success_delete_server"), host));
         Ok(())
     }
 
+    /// 删除多台主机：列出全部目标、确认一次、备份一次后逐台删除
+    ///
+    /// `type_word` 为true时需要输入单词 `delete` 而不是 `y` 才会继续；
+    /// 任一主机删除失败时继续处理其余主机，最后返回第一个失败的错误
+    fn delete_hosts_command(
+        &mut self,
+        targets: &[String],
+        yes: bool,
+        keep_password: bool,
+        type_word: bool,
+    ) -> Result<()> {
+        let hosts = self.config_manager.get_hosts()?.to_vec();
+        let password_hosts = self.config_manager.stored_password_hosts();
+
+        println!(
+            "{}",
+            t("delete_many_preview").replace("{}", &targets.len().to_string())
+        );
+        for name in targets {
+            let line = match hosts.iter().find(|h| &h.host == name) {
+                Some(host) => {
+                    let address = match (&host.user, &host.hostname) {
+                        (Some(user), Some(hostname)) => format!("{}@{}", user, hostname),
+                        (None, Some(hostname)) => hostname.clone(),
+                        _ => String::new(),
+                    };
+                    let password = if password_hosts.contains(name) {
+                        format!(" [{}]", t("cli_labels.stored_password"))
+                    } else {
+                        String::new()
+                    };
+                    format!("  {} {}{}", name, address, password)
+                }
+                None => format!("  {} ({})", name, t("delete_many_not_found")),
+            };
+            println!("{}", line.trim_end());
+        }
+
+        if !yes {
+            if !std::io::IsTerminal::is_terminal(&std::io::stdin()) {
+                return Err(SshConnError::InvalidArgument(t(
+                    "error.delete_requires_yes",
+                )));
+            }
+            let confirmed = if type_word {
+                confirm_word(&t("delete_type_word_confirm"), "delete")?
+            } else {
+                confirm(&t("delete_many_confirm"))?
+            };
+            if !confirmed {
+                println!("{}", t("bulk_edit_cancelled"));
+                return Ok(());
+            }
+        }
+
+        // 备份失败时整个操作中止，不删除任何主机
+        let backup_path = self.config_manager.backup_config()?;
+        output::info(format_args!(
+            "✓ {}: {}",
+            t("config_backup_success"),
+            backup_path
+        ));
+
+        let mut first_error = None;
+        let mut failed = 0;
+        for name in targets {
+            match self.config_manager.remove_host(name, keep_password) {
+                Ok(()) => println!("✓ {}", name),
+                Err(e) => {
+                    failed += 1;
+                    eprintln!("✗ {}: {}", name, e);
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        println!(
+            "{}",
+            t("delete_many_summary")
+                .replacen("{}", &(targets.len() - failed).to_string(), 1)
+                .replacen("{}", &failed.to_string(), 1)
+        );
+        first_error.map_or(Ok(()), Err)
+    }
+
     /// 格式化主机信息显示
-    fn format_host_info(&self, host: &crate::models::SshHost) -> String {
+    fn format_host_info(&self, host: &SshHost) -> String {
         let mut lines = vec![format!("{}: {}", t("cli_labels.host"), host.host)];
 
         if let Some(hostname) = &host.hostname {
@@ -279,6 +2874,600 @@ impl CliApp {
             lines.push(format!("  {}: {}", t("cli_labels.identity_file"), identity));
         }
 
+        if let Some(remote_command) = &host.remote_command {
+            lines.push(format!(
+                "  {}: {}",
+                t("cli_labels.remote_command"),
+                remote_command
+            ));
+        }
+
+        if let Some(request_tty) = &host.request_tty {
+            lines.push(format!(
+                "  {}: {}",
+                t("cli_labels.request_tty"),
+                request_tty
+            ));
+        }
+
+        if !host.set_env.is_empty() {
+            lines.push(format!(
+                "  {}: {}",
+                t("cli_labels.set_env"),
+                host.set_env_string()
+            ));
+        }
+
+        for (keyword, value) in host
+            .address_options()
+            .into_iter()
+            .chain(host.local_command_options())
+            .chain(host.crypto_options())
+        {
+            if let Some(value) = value {
+                lines.push(format!("  {}: {}", keyword, value));
+            }
+        }
+
+        let warnings = host.legacy_crypto_warnings();
+        if !warnings.is_empty() {
+            lines[0] = format!("{}  ⚠ {}", lines[0], t("cli_labels.legacy_crypto"));
+        }
+
         lines.join("\n")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::password::PasswordManager;
+
+    fn manager_with_config(dir: &tempfile::TempDir, config: &str) -> ConfigManager {
+        let config_path = dir.path().join("config");
+        std::fs::write(&config_path, config).unwrap();
+        let password_manager = PasswordManager::with_path(dir.path().join("passwords.db")).unwrap();
+        ConfigManager::with_path(config_path, password_manager).unwrap()
+    }
+
+    fn fixture_hosts() -> Vec<SshHost> {
+        let host = |name: &str, hostname: Option<&str>, user: Option<&str>, port: Option<&str>| {
+            let mut h = SshHost::new(name.to_string());
+            h.hostname = hostname.map(str::to_string);
+            h.user = user.map(str::to_string);
+            h.port = port.map(str::to_string);
+            h
+        };
+
+        let mut web1 = host("web1", Some("10.0.0.1"), Some("deploy"), None);
+        web1.identity_file = Some("~/.ssh/deploy".to_string());
+        let mut web2 = host("web2", Some("10.0.0.2"), Some("deploy"), Some("2222"));
+        web2.identity_file = Some("~/.ssh/deploy".to_string());
+        web2.custom_options
+            .insert("ProxyJump".to_string(), "bastion".to_string());
+        let mut db = host("db", Some("10.0.0.3"), Some("root"), Some("2222"));
+        db.proxy_command = Some("ssh -W %h:%p bastion".to_string());
+        let alias = host("alias", None, None, None);
+
+        vec![web1, web2, db, alias]
+    }
+
+    #[test]
+    fn test_edit_host_interactive_updates_block() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut app = CliApp::new(manager_with_config(
+            &dir,
+            "Host web\n    # 生产环境\n    HostName 10.0.0.5\n    User deploy\n    Port 2222\n    \
+             ConnectTimeout 5\n    ProxyJump bastion\n    ForwardAgent yes\n\n\
+             Host db\n    HostName 10.0.0.7\n",
+        ));
+
+        let mut prompter = crate::prompt::ScriptedPrompter::new(&[
+            "",       // HostName 保留
+            "admin",  // User 替换
+            "-",      // Port 清除
+            "",       // ProxyCommand
+            "",       // IdentityFile
+            "0",      // ConnectTimeout 无效
+            "10",     // ConnectTimeout 替换
+            "",       // ServerAliveInterval
+            "",       // RemoteCommand
+            "",       // RequestTTY
+            "",       // AddressFamily
+            "",       // BindAddress
+            "LANG=C", // SetEnv
+            "-",      // ForwardAgent 清除
+            "jump2",  // ProxyJump 替换
+            "",       // 密码保留
+            "y",      // 确认
+        ]);
+        app.edit_host_interactive("web", &mut prompter).unwrap();
+
+        assert!(
+            prompter
+                .output
+                .contains(&"ConnectTimeout [5]: ".to_string())
+        );
+        assert!(prompter.output.contains(&"  - Port 2222".to_string()));
+        assert!(prompter.output.contains(&"  + User admin".to_string()));
+
+        let config = std::fs::read_to_string(dir.path().join("config")).unwrap();
+        assert_eq!(
+            config,
+            "Host web\n    # 生产环境\n    HostName 10.0.0.5\n    User admin\n    \
+             ConnectTimeout 10\n    ProxyJump jump2\n    SetEnv LANG=C\n\n\
+             Host db\n    HostName 10.0.0.7\n"
+        );
+    }
+
+    #[test]
+    fn test_edit_host_interactive_cancel_leaves_config() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let original = "Host web\n    HostName 10.0.0.5\n    User deploy\n";
+        let mut app = CliApp::new(manager_with_config(&dir, original));
+
+        // 输入中途结束
+        let mut prompter = crate::prompt::ScriptedPrompter::new(&["", "admin"]);
+        app.edit_host_interactive("web", &mut prompter).unwrap();
+        // 不确认
+        let answers = ["", "admin", "", "", "", "", "", "", "", "", "", "n"];
+        let mut prompter = crate::prompt::ScriptedPrompter::new(&answers);
+        app.edit_host_interactive("web", &mut prompter).unwrap();
+
+        let config = std::fs::read_to_string(dir.path().join("config")).unwrap();
+        assert_eq!(config, original);
+    }
+
+    #[test]
+    fn test_compute_stats() {
+        let passwords = vec!["db".to_string(), "removed-host".to_string()];
+        let stats = compute_stats(&fixture_hosts(), &passwords);
+
+        let counts = |list: &[ValueCount]| -> Vec<(String, usize)> {
+            list.iter().map(|c| (c.value.clone(), c.count)).collect()
+        };
+        assert_eq!(stats.total, 4);
+        assert_eq!(
+            counts(&stats.users),
+            [
+                ("deploy".to_string(), 2),
+                ("(default)".to_string(), 1),
+                ("root".to_string(), 1)
+            ]
+        );
+        assert_eq!(
+            counts(&stats.ports),
+            [("(default)".to_string(), 2), ("2222".to_string(), 2)]
+        );
+        assert_eq!(
+            counts(&stats.identity_files),
+            [
+                ("(default)".to_string(), 2),
+                ("~/.ssh/deploy".to_string(), 2)
+            ]
+        );
+        assert_eq!(stats.proxied, 2);
+        // 不在配置中的密码不计入
+        assert_eq!(stats.stored_passwords, 1);
+        assert_eq!(stats.missing_hostname, 1);
+
+        assert_eq!(compute_stats(&[], &[]), InventoryStats::default());
+    }
+
+    #[test]
+    fn test_stats_output_formats() {
+        let stats = compute_stats(&fixture_hosts(), &[]);
+
+        let json: serde_json::Value = serde_json::to_value(&stats).unwrap();
+        assert_eq!(json["total"], 4);
+        assert_eq!(json["users"][0]["value"], "deploy");
+        assert_eq!(json["users"][0]["count"], 2);
+
+        // 表格中数量列右对齐在同一位置
+        let table = format_stats_table(&stats);
+        let widths: Vec<usize> = table
+            .lines()
+            .filter(|line| !line.is_empty() && !line.ends_with(':'))
+            .map(|line| line.chars().count())
+            .collect();
+        assert!(widths.windows(2).all(|w| w[0] == w[1]), "{table}");
+        assert!(table.contains("  deploy"));
+    }
+
+    #[test]
+    fn test_search_output_json() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut manager = manager_with_config(
+            &dir,
+            "Host web1\n    HostName 10.0.0.1\n    User deploy\n\n\
+             Host db\n    HostName 10.0.0.2\n\n\
+             Host web2\n    HostName 10.0.0.3\n    Port 2222\n",
+        );
+
+        let hosts = manager
+            .search_hosts_in_fields("web", SearchFields::all())
+            .unwrap();
+        let json = serialize_hosts(&hosts, 3, OutputFormat::Json)
+            .unwrap()
+            .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["count"], 2);
+        assert_eq!(value["total"], 3);
+        let parsed: Vec<SshHost> = serde_json::from_value(value["hosts"].clone()).unwrap();
+
+        let names: Vec<&str> = parsed.iter().map(|h| h.host.as_str()).collect();
+        assert_eq!(names, ["web1", "web2"]);
+        assert_eq!(parsed[0].user.as_deref(), Some("deploy"));
+        assert_eq!(parsed[1].port.as_deref(), Some("2222"));
+
+        let yaml = serialize_hosts(&hosts, 3, OutputFormat::Yaml)
+            .unwrap()
+            .unwrap();
+        let value: serde_yaml::Value = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(value["count"].as_u64(), Some(2));
+        let parsed: Vec<SshHost> = serde_yaml::from_value(value["hosts"].clone()).unwrap();
+        assert_eq!(parsed, hosts);
+
+        // 没有匹配结果时输出空数组
+        let json = serialize_hosts(&[], 3, OutputFormat::Json)
+            .unwrap()
+            .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["count"], 0);
+        assert_eq!(value["hosts"], serde_json::json!([]));
+        assert!(
+            serialize_hosts(&hosts, 3, OutputFormat::Text)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_export_host_config_format() {
+        let mut host = SshHost::new("web".to_string());
+        host.hostname = Some("10.0.0.5".to_string());
+        host.user = Some("deploy".to_string());
+        host.port = Some("2222".to_string());
+        host.proxy_command = Some("ssh -W %h:%p bastion".to_string());
+        host.identity_file = Some("~/.ssh/id_ed25519".to_string());
+        host.connect_timeout = Some("10".to_string());
+        host.server_alive_interval = Some("60".to_string());
+        host.remote_command = Some("tmux new -A -s main".to_string());
+        host.request_tty = Some("yes".to_string());
+        host.address_family = Some("inet".to_string());
+        host.bind_address = Some("192.168.1.10".to_string());
+        host.ciphers = Some("aes256-gcm@openssh.com".to_string());
+        host.set_env = vec![("LANG".to_string(), "C".to_string())];
+        host.custom_options
+            .insert("Compression".to_string(), "yes".to_string());
+        host.password_command = Some("pass show web".to_string());
+        host.source_file = Some(std::path::PathBuf::from("/home/me/.ssh/config"));
+
+        let exported = export_host(&host, true, OutputFormat::Text).unwrap();
+        let (note, block) = exported.split_once('\n').unwrap();
+        assert_eq!(note, format!("# {}", t("show_password_stored")));
+        assert_eq!(block, host.to_config_format());
+        // 导出的配置块可以原样解析回同一台主机
+        let parsed: SshHost = block.parse().unwrap();
+        assert_eq!(
+            parsed,
+            SshHost {
+                source_file: None,
+                ..host.clone()
+            }
+        );
+        assert!(!exported.contains("/home/me"));
+
+        let exported = export_host(&host, false, OutputFormat::Text).unwrap();
+        assert!(exported.starts_with(&format!("# {}\n", t("show_no_password"))));
+
+        let json = export_host(&host, true, OutputFormat::Json).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["host"], "web");
+        assert_eq!(value["port"], "2222");
+        assert_eq!(value["password_stored"], true);
+        assert!(value.get("source_file").is_none());
+
+        let yaml = export_host(&host, false, OutputFormat::Yaml).unwrap();
+        let value: serde_yaml::Value = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(value["user"].as_str(), Some("deploy"));
+        assert_eq!(value["password_stored"].as_bool(), Some(false));
+    }
+
+    #[test]
+    fn test_count_summary() {
+        let servers = |n: usize| t("cli_labels.count_servers").replace("{}", &n.to_string());
+        assert_eq!(count_summary(0, None), servers(0));
+        assert_eq!(count_summary(12, None), servers(12));
+
+        let matched = count_summary(2, Some(5));
+        assert!(matched.contains('2') && matched.contains('5'), "{matched}");
+        assert!(matched.find('2') < matched.find('5'));
+        let none = count_summary(0, Some(0));
+        assert_eq!(none.matches('0').count(), 2, "{none}");
+        assert_ne!(count_summary(2, Some(5)), count_summary(5, Some(2)));
+    }
+
+    fn assign(text: &str) -> FieldAssignment {
+        FieldAssignment::parse(text).unwrap()
+    }
+
+    #[test]
+    fn test_field_assignment_parse() {
+        assert_eq!(
+            assign("User=svc-deploy"),
+            FieldAssignment {
+                field: EditableField::User,
+                value: "svc-deploy".to_string()
+            }
+        );
+        assert_eq!(assign("proxy-jump=bastion").field, EditableField::ProxyJump);
+        assert!(FieldAssignment::parse("hostname=10.0.0.1").is_err());
+        assert!(FieldAssignment::parse("port=0").is_err());
+        assert!(FieldAssignment::parse("connect_timeout=soon").is_err());
+        assert!(FieldAssignment::parse("user=").is_err());
+        assert!(FieldAssignment::parse("user").is_err());
+    }
+
+    #[test]
+    fn test_backup_passphrase_confirmation() {
+        use crate::prompt::ScriptedPrompter;
+
+        let mut prompter = ScriptedPrompter::new(&["s3cret", "s3cret"]);
+        assert_eq!(backup_passphrase(&mut prompter).unwrap().as_str(), "s3cret");
+
+        let mut prompter = ScriptedPrompter::new(&["s3cret", "typo"]);
+        assert!(backup_passphrase(&mut prompter).is_err());
+
+        let mut prompter = ScriptedPrompter::new(&["", ""]);
+        assert!(backup_passphrase(&mut prompter).is_err());
+    }
+
+    #[test]
+    fn test_set_key_passphrase_keeps_it_after_host_delete() {
+        use crate::prompt::ScriptedPrompter;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let key = dir.path().join("id_ed25519");
+        std::fs::write(&key, "key").unwrap();
+        let key = key.to_string_lossy().into_owned();
+        let mut app = CliApp::new(manager_with_config(&dir, ""));
+        let mut web = SshHost::new("web".to_string());
+        web.hostname = Some("10.0.0.5".to_string());
+        app.config_manager
+            .add_host_entry(&web, Some("host-secret"))
+            .unwrap();
+
+        let mut prompter = ScriptedPrompter::new(&["s3cret", "typo"]);
+        assert!(
+            app.password_command(
+                PasswordAction::SetKeyphrase { path: key.clone() },
+                &mut prompter
+            )
+            .is_err()
+        );
+        assert!(app.config_manager.stored_key_passphrases().is_empty());
+
+        let mut prompter = ScriptedPrompter::new(&["s3cret", "s3cret"]);
+        app.password_command(
+            PasswordAction::SetKeyphrase { path: key.clone() },
+            &mut prompter,
+        )
+        .unwrap();
+        app.config_manager.delete_host("web").unwrap();
+        assert!(app.config_manager.stored_password_hosts().is_empty());
+        assert_eq!(app.config_manager.stored_key_passphrases(), [key.as_str()]);
+
+        // 私钥文件不存在时拒绝
+        let missing = dir.path().join("missing").to_string_lossy().into_owned();
+        assert!(
+            app.password_command(
+                PasswordAction::SetKeyphrase { path: missing },
+                &mut ScriptedPrompter::new(&["a", "a"])
+            )
+            .is_err()
+        );
+
+        app.password_command(
+            PasswordAction::DeleteKeyphrase { path: key.clone() },
+            &mut prompter,
+        )
+        .unwrap();
+        assert!(
+            app.password_command(PasswordAction::DeleteKeyphrase { path: key }, &mut prompter)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_expand_add_targets() {
+        let targets = expand_add_targets("node[01-20]", "10.0.0.[1-20]").unwrap();
+        assert_eq!(targets.len(), 20);
+        assert_eq!(targets[0], ("node01".to_string(), "10.0.0.1".to_string()));
+        assert_eq!(targets[19], ("node20".to_string(), "10.0.0.20".to_string()));
+
+        assert_eq!(
+            expand_add_targets("web", "10.0.0.1").unwrap(),
+            [("web".to_string(), "10.0.0.1".to_string())]
+        );
+
+        let err = expand_add_targets("node[01-20]", "10.0.0.[1-10]").unwrap_err();
+        assert!(matches!(err, SshConnError::InvalidArgument(_)));
+        assert!(expand_add_targets("node[1-3]", "db.example.com").is_err());
+
+        // 超过上限时在展开之前报错
+        let err = expand_add_targets("node[1-99999999999]", "10.0.0.1").unwrap_err();
+        assert!(matches!(err, SshConnError::InvalidArgument(_)));
+        let err = expand_add_targets("node[1-100]", "10.[0-255].[0-255].1").unwrap_err();
+        assert!(matches!(err, SshConnError::InvalidArgument(_)));
+    }
+
+    #[test]
+    fn test_add_host_range() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut app = CliApp::new(manager_with_config(
+            &dir,
+            "Host node02\n    HostName 10.0.0.2\n",
+        ));
+        let mut entry = SshHost::new("node[01-03]".to_string());
+        entry.user = Some("admin".to_string());
+        let targets = expand_add_targets(&entry.host, "10.0.0.[1-3]").unwrap();
+
+        // 有一个名称已存在时不添加任何主机
+        assert!(matches!(
+            app.add_host_range(&entry, &targets),
+            Err(SshConnError::HostAlreadyExists { .. })
+        ));
+        assert_eq!(app.config_manager.get_hosts().unwrap().len(), 1);
+
+        app.config_manager.delete_host("node02").unwrap();
+        app.add_host_range(&entry, &targets).unwrap();
+        let hosts = app.config_manager.get_hosts().unwrap();
+        assert_eq!(hosts.len(), 3);
+        assert_eq!(hosts[2].host, "node03");
+        assert_eq!(hosts[2].hostname.as_deref(), Some("10.0.0.3"));
+        assert_eq!(hosts[2].user.as_deref(), Some("admin"));
+    }
+
+    #[test]
+    fn test_select_delete_targets() {
+        let mut hosts = fixture_hosts();
+        hosts.push(SshHost::new("*".to_string()));
+
+        let names = vec!["web1".to_string(), "gone".to_string(), "web1".to_string()];
+        assert_eq!(
+            select_delete_targets(&hosts, DeleteSelector::Names(&names)).unwrap(),
+            ["web1", "gone"]
+        );
+
+        assert_eq!(
+            select_delete_targets(&hosts, DeleteSelector::Query("web")).unwrap(),
+            ["web1", "web2"]
+        );
+        // 空查询匹配全部主机，但不包括通配符块
+        assert_eq!(
+            select_delete_targets(&hosts, DeleteSelector::Query("")).unwrap(),
+            ["web1", "web2", "db", "alias"]
+        );
+
+        let err = select_delete_targets(&hosts, DeleteSelector::Query("nope")).unwrap_err();
+        assert!(matches!(err, SshConnError::InvalidArgument(_)));
+    }
+
+    #[test]
+    fn test_select_delete_targets_glob() {
+        let mut hosts = fixture_hosts();
+        hosts.push(SshHost::new("*".to_string()));
+        hosts.push(SshHost::new("web-*".to_string()));
+
+        let patterns = ["web?".to_string(), "d*".to_string()];
+        assert_eq!(
+            select_delete_targets(&hosts, DeleteSelector::Glob(&patterns)).unwrap(),
+            ["web1", "web2", "db"]
+        );
+
+        // `*` 模式匹配所有具体主机，但永远不会选中配置中的通配符块
+        let all = ["*".to_string()];
+        assert_eq!(
+            select_delete_targets(&hosts, DeleteSelector::Glob(&all)).unwrap(),
+            ["web1", "web2", "db", "alias"]
+        );
+
+        let none = ["staging-*".to_string()];
+        assert!(select_delete_targets(&hosts, DeleteSelector::Glob(&none)).is_err());
+    }
+
+    #[test]
+    fn test_plan_bulk_edit_preview() {
+        let hosts = fixture_hosts();
+        let plans = plan_bulk_edit(
+            &hosts,
+            "web",
+            &[
+                assign("user=svc-deploy"),
+                assign("port=2200"),
+                assign("port=2222"),
+            ],
+        );
+
+        // web2 已经是2222端口，只修改用户；后一次 port 赋值生效
+        assert_eq!(
+            plans,
+            [
+                BulkEditPlan {
+                    host: "web1".to_string(),
+                    changes: vec![
+                        FieldChange {
+                            field: EditableField::User,
+                            before: Some("deploy".to_string()),
+                            after: "svc-deploy".to_string(),
+                        },
+                        FieldChange {
+                            field: EditableField::Port,
+                            before: None,
+                            after: "2222".to_string(),
+                        },
+                    ],
+                },
+                BulkEditPlan {
+                    host: "web2".to_string(),
+                    changes: vec![FieldChange {
+                        field: EditableField::User,
+                        before: Some("deploy".to_string()),
+                        after: "svc-deploy".to_string(),
+                    }],
+                },
+            ]
+        );
+
+        let preview = format_bulk_edit_preview(&plans);
+        assert_eq!(
+            preview,
+            format!(
+                "  web1: User deploy → svc-deploy, Port {} → 2222\n  web2: User deploy → svc-deploy",
+                t("bulk_edit_unset")
+            )
+        );
+
+        // 取值都已相同时没有需要修改的主机
+        assert!(plan_bulk_edit(&hosts, "web2", &[assign("proxy_jump=bastion")]).is_empty());
+    }
+
+    #[test]
+    fn test_apply_bulk_edit_reports_partial_failure() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut manager = manager_with_config(
+            &dir,
+            "Host web1\n    HostName 10.0.0.1\n    User deploy\n    # 保留的注释\n    Compression yes\n\nHost web2\n    HostName 10.0.0.2\n    User deploy\n",
+        );
+        let plans = plan_bulk_edit(
+            manager.get_hosts().unwrap(),
+            "web",
+            &[assign("user=svc-deploy"), assign("connect_timeout=5")],
+        );
+        assert_eq!(plans.len(), 2);
+
+        // 预览之后web2被删除，应用时只有它失败
+        manager.delete_host("web2").unwrap();
+        let results = apply_bulk_edit(&mut manager, &plans);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, "web1");
+        assert!(results[0].1.is_ok());
+        assert_eq!(results[1].0, "web2");
+        assert!(matches!(
+            results[1].1,
+            Err(SshConnError::HostNotFound { .. })
+        ));
+
+        let web1 = manager.get_host("web1").unwrap().unwrap();
+        assert_eq!(web1.user.as_deref(), Some("svc-deploy"));
+        assert_eq!(web1.connect_timeout.as_deref(), Some("5"));
+        let content = std::fs::read_to_string(dir.path().join("config")).unwrap();
+        assert!(content.contains("    # 保留的注释\n    Compression yes\n    ConnectTimeout 5\n"));
+
+        // 报告全部结果后以失败主机的错误结束，退出码与批量删除一致
+        let err = report_bulk_edit(results).unwrap_err();
+        assert!(matches!(err, SshConnError::HostNotFound { .. }));
+        assert!(report_bulk_edit(vec![("web1".to_string(), Ok(()))]).is_ok());
+    }
+}
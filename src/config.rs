@@ -1,63 +1,743 @@
 //! SSH配置文件管理模块
 
-use std::fs::{File, OpenOptions};
-use std::io::{BufRead, BufReader, Write};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+use std::sync::Arc;
+use std::time::Duration;
 
 #[cfg(unix)]
 use std::os::unix::process::CommandExt;
 
+use crate::audit::{self, AuditOperation};
 use crate::error::{Result, SshConnError};
 use crate::i18n::t;
-use crate::models::SshHost;
+use crate::models::{OptionalField, SearchFields, SessionKind, SshHost};
+use crate::output;
 use crate::password::PasswordManager;
+use crate::settings::Settings;
+use crate::store::{ConfigStore, FileConfigStore};
 use crate::utils::*;
+use zeroize::Zeroizing;
 
-/// 通用SSH连接参数
-const DEFAULT_SSH_OPTIONS: &[&str] = &[
-    "-o",
-    "StrictHostKeyChecking=accept-new",
-    "-o",
-    "LogLevel=ERROR",
-];
+/// 按搜索相关度筛选并排序主机（稳定排序），只匹配 `fields` 中的字段
+pub fn rank_hosts(hosts: &[SshHost], query: &str, fields: SearchFields) -> Vec<SshHost> {
+    let mut ranked: Vec<(u8, &SshHost)> = hosts
+        .iter()
+        .filter_map(|host| host.match_rank_in(query, fields).map(|rank| (rank, host)))
+        .collect();
+    ranked.sort_by_key(|(rank, _)| *rank);
+    ranked.into_iter().map(|(_, host)| host.clone()).collect()
+}
 
-/// TUI模式的SSH连接参数
-const TUI_SSH_OPTIONS: &[&str] = &[
-    "-o",
-    "StrictHostKeyChecking=accept-new",
-    "-o",
-    "LogLevel=ERROR",
-    "-o",
-    "RequestTTY=force",
-    "-tt",
-];
+/// 构建连接测试的完整SSH参数
+///
+/// 测试时显式执行 `exit`，因此需要禁用配置中的RemoteCommand，否则ssh会拒绝同时执行两个命令
+fn build_test_args(base_options: &[String], host: &str) -> Vec<String> {
+    let mut args = base_options.to_vec();
+    args.push("-o".to_string());
+    args.push("RemoteCommand=none".to_string());
+    args.push(host.to_string());
+    args.push("exit".to_string());
+    args
+}
+
+/// 构建sftp/scp命令参数（不含传输目标）
+///
+/// 只保留基础参数中的 `-o` 选项（sftp/scp不支持 `-tt` 等ssh专用标志），
+/// 禁用RemoteCommand以免与文件传输子系统冲突，并显式带上主机的端口和私钥
+fn build_transfer_options(host: &SshHost, base_options: &[String]) -> Vec<String> {
+    let mut options = Vec::new();
+    let mut iter = base_options.iter();
+    while let Some(option) = iter.next() {
+        if option != "-o" {
+            continue;
+        }
+        if let Some(value) = iter.next() {
+            let key = value.split('=').next().unwrap_or_default();
+            if !key.eq_ignore_ascii_case("RequestTTY") && !key.eq_ignore_ascii_case("RemoteCommand")
+            {
+                options.push(option.clone());
+                options.push(value.clone());
+            }
+        }
+    }
 
-/// 连接测试的SSH参数
-const TEST_SSH_OPTIONS: &[&str] = &["-o", "ConnectTimeout=10", "-o", "StrictHostKeyChecking=yes"];
+    options.push("-o".to_string());
+    options.push("RemoteCommand=none".to_string());
 
-/// 写入SSH配置选项的辅助函数
-fn write_ssh_option<W: Write>(
-    file: &mut W,
-    key: &str,
-    new_value: Option<&str>,
-    original_value: Option<&str>,
-) -> Result<()> {
-    if let Some(value) = new_value {
-        writeln!(file, "    {} {}", key, value)?;
-    } else if let Some(value) = original_value {
-        writeln!(file, "    {} {}", key, value)?;
+    if let Some(port) = &host.port {
+        options.push("-P".to_string());
+        options.push(port.clone());
+    }
+    if let Some(identity_file) = &host.identity_file {
+        options.push("-i".to_string());
+        options.push(identity_file.clone());
+    }
+
+    options
+}
+
+/// scp的一端：远程主机上的路径或本地路径
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScpEndpoint {
+    /// `<host>:<path>`，host为ssh config中的Host名称
+    Remote { host: String, path: String },
+    /// 本地路径
+    Local(String),
+}
+
+impl ScpEndpoint {
+    /// 解析scp参数，与scp一致：第一个 `:` 之前不含 `/` 的部分视为主机名
+    pub fn parse(spec: &str) -> Self {
+        match spec.split_once(':') {
+            Some((host, path)) if !host.is_empty() && !host.contains('/') => ScpEndpoint::Remote {
+                host: host.to_string(),
+                path: path.to_string(),
+            },
+            _ => ScpEndpoint::Local(spec.to_string()),
+        }
+    }
+
+    /// 生成传给scp的参数，远程端保留Host别名
+    ///
+    /// scp经由 `-F` 读取同一份配置，由别名解析User、HostName以及ProxyJump等选项；
+    /// 换成真实地址后这些只对别名生效的配置都会丢失
+    fn to_scp_arg(&self) -> String {
+        match self {
+            ScpEndpoint::Local(path) => path.clone(),
+            ScpEndpoint::Remote { host, path } => format!("{}:{}", host, path),
+        }
+    }
+}
+
+/// 构建scp命令参数
+fn build_scp_args(
+    host: &SshHost,
+    base_options: &[String],
+    source: &ScpEndpoint,
+    destination: &ScpEndpoint,
+    recursive: bool,
+) -> Vec<String> {
+    let mut args = build_transfer_options(host, base_options);
+    if recursive {
+        args.push("-r".to_string());
+    }
+    args.push(source.to_scp_arg());
+    args.push(destination.to_scp_arg());
+    args
+}
+
+/// ssh命令的使用场景，决定基础参数和执行的程序
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandMode {
+    /// `ssh-conn connect`
+    Connect,
+    /// TUI中连接，可临时指定跳板机（`-J`）
+    Tui { jump_host: Option<String> },
+    /// SFTP会话
+    Sftp,
+    /// 以 `BatchMode` 运行 `ssh <host> exit` 检测连通性，经过配置中的 ProxyJump/ProxyCommand
+    Probe,
+    /// 在探测的基础上加 `PreferredAuthentications=none`，让服务器列出它接受的认证方式
+    AuthProbe,
+}
+
+/// 将要执行的ssh/sftp命令，不含密码本身
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandSpec {
+    /// 执行的程序（`ssh` 或 `sftp`）
+    pub program: &'static str,
+    /// 完整参数，目标主机在最后（探测模式下其后还有远程命令 `exit`）
+    pub args: Vec<String>,
+    /// 是否使用存储的密码，此时经由 `sshpass -e` 执行，密码通过环境变量 `SSHPASS` 传递
+    pub use_password: bool,
+    /// 连接时运行以获取密码的命令，设置时代替存储的密码
+    pub password_command: Option<String>,
+}
+
+impl CommandSpec {
+    /// 实际执行的argv
+    pub fn argv(&self) -> Vec<String> {
+        let mut argv = Vec::new();
+        if self.use_password {
+            argv.push("sshpass".to_string());
+            argv.push("-e".to_string());
+        }
+        argv.push(self.program.to_string());
+        argv.extend(self.args.iter().cloned());
+        argv
+    }
+
+    /// shell转义后的命令行，可直接复制到终端执行（密码需自行通过 `SSHPASS` 提供）
+    pub fn to_command_line(&self) -> String {
+        self.argv()
+            .iter()
+            .map(|arg| shell_quote(arg))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// 以 `-l <user>` 指定本次连接的用户名，命令行参数优先于配置文件中的User
+    ///
+    /// 存储的密码和密码命令属于配置中的用户，换用其他用户时不再使用，由ssh提示输入
+    fn with_login_user(mut self, user: &str, configured: Option<&str>) -> Self {
+        let target = self.args.len().saturating_sub(1);
+        self.args
+            .splice(target..target, ["-l".to_string(), user.to_string()]);
+        if configured != Some(user) {
+            self.use_password = false;
+            self.password_command = None;
+        }
+        self
+    }
+}
+
+/// 连接时使用的认证方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthMethod {
+    /// 存储的密码，经由 `sshpass -e` 传递
+    StoredPassword,
+    /// 外部命令输出的密码，经由 `sshpass -e` 传递
+    PasswordCommand(String),
+    /// 主机配置的IdentityFile
+    IdentityFile(String),
+    /// 由ssh自行处理（ssh-agent、默认密钥或交互输入密码）
+    SshDefault,
+}
+
+/// 备份选项
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BackupOptions {
+    /// gzip压缩，目标以 `.gz` 结尾时自动启用
+    pub compress: bool,
+    /// 覆盖已存在的目标文件
+    pub force: bool,
+}
+
+/// 写入的备份文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupOutcome {
+    /// 最终路径（压缩时会补上 `.gz` 扩展名）
+    pub path: PathBuf,
+    /// 文件大小（字节）
+    pub size: u64,
+}
+
+/// 读取备份文件内容，`.gz` 文件先解压
+pub fn read_backup(path: &Path) -> Result<String> {
+    let is_gzip = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("gz"));
+    if !is_gzip {
+        return Ok(std::fs::read_to_string(path)?);
+    }
+    let mut content = String::new();
+    std::io::Read::read_to_string(
+        &mut flate2::read::GzDecoder::new(std::fs::File::open(path)?),
+        &mut content,
+    )?;
+    Ok(content)
+}
+
+/// 写入备份文件，按需gzip压缩并创建上级目录
+///
+/// 目标已存在且未指定 `force` 时拒绝覆盖
+fn write_backup_file(dest: &Path, data: &[u8], options: &BackupOptions) -> Result<BackupOutcome> {
+    let has_gz_extension = dest
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("gz"));
+    let compress = options.compress || has_gz_extension;
+    let path = if compress && !has_gz_extension {
+        let mut path = dest.as_os_str().to_owned();
+        path.push(".gz");
+        PathBuf::from(path)
+    } else {
+        dest.to_path_buf()
+    };
+
+    if path.exists() && !options.force {
+        return Err(SshConnError::InvalidArgument(
+            t("error.backup_exists").replace("{}", &path.to_string_lossy()),
+        ));
+    }
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    if compress {
+        let file = crate::permissions::private_open_options()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)?;
+        let mut encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+        encoder.write_all(data)?;
+        encoder.finish()?;
+    } else {
+        crate::permissions::write_private(&path, data)?;
+    }
+
+    let size = std::fs::metadata(&path)?.len();
+    Ok(BackupOutcome { path, size })
+}
+
+/// `connect --dry-run` 解析出的连接计划
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectPlan {
+    /// 实际执行的命令
+    pub spec: CommandSpec,
+    /// 使用的认证方式
+    pub auth: AuthMethod,
+}
+
+impl ConnectPlan {
+    /// 执行命令需要、但在 PATH 中找不到的程序
+    pub fn missing_programs(&self) -> Vec<String> {
+        let mut programs = vec![self.spec.program];
+        if self.spec.use_password {
+            programs.insert(0, "sshpass");
+        }
+        programs
+            .into_iter()
+            .filter(|program| crate::utils::find_program(program).is_none())
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// 供 `--dry-run` 输出的说明：解析后的命令及认证方式
+    pub fn describe(&self) -> String {
+        let auth = match &self.auth {
+            AuthMethod::StoredPassword => t("dry_run_auth_password"),
+            AuthMethod::PasswordCommand(command) => {
+                t("dry_run_auth_password_cmd").replace("{}", command)
+            }
+            AuthMethod::IdentityFile(path) => t("dry_run_auth_key").replace("{}", path),
+            AuthMethod::SshDefault => t("dry_run_auth_default"),
+        };
+        format!(
+            "{}: {}\n{}: {}\n",
+            t("dry_run_command"),
+            self.spec.to_command_line(),
+            t("dry_run_auth"),
+            auth
+        )
+    }
+}
+
+/// 构建连接时使用的SSH参数
+///
+/// 主机设置了RequestTTY时，去掉基础参数中的 `RequestTTY=...` 和 `-t`/`-tt`/`-T`，
+/// 改用主机的取值（TUI默认强制分配TTY，`no` 可用于端口转发等无shell的连接）。
+///
+/// 设置了RemoteCommand时追加 `-o RemoteCommand=...`，远程命令需要TTY（如tmux），
+/// 因此在尚未指定TTY方式时额外追加 `RequestTTY=yes`
+fn build_connect_options(
+    base_options: &[String],
+    remote_command: Option<&str>,
+    request_tty: Option<&str>,
+) -> Vec<String> {
+    let mut options = match request_tty {
+        Some(mode) => {
+            let mut options = strip_tty_options(base_options);
+            options.push("-o".to_string());
+            options.push(format!("RequestTTY={}", mode));
+            options
+        }
+        None => base_options.to_vec(),
+    };
+
+    if let Some(command) = remote_command.filter(|c| !c.trim().is_empty()) {
+        options.push("-o".to_string());
+        options.push(format!("RemoteCommand={}", command));
+
+        let tty_requested = options
+            .iter()
+            .any(|o| o.starts_with("RequestTTY=") || o == "-t" || o == "-tt");
+        if !tty_requested {
+            options.push("-o".to_string());
+            options.push("RequestTTY=yes".to_string());
+        }
+    }
+
+    options
+}
+
+/// 探测模式的ssh参数
+///
+/// 不分配TTY，禁止交互认证，覆盖配置中的RemoteCommand以便执行 `exit`；
+/// 超时取主机的ConnectTimeout，未设置时为5秒
+fn build_probe_options(host: &SshHost, base_options: &[String]) -> Vec<String> {
+    let timeout = host
+        .connect_timeout
+        .as_deref()
+        .and_then(|t| t.parse::<u64>().ok())
+        .unwrap_or(5);
+    let mut options: Vec<String> = strip_tty_options(base_options)
+        .into_iter()
+        .filter(|o| o != "-t" && o != "-tt")
+        .collect();
+    for option in [
+        "BatchMode=yes".to_string(),
+        format!("ConnectTimeout={}", timeout),
+        "RemoteCommand=none".to_string(),
+        "RequestTTY=no".to_string(),
+    ] {
+        options.push("-o".to_string());
+        options.push(option);
+    }
+    options
+}
+
+/// 全局保活参数：主机配置和ssh参数中都没有ServerAliveInterval时，
+/// 追加 `-o ServerAliveInterval=N -o ServerAliveCountMax=3`，`interval` 为0时不添加
+fn build_keepalive_options(host: &SshHost, options: &[String], interval: u64) -> Vec<String> {
+    use crate::settings::has_ssh_option;
+
+    let host_defines = |key: &str| {
+        host.custom_options
+            .keys()
+            .any(|k| k.eq_ignore_ascii_case(key))
+    };
+    if interval == 0
+        || host.server_alive_interval.is_some()
+        || has_ssh_option(options, "ServerAliveInterval")
+    {
+        return Vec::new();
+    }
+
+    let mut keepalive = vec![
+        "-o".to_string(),
+        format!("ServerAliveInterval={}", interval),
+    ];
+    if !host_defines("ServerAliveCountMax") && !has_ssh_option(options, "ServerAliveCountMax") {
+        keepalive.push("-o".to_string());
+        keepalive.push("ServerAliveCountMax=3".to_string());
+    }
+    keepalive
+}
+
+/// 去掉参数中与TTY分配相关的 `-o RequestTTY=...` 和 `-t`/`-tt`/`-T`
+fn strip_tty_options(options: &[String]) -> Vec<String> {
+    let mut stripped = Vec::new();
+    let mut iter = options.iter().peekable();
+    while let Some(option) = iter.next() {
+        if option == "-o"
+            && let Some(value) = iter.peek()
+            && value
+                .split('=')
+                .next()
+                .is_some_and(|key| key.eq_ignore_ascii_case("RequestTTY"))
+        {
+            iter.next();
+            continue;
+        }
+        if matches!(option.as_str(), "-t" | "-tt" | "-T") {
+            continue;
+        }
+        stripped.push(option.clone());
+    }
+    stripped
+}
+
+/// 在指定Host块中设置一个选项，保留块内的注释、缩进和其他选项
+///
+/// 块内已有同名选项（不区分大小写）时替换该行，否则追加到块内最后一个非空行之后。
+/// 找不到该Host块时返回None
+fn set_option_in_block(content: &str, host: &str, keyword: &str, value: &str) -> Option<String> {
+    replace_option_in_block(content, host, keyword, &[value.to_string()])
+}
+
+/// 用 `values` 替换指定Host块中某个选项的全部取值，保留块内的注释、缩进和其他选项
+///
+/// 新的取值写在原来第一处同名选项的位置，原本没有时追加到块内最后一个非空行之后；
+/// `values` 为空时删除该选项。找不到该Host块时返回None
+fn replace_option_in_block(
+    content: &str,
+    host: &str,
+    keyword: &str,
+    values: &[String],
+) -> Option<String> {
+    let is_block_start = |line: &str| {
+        let trimmed = line.trim();
+        (trimmed.starts_with("Host ") && !trimmed.starts_with("HostName"))
+            || trimmed.starts_with("Match ")
+    };
+
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let start = lines.iter().position(|line| {
+        let trimmed = line.trim();
+        trimmed.starts_with("Host ") && trimmed[5..].split_whitespace().any(|h| h == host)
+    })?;
+    let end = lines[start + 1..]
+        .iter()
+        .position(|line| is_block_start(line))
+        .map_or(lines.len(), |offset| start + 1 + offset);
+
+    let option_key = |line: &str| {
+        line.trim()
+            .split(|c: char| c.is_whitespace() || c == '=')
+            .next()
+            .unwrap_or_default()
+            .to_string()
+    };
+    let indent_of = |line: &str| line[..line.len() - line.trim_start().len()].to_string();
+
+    let existing: Vec<usize> = (start + 1..end)
+        .filter(|&i| option_key(&lines[i]).eq_ignore_ascii_case(keyword))
+        .collect();
+    let indent = existing.first().map_or_else(
+        || {
+            (start + 1..end)
+                .map(|i| &lines[i])
+                .find(|line| !line.trim().is_empty() && !line.trim().starts_with('#'))
+                .map_or_else(|| "    ".to_string(), |line| indent_of(line))
+        },
+        |&i| indent_of(&lines[i]),
+    );
+    let position = match existing.first() {
+        Some(&i) => i,
+        None => {
+            (start..end)
+                .rev()
+                .find(|&i| !lines[i].trim().is_empty())
+                .unwrap_or(start)
+                + 1
+        }
+    };
+
+    for &i in existing.iter().rev() {
+        lines.remove(i);
+    }
+    for (offset, value) in values.iter().enumerate() {
+        lines.insert(
+            position + offset,
+            format!("{}{} {}", indent, keyword, value),
+        );
+    }
+
+    let mut updated = lines.join("\n");
+    if content.ends_with('\n') || content.is_empty() {
+        updated.push('\n');
+    }
+    Some(updated)
+}
+
+/// 在 `content` 中把主机从 `original` 改为 `updated`，只改写取值变化的指令
+///
+/// 块内的注释、`# ssh-conn-password-cmd:` 以及未改动的ConnectTimeout、自定义选项等原样保留
+fn apply_host_changes(content: &str, original: &SshHost, updated: &SshHost) -> Result<String> {
+    let host = original.host.as_str();
+    let mut content = content.to_string();
+    for (keyword, _, values) in original.changed_options(updated) {
+        content = replace_option_in_block(&content, host, &keyword, &values).ok_or_else(|| {
+            SshConnError::HostNotFound {
+                host: host.to_string(),
+            }
+        })?;
+    }
+    Ok(content)
+}
+
+/// 删除 `host` 所在的整个Host块（含块内的注释），其余内容原样保留
+fn remove_host_block(content: &str, host: &str) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut new_content = String::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim();
+
+        if trimmed.starts_with("Host ") && !trimmed.starts_with("HostName") {
+            let hosts_in_line: Vec<&str> = trimmed[5..].split_whitespace().collect();
+
+            if hosts_in_line.contains(&host) {
+                // 跳过这个Host块的所有行
+                i += 1;
+                while i < lines.len() {
+                    let next_line = lines[i].trim();
+                    if next_line.starts_with("Host ") && !next_line.starts_with("HostName") {
+                        break;
+                    }
+                    i += 1;
+                }
+                continue;
+            }
+        }
+
+        new_content.push_str(line);
+        new_content.push('\n');
+        i += 1;
+    }
+    new_content
+}
+
+/// 校验要新增的主机，返回规范化（如RequestTTY、AddressFamily、PermitLocalCommand取值）后的配置
+fn validated_new_entry(entry: &SshHost) -> Result<SshHost> {
+    validate_host(&entry.host)?;
+    validate_hostname(entry.hostname.as_deref().unwrap_or_default())?;
+
+    if let Some(port) = &entry.port {
+        validate_port(port)?;
+    }
+
+    let mut entry = entry.clone();
+    entry.request_tty = entry
+        .request_tty
+        .as_deref()
+        .map(validate_request_tty)
+        .transpose()?;
+    entry.address_family = entry
+        .address_family
+        .as_deref()
+        .map(validate_address_family)
+        .transpose()?;
+    if let Some(bind_address) = &entry.bind_address {
+        validate_bind_address(bind_address)?;
+    }
+    entry.permit_local_command = entry
+        .permit_local_command
+        .as_deref()
+        .map(validate_permit_local_command)
+        .transpose()?;
+    if let Some(local_command) = &entry.local_command {
+        validate_local_command(local_command)?;
+    }
+    if let Some(identity_file) = &entry.identity_file {
+        warn_missing_identity_file(identity_file);
+    }
+    Ok(entry)
+}
+
+/// 校验编辑主机时传入的字段，返回规范化后的RequestTTY
+fn validate_edit_fields(
+    host: &str,
+    hostname: Option<&str>,
+    port: Option<u16>,
+    request_tty: Option<&str>,
+) -> Result<Option<String>> {
+    validate_host(host)?;
+
+    if let Some(h) = hostname {
+        validate_hostname(h)?;
+    }
+
+    if let Some(p) = port {
+        validate_port(&p.to_string())?;
+    }
+
+    request_tty.map(validate_request_tty).transpose()
+}
+
+/// `config_path` 在默认位置的全部备份（手动和自动），按时间从旧到新排列，不含加密的密码导出文件
+fn list_backups(config_path: &str) -> Vec<PathBuf> {
+    let config_path = Path::new(config_path);
+    let Some(file_name) = config_path.file_name().map(|n| n.to_string_lossy()) else {
+        return Vec::new();
+    };
+    let prefix = format!("{}.backup.", file_name);
+    let dir = match config_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    // 时间戳格式固定，按文件名排序即按时间排序
+    let mut backups: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.starts_with(&prefix) && !name.ends_with(PASSWORD_EXPORT_SUFFIX)
+        })
+        .map(|entry| entry.path())
+        .collect();
+    backups.sort();
+    backups
+}
+
+/// `config_path` 的自动备份，按时间从旧到新排列
+fn list_auto_backups(config_path: &str) -> Vec<PathBuf> {
+    let mut backups = list_backups(config_path);
+    backups.retain(|path| path.to_string_lossy().ends_with(AUTO_BACKUP_SUFFIX));
+    backups
+}
+
+/// 把 `content` 写入一份新的自动备份
+///
+/// 内容与最近一次自动备份相同时不生成新文件；写入后按 `keep` 删除最旧的自动备份（0为不限），
+/// 删除失败只记录日志
+fn write_auto_backup(config_path: &str, content: &str, keep: usize) -> Result<()> {
+    let unchanged = list_auto_backups(config_path)
+        .last()
+        .and_then(|latest| std::fs::read(latest).ok())
+        .is_some_and(|latest| latest == content.as_bytes());
+    if unchanged {
+        return Ok(());
+    }
+
+    let stamp = chrono::Utc::now().format("%Y%m%d_%H%M%S_%6f").to_string();
+    let mut backup_path = format!("{}.backup.{}{}", config_path, stamp, AUTO_BACKUP_SUFFIX);
+    // 同一时刻已有备份时加序号，序号排在原文件名之后
+    let mut n = 1;
+    while Path::new(&backup_path).exists() {
+        backup_path = format!(
+            "{}.backup.{}_{}{}",
+            config_path, stamp, n, AUTO_BACKUP_SUFFIX
+        );
+        n += 1;
+    }
+    crate::permissions::write_private(Path::new(&backup_path), content)?;
+    log::info!("{}", t("backup_created_at").replace("{}", &backup_path));
+
+    if keep > 0 {
+        let backups = list_auto_backups(config_path);
+        let excess = backups.len().saturating_sub(keep);
+        for path in &backups[..excess] {
+            if let Err(e) = std::fs::remove_file(path) {
+                log::warn!("{}: {}", path.display(), e);
+            }
+        }
     }
     Ok(())
 }
 
+/// 为单次连接追加 `-J <跳板机>` 参数
+///
+/// 跳板机使用配置中的Host名称，由ssh按同一配置文件解析其地址、用户和端口
+fn build_jump_options(mut options: Vec<String>, jump_host: &str) -> Vec<String> {
+    options.push("-J".to_string());
+    options.push(jump_host.to_string());
+    options
+}
+
 /// SSH配置管理器
 #[derive(Clone)]
 pub struct ConfigManager {
     config_path: String,
+    /// 配置内容的读写后端
+    store: Arc<dyn ConfigStore>,
     password_manager: PasswordManager,
+    /// 应用设置
+    settings: Settings,
     /// 缓存的主机配置
     hosts_cache: Option<Vec<SshHost>>,
+    /// 与主机配置一同解析的 `Host *` 默认选项
+    wildcard_defaults: Option<SshHost>,
+    /// 本次运行是否已经做过自动备份
+    auto_backup_done: bool,
 }
 
+/// 自动备份文件名的后缀，与手动备份 `<配置>.backup.<时间>` 区分
+const AUTO_BACKUP_SUFFIX: &str = ".auto";
+
+/// 加密的密码导出文件后缀，写在对应备份文件名之后
+pub const PASSWORD_EXPORT_SUFFIX: &str = ".passwords.enc";
+
 /// 跨平台执行命令的辅助函数
 /// 在Unix系统上使用exec()替换当前进程，在Windows上使用spawn()并等待
 #[cfg(unix)]
@@ -87,18 +767,242 @@ fn exec_command(mut cmd: std::process::Command) -> Result<()> {
     }
 }
 
+/// 构建ssh系列程序的命令：有密码时经由 `sshpass -e`，密码通过环境变量传递，不出现在进程参数中
+///
+/// 返回命令以及启动失败时使用的提示键；`quiet` 时不输出认证方式提示
+fn ssh_program_command(
+    program: &str,
+    args: &[String],
+    password: Option<&Zeroizing<String>>,
+    quiet: bool,
+) -> (std::process::Command, &'static str) {
+    match password {
+        Some(password) if !password.is_empty() => {
+            log::info!("{}", t("using_stored_password_auto_login"));
+            if !quiet {
+                output::info(t("using_stored_password"));
+            }
+            let mut cmd = std::process::Command::new("sshpass");
+            cmd.arg("-e")
+                .arg(program)
+                .args(args)
+                .env("SSHPASS", password.as_str());
+            (cmd, "sshpass_not_available")
+        }
+        _ => {
+            log::info!("{}", t("using_ssh_key_auth"));
+            if !quiet {
+                output::info(t("using_ssh_key_or_manual"));
+            }
+            let mut cmd = std::process::Command::new(program);
+            cmd.args(args);
+            (cmd, "ssh_start_failed")
+        }
+    }
+}
+
+/// 运行命令并等待结束，返回退出码（被信号终止时为None）
+///
+/// 程序不存在时返回 [`SshConnError::BinaryNotFound`]
+fn wait_ssh_program(mut cmd: std::process::Command, start_failed: &str) -> Result<Option<i32>> {
+    let program = cmd.get_program().to_string_lossy().into_owned();
+    let status = cmd.status().map_err(|e| {
+        SshConnError::spawn_failed(&program, e, |e| {
+            SshConnError::SshConnectionError(t(start_failed).replace("{}", &e.to_string()))
+        })
+    })?;
+    Ok(status.code())
+}
+
+/// ssh以255退出表示连接失败，其他退出码来自远程会话本身
+fn check_ssh_exit(code: Option<i32>) -> Result<()> {
+    if code == Some(255) {
+        return Err(SshConnError::SshConnectionError(format!(
+            "{}: {}",
+            t("ssh_connection_failed_code"),
+            255
+        )));
+    }
+    Ok(())
+}
+
+/// `connect --retry` 的重试策略
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// 最多重试的次数
+    pub retries: u32,
+    /// 第一次重试前的等待时间，之后每次递增同样的时长
+    pub delay: Duration,
+    /// 在此时间内以255退出才视为网络故障；超过时说明会话已经开始，不再重试
+    pub quick_failure: Duration,
+}
+
+impl RetryPolicy {
+    /// 快速失败的默认阈值
+    pub const DEFAULT_QUICK_FAILURE: Duration = Duration::from_secs(10);
+
+    pub fn new(retries: u32, delay: Duration) -> Self {
+        Self {
+            retries,
+            delay,
+            quick_failure: Self::DEFAULT_QUICK_FAILURE,
+        }
+    }
+
+    /// 第 `attempt` 次重试（从1开始）前的等待时间
+    pub fn delay_before(&self, attempt: u32) -> Duration {
+        self.delay * attempt
+    }
+}
+
+/// 重试循环：`attempt` 返回ssh的退出码和耗时，`sleep` 可在测试中替换
+pub(crate) fn run_with_retry(
+    policy: &RetryPolicy,
+    mut attempt: impl FnMut() -> Result<(Option<i32>, Duration)>,
+    mut sleep: impl FnMut(Duration),
+) -> Result<()> {
+    let mut retried = 0;
+    loop {
+        let (code, elapsed) = attempt()?;
+        let transient = code == Some(255) && elapsed < policy.quick_failure;
+        if !transient || retried >= policy.retries {
+            return check_ssh_exit(code);
+        }
+        retried += 1;
+        let delay = policy.delay_before(retried);
+        log::info!(
+            "ssh exited with 255 after {:?}, retrying in {:?}",
+            elapsed,
+            delay
+        );
+        output::info(
+            t("connect_retrying")
+                .replacen("{}", &retried.to_string(), 1)
+                .replacen("{}", &policy.retries.to_string(), 1),
+        );
+        sleep(delay);
+    }
+}
+
 impl ConfigManager {
     /// 创建一个新的配置管理器
-    pub fn new(password_manager: PasswordManager) -> Result<Self> {
-        let config_path = get_ssh_config_path()?.to_string_lossy().to_string();
+    ///
+    /// SSH配置文件路径优先使用设置中的 `ssh_config_path`
+    pub fn new(password_manager: PasswordManager, settings: Settings) -> Result<Self> {
+        let config_path = Self::resolve_config_path(&settings)?;
+        let store = Arc::new(FileConfigStore::new(&config_path));
+        Self::with_store(password_manager, settings, store)
+    }
+
+    /// 使用指定的SSH配置文件创建配置管理器，其余设置取默认值
+    ///
+    /// 供库的使用者和测试操作临时文件，不读取也不修改 `~/.ssh/config`
+    pub fn with_path(path: impl Into<PathBuf>, password_manager: PasswordManager) -> Result<Self> {
+        let settings = Settings {
+            ssh_config_path: Some(path.into()),
+            ..Settings::default()
+        };
+        Self::new(password_manager, settings)
+    }
+
+    /// 使用自定义存储后端创建配置管理器，如测试中使用 [`MemoryConfigStore`](crate::store::MemoryConfigStore)
+    ///
+    /// 解析和编辑都通过 `store` 进行；传给ssh的 `-F` 路径仍按设置确定
+    pub fn with_store(
+        password_manager: PasswordManager,
+        settings: Settings,
+        store: Arc<dyn ConfigStore>,
+    ) -> Result<Self> {
+        let config_path = Self::resolve_config_path(&settings)?;
 
         Ok(Self {
             config_path,
+            store,
             password_manager,
+            settings,
             hosts_cache: None,
+            wildcard_defaults: None,
+            auto_backup_done: false,
+        })
+    }
+
+    /// SSH配置文件路径，优先使用设置中的 `ssh_config_path`
+    fn resolve_config_path(settings: &Settings) -> Result<String> {
+        Ok(match &settings.ssh_config_path {
+            Some(path) => path.to_string_lossy().to_string(),
+            None => get_ssh_config_path()?.to_string_lossy().to_string(),
         })
     }
 
+    /// 存储了密码的主机名
+    pub fn stored_password_hosts(&self) -> Vec<String> {
+        self.password_manager.list_hosts()
+    }
+
+    /// 存储了口令的私钥路径
+    pub fn stored_key_passphrases(&self) -> Vec<String> {
+        self.password_manager.list_key_passphrases()
+    }
+
+    /// 保存私钥的口令，连接前载入ssh-agent时使用（设置 `add_keys_to_agent` 或 `connect --add-key`）
+    pub fn save_key_passphrase(&mut self, identity_file: &str, passphrase: &str) -> Result<()> {
+        self.password_manager
+            .save_key_passphrase(identity_file, passphrase)
+    }
+
+    /// 删除私钥的口令，返回是否存在过
+    pub fn delete_key_passphrase(&mut self, identity_file: &str) -> Result<bool> {
+        self.password_manager.delete_key_passphrase(identity_file)
+    }
+
+    /// 主机是否存储了密码（只查内存缓存）
+    pub fn has_stored_password(&self, host: &str) -> bool {
+        self.password_manager.has_password(host)
+    }
+
+    /// 获取应用设置
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// 获取当前管理的SSH配置文件路径
+    pub fn config_path(&self) -> &str {
+        &self.config_path
+    }
+
+    /// 检查 `~/.ssh`、SSH配置文件和密码数据库的权限，返回权限过宽的项
+    pub fn permission_issues(&self) -> Vec<crate::permissions::PermissionIssue> {
+        let ssh_dir = get_ssh_dir().ok();
+        crate::permissions::check_ssh_permissions(
+            ssh_dir.as_deref(),
+            Path::new(&self.config_path),
+            Path::new(self.password_manager.db_path()),
+        )
+    }
+
+    /// 切换到设置中的命名配置档案，之后读写该档案对应的配置文件
+    pub fn switch_profile(&mut self, name: &str) -> Result<()> {
+        self.settings.use_profile(name)?;
+        self.config_path = Self::resolve_config_path(&self.settings)?;
+        self.store = Arc::new(FileConfigStore::new(&self.config_path));
+        self.auto_backup_done = false;
+        self.clear_cache();
+        Ok(())
+    }
+
+    /// 在参数前加上 `-F <配置文件>`，确保ssh/sftp读取的正是本工具解析和编辑的配置文件
+    ///
+    /// 设置 `use_system_config = true` 时不添加，由ssh按默认规则查找配置
+    fn with_config_file(&self, options: Vec<String>) -> Vec<String> {
+        if self.settings.use_system_config {
+            return options;
+        }
+
+        let mut args = vec!["-F".to_string(), self.config_path.clone()];
+        args.extend(options);
+        args
+    }
+
     /// 获取所有主机配置
     pub fn get_hosts(&mut self) -> Result<&Vec<SshHost>> {
         // 如果缓存存在，直接返回缓存
@@ -106,80 +1010,49 @@ impl ConfigManager {
             return Ok(hosts);
         }
 
-        // 否则解析配置文件
-        let hosts = self.parse_ssh_config()?;
-        self.hosts_cache = Some(hosts);
-        Ok(self.hosts_cache.as_ref().unwrap())
+        // 否则解析配置文件，跟随其中的Include指令
+        let content = self.store.read()?;
+        let hosts = content
+            .as_deref()
+            .map(|content| parse_config_with_includes(content, Path::new(&self.config_path)))
+            .unwrap_or_default();
+        Ok(self.cache_hosts(hosts, content.as_deref()))
+    }
+
+    /// 缓存解析出的主机，并从主配置内容中取出 `Host *` 默认选项
+    fn cache_hosts(&mut self, hosts: Vec<SshHost>, content: Option<&str>) -> &Vec<SshHost> {
+        self.wildcard_defaults = content.and_then(parse_wildcard_defaults);
+        self.hosts_cache.insert(hosts)
     }
 
     /// 清除缓存
     pub fn clear_cache(&mut self) {
         self.hosts_cache = None;
+        self.wildcard_defaults = None;
     }
 
-    /// 解析SSH配置文件
-    fn parse_ssh_config(&self) -> Result<Vec<SshHost>> {
-        let file = match File::open(&self.config_path) {
-            Ok(file) => file,
-            Err(_) => {
-                // 如果配置文件不存在，返回空列表
-                return Ok(Vec::new());
-            }
-        };
-
-        let reader = BufReader::new(file);
-        let mut hosts = Vec::new();
-        let mut current: Option<SshHost> = None;
-
-        for line_result in reader.lines() {
-            let line = line_result?;
-            let line = line.trim();
-
-            if line.starts_with("Host ") && !line.starts_with("HostName") {
-                if let Some(h) = current.take() {
-                    hosts.push(h);
-                }
-
-                for h in line[5..].split_whitespace() {
-                    if h != "*" {
-                        // 忽略通配符主机
-                        current = Some(SshHost::new(h.to_string()));
-                        break; // 只取第一个非通配符主机
-                    }
-                }
-            } else if let Some(ref mut h) = current {
-                if let Some(stripped) = line.strip_prefix("HostName ") {
-                    h.hostname = Some(stripped.trim().to_string());
-                } else if let Some(stripped) = line.strip_prefix("User ") {
-                    h.user = Some(stripped.trim().to_string());
-                } else if let Some(stripped) = line.strip_prefix("Port ") {
-                    h.port = Some(stripped.trim().to_string());
-                } else if let Some(stripped) = line.strip_prefix("ProxyCommand ") {
-                    h.proxy_command = Some(stripped.trim().to_string());
-                } else if let Some(stripped) = line.strip_prefix("IdentityFile ") {
-                    h.identity_file = Some(stripped.trim().to_string());
-                } else if let Some(stripped) = line.strip_prefix("ConnectTimeout ") {
-                    h.connect_timeout = Some(stripped.trim().to_string());
-                } else if let Some(stripped) = line.strip_prefix("ServerAliveInterval ") {
-                    h.server_alive_interval = Some(stripped.trim().to_string());
-                } else {
-                    // 处理其他自定义选项
-                    if let Some(space_pos) = line.find(' ') {
-                        let key = line[..space_pos].trim().to_string();
-                        let value = line[space_pos + 1..].trim().to_string();
-                        if !key.is_empty() && !value.is_empty() {
-                            h.custom_options.insert(key, value);
-                        }
-                    }
-                }
-            }
-        }
+    /// 主配置中 `Host *` 块的默认选项，取自最近一次解析（[`get_hosts`](Self::get_hosts)）
+    pub fn wildcard_defaults(&self) -> Option<&SshHost> {
+        self.wildcard_defaults.as_ref()
+    }
 
-        if let Some(h) = current {
-            hosts.push(h);
+    /// 合并 `Host *` 默认选项后的主机配置，用于显示和连接测试，不写回配置文件
+    pub fn effective_host(&self, host: &SshHost) -> SshHost {
+        match &self.wildcard_defaults {
+            Some(defaults) => host.with_defaults(defaults),
+            None => host.clone(),
         }
+    }
 
-        Ok(hosts)
+    /// 主机所在配置文件的存储：经Include引入的主机读写被引入的文件，其余读写主配置
+    fn store_for(&mut self, host: &str) -> Result<Arc<dyn ConfigStore>> {
+        let source = self.get_host(host)?.and_then(|h| h.source_file);
+        Ok(match source {
+            Some(path) if path != Path::new(&self.config_path) => {
+                Arc::new(FileConfigStore::new(path))
+            }
+            _ => self.store.clone(),
+        })
     }
 
     /// 列出所有主机
@@ -198,61 +1071,110 @@ impl ConfigManager {
         port: Option<u16>,
         proxy_command: Option<&str>,
         identity_file: Option<&str>,
+        remote_command: Option<&str>,
+        request_tty: Option<&str>,
+        set_env: &[(String, String)],
         password: Option<&str>,
     ) -> Result<()> {
-        // 验证输入
-        validate_host(host)?;
-        validate_hostname(hostname)?;
+        let mut entry = SshHost::new(host.to_string());
+        entry.hostname = Some(hostname.to_string());
+        entry.user = user.map(str::to_string);
+        entry.port = port.map(|p| p.to_string());
+        entry.proxy_command = proxy_command.map(str::to_string);
+        entry.identity_file = identity_file.map(str::to_string);
+        entry.remote_command = remote_command.map(str::to_string);
+        entry.request_tty = request_tty.map(str::to_string);
+        entry.set_env = set_env.to_vec();
 
-        if let Some(p) = port {
-            validate_port(&p.to_string())?;
-        }
+        self.add_host_entry(&entry, password)
+    }
 
-        // 检查主机名是否已存在
+    /// 添加完整的主机配置（含自定义选项），如套用模板后的主机
+    pub fn add_host_entry(&mut self, entry: &SshHost, password: Option<&str>) -> Result<()> {
+        let entry = validated_new_entry(entry)?;
+        self.ensure_host_absent(&entry.host)?;
+
+        self.auto_backup()?;
+        self.store
+            .append(&format!("\n{}\n", entry.to_config_format()))?;
+        self.finish_host_write(
+            &entry.host,
+            password,
+            AuditOperation::Add,
+            &audit::describe_host(&entry),
+        )
+    }
+
+    /// 主机名已存在时返回错误
+    fn ensure_host_absent(&mut self, host: &str) -> Result<()> {
         if self.host_exists(host)? {
             return Err(SshConnError::HostAlreadyExists {
                 host: host.to_string(),
             });
         }
+        Ok(())
+    }
 
-        let mut file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&self.config_path)?;
-
-        writeln!(file, "\nHost {}", host)?;
-        writeln!(file, "    HostName {}", hostname)?;
-
-        if let Some(user) = user {
-            writeln!(file, "    User {}", user)?;
-        }
-
-        if let Some(port) = port {
-            writeln!(file, "    Port {}", port)?;
-        }
-
-        if let Some(proxy_command) = proxy_command {
-            writeln!(file, "    ProxyCommand {}", proxy_command)?;
-        }
-
-        if let Some(identity_file) = identity_file {
-            writeln!(file, "    IdentityFile {}", identity_file)?;
-        }
-
+    /// 写入主机配置之后：保存提供的密码、清除缓存并记录日志和审计日志
+    ///
+    /// `details` 为审计日志中的字段摘要，保存了密码时追加 `Password=(stored)`（不记录密码本身）
+    fn finish_host_write(
+        &mut self,
+        host: &str,
+        password: Option<&str>,
+        operation: AuditOperation,
+        details: &str,
+    ) -> Result<()> {
+        let mut details = details.to_string();
         // 如果提供了密码，保存到密码管理器
-        if let Some(password) = password {
-            if !password.is_empty() {
-                self.password_manager.save_password(host, password)?;
+        if let Some(password) = password
+            && !password.is_empty()
+        {
+            self.password_manager.save_password(host, password)?;
+            if !details.is_empty() {
+                details.push_str(", ");
             }
+            details.push_str("Password=(stored)");
         }
 
         // 清除缓存
         self.clear_cache();
 
-        log::info!("{}: {}", t("log_success_add_host"), host);
+        let log_key = match operation {
+            AuditOperation::Add => "log_success_add_host",
+            AuditOperation::Edit | AuditOperation::Delete => "log_success_edit_host",
+        };
+        log::info!("{}: {}", t(log_key), host);
+        self.audit(operation, host, &details);
         Ok(())
     }
 
+    /// 向审计日志追加一条记录（设置 `audit_log`），写入失败只记录警告
+    fn audit(&self, operation: AuditOperation, host: &str, details: &str) {
+        if !self.settings.audit_log {
+            return;
+        }
+        let path = self.audit_log_path();
+        let line = audit::format_entry(
+            chrono::Local::now(),
+            &audit::current_user(),
+            operation,
+            host,
+            details,
+        );
+        if let Err(e) = audit::append_entry(&path, &line) {
+            log::warn!("Cannot write audit log {}: {}", path.display(), e);
+        }
+    }
+
+    /// 审计日志路径：设置 `audit_log_path`，未设置时为SSH配置所在目录下的 `ssh_conn_audit.log`
+    pub fn audit_log_path(&self) -> PathBuf {
+        self.settings
+            .audit_log_path
+            .clone()
+            .unwrap_or_else(|| audit::default_audit_log_path(Path::new(&self.config_path)))
+    }
+
     /// 编辑主机
     #[allow(clippy::too_many_arguments)]
     pub fn edit_host(
@@ -263,261 +1185,597 @@ impl ConfigManager {
         port: Option<u16>,
         proxy_command: Option<&str>,
         identity_file: Option<&str>,
+        remote_command: Option<&str>,
+        request_tty: Option<&str>,
+        set_env: Option<&[(String, String)]>,
         password: Option<&str>,
+        clear: &[OptionalField],
     ) -> Result<()> {
-        // 验证输入
-        validate_host(host)?;
-
-        if let Some(h) = hostname {
-            validate_hostname(h)?;
-        }
-
-        if let Some(p) = port {
-            validate_port(&p.to_string())?;
-        }
-
-        // 获取当前主机列表并保存原始配置
-        let original_host = {
-            let hosts = self.get_hosts()?;
-
-            // 检查主机是否存在
-            if !hosts.iter().any(|h| h.host == host) {
-                return Err(SshConnError::HostNotFound {
-                    host: host.to_string(),
-                });
-            }
-
-            // 保存原始主机配置
-            hosts.iter().find(|h| h.host == host).cloned()
-        };
-
-        // 使用更简洁的方法：删除旧的配置，添加新的配置
-        self.delete_host_internal(host)?;
-
-        // 重新添加主机配置
-        let mut file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&self.config_path)?;
-
-        writeln!(file, "\nHost {}", host)?;
-
-        // 使用辅助函数简化代码
-        write_ssh_option(
-            &mut file,
-            "HostName",
+        let request_tty = validate_edit_fields(host, hostname, port, request_tty)?;
+        let (original, updated) = self.edited_host(
+            host,
             hostname,
-            original_host.as_ref().and_then(|o| o.hostname.as_deref()),
-        )?;
-
-        write_ssh_option(
-            &mut file,
-            "User",
             user,
-            original_host.as_ref().and_then(|o| o.user.as_deref()),
+            port,
+            proxy_command,
+            identity_file,
+            remote_command,
+            request_tty.as_deref(),
+            set_env,
+            clear,
         )?;
 
-        write_ssh_option(
-            &mut file,
-            "Port",
-            port.map(|p| p.to_string()).as_deref(),
-            original_host.as_ref().and_then(|o| o.port.as_deref()),
-        )?;
+        // 在原有Host块中就地修改（写回主机所在的文件）
+        let store = self.store_for(host)?;
+        let content = store.read()?.unwrap_or_default();
+        let content = apply_host_changes(&content, &original, &updated)?;
+        self.auto_backup()?;
+        store.write(&content)?;
+        let changes = audit::describe_changes(&original, &updated);
+        self.finish_host_write(host, password, AuditOperation::Edit, &changes)
+    }
 
-        write_ssh_option(
-            &mut file,
-            "ProxyCommand",
+    /// 预览 [`edit_host`](Self::edit_host) 及之后的 [`set_host_option`](Self::set_host_option)
+    /// （`options`）写入后的主机配置，不修改配置文件，用于保存前显示差异
+    #[allow(clippy::too_many_arguments)]
+    pub fn preview_edit_host(
+        &mut self,
+        host: &str,
+        hostname: Option<&str>,
+        user: Option<&str>,
+        port: Option<u16>,
+        proxy_command: Option<&str>,
+        identity_file: Option<&str>,
+        remote_command: Option<&str>,
+        request_tty: Option<&str>,
+        set_env: Option<&[(String, String)]>,
+        clear: &[OptionalField],
+        options: &[(&str, &str)],
+    ) -> Result<SshHost> {
+        let request_tty = validate_edit_fields(host, hostname, port, request_tty)?;
+        let (original, updated) = self.edited_host(
+            host,
+            hostname,
+            user,
+            port,
             proxy_command,
-            original_host
-                .as_ref()
-                .and_then(|o| o.proxy_command.as_deref()),
-        )?;
-
-        write_ssh_option(
-            &mut file,
-            "IdentityFile",
             identity_file,
-            original_host
-                .as_ref()
-                .and_then(|o| o.identity_file.as_deref()),
+            remote_command,
+            request_tty.as_deref(),
+            set_env,
+            clear,
         )?;
+        let content = self.store_for(host)?.read()?.unwrap_or_default();
+        let mut content = apply_host_changes(&content, &original, &updated)?;
+        for (keyword, value) in options {
+            if let Some(updated) = set_option_in_block(&content, host, keyword, value) {
+                content = updated;
+            }
+        }
+        parse_config_content(&content)
+            .into_iter()
+            .find(|h| h.host == host)
+            .ok_or_else(|| SshConnError::HostNotFound {
+                host: host.to_string(),
+            })
+    }
 
-        // 如果提供了密码，保存到密码管理器
-        if let Some(password) = password {
-            if !password.is_empty() {
-                self.password_manager.save_password(host, password)?;
+    /// 编辑前后的主机：未传入的字段沿用原配置，`clear` 中的字段被删除
+    ///
+    /// 只读取（已缓存的）主机列表，不写入；写入时由 [`apply_host_changes`] 只改写取值变化的指令
+    #[allow(clippy::too_many_arguments)]
+    fn edited_host(
+        &mut self,
+        host: &str,
+        hostname: Option<&str>,
+        user: Option<&str>,
+        port: Option<u16>,
+        proxy_command: Option<&str>,
+        identity_file: Option<&str>,
+        remote_command: Option<&str>,
+        request_tty: Option<&str>,
+        set_env: Option<&[(String, String)]>,
+        clear: &[OptionalField],
+    ) -> Result<(SshHost, SshHost)> {
+        let original = self
+            .get_host(host)?
+            .ok_or_else(|| SshConnError::HostNotFound {
+                host: host.to_string(),
+            })?;
+
+        let mut updated = original.clone();
+        for field in clear {
+            updated.clear_field(*field);
+        }
+        let port = port.map(|p| p.to_string());
+        for (slot, value) in [
+            (&mut updated.hostname, hostname),
+            (&mut updated.user, user),
+            (&mut updated.port, port.as_deref()),
+            (&mut updated.proxy_command, proxy_command),
+            (&mut updated.identity_file, identity_file),
+            (&mut updated.remote_command, remote_command),
+            (&mut updated.request_tty, request_tty),
+        ] {
+            if let Some(value) = value {
+                *slot = Some(value.to_string());
             }
         }
+        // SetEnv整体替换：传入Some时使用新的列表（可为空以清除），否则保留原值
+        if let Some(entries) = set_env {
+            updated.set_env = entries.to_vec();
+        }
+        Ok((original, updated))
+    }
 
-        // 清除缓存
+    /// 就地设置主机的单个选项，保留块内的注释和其他选项（批量修改使用）
+    pub fn set_host_option(&mut self, host: &str, keyword: &str, value: &str) -> Result<()> {
+        validate_host(host)?;
+
+        let store = self.store_for(host)?;
+        let content = store.read()?.unwrap_or_default();
+        let updated = set_option_in_block(&content, host, keyword, value).ok_or_else(|| {
+            SshConnError::HostNotFound {
+                host: host.to_string(),
+            }
+        })?;
+        self.auto_backup()?;
+        store.write(&updated)?;
         self.clear_cache();
 
-        log::info!("{}: {}", t("log_success_edit_host"), host);
+        log::info!(
+            "{}: {} {}={}",
+            t("log_success_edit_host"),
+            host,
+            keyword,
+            value
+        );
+        self.audit(
+            AuditOperation::Edit,
+            host,
+            &format!("{}={}", keyword, value),
+        );
         Ok(())
     }
 
-    /// 删除主机（内部方法，不删除密码）
-    fn delete_host_internal(&mut self, host: &str) -> Result<()> {
-        let content = std::fs::read_to_string(&self.config_path)?;
-        let lines: Vec<&str> = content.lines().collect();
-        let mut new_content = String::new();
-        let mut i = 0;
+    /// 将主机配置更新为 `updated`，只改写取值变化的指令，保留块内的注释和其他指令
+    ///
+    /// 与 [`edit_host`](Self::edit_host) 不同，ConnectTimeout、自定义选项等全部字段都会按 `updated` 写入
+    pub fn update_host(&mut self, updated: &SshHost, password: Option<&str>) -> Result<()> {
+        let host = updated.host.as_str();
+        validate_host(host)?;
+        validate_hostname(updated.hostname.as_deref().unwrap_or_default())?;
+        if let Some(user) = &updated.user {
+            validate_username(user)?;
+        }
+        if let Some(identity_file) = &updated.identity_file {
+            warn_missing_identity_file(identity_file);
+        }
+        if let Some(port) = &updated.port {
+            validate_port(port)?;
+        }
+        if let Some(request_tty) = &updated.request_tty {
+            validate_request_tty(request_tty)?;
+        }
+        if let Some(address_family) = &updated.address_family {
+            validate_address_family(address_family)?;
+        }
+        if let Some(bind_address) = &updated.bind_address {
+            validate_bind_address(bind_address)?;
+        }
+        if let Some(permit_local_command) = &updated.permit_local_command {
+            validate_permit_local_command(permit_local_command)?;
+        }
+        if let Some(local_command) = &updated.local_command {
+            validate_local_command(local_command)?;
+        }
 
-        while i < lines.len() {
-            let line = lines[i];
-            let trimmed = line.trim();
+        let original = self
+            .get_host(host)?
+            .ok_or_else(|| SshConnError::HostNotFound {
+                host: host.to_string(),
+            })?;
 
-            if trimmed.starts_with("Host ") && !trimmed.starts_with("HostName") {
-                let hosts_in_line: Vec<&str> = trimmed[5..].split_whitespace().collect();
+        let store = self.store_for(host)?;
+        let content = store.read()?.unwrap_or_default();
+        let content = apply_host_changes(&content, &original, updated)?;
+        let mut changes = audit::describe_changes(&original, updated);
+        self.auto_backup()?;
+        store.write(&content)?;
 
-                if hosts_in_line.contains(&host) {
-                    // 跳过这个Host块的所有行
-                    i += 1;
-                    while i < lines.len() {
-                        let next_line = lines[i].trim();
-                        if next_line.starts_with("Host ") && !next_line.starts_with("HostName") {
-                            break;
-                        }
-                        i += 1;
-                    }
-                    continue;
-                }
+        if let Some(password) = password
+            && !password.is_empty()
+        {
+            self.password_manager.save_password(host, password)?;
+            if !changes.is_empty() {
+                changes.push_str(", ");
             }
-
-            new_content.push_str(line);
-            new_content.push('\n');
-            i += 1;
+            changes.push_str("Password=(stored)");
         }
 
-        std::fs::write(&self.config_path, new_content)?;
+        self.clear_cache();
+
+        log::info!("{}: {}", t("log_success_edit_host"), host);
+        self.audit(AuditOperation::Edit, host, &changes);
+        Ok(())
+    }
+
+    /// 从 `store` 中删除主机（内部方法，不删除密码）
+    fn delete_host_internal(&mut self, store: &dyn ConfigStore, host: &str) -> Result<()> {
+        let content = store.read()?.unwrap_or_default();
+        let new_content = remove_host_block(&content, host);
+        self.auto_backup()?;
+        store.write(&new_content)?;
         Ok(())
     }
 
-    /// 删除主机
+    /// 删除主机及其存储的密码
     pub fn delete_host(&mut self, host: &str) -> Result<()> {
+        self.remove_host(host, false)
+    }
+
+    /// 删除主机，`keep_password` 为true时保留存储的密码
+    pub fn remove_host(&mut self, host: &str, keep_password: bool) -> Result<()> {
         validate_host(host)?;
+        self.ensure_host_present(host)?;
+
+        let store = self.store_for(host)?;
+        self.delete_host_internal(store.as_ref(), host)?;
+        self.finish_host_removal(host, keep_password)
+    }
 
-        // 检查主机是否存在
+    /// 主机不存在时返回错误
+    fn ensure_host_present(&mut self, host: &str) -> Result<()> {
         if !self.host_exists(host)? {
             return Err(SshConnError::HostNotFound {
                 host: host.to_string(),
             });
         }
+        Ok(())
+    }
 
-        self.delete_host_internal(host)?;
-
+    /// 删除主机配置之后：按需删除密码、清除缓存并记录日志和审计日志
+    fn finish_host_removal(&mut self, host: &str, keep_password: bool) -> Result<()> {
         // 删除密码
-        self.password_manager.delete_password(host)?;
+        if !keep_password {
+            self.password_manager.delete_password(host)?;
+        }
 
         // 清除缓存
         self.clear_cache();
 
         log::info!("{}: {}", t("log_success_delete_host"), host);
+        self.audit(
+            AuditOperation::Delete,
+            host,
+            if keep_password { "Password=(kept)" } else { "" },
+        );
         Ok(())
     }
     /// 连接到主机
-    pub fn connect_host(&self, host: &str) -> Result<()> {
+    ///
+    /// `remote_command` 为本次连接临时指定的远程命令，优先于主机配置中的RemoteCommand；
+    /// `user` 为本次连接使用的用户名，优先于主机配置中的User
+    pub fn connect_host(
+        &mut self,
+        host: &str,
+        remote_command: Option<&str>,
+        user: Option<&str>,
+    ) -> Result<()> {
         validate_host(host)?;
 
         log::info!("{}: {}", t("log_connecting_to_host"), host);
 
         // 显示连接信息
-        println!("{}: {}", t("connecting_to_host"), host);
+        output::info(format_args!("{}: {}", t("connecting_to_host"), host));
 
-        self.connect_host_internal(host)
+        let spec = self.connect_command(host, remote_command, user)?;
+        self.add_key_to_agent_by_name(host)?;
+        self.run_ssh_command(&spec, host, false)
     }
 
-    /// 内部SSH连接方法
-    fn connect_host_internal(&self, host: &str) -> Result<()> {
-        self.execute_ssh_connection(host, true, DEFAULT_SSH_OPTIONS, false)
+    /// 连接前是否把主机的私钥载入ssh-agent（设置 `add_keys_to_agent`）
+    pub fn set_add_keys_to_agent(&mut self, enabled: bool) {
+        self.settings.add_keys_to_agent = enabled;
     }
 
-    /// 执行SSH连接的辅助方法
-    fn execute_ssh_connection(
-        &self,
+    /// 启用 `add_keys_to_agent` 时，把主机（含 `Host *` 中）的IdentityFile载入ssh-agent
+    fn add_key_to_agent(&self, host: &SshHost) {
+        if !self.settings.add_keys_to_agent {
+            return;
+        }
+        if let Some(identity_file) = self.effective_host(host).identity_file {
+            let passphrase = self
+                .password_manager
+                .get_key_passphrase(&identity_file)
+                .map(Zeroizing::new);
+            crate::agent::ensure_key_loaded(
+                &identity_file,
+                passphrase.as_deref().map(String::as_str),
+            );
+        }
+    }
+
+    fn add_key_to_agent_by_name(&mut self, host: &str) -> Result<()> {
+        if self.settings.add_keys_to_agent
+            && let Some(ssh_host) = self.get_host(host)?
+        {
+            self.add_key_to_agent(&ssh_host);
+        }
+        Ok(())
+    }
+
+    /// `connect` 执行的命令，`remote_command` 覆盖主机配置中的RemoteCommand，
+    /// `user` 覆盖主机配置中的User（见 [`CommandSpec::with_login_user`]）
+    ///
+    /// 配置中找不到的主机仍按别名交给ssh处理（如通过Include引入的主机）
+    pub fn connect_command(
+        &mut self,
         host: &str,
-        use_password: bool,
-        additional_options: &[&str],
-        use_exec: bool,
-    ) -> Result<()> {
-        let password = if use_password {
-            self.password_manager.get_password(host)
+        remote_command: Option<&str>,
+        user: Option<&str>,
+    ) -> Result<CommandSpec> {
+        validate_host(host)?;
+        if let Some(user) = user {
+            validate_username(user)?;
+        }
+
+        let mut ssh_host = self
+            .get_host(host)?
+            .unwrap_or_else(|| SshHost::new(host.to_string()));
+        if let Some(command) = remote_command {
+            ssh_host.remote_command = Some(command.to_string());
+        }
+        let spec = self.build_ssh_command(&ssh_host, &CommandMode::Connect);
+        Ok(match user {
+            Some(user) => spec.with_login_user(user, ssh_host.user.as_deref()),
+            None => spec,
+        })
+    }
+
+    /// 解析 `connect` 将要执行的命令和认证方式而不连接
+    ///
+    /// 与 `connect` 不同，主机必须存在于配置中；存储的密码会被实际读取一次，
+    /// 读取失败或为空时按ssh默认方式处理，与真实连接的行为一致
+    pub fn plan_connect(
+        &mut self,
+        host: &str,
+        remote_command: Option<&str>,
+        user: Option<&str>,
+    ) -> Result<ConnectPlan> {
+        validate_host(host)?;
+        let ssh_host = self
+            .get_host(host)?
+            .ok_or_else(|| SshConnError::HostNotFound {
+                host: host.to_string(),
+            })?;
+
+        let mut spec = self.connect_command(host, remote_command, user)?;
+        spec.use_password = spec.password_command.is_some()
+            || spec.use_password
+                && self
+                    .password_manager
+                    .get_password(host)
+                    .map(Zeroizing::new)
+                    .is_some_and(|password| !password.is_empty());
+
+        let auth = if let Some(command) = &spec.password_command {
+            AuthMethod::PasswordCommand(command.clone())
+        } else if spec.use_password {
+            AuthMethod::StoredPassword
+        } else if let Some(path) = ssh_host.identity_file {
+            AuthMethod::IdentityFile(path)
         } else {
-            None
+            AuthMethod::SshDefault
         };
+        Ok(ConnectPlan { spec, auth })
+    }
 
-        match password {
-            Some(password) if !password.is_empty() => {
-                log::info!("{}", t("using_stored_password_auto_login"));
-                if !use_exec {
-                    println!("{}", t("using_stored_password"));
-                }
+    /// 构建连接主机时执行的命令，不执行也不读取密码本身
+    ///
+    /// `connect`、TUI、sftp 以及 `cmd` 子命令都通过这里得到参数，保证打印的命令与实际执行的一致
+    pub fn build_ssh_command(&self, host: &SshHost, mode: &CommandMode) -> CommandSpec {
+        let (program, options) = match mode {
+            CommandMode::Connect => (
+                "ssh",
+                build_connect_options(
+                    &self.settings.ssh_options.connect_args(),
+                    host.remote_command.as_deref(),
+                    host.request_tty.as_deref(),
+                ),
+            ),
+            CommandMode::Tui { jump_host } => {
+                let options = build_connect_options(
+                    &self.settings.ssh_options.tui_args(),
+                    host.remote_command.as_deref(),
+                    host.request_tty.as_deref(),
+                );
+                let options = match jump_host {
+                    Some(jump_host) => build_jump_options(options, jump_host),
+                    None => options,
+                };
+                ("ssh", options)
+            }
+            CommandMode::Sftp => (
+                "sftp",
+                build_transfer_options(host, &self.settings.ssh_options.connect_args()),
+            ),
+            CommandMode::Probe => (
+                "ssh",
+                build_probe_options(host, &self.settings.ssh_options.connect_args()),
+            ),
+            CommandMode::AuthProbe => {
+                let mut options =
+                    build_probe_options(host, &self.settings.ssh_options.connect_args());
+                options.push("-o".to_string());
+                options.push("PreferredAuthentications=none".to_string());
+                ("ssh", options)
+            }
+        };
+        let probe = matches!(mode, CommandMode::Probe | CommandMode::AuthProbe);
+        // 探测立即退出，不需要保活
+        let options = if probe {
+            options
+        } else {
+            // `Host *` 中的ServerAliveInterval同样视为已设置，不用默认值覆盖
+            let keepalive = build_keepalive_options(
+                &self.effective_host(host),
+                &options,
+                self.settings.default_server_alive_interval,
+            );
+            [options, keepalive].concat()
+        };
 
-                let mut cmd = std::process::Command::new("sshpass");
-                cmd.arg("-p").arg(&password).arg("ssh");
+        let mut args = self.with_config_file(options);
+        args.push(host.host.clone());
+        // 探测不能交互输入密码，也不经由sshpass
+        if probe {
+            args.push("exit".to_string());
+        }
+        let password_command = host.password_command.clone().filter(|_| !probe);
+        CommandSpec {
+            program,
+            args,
+            use_password: !probe
+                && (password_command.is_some() || self.has_stored_password(&host.host)),
+            password_command,
+        }
+    }
 
-                for option in additional_options {
-                    cmd.arg(option);
-                }
-                cmd.arg(host);
+    /// 执行 [`build_ssh_command`](Self::build_ssh_command) 得到的命令
+    fn run_ssh_command(&self, spec: &CommandSpec, host: &str, use_exec: bool) -> Result<()> {
+        self.execute_ssh_program(
+            spec.program,
+            host,
+            spec.use_password,
+            spec.password_command.as_deref(),
+            &spec.args,
+            use_exec,
+        )
+    }
 
-                if use_exec {
-                    return exec_command(cmd);
-                } else {
-                    let status = cmd.status().map_err(|e| {
-                        SshConnError::SshConnectionError(
-                            t("sshpass_not_available").replace("{}", &e.to_string()),
-                        )
-                    })?;
-
-                    if let Some(code) = status.code() {
-                        if code == 255 {
-                            return Err(SshConnError::SshConnectionError(format!(
-                                "{}: {}",
-                                t("ssh_connection_failed_code"),
-                                code
-                            )));
-                        }
-                    }
-                }
-            }
-            _ => {
-                log::info!("{}", t("using_ssh_key_auth"));
-                if !use_exec {
-                    println!("{}", t("using_ssh_key_or_manual"));
-                }
+    /// 连接时使用的密码：配置了密码命令时运行命令获取，否则读取存储的密码
+    ///
+    /// 密码用完即清零，关闭缓存时内存中不会留下明文
+    fn connection_password(
+        &self,
+        host: &str,
+        use_password: bool,
+        password_command: Option<&str>,
+    ) -> Result<Option<Zeroizing<String>>> {
+        if !use_password {
+            return Ok(None);
+        }
+        match password_command {
+            Some(command) => crate::password::run_password_command(command).map(Some),
+            None => Ok(self.password_manager.get_password(host).map(Zeroizing::new)),
+        }
+    }
 
-                let mut cmd = std::process::Command::new("ssh");
-                for option in additional_options {
-                    cmd.arg(option);
-                }
-                cmd.arg(host);
+    /// 在新的终端窗口中连接主机，不占用当前终端
+    ///
+    /// 连接参数与 `connect` 相同；有存储的密码时通过 `sshpass -e` 从环境变量 `SSHPASS` 读取，
+    /// 密码不会出现在命令模板或命令行参数中
+    pub fn open_in_terminal(&mut self, host: &str) -> Result<()> {
+        validate_host(host)?;
 
-                if use_exec {
-                    return exec_command(cmd);
-                } else {
-                    let status = cmd.status().map_err(|e| {
-                        SshConnError::SshConnectionError(
-                            t("ssh_start_failed").replace("{}", &e.to_string()),
-                        )
-                    })?;
-
-                    if let Some(code) = status.code() {
-                        if code == 255 {
-                            return Err(SshConnError::SshConnectionError(format!(
-                                "{}: {}",
-                                t("ssh_connection_failed_code"),
-                                code
-                            )));
-                        }
-                    }
-                }
-            }
+        let template = self
+            .settings
+            .terminal_command
+            .clone()
+            .or_else(crate::utils::default_terminal_command)
+            .ok_or_else(|| SshConnError::SshConnectionError(t("error.terminal_not_found")))?;
+
+        let mut spec = self.connect_command(host, None, None)?;
+
+        // 经由 osascript/open 启动的终端不继承环境变量，此时不转交密码，由ssh提示输入
+        let inherits_env = !template.trim_start().starts_with("osascript")
+            && !template.trim_start().starts_with("open ");
+        spec.use_password &= inherits_env;
+        let password =
+            self.connection_password(host, spec.use_password, spec.password_command.as_deref())?;
+
+        let argv = crate::utils::expand_terminal_command(&template, &spec.argv())?;
+        let mut cmd = std::process::Command::new(&argv[0]);
+        cmd.args(&argv[1..])
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null());
+        if let Some(password) = &password {
+            cmd.env("SSHPASS", password.as_str());
         }
 
+        let mut child = cmd.spawn().map_err(|e| {
+            SshConnError::SshConnectionError(
+                t("error.terminal_spawn_failed")
+                    .replacen("{}", &argv[0], 1)
+                    .replacen("{}", &e.to_string(), 1),
+            )
+        })?;
+        // 在后台回收终端进程，避免留下僵尸进程
+        std::thread::spawn(move || {
+            let _ = child.wait();
+        });
+
+        log::info!("Opened {} in a new terminal via {}", host, argv[0]);
         Ok(())
     }
 
+    /// 执行ssh系列程序（ssh/sftp/scp），有存储的密码时通过sshpass调用
+    ///
+    /// `host` 仅用于查找存储的密码，`args` 为完整的命令参数
+    fn execute_ssh_program(
+        &self,
+        program: &str,
+        host: &str,
+        use_password: bool,
+        password_command: Option<&str>,
+        args: &[String],
+        use_exec: bool,
+    ) -> Result<()> {
+        let password = self.connection_password(host, use_password, password_command)?;
+        let (cmd, start_failed) = ssh_program_command(program, args, password.as_ref(), use_exec);
+
+        if use_exec {
+            return exec_command(cmd);
+        }
+        check_ssh_exit(wait_ssh_program(cmd, start_failed)?)
+    }
+
+    /// 连接主机，ssh很快以255退出（多为网络故障）时按 `policy` 等待后重试
+    ///
+    /// 密码只获取一次；会话持续超过 `policy.quick_failure` 后退出不再重试，避免重复进入已结束的会话
+    pub fn connect_host_with_retry(
+        &mut self,
+        host: &str,
+        remote_command: Option<&str>,
+        user: Option<&str>,
+        policy: &RetryPolicy,
+    ) -> Result<()> {
+        validate_host(host)?;
+        log::info!("{}: {}", t("log_connecting_to_host"), host);
+        output::info(format_args!("{}: {}", t("connecting_to_host"), host));
+
+        let spec = self.connect_command(host, remote_command, user)?;
+        self.add_key_to_agent_by_name(host)?;
+        let password =
+            self.connection_password(host, spec.use_password, spec.password_command.as_deref())?;
+        let mut first = true;
+        run_with_retry(
+            policy,
+            || {
+                // 认证方式提示只输出一次
+                let quiet = !std::mem::take(&mut first);
+                let (cmd, start_failed) =
+                    ssh_program_command(spec.program, &spec.args, password.as_ref(), quiet);
+                let started = std::time::Instant::now();
+                let code = wait_ssh_program(cmd, start_failed)?;
+                Ok((code, started.elapsed()))
+            },
+            std::thread::sleep,
+        )
+    }
+
     /// 检测主机密钥验证失败
     fn is_host_key_verification_failed(stderr: &str) -> bool {
         stderr.contains("Host key verification failed")
@@ -528,7 +1786,13 @@ impl ConfigManager {
 
     /// 处理主机密钥验证失败（TUI专用方法）
     /// 使用与TUI连接一致的方式，确保能够正常返回界面
-    pub fn handle_host_key_verification_failed_for_tui(&self, host: &str) -> Result<()> {
+    ///
+    /// 移除旧密钥后按 `session` 重新建立连接（shell或sftp）
+    pub fn handle_host_key_verification_failed_for_tui(
+        &self,
+        host: &str,
+        session: SessionKind,
+    ) -> Result<()> {
         log::info!("{}", t("tui_mode_host_key_failed"));
 
         // 从known_hosts中移除旧的主机密钥
@@ -547,70 +1811,37 @@ impl ConfigManager {
         }
 
         // 重新尝试连接，这次接受新的主机密钥，并自动带入存储的密码
-        println!("{}", t("reconnecting_accept_key"));
-
-        // 检查是否有存储的密码
-        match self.password_manager.get_password(host) {
-            Some(password) => {
-                log::info!("{}", t("log_using_stored_password_reconnect"));
-                println!("{}", t("using_stored_password"));
-
-                // 使用 sshpass 和存储的密码，保存主机密钥到known_hosts
-                let status = std::process::Command::new("sshpass")
-                    .arg("-p")
-                    .arg(&password)
-                    .arg("ssh")
-                    .args(TUI_SSH_OPTIONS)
-                    .arg(host)
-                    .status()
-                    .map_err(|e| {
-                        SshConnError::SshConnectionError(
-                            t("sshpass_not_available_simple").replace("{}", &e.to_string()),
-                        )
-                    })?;
-
-                // 使用与TUI连接一致的错误处理逻辑
-                if let Some(code) = status.code() {
-                    if code == 255 {
-                        return Err(SshConnError::SshConnectionError(format!(
-                            "{}: {}",
-                            t("ssh_connection_failed_code"),
-                            code
-                        )));
-                    }
-                    // 其他退出码（如1,2等）通常表示用户正常退出或远程命令执行结果，不是连接错误
-                }
-            }
-            None => {
-                log::info!("{}", t("log_no_stored_password_use_ssh"));
-                println!("{}", t("using_ssh_key_or_manual"));
-
-                // 使用普通 SSH 连接，保存主机密钥到known_hosts
-                let status = std::process::Command::new("ssh")
-                    .args(TUI_SSH_OPTIONS)
-                    .arg(host)
-                    .status()
-                    .map_err(|e| {
-                        SshConnError::SshConnectionError(
-                            t("ssh_start_failed").replace("{}", &e.to_string()),
-                        )
-                    })?;
-
-                // 使用与TUI连接一致的错误处理逻辑
-                if let Some(code) = status.code() {
-                    if code == 255 {
-                        return Err(SshConnError::SshConnectionError(format!(
-                            "{}: {}",
-                            t("ssh_connection_failed_code"),
-                            code
-                        )));
-                    }
-                    // 其他退出码（如1,2等）通常表示用户正常退出或远程命令执行结果，不是连接错误
-                }
-            }
+        output::info(t("reconnecting_accept_key"));
+
+        // SFTP会话使用同样接受新密钥的连接参数
+        if session == SessionKind::Sftp {
+            return self.sftp_host_for_tui(host);
         }
 
-        Ok(())
+        // 使用与TUI连接一致的错误处理逻辑：255表示连接失败，
+        // 其他退出码（如1,2等）通常表示用户正常退出或远程命令执行结果，不是连接错误
+        let (cmd, start_failed) =
+            self.reconnect_command(host, self.settings.ssh_options.tui_args());
+        check_ssh_exit(wait_ssh_program(cmd, start_failed)?)
+    }
+
+    /// 移除旧主机密钥后重新连接的命令，保存新的主机密钥到known_hosts
+    ///
+    /// 有存储的密码时经由 `sshpass -e` 执行，密码通过环境变量传递，不出现在进程参数中
+    fn reconnect_command(
+        &self,
+        host: &str,
+        options: Vec<String>,
+    ) -> (std::process::Command, &'static str) {
+        let password = self.password_manager.get_password(host).map(Zeroizing::new);
+        if password.is_some() {
+            log::info!("{}", t("log_using_stored_password_reconnect"));
+        } else {
+            log::info!("{}", t("log_no_stored_password_use_ssh"));
+        }
+        let mut args = self.with_config_file(options);
+        args.push(host.to_string());
+        ssh_program_command("ssh", &args, password.as_ref(), false)
     }
 
     /// 处理主机密钥验证失败（非交互模式，用于CLI）
@@ -633,86 +1864,76 @@ impl ConfigManager {
         }
 
         // 重新尝试连接，这次接受新的主机密钥，并自动带入存储的密码
-        println!("{}", t("reconnecting_accept_key"));
-
-        // 检查是否有存储的密码
-        match self.password_manager.get_password(host) {
-            Some(password) => {
-                log::info!("{}", t("log_using_stored_password_reconnect"));
-                println!("{}", t("using_stored_password"));
-
-                // CLI模式使用 exec，替换当前进程，保存主机密钥到known_hosts
-                let mut cmd = std::process::Command::new("sshpass");
-                cmd.arg("-p")
-                    .arg(&password)
-                    .arg("ssh")
-                    .args(DEFAULT_SSH_OPTIONS)
-                    .arg(host);
-
-                exec_command(cmd)
-            }
-            None => {
-                log::info!("{}", t("log_no_stored_password_use_ssh"));
-                println!("{}", t("using_ssh_key_or_manual"));
-
-                // CLI模式使用 exec，替换当前进程
-                let mut cmd = std::process::Command::new("ssh");
-                cmd.args(DEFAULT_SSH_OPTIONS).arg(host);
+        output::info(t("reconnecting_accept_key"));
 
-                exec_command(cmd)
-            }
-        }
+        // CLI模式使用 exec，替换当前进程
+        let (cmd, _) = self.reconnect_command(host, self.settings.ssh_options.connect_args());
+        exec_command(cmd)
     }
 
     /// 尝试连接主机并检测主机密钥验证失败（用于TUI模式）
     /// 返回 (success, host_key_error, error_message)
     pub fn try_connect_host(&self, host: &str) -> (bool, bool, Option<String>) {
-        let _ssh_host = match self
-            .hosts_cache
-            .as_ref()
-            .and_then(|hosts| hosts.iter().find(|h| h.host == host))
-        {
-            Some(host) => host,
-            None => return (false, false, Some(t("host_not_exists"))),
+        let (test_args, password_attempt) = match self.try_connect_plan(host) {
+            Ok(plan) => plan,
+            Err(outcome) => return outcome,
         };
 
         // 首先尝试使用密码连接（如果有密码）
-        if let Some(password) = self.password_manager.get_password(host) {
-            if !password.is_empty() {
-                let output = std::process::Command::new("sshpass")
-                    .arg("-p")
-                    .arg(&password)
-                    .arg("ssh")
-                    .args(TEST_SSH_OPTIONS)
-                    .arg(host)
-                    .arg("exit")
-                    .output();
-
-                match output {
-                    Ok(result) => {
-                        if result.status.success() {
-                            return (true, false, None);
-                        } else {
-                            let stderr = String::from_utf8_lossy(&result.stderr);
-                            if Self::is_host_key_verification_failed(&stderr) {
-                                return (false, true, Some(stderr.to_string()));
-                            }
-                        }
-                    }
-                    Err(_) => {
-                        // sshpass 不可用，继续尝试普通 SSH
-                    }
-                }
-            }
+        if let Some(mut cmd) = password_attempt
+            && let Ok(output) = cmd.output()
+            && let Some(outcome) = Self::password_attempt_outcome(&output)
+        {
+            return outcome;
         }
 
         // 尝试普通SSH连接
-        let output = std::process::Command::new("ssh")
-            .args(TEST_SSH_OPTIONS)
-            .arg(host)
-            .arg("exit")
-            .output();
+        Self::ssh_attempt_outcome(std::process::Command::new("ssh").args(&test_args).output())
+    }
+
+    /// 连接测试的ssh参数，以及有存储的密码时先尝试的 `sshpass -e ssh ...` 命令
+    /// （密码通过环境变量 `SSHPASS` 传递）；主机不在缓存中时直接返回测试结果
+    #[allow(clippy::type_complexity)]
+    fn try_connect_plan(
+        &self,
+        host: &str,
+    ) -> std::result::Result<
+        (Vec<String>, Option<std::process::Command>),
+        (bool, bool, Option<String>),
+    > {
+        if !self
+            .hosts_cache
+            .as_ref()
+            .is_some_and(|hosts| hosts.iter().any(|h| h.host == host))
+        {
+            return Err((false, false, Some(t("host_not_exists"))));
+        }
+
+        let test_args = build_test_args(
+            &self.with_config_file(self.settings.ssh_options.test_args()),
+            host,
+        );
+        let password_attempt = self
+            .password_manager
+            .get_password(host)
+            .map(Zeroizing::new)
+            .filter(|password| !password.is_empty())
+            .map(|password| ssh_program_command("ssh", &test_args, Some(&password), true).0);
+        Ok((test_args, password_attempt))
+    }
+
+    /// 使用密码（sshpass）测试的结果；失败但不是主机密钥问题时返回None，继续尝试普通SSH
+    fn password_attempt_outcome(output: &Output) -> Option<(bool, bool, Option<String>)> {
+        if output.status.success() {
+            return Some((true, false, None));
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Self::is_host_key_verification_failed(&stderr)
+            .then(|| (false, true, Some(stderr.to_string())))
+    }
 
+    /// 普通SSH测试的结果
+    fn ssh_attempt_outcome(output: std::io::Result<Output>) -> (bool, bool, Option<String>) {
         match output {
             Ok(result) => {
                 if result.status.success() {
@@ -740,18 +1961,120 @@ impl ConfigManager {
         Ok(hosts.iter().find(|h| h.host == host).cloned())
     }
 
-    /// 备份配置文件
+    /// 备份配置文件到 `<配置>.backup.<时间>`
     pub fn backup_config(&self) -> Result<String> {
-        let backup_path = format!(
+        let outcome =
+            self.backup_config_to(&self.default_backup_path(), &BackupOptions::default())?;
+        Ok(outcome.path.to_string_lossy().to_string())
+    }
+
+    /// 默认的备份路径 `<配置>.backup.<时间>`
+    pub fn default_backup_path(&self) -> PathBuf {
+        PathBuf::from(format!(
             "{}.backup.{}",
             self.config_path,
             chrono::Utc::now().format("%Y%m%d_%H%M%S")
+        ))
+    }
+
+    /// 备份配置文件到 `dest`，按 `options` 压缩或覆盖已有文件
+    pub fn backup_config_to(&self, dest: &Path, options: &BackupOptions) -> Result<BackupOutcome> {
+        let content = self.store.read()?.ok_or_else(|| {
+            SshConnError::Io(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                self.config_path.clone(),
+            ))
+        })?;
+        let outcome = write_backup_file(dest, content.as_bytes(), options)?;
+        log::info!(
+            "{}",
+            t("backup_created_at").replace("{}", &outcome.path.to_string_lossy())
         );
+        Ok(outcome)
+    }
+
+    /// 把存储的密码用口令加密后写入 `dest`，不压缩（密文无法压缩）
+    pub fn backup_passwords_to(
+        &self,
+        dest: &Path,
+        passphrase: &str,
+        options: &BackupOptions,
+    ) -> Result<BackupOutcome> {
+        let data = self.password_manager.export_encrypted(passphrase)?;
+        let options = BackupOptions {
+            compress: false,
+            ..options.clone()
+        };
+        write_backup_file(dest, &data, &options)
+    }
+
+    /// 在本次运行第一次写入配置前自动备份（设置 `auto_backup = false` 时不做）
+    ///
+    /// 配置不存在或只有空白时不备份；内容与最近一次自动备份相同时不生成新文件，
+    /// 备份后按 `auto_backup_keep` 删除最旧的自动备份。备份失败时不写入配置
+    fn auto_backup(&mut self) -> Result<()> {
+        if !self.auto_backup_pending() {
+            return Ok(());
+        }
+
+        if let Some(content) = self.store.read()?.filter(|c| !c.trim().is_empty()) {
+            write_auto_backup(&self.config_path, &content, self.settings.auto_backup_keep)?;
+        }
+
+        self.auto_backup_done = true;
+        Ok(())
+    }
 
-        std::fs::copy(&self.config_path, &backup_path)?;
-        log::info!("{}", t("backup_created_at").replace("{}", &backup_path));
+    /// 本次运行是否还需要在写入前自动备份
+    fn auto_backup_pending(&self) -> bool {
+        self.settings.auto_backup && !self.auto_backup_done
+    }
+
+    /// 当前配置文件的自动备份，按时间从旧到新排列
+    pub fn auto_backups(&self) -> Vec<PathBuf> {
+        list_auto_backups(&self.config_path)
+    }
+
+    /// 默认位置下当前配置文件的全部备份（手动和自动），按时间从旧到新排列
+    ///
+    /// 不含加密的密码导出文件
+    pub fn backups(&self) -> Vec<PathBuf> {
+        list_backups(&self.config_path)
+    }
+
+    /// 最近一次自动备份
+    pub fn latest_auto_backup(&self) -> Option<PathBuf> {
+        self.auto_backups().pop()
+    }
+
+    /// 最近一次备份（手动或自动）
+    pub fn latest_backup(&self) -> Option<PathBuf> {
+        self.backups().pop()
+    }
+
+    /// 当前配置文件的完整内容，文件不存在时为空
+    pub fn config_content(&self) -> Result<String> {
+        Ok(self.store.read()?.unwrap_or_default())
+    }
+
+    /// 用备份内容替换整个配置文件，替换前同样会自动备份当前配置
+    pub fn restore_config(&mut self, content: &str) -> Result<()> {
+        self.auto_backup()?;
+        self.store.write(content)?;
+        self.clear_cache();
+        Ok(())
+    }
 
-        Ok(backup_path)
+    /// 合并同名的Host块；有需要合并的块时先备份配置，返回合并结果和备份路径
+    pub fn dedupe_hosts(&mut self) -> Result<(DedupeResult, Option<String>)> {
+        let result = merge_duplicate_blocks(&self.config_content()?);
+        if result.merged.is_empty() {
+            return Ok((result, None));
+        }
+        let backup_path = self.backup_config()?;
+        self.store.write(&result.content)?;
+        self.clear_cache();
+        Ok((result, Some(backup_path)))
     }
 
     /// 检查主机是否存在于配置中
@@ -783,12 +2106,30 @@ impl ConfigManager {
             .collect())
     }
 
+    /// 搜索主机配置并按相关度排序
+    ///
+    /// 完全匹配Host名称的排在最前，其次是Host名称前缀匹配，最后是其他字段匹配，
+    /// 同一等级内保持配置文件中的顺序
+    pub fn search_hosts_ranked(&mut self, query: &str) -> Result<Vec<SshHost>> {
+        self.search_hosts_in_fields(query, SearchFields::all())
+    }
+
+    /// 只在指定字段中搜索主机配置，排序规则同 [`Self::search_hosts_ranked`]
+    pub fn search_hosts_in_fields(
+        &mut self,
+        query: &str,
+        fields: SearchFields,
+    ) -> Result<Vec<SshHost>> {
+        Ok(rank_hosts(self.get_hosts()?, query, fields))
+    }
+
     /// 不使用密码连接主机（仅测试连接）
     pub fn connect_host_without_password(&self, host: &str) -> Result<bool> {
         use std::process::Command;
 
         // 使用 SSH 的 ConnectTimeout 和 BatchMode 来快速测试连接
         let output = Command::new("ssh")
+            .args(self.with_config_file(Vec::new()))
             .args([
                 "-o",
                 "ConnectTimeout=5",
@@ -800,6 +2141,8 @@ impl ConfigManager {
                 "PubkeyAuthentication=yes",
                 "-o",
                 "StrictHostKeyChecking=no",
+                "-o",
+                "RemoteCommand=none",
                 host,
                 "exit",
             ])
@@ -815,10 +2158,2834 @@ impl ConfigManager {
     /// 为TUI模式提供的简化连接方法
     /// 直接执行SSH连接，优化终端显示效果
     pub fn connect_host_for_tui(&self, host: &str) -> Result<()> {
+        self.connect_host_via_for_tui(host, None, None)
+    }
+
+    /// TUI模式下连接主机，可临时指定一个跳板机（相当于 `ssh -J <jump_host>`）
+    /// 和本次连接使用的用户名（相当于 `ssh -l <user>`）
+    pub fn connect_host_via_for_tui(
+        &self,
+        host: &str,
+        jump_host: Option<&str>,
+        user: Option<&str>,
+    ) -> Result<()> {
         validate_host(host)?;
+        if let Some(user) = user {
+            validate_username(user)?;
+        }
 
         log::info!("{}: {}", t("log_tui_connecting_to_host"), host);
 
-        self.execute_ssh_connection(host, true, TUI_SSH_OPTIONS, false)
+        if let Some(jump_host) = jump_host {
+            validate_host(jump_host)?;
+        }
+
+        let ssh_host = self
+            .hosts_cache
+            .as_ref()
+            .and_then(|hosts| hosts.iter().find(|h| h.host == host))
+            .cloned()
+            .unwrap_or_else(|| SshHost::new(host.to_string()));
+        let spec = self.build_ssh_command(
+            &ssh_host,
+            &CommandMode::Tui {
+                jump_host: jump_host.map(str::to_string),
+            },
+        );
+        let spec = match user {
+            Some(user) => spec.with_login_user(user, ssh_host.user.as_deref()),
+            None => spec,
+        };
+        self.add_key_to_agent(&ssh_host);
+        self.run_ssh_command(&spec, host, false)
+    }
+
+    /// 打开SFTP会话（CLI模式，替换当前进程）
+    pub fn sftp_host(&mut self, host: &str) -> Result<()> {
+        validate_host(host)?;
+
+        let ssh_host = self
+            .get_host(host)?
+            .ok_or_else(|| SshConnError::HostNotFound {
+                host: host.to_string(),
+            })?;
+
+        log::info!("{}: {} (sftp)", t("log_connecting_to_host"), host);
+        output::info(format_args!("{}: {}", t("connecting_to_host"), host));
+
+        let spec = self.build_ssh_command(&ssh_host, &CommandMode::Sftp);
+        self.run_ssh_command(&spec, host, true)
+    }
+
+    /// 使用主机配置复制文件（CLI模式，替换当前进程）
+    ///
+    /// `source` 和 `destination` 中必须有且只有一个是 `<host>:<path>` 形式
+    pub fn scp(&mut self, source: &str, destination: &str, recursive: bool) -> Result<()> {
+        let source = ScpEndpoint::parse(source);
+        let destination = ScpEndpoint::parse(destination);
+
+        let host = match (&source, &destination) {
+            (ScpEndpoint::Remote { host, .. }, ScpEndpoint::Local(_))
+            | (ScpEndpoint::Local(_), ScpEndpoint::Remote { host, .. }) => host.clone(),
+            _ => return Err(SshConnError::InvalidArgument(t("scp_remote_required"))),
+        };
+        validate_host(&host)?;
+
+        let ssh_host = self
+            .get_host(&host)?
+            .ok_or_else(|| SshConnError::HostNotFound { host: host.clone() })?;
+
+        log::info!("{}: {} (scp)", t("log_connecting_to_host"), host);
+
+        let args = self.with_config_file(build_scp_args(
+            &ssh_host,
+            &self.settings.ssh_options.connect_args(),
+            &source,
+            &destination,
+            recursive,
+        ));
+        self.execute_ssh_program(
+            "scp",
+            &host,
+            true,
+            ssh_host.password_command.as_deref(),
+            &args,
+            true,
+        )
+    }
+
+    /// 为TUI模式提供的SFTP会话，结束后返回界面
+    pub fn sftp_host_for_tui(&self, host: &str) -> Result<()> {
+        validate_host(host)?;
+
+        let ssh_host = self
+            .hosts_cache
+            .as_ref()
+            .and_then(|hosts| hosts.iter().find(|h| h.host == host))
+            .ok_or_else(|| SshConnError::HostNotFound {
+                host: host.to_string(),
+            })?;
+
+        log::info!("{}: {} (sftp)", t("log_tui_connecting_to_host"), host);
+
+        let spec = self.build_ssh_command(ssh_host, &CommandMode::Sftp);
+        self.run_ssh_command(&spec, host, false)
+    }
+
+    /// 按会话类型在TUI模式下建立连接
+    pub fn open_session_for_tui(&self, host: &str, session: SessionKind) -> Result<()> {
+        match session {
+            SessionKind::Shell => self.connect_host_for_tui(host),
+            SessionKind::Sftp => self.sftp_host_for_tui(host),
+        }
+    }
+}
+
+/// 异步API（`async` 特性），供运行在tokio上的程序使用，避免同步的文件和子进程调用阻塞运行时
+///
+/// 配置文件通过 `tokio::fs` 读写，连接测试通过 `tokio::process` 运行ssh；
+/// 校验、生成配置块等规则与同步方法共用，结果与对应的同步方法相同。
+/// 密码的读写仍是同步的SQLite操作
+#[cfg(feature = "async")]
+impl ConfigManager {
+    /// [`get_hosts`](Self::get_hosts) 的异步版本
+    pub async fn get_hosts_async(&mut self) -> Result<&Vec<SshHost>> {
+        if let Some(ref hosts) = self.hosts_cache {
+            return Ok(hosts);
+        }
+
+        let content = crate::store::read_async(self.store.as_ref()).await?;
+        let hosts = match content.clone() {
+            // Include引入的文件在阻塞线程池中读取
+            Some(main) => {
+                let source = PathBuf::from(&self.config_path);
+                run_blocking(move || Ok(parse_config_with_includes(&main, &source))).await?
+            }
+            None => Vec::new(),
+        };
+        Ok(self.cache_hosts(hosts, content.as_deref()))
+    }
+
+    /// [`add_host_entry`](Self::add_host_entry) 的异步版本
+    pub async fn add_host_entry_async(
+        &mut self,
+        entry: &SshHost,
+        password: Option<&str>,
+    ) -> Result<()> {
+        let entry = validated_new_entry(entry)?;
+        self.get_hosts_async().await?;
+        self.ensure_host_absent(&entry.host)?;
+
+        self.auto_backup_async().await?;
+        crate::store::append_async(
+            self.store.as_ref(),
+            &format!("\n{}\n", entry.to_config_format()),
+        )
+        .await?;
+        self.finish_host_write(
+            &entry.host,
+            password,
+            AuditOperation::Add,
+            &audit::describe_host(&entry),
+        )
+    }
+
+    /// [`edit_host`](Self::edit_host) 的异步版本
+    #[allow(clippy::too_many_arguments)]
+    pub async fn edit_host_async(
+        &mut self,
+        host: &str,
+        hostname: Option<&str>,
+        user: Option<&str>,
+        port: Option<u16>,
+        proxy_command: Option<&str>,
+        identity_file: Option<&str>,
+        remote_command: Option<&str>,
+        request_tty: Option<&str>,
+        set_env: Option<&[(String, String)]>,
+        password: Option<&str>,
+        clear: &[OptionalField],
+    ) -> Result<()> {
+        let request_tty = validate_edit_fields(host, hostname, port, request_tty)?;
+        // 先载入主机列表，之后的查找都命中缓存
+        self.get_hosts_async().await?;
+        let (original, updated) = self.edited_host(
+            host,
+            hostname,
+            user,
+            port,
+            proxy_command,
+            identity_file,
+            remote_command,
+            request_tty.as_deref(),
+            set_env,
+            clear,
+        )?;
+
+        let store = self.store_for(host)?;
+        let content = crate::store::read_async(store.as_ref())
+            .await?
+            .unwrap_or_default();
+        let content = apply_host_changes(&content, &original, &updated)?;
+        self.auto_backup_async().await?;
+        crate::store::write_async(store.as_ref(), &content).await?;
+        let changes = audit::describe_changes(&original, &updated);
+        self.finish_host_write(host, password, AuditOperation::Edit, &changes)
+    }
+
+    /// [`delete_host`](Self::delete_host) 的异步版本
+    pub async fn delete_host_async(&mut self, host: &str) -> Result<()> {
+        self.remove_host_async(host, false).await
+    }
+
+    /// [`remove_host`](Self::remove_host) 的异步版本
+    pub async fn remove_host_async(&mut self, host: &str, keep_password: bool) -> Result<()> {
+        validate_host(host)?;
+        self.get_hosts_async().await?;
+        self.ensure_host_present(host)?;
+
+        let store = self.store_for(host)?;
+        self.delete_host_internal_async(store.as_ref(), host)
+            .await?;
+        self.finish_host_removal(host, keep_password)
+    }
+
+    /// [`try_connect_host`](Self::try_connect_host) 的异步版本
+    pub async fn try_connect_host_async(&self, host: &str) -> (bool, bool, Option<String>) {
+        let (test_args, password_attempt) = match self.try_connect_plan(host) {
+            Ok(plan) => plan,
+            Err(outcome) => return outcome,
+        };
+
+        if let Some(cmd) = password_attempt
+            && let Ok(output) = tokio::process::Command::from(cmd).output().await
+            && let Some(outcome) = Self::password_attempt_outcome(&output)
+        {
+            return outcome;
+        }
+
+        Self::ssh_attempt_outcome(
+            tokio::process::Command::new("ssh")
+                .args(&test_args)
+                .output()
+                .await,
+        )
+    }
+
+    async fn delete_host_internal_async(
+        &mut self,
+        store: &dyn ConfigStore,
+        host: &str,
+    ) -> Result<()> {
+        let content = crate::store::read_async(store).await?.unwrap_or_default();
+        let new_content = remove_host_block(&content, host);
+        self.auto_backup_async().await?;
+        crate::store::write_async(store, &new_content).await
+    }
+
+    async fn auto_backup_async(&mut self) -> Result<()> {
+        if !self.auto_backup_pending() {
+            return Ok(());
+        }
+
+        if let Some(content) = crate::store::read_async(self.store.as_ref())
+            .await?
+            .filter(|c| !c.trim().is_empty())
+        {
+            let config_path = self.config_path.clone();
+            let keep = self.settings.auto_backup_keep;
+            run_blocking(move || write_auto_backup(&config_path, &content, keep)).await?;
+        }
+
+        self.auto_backup_done = true;
+        Ok(())
+    }
+}
+
+/// 在tokio的阻塞线程池中运行同步操作
+#[cfg(feature = "async")]
+async fn run_blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T> + Send + 'static,
+) -> Result<T> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| SshConnError::Io(std::io::Error::other(e)))?
+}
+
+/// 两份配置之间按主机块比较的结果
+///
+/// 主机改名时表现为删除旧名称、新增新名称
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct HostDiffSummary {
+    /// 仅出现在新配置中的主机
+    pub added: Vec<String>,
+    /// 仅出现在旧配置中的主机
+    pub removed: Vec<String>,
+    /// 两边都有但选项不同的主机
+    pub modified: Vec<ModifiedHost>,
+}
+
+/// 选项有变化的主机
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ModifiedHost {
+    pub host: String,
+    pub changes: Vec<OptionChange>,
+}
+
+/// 单个指令的变化，可重复的指令（如SetEnv）按整组列出
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OptionChange {
+    pub option: String,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+impl HostDiffSummary {
+    /// 两份配置的主机是否完全一致
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// 解析两份配置文本并比较主机块，结果按各自在配置中的顺序排列
+pub fn diff_config_hosts(old: &str, new: &str) -> HostDiffSummary {
+    let old_hosts = parse_config_content(old);
+    let new_hosts = parse_config_content(new);
+    let find = |hosts: &[SshHost], name: &str| hosts.iter().position(|h| h.host == name);
+
+    let mut summary = HostDiffSummary::default();
+    for host in &new_hosts {
+        let Some(i) = find(&old_hosts, &host.host) else {
+            summary.added.push(host.host.clone());
+            continue;
+        };
+        let changes: Vec<OptionChange> = old_hosts[i]
+            .changed_options(host)
+            .into_iter()
+            .map(|(option, before, after)| OptionChange {
+                option,
+                before,
+                after,
+            })
+            .collect();
+        if !changes.is_empty() {
+            summary.modified.push(ModifiedHost {
+                host: host.host.clone(),
+                changes,
+            });
+        }
+    }
+    summary.removed = old_hosts
+        .iter()
+        .filter(|h| find(&new_hosts, &h.host).is_none())
+        .map(|h| h.host.clone())
+        .collect();
+    summary
+}
+
+/// 合并重复主机块时取值不同的选项，后出现的值生效
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeConflict {
+    pub host: String,
+    pub option: String,
+    /// 被覆盖的值
+    pub discarded: String,
+    /// 生效的值
+    pub kept: String,
+}
+
+/// [`merge_duplicate_blocks`] 的结果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DedupeResult {
+    /// 合并后的配置内容
+    pub content: String,
+    /// 被合并的主机及原有的块数
+    pub merged: Vec<(String, usize)>,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// 可以出现多次、每次都生效的指令，合并时全部保留
+const REPEATABLE_OPTIONS: [&str; 7] = [
+    "identityfile",
+    "certificatefile",
+    "localforward",
+    "remoteforward",
+    "dynamicforward",
+    "sendenv",
+    "setenv",
+];
+
+fn is_host_line(line: &str) -> bool {
+    let line = line.trim();
+    line.starts_with("Host ") && !line.starts_with("HostName")
+}
+
+/// 指令行的关键字（小写）和取值，支持 `Key Value` 与 `Key=Value`；注释中只识别ssh-conn的指令
+fn directive_parts(line: &str) -> Option<(String, &str)> {
+    if line.starts_with('#') {
+        let value = line.strip_prefix(crate::models::PASSWORD_COMMAND_DIRECTIVE)?;
+        return Some((
+            crate::models::PASSWORD_COMMAND_DIRECTIVE.to_string(),
+            value.trim(),
+        ));
+    }
+    let end = line.find([' ', '\t', '=']).unwrap_or(line.len());
+    let value = line[end..].trim_start_matches([' ', '\t', '=']).trim();
+    Some((line[..end].to_lowercase(), value))
+}
+
+/// 把同名的Host块合并为一个，放在第一次出现的位置
+///
+/// 按出现顺序收集各块的指令，同一指令后出现的值生效并记录冲突；可重复的指令和注释去重后全部保留。
+/// 只合并Host行完全相同且不含通配符的块，其余内容原样保留
+pub fn merge_duplicate_blocks(content: &str) -> DedupeResult {
+    let mut preamble = Vec::new();
+    let mut blocks: Vec<Vec<&str>> = Vec::new();
+    for line in content.lines() {
+        match blocks.last_mut() {
+            _ if is_host_line(line) => blocks.push(vec![line]),
+            Some(block) => block.push(line),
+            None => preamble.push(line),
+        }
+    }
+
+    let key = |block: &[&str]| {
+        block[0].trim()[5..]
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let mut groups: HashMap<String, Vec<usize>> = HashMap::new();
+    for (index, block) in blocks.iter().enumerate() {
+        let key = key(block);
+        if !key.contains(['*', '?', '!']) {
+            groups.entry(key).or_default().push(index);
+        }
+    }
+
+    let mut result = DedupeResult::default();
+    let mut lines: Vec<String> = preamble.iter().map(|l| l.to_string()).collect();
+    for (index, block) in blocks.iter().enumerate() {
+        let host = key(block);
+        let group = match groups.get(&host) {
+            Some(group) if group.len() > 1 => group,
+            _ => {
+                lines.extend(block.iter().map(|l| l.to_string()));
+                continue;
+            }
+        };
+        if group[0] != index {
+            continue;
+        }
+
+        let mut merged = vec![block[0].to_string()];
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        for line in group.iter().flat_map(|&i| &blocks[i][1..]) {
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+            match directive_parts(trimmed) {
+                Some((keyword, value)) if !REPEATABLE_OPTIONS.contains(&keyword.as_str()) => {
+                    match positions.get(&keyword) {
+                        Some(&position) => {
+                            let (_, previous) = directive_parts(merged[position].trim())
+                                .expect("merged directive lines always parse");
+                            if previous != value {
+                                result.conflicts.push(MergeConflict {
+                                    host: host.clone(),
+                                    option: trimmed[..trimmed.len() - value.len()]
+                                        .trim_end_matches([' ', '\t', '='])
+                                        .to_string(),
+                                    discarded: previous.to_string(),
+                                    kept: value.to_string(),
+                                });
+                                merged[position] = line.to_string();
+                            }
+                        }
+                        None => {
+                            positions.insert(keyword, merged.len());
+                            merged.push(line.to_string());
+                        }
+                    }
+                }
+                _ => {
+                    if !merged.iter().any(|l| l.trim() == trimmed) {
+                        merged.push(line.to_string());
+                    }
+                }
+            }
+        }
+        // 保留块之间的空行
+        if block.last().is_some_and(|l| l.trim().is_empty()) {
+            merged.push(String::new());
+        }
+        lines.extend(merged);
+        result.merged.push((host, group.len()));
+    }
+
+    // 删除末尾的块后不留下多余的空行
+    if !content.lines().last().is_some_and(|l| l.trim().is_empty()) {
+        while lines.last().is_some_and(|l| l.trim().is_empty()) {
+            lines.pop();
+        }
+    }
+    result.content = lines.iter().map(|l| format!("{}\n", l)).collect();
+    result
+}
+
+/// 解析SSH配置文本，忽略通配符主机
+pub(crate) fn parse_config_content(content: &str) -> Vec<SshHost> {
+    let mut hosts = Vec::new();
+    let mut current: Option<SshHost> = None;
+
+    for line in content.lines() {
+        let line = line.trim();
+
+        if line.starts_with("Host ") && !line.starts_with("HostName") {
+            if let Some(h) = current.take() {
+                hosts.push(h);
+            }
+
+            for h in line[5..].split_whitespace() {
+                if h != "*" {
+                    // 忽略通配符主机
+                    current = Some(SshHost::new(h.to_string()));
+                    break; // 只取第一个非通配符主机
+                }
+            }
+        } else if let Some(ref mut h) = current {
+            apply_option_line(h, line);
+        }
+    }
+
+    if let Some(h) = current {
+        hosts.push(h);
+    }
+
+    hosts
+}
+
+/// 把Host块中的一行选项写入 `h`，未知的选项记为自定义选项
+fn apply_option_line(h: &mut SshHost, line: &str) {
+    if let Some(stripped) = line.strip_prefix("HostName ") {
+        h.hostname = Some(stripped.trim().to_string());
+    } else if let Some(stripped) = line.strip_prefix("User ") {
+        h.user = Some(stripped.trim().to_string());
+    } else if let Some(stripped) = line.strip_prefix("Port ") {
+        h.port = Some(stripped.trim().to_string());
+    } else if let Some(stripped) = line.strip_prefix("ProxyCommand ") {
+        h.proxy_command = Some(stripped.trim().to_string());
+    } else if let Some(stripped) = line.strip_prefix("IdentityFile ") {
+        h.identity_file = Some(stripped.trim().to_string());
+    } else if let Some(stripped) = line.strip_prefix("ConnectTimeout ") {
+        h.connect_timeout = Some(stripped.trim().to_string());
+    } else if let Some(stripped) = line.strip_prefix("ServerAliveInterval ") {
+        h.server_alive_interval = Some(stripped.trim().to_string());
+    } else if let Some(stripped) = line.strip_prefix("RemoteCommand ") {
+        h.remote_command = Some(stripped.trim().to_string());
+    } else if let Some(stripped) = line.strip_prefix("RequestTTY ") {
+        h.request_tty = Some(stripped.trim().to_string());
+    } else if let Some(stripped) = line.strip_prefix("AddressFamily ") {
+        h.address_family = Some(stripped.trim().to_string());
+    } else if let Some(stripped) = line.strip_prefix("BindAddress ") {
+        h.bind_address = Some(stripped.trim().to_string());
+    } else if let Some(stripped) = line.strip_prefix("PermitLocalCommand ") {
+        h.permit_local_command = Some(stripped.trim().to_string());
+    } else if let Some(stripped) = line.strip_prefix("LocalCommand ") {
+        h.local_command = Some(stripped.trim().to_string());
+    } else if let Some(stripped) = line.strip_prefix("Ciphers ") {
+        h.ciphers = Some(stripped.trim().to_string());
+    } else if let Some(stripped) = line.strip_prefix("MACs ") {
+        h.macs = Some(stripped.trim().to_string());
+    } else if let Some(stripped) = line.strip_prefix("HostKeyAlgorithms ") {
+        h.host_key_algorithms = Some(stripped.trim().to_string());
+    } else if let Some(stripped) = line.strip_prefix("PubkeyAcceptedAlgorithms ") {
+        h.pubkey_accepted_algorithms = Some(stripped.trim().to_string());
+    } else if let Some(stripped) = line.strip_prefix(crate::models::PASSWORD_COMMAND_DIRECTIVE) {
+        let command = stripped.trim();
+        if !command.is_empty() {
+            h.password_command = Some(command.to_string());
+        }
+    } else if let Some(stripped) = line.strip_prefix("SetEnv ") {
+        // 多个SetEnv行累加，格式不正确的条目跳过
+        for entry in split_config_args(stripped) {
+            match validate_env_var(&entry) {
+                Ok(pair) => h.set_env.push(pair),
+                Err(_) => log::warn!("Ignoring invalid SetEnv entry: {}", entry),
+            }
+        }
+    } else {
+        // 处理其他自定义选项
+        if let Some(space_pos) = line.find(' ') {
+            let key = line[..space_pos].trim().to_string();
+            let value = line[space_pos + 1..].trim().to_string();
+            if !key.is_empty() && !value.is_empty() {
+                h.custom_options.insert(key, value);
+            }
+        }
+    }
+}
+
+/// 主配置中 `Host *` 块的默认选项，没有该块时返回None
+///
+/// 多个 `Host *` 块按出现顺序合并，同一选项先出现的值生效；
+/// 只识别模式恰好为 `*` 的块，`Host * !bastion` 等带条件的块不计入
+pub(crate) fn parse_wildcard_defaults(content: &str) -> Option<SshHost> {
+    let mut defaults: Option<SshHost> = None;
+    let mut current: Option<SshHost> = None;
+    for line in content.lines().map(str::trim) {
+        if is_host_line(line) {
+            if let Some(block) = current.take() {
+                defaults = Some(match defaults {
+                    Some(earlier) => earlier.with_defaults(&block),
+                    None => block,
+                });
+            }
+            if line[5..].split_whitespace().eq(["*"]) {
+                current = Some(SshHost::new("*".to_string()));
+            }
+        } else if let Some(ref mut h) = current {
+            apply_option_line(h, line);
+        }
+    }
+    if let Some(block) = current {
+        defaults = Some(match defaults {
+            Some(earlier) => earlier.with_defaults(&block),
+            None => block,
+        });
+    }
+    defaults
+}
+
+/// 解析内存中的SSH配置文本（如从密钥管理服务取得的内容），忽略通配符主机
+///
+/// 文本不在任何文件中，无法确定Include的相对路径，含Include指令时返回错误；
+/// 需要跟随Include时通过 [`ConfigManager`] 读取配置文件
+///
+/// ```
+/// use ssh_conn::config::parse_ssh_config_str;
+///
+/// let hosts = parse_ssh_config_str("Host web\n    HostName 10.0.0.5\n    User deploy\n").unwrap();
+/// assert_eq!(hosts[0].host, "web");
+/// assert_eq!(hosts[0].user.as_deref(), Some("deploy"));
+///
+/// assert!(parse_ssh_config_str("Include conf.d/*\n").is_err());
+/// ```
+pub fn parse_ssh_config_str(content: &str) -> Result<Vec<SshHost>> {
+    if let Some(value) = include_values(content).next() {
+        return Err(SshConnError::ConfigParse(
+            t("error.include_unsupported").replace("{}", value),
+        ));
+    }
+    Ok(parse_config_content(content))
+}
+
+/// 从 `reader` 读取SSH配置并解析，规则同 [`parse_ssh_config_str`]
+pub fn parse_ssh_config_reader(mut reader: impl std::io::Read) -> Result<Vec<SshHost>> {
+    let mut content = String::new();
+    reader.read_to_string(&mut content)?;
+    parse_ssh_config_str(&content)
+}
+
+/// 配置文本中各Include指令的取值
+fn include_values(content: &str) -> impl Iterator<Item = &str> {
+    content
+        .lines()
+        .filter_map(|line| directive_parts(line.trim()))
+        .filter(|(keyword, _)| keyword == "include")
+        .map(|(_, value)| value)
+}
+
+/// Include嵌套的最大深度，与OpenSSH相同
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// 解析 `source` 文件的内容并跟随其中的Include指令，记录每台主机所在的文件
+///
+/// 本文件中的主机在前，被引入文件中的主机按Include出现的顺序排在其后；
+/// 找不到或读取失败的文件跳过
+pub(crate) fn parse_config_with_includes(content: &str, source: &Path) -> Vec<SshHost> {
+    let mut hosts = Vec::new();
+    collect_included_hosts(content, source, 0, &mut hosts);
+    hosts
+}
+
+fn collect_included_hosts(content: &str, source: &Path, depth: usize, hosts: &mut Vec<SshHost>) {
+    hosts.extend(parse_config_content(content).into_iter().map(|mut host| {
+        host.source_file = Some(source.to_path_buf());
+        host
+    }));
+    if depth >= MAX_INCLUDE_DEPTH {
+        log::warn!("Include nested too deeply in {}", source.display());
+        return;
+    }
+
+    for pattern in include_values(content).flat_map(split_config_args) {
+        for path in resolve_include(&pattern) {
+            match std::fs::read_to_string(&path) {
+                Ok(included) => collect_included_hosts(&included, &path, depth + 1, hosts),
+                Err(e) => log::warn!("Skipping included file {}: {}", path.display(), e),
+            }
+        }
+    }
+}
+
+/// Include指令的一个参数对应的文件
+///
+/// 与OpenSSH相同，`~` 和环境变量会被展开（见 [`expand_path`]），相对路径相对于 `~/.ssh`；
+/// 文件名中的 `*`/`?` 按通配符匹配目录中的文件，结果按名称排序
+fn resolve_include(pattern: &str) -> Vec<PathBuf> {
+    let expanded = expand_path(pattern);
+    let path = if expanded.is_absolute() {
+        expanded
+    } else {
+        dirs::home_dir()
+            .unwrap_or_default()
+            .join(".ssh")
+            .join(expanded)
+    };
+
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if !name.contains(['*', '?']) {
+        return if path.is_file() {
+            vec![path]
+        } else {
+            Vec::new()
+        };
+    }
+
+    let Some(dir) = path.parent() else {
+        return Vec::new();
+    };
+    let mut matches: Vec<PathBuf> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| glob_match(&name, &entry.file_name().to_string_lossy()))
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect();
+    matches.sort();
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SearchField;
+    use crate::store::MemoryConfigStore;
+
+    fn default_settings() -> Settings {
+        Settings::default()
+    }
+
+    fn argv(args: &[String]) -> Vec<&str> {
+        args.iter().map(|s| s.as_str()).collect()
+    }
+
+    #[test]
+    fn test_diff_config_hosts() {
+        let current = "Host web\n    HostName 10.0.0.5\n\nHost db\n    HostName 10.0.0.7\n";
+
+        // 内容相同（仅空白不同）时没有差异
+        let same = "Host web\n  HostName 10.0.0.5\nHost db\n  HostName 10.0.0.7\n";
+        assert!(diff_config_hosts(current, same).is_empty());
+
+        let added = format!("{}\nHost cache\n    HostName 10.0.0.9\n", current);
+        let summary = diff_config_hosts(current, &added);
+        assert_eq!(summary.added, ["cache"]);
+        assert!(summary.removed.is_empty() && summary.modified.is_empty());
+
+        let removed = "Host web\n    HostName 10.0.0.5\n";
+        let summary = diff_config_hosts(current, removed);
+        assert_eq!(summary.removed, ["db"]);
+        assert!(summary.added.is_empty() && summary.modified.is_empty());
+
+        let modified =
+            "Host web\n    HostName 10.0.0.5\n    User deploy\n\nHost db\n    HostName 10.0.0.8\n";
+        let summary = diff_config_hosts(current, modified);
+        let names: Vec<&str> = summary.modified.iter().map(|m| m.host.as_str()).collect();
+        assert_eq!(names, ["web", "db"]);
+        assert!(summary.added.is_empty() && summary.removed.is_empty());
+    }
+
+    #[test]
+    fn test_diff_config_hosts_rename_is_remove_and_add() {
+        let before = "Host web\n    HostName 10.0.0.5\n\nHost old-db\n    HostName 10.0.0.7\n";
+        let after = "Host web\n    HostName 10.0.0.5\n\nHost new-db\n    HostName 10.0.0.7\n";
+        let summary = diff_config_hosts(before, after);
+        assert_eq!(summary.removed, ["old-db"]);
+        assert_eq!(summary.added, ["new-db"]);
+        assert!(summary.modified.is_empty());
+    }
+
+    #[test]
+    fn test_diff_config_hosts_single_option_change() {
+        let before = "Host jump\n    HostName 10.0.0.1\n    User ops\n    Port 22\n";
+        let after = "Host jump\n    HostName 10.0.0.1\n    User ops\n    Port 2222\n";
+        let summary = diff_config_hosts(before, after);
+        assert_eq!(
+            summary.modified,
+            [ModifiedHost {
+                host: "jump".to_string(),
+                changes: vec![OptionChange {
+                    option: "Port".to_string(),
+                    before: vec!["22".to_string()],
+                    after: vec!["2222".to_string()],
+                }],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_build_connect_options_without_remote_command() {
+        let base = default_settings().ssh_options.connect_args();
+        let options = build_connect_options(&base, None, None);
+        assert_eq!(options, base);
+
+        // 空白命令视为未设置
+        let options = build_connect_options(&base, Some("  "), None);
+        assert_eq!(options, base);
+    }
+
+    #[test]
+    fn test_build_connect_options_with_remote_command() {
+        let base = default_settings().ssh_options.connect_args();
+        let options = build_connect_options(&base, Some("tmux new -A -s main"), None);
+        assert_eq!(
+            argv(&options[base.len()..]),
+            [
+                "-o",
+                "RemoteCommand=tmux new -A -s main",
+                "-o",
+                "RequestTTY=yes"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_connect_options_tui_keeps_forced_tty() {
+        let base = default_settings().ssh_options.tui_args();
+        let options = build_connect_options(&base, Some("tmux attach"), None);
+        assert!(options.contains(&"RemoteCommand=tmux attach".to_string()));
+        assert!(!options.contains(&"RequestTTY=yes".to_string()));
+        assert!(options.contains(&"RequestTTY=force".to_string()));
+    }
+
+    #[test]
+    fn test_build_connect_options_host_request_tty_overrides_default() {
+        let base = default_settings().ssh_options.tui_args();
+
+        // no：去掉TUI默认的强制TTY，适合只做端口转发的主机
+        let options = build_connect_options(&base, None, Some("no"));
+        assert_eq!(
+            argv(&options),
+            [
+                "-o",
+                "StrictHostKeyChecking=accept-new",
+                "-o",
+                "LogLevel=ERROR",
+                "-o",
+                "RequestTTY=no"
+            ]
+        );
+
+        // 有远程命令时不再额外追加 RequestTTY=yes
+        let options = build_connect_options(&base, Some("tmux attach"), Some("auto"));
+        assert_eq!(
+            argv(&options[options.len() - 4..]),
+            ["-o", "RequestTTY=auto", "-o", "RemoteCommand=tmux attach"]
+        );
+        assert!(!options.iter().any(|o| o == "-tt" || o == "RequestTTY=yes"));
+    }
+
+    #[test]
+    fn test_crypto_options_round_trip() {
+        let store = MemoryConfigStore::with_content(
+            "Host switch\n    HostName 10.0.0.9\n    Ciphers aes128-cbc,aes128-ctr\n    MACs hmac-md5\n    HostKeyAlgorithms +ssh-rsa\n    PubkeyAcceptedAlgorithms +ssh-rsa\n",
+        );
+        let mut manager = memory_manager(&store);
+
+        let host = manager.get_host("switch").unwrap().unwrap();
+        assert_eq!(host.ciphers.as_deref(), Some("aes128-cbc,aes128-ctr"));
+        assert_eq!(host.host_key_algorithms.as_deref(), Some("+ssh-rsa"));
+        assert!(host.custom_options.is_empty());
+        let config = host.to_config_format();
+        for line in [
+            "    Ciphers aes128-cbc,aes128-ctr",
+            "    MACs hmac-md5",
+            "    HostKeyAlgorithms +ssh-rsa",
+            "    PubkeyAcceptedAlgorithms +ssh-rsa",
+        ] {
+            assert!(
+                config.lines().any(|l| l == line),
+                "missing {line:?} in {config}"
+            );
+        }
+        assert_eq!(host.legacy_crypto_warnings().len(), 4);
+
+        // 编辑其他字段时保留加密选项
+        manager
+            .edit_host(
+                "switch",
+                None,
+                Some("admin"),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &[],
+            )
+            .unwrap();
+        let host = manager.get_host("switch").unwrap().unwrap();
+        assert_eq!(host.macs.as_deref(), Some("hmac-md5"));
+        assert_eq!(host.pubkey_accepted_algorithms.as_deref(), Some("+ssh-rsa"));
+    }
+
+    #[test]
+    fn test_build_ssh_command_modes() {
+        let store = MemoryConfigStore::with_content(
+            "Host web\n    HostName 10.0.0.5\n    Port 2222\n    RemoteCommand tmux attach\n",
+        );
+        let mut manager = memory_manager(&store);
+
+        let spec = manager.connect_command("web", None, None).unwrap();
+        assert_eq!(spec.program, "ssh");
+        assert!(!spec.use_password);
+        assert_eq!(
+            spec.to_command_line(),
+            "ssh -F /nonexistent/ssh_config -o StrictHostKeyChecking=accept-new -o LogLevel=ERROR \
+             -o 'RemoteCommand=tmux attach' -o RequestTTY=yes \
+             -o ServerAliveInterval=60 -o ServerAliveCountMax=3 web"
+        );
+
+        // 临时指定的远程命令覆盖主机配置
+        let spec = manager
+            .connect_command("web", Some("uptime"), None)
+            .unwrap();
+        assert!(spec.args.contains(&"RemoteCommand=uptime".to_string()));
+
+        // 配置中没有的主机按别名交给ssh
+        let spec = manager.connect_command("other", None, None).unwrap();
+        assert_eq!(spec.args.last().map(String::as_str), Some("other"));
+
+        let host = manager.get_host("web").unwrap().unwrap();
+        let spec = manager.build_ssh_command(
+            &host,
+            &CommandMode::Tui {
+                jump_host: Some("bastion".to_string()),
+            },
+        );
+        // TUI默认强制分配TTY
+        assert!(spec.args.contains(&"-tt".to_string()));
+        assert!(spec.args.windows(2).any(|w| w == ["-J", "bastion"]));
+        assert_eq!(spec.args.last().map(String::as_str), Some("web"));
+
+        let spec = manager.build_ssh_command(&host, &CommandMode::Sftp);
+        assert_eq!(spec.program, "sftp");
+        assert!(spec.args.windows(2).any(|w| w == ["-P", "2222"]));
+        assert!(spec.args.contains(&"RemoteCommand=none".to_string()));
+    }
+
+    #[test]
+    fn test_merge_duplicate_blocks() {
+        let content = "# managed by hand\n\nHost web\n    HostName 10.0.0.5\n    Port 2222\n\nHost db\n    HostName 10.0.0.7\n\nHost web\n    HostName 10.0.0.6\n    User admin\n    LocalForward 8080 localhost:80\n\nHost *\n    User root\n\nHost *\n    Port 22\n";
+        let result = merge_duplicate_blocks(content);
+        assert_eq!(result.merged, [("web".to_string(), 2)]);
+        assert_eq!(
+            result.conflicts,
+            [MergeConflict {
+                host: "web".to_string(),
+                option: "HostName".to_string(),
+                discarded: "10.0.0.5".to_string(),
+                kept: "10.0.0.6".to_string(),
+            }]
+        );
+        // 合并后的块在第一次出现的位置，其他主机和通配符块保持不变
+        assert_eq!(
+            result.content,
+            "# managed by hand\n\nHost web\n    HostName 10.0.0.6\n    Port 2222\n    User admin\n    LocalForward 8080 localhost:80\n\nHost db\n    HostName 10.0.0.7\n\nHost *\n    User root\n\nHost *\n    Port 22\n"
+        );
+
+        let hosts = parse_config_content(&result.content);
+        let web = hosts.iter().find(|h| h.host == "web").unwrap();
+        assert_eq!(web.port.as_deref(), Some("2222"));
+        assert_eq!(web.user.as_deref(), Some("admin"));
+        assert_eq!(hosts.iter().filter(|h| h.host == "web").count(), 1);
+
+        // 没有重复时内容不变
+        let unique = "Host web\n    Port 22\n\nHost db\n    User root\n";
+        let result = merge_duplicate_blocks(unique);
+        assert!(result.merged.is_empty());
+        assert_eq!(result.content, unique);
+    }
+
+    #[test]
+    fn test_host_remote_command_connect_assembly() {
+        let store = MemoryConfigStore::with_content(
+            "Host work\n    HostName 10.0.0.5\n    User admin\n    RemoteCommand tmux attach\n",
+        );
+        let mut manager = memory_manager(&store);
+        let host = manager.get_host("work").unwrap().unwrap();
+        assert_eq!(host.remote_command.as_deref(), Some("tmux attach"));
+        assert_eq!(
+            host.get_full_ssh_command(),
+            "ssh -t admin@10.0.0.5 'tmux attach'"
+        );
+
+        let spec = manager.connect_command("work", None, None).unwrap();
+        assert!(
+            spec.args
+                .windows(2)
+                .any(|w| w == ["-o", "RemoteCommand=tmux attach"])
+        );
+        assert!(spec.args.windows(2).any(|w| w == ["-o", "RequestTTY=yes"]));
+
+        // 临时指定的远程命令优先于主机保存的命令
+        let spec = manager.connect_command("work", Some("htop"), None).unwrap();
+        assert!(spec.args.contains(&"RemoteCommand=htop".to_string()));
+        assert!(!spec.args.contains(&"RemoteCommand=tmux attach".to_string()));
+    }
+
+    #[test]
+    fn test_keepalive_options_layering() {
+        let store = MemoryConfigStore::with_content(
+            "Host plain\n    HostName 10.0.0.5\n\nHost own\n    HostName 10.0.0.6\n    ServerAliveInterval 15\n\nHost count\n    HostName 10.0.0.7\n    ServerAliveCountMax 10\n",
+        );
+        let mut manager = memory_manager(&store);
+        let keepalive = |spec: &CommandSpec| -> Vec<String> {
+            spec.args
+                .iter()
+                .filter(|a| a.starts_with("ServerAlive"))
+                .cloned()
+                .collect()
+        };
+
+        // 全局设置补上主机缺少的保活参数，TUI和sftp同样适用，探测不需要
+        let plain = manager.get_host("plain").unwrap().unwrap();
+        for mode in [
+            CommandMode::Connect,
+            CommandMode::Tui { jump_host: None },
+            CommandMode::Sftp,
+        ] {
+            assert_eq!(
+                keepalive(&manager.build_ssh_command(&plain, &mode)),
+                ["ServerAliveInterval=60", "ServerAliveCountMax=3"]
+            );
+        }
+        assert!(keepalive(&manager.build_ssh_command(&plain, &CommandMode::Probe)).is_empty());
+
+        // 主机自己的设置优先，由ssh从配置文件读取
+        let own = manager.get_host("own").unwrap().unwrap();
+        assert!(keepalive(&manager.build_ssh_command(&own, &CommandMode::Connect)).is_empty());
+        let count = manager.get_host("count").unwrap().unwrap();
+        assert_eq!(
+            keepalive(&manager.build_ssh_command(&count, &CommandMode::Connect)),
+            ["ServerAliveInterval=60"]
+        );
+
+        // ssh_options中的设置同样优先；设为0时不添加
+        manager.settings.ssh_options.connect.extra = vec!["-o ServerAliveInterval=30".to_string()];
+        assert_eq!(
+            keepalive(&manager.build_ssh_command(&plain, &CommandMode::Connect)),
+            ["ServerAliveInterval=30"]
+        );
+        manager.settings.ssh_options.connect.extra.clear();
+        manager.settings.default_server_alive_interval = 0;
+        assert!(keepalive(&manager.build_ssh_command(&plain, &CommandMode::Connect)).is_empty());
+    }
+
+    /// 命令的argv（程序和参数）以及SSHPASS环境变量
+    fn command_argv(cmd: &std::process::Command) -> (Vec<String>, Option<String>) {
+        let argv = std::iter::once(cmd.get_program())
+            .chain(cmd.get_args())
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        let sshpass = cmd
+            .get_envs()
+            .find(|(key, _)| *key == "SSHPASS")
+            .and_then(|(_, value)| value)
+            .map(|value| value.to_string_lossy().into_owned());
+        (argv, sshpass)
+    }
+
+    #[test]
+    fn test_host_key_reconnect_keeps_password_out_of_argv() {
+        let store = MemoryConfigStore::with_content("Host web\n    HostName 10.0.0.5\n");
+        let mut manager = memory_manager(&store);
+        manager
+            .password_manager
+            .save_password("web", "hunter2")
+            .unwrap();
+
+        // TUI和CLI（exec）在移除旧主机密钥后都通过这里重新连接
+        for options in [
+            manager.settings.ssh_options.tui_args(),
+            manager.settings.ssh_options.connect_args(),
+        ] {
+            let (cmd, _) = manager.reconnect_command("web", options);
+            let (argv, sshpass) = command_argv(&cmd);
+            assert_eq!(argv[..3], ["sshpass", "-e", "ssh"]);
+            assert_eq!(argv.last().map(String::as_str), Some("web"));
+            assert!(
+                !argv.iter().any(|arg| arg.contains("hunter2")),
+                "{:?}",
+                argv
+            );
+            assert_eq!(sshpass.as_deref(), Some("hunter2"));
+        }
+
+        // 没有存储的密码时直接运行ssh
+        manager.password_manager.delete_password("web").unwrap();
+        let (cmd, _) = manager.reconnect_command("web", manager.settings.ssh_options.tui_args());
+        let (argv, sshpass) = command_argv(&cmd);
+        assert_eq!(argv[0], "ssh");
+        assert_eq!(sshpass, None);
+    }
+
+    #[test]
+    fn test_try_connect_password_attempt_keeps_password_out_of_argv() {
+        let store = MemoryConfigStore::with_content("Host web\n    HostName 10.0.0.5\n");
+        let mut manager = memory_manager(&store);
+        manager
+            .password_manager
+            .save_password("web", "hunter2")
+            .unwrap();
+        manager.get_hosts().unwrap();
+
+        // try_connect_host 和 try_connect_host_async 共用这里的命令
+        let (test_args, attempt) = manager.try_connect_plan("web").unwrap();
+        let (argv, sshpass) = command_argv(&attempt.expect("password attempt"));
+        assert_eq!(argv[..3], ["sshpass", "-e", "ssh"]);
+        assert_eq!(argv[3..], test_args[..]);
+        assert!(
+            !argv.iter().any(|arg| arg.contains("hunter2")),
+            "{:?}",
+            argv
+        );
+        assert_eq!(sshpass.as_deref(), Some("hunter2"));
+
+        manager.password_manager.delete_password("web").unwrap();
+        let (_, attempt) = manager.try_connect_plan("web").unwrap();
+        assert!(attempt.is_none());
+    }
+
+    #[test]
+    fn test_build_probe_command_for_proxied_host() {
+        let store = MemoryConfigStore::with_content(
+            "Host bastion\n    HostName 203.0.113.1\n\nHost internal\n    HostName 10.1.0.5\n    ProxyJump bastion\n    ConnectTimeout 3\n    RemoteCommand tmux attach\n",
+        );
+        let mut manager = memory_manager(&store);
+        manager
+            .password_manager
+            .save_password("internal", "hunter2")
+            .unwrap();
+
+        let host = manager.get_host("internal").unwrap().unwrap();
+        let spec = manager.build_ssh_command(&host, &CommandMode::Probe);
+
+        // 跳板机交给ssh按配置文件处理，不在命令行中展开；有存储的密码也不使用sshpass
+        assert!(!spec.use_password);
+        assert_eq!(
+            spec.to_command_line(),
+            "ssh -F /nonexistent/ssh_config -o StrictHostKeyChecking=accept-new -o LogLevel=ERROR \
+             -o BatchMode=yes -o ConnectTimeout=3 -o RemoteCommand=none -o RequestTTY=no internal exit"
+        );
+
+        let spec = manager.build_ssh_command(&host, &CommandMode::AuthProbe);
+        assert!(!spec.use_password);
+        assert!(
+            spec.to_command_line()
+                .ends_with("-o RequestTTY=no -o PreferredAuthentications=none internal exit"),
+            "{}",
+            spec.to_command_line()
+        );
+    }
+
+    #[test]
+    fn test_build_ssh_command_never_contains_password() {
+        let store = MemoryConfigStore::new();
+        let mut manager = memory_manager(&store);
+        manager
+            .add_host(
+                "db",
+                "10.0.0.7",
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &[],
+                Some("hunter2"),
+            )
+            .unwrap();
+
+        let spec = manager.connect_command("db", None, None).unwrap();
+        assert!(spec.use_password);
+        assert_eq!(spec.argv()[..3], ["sshpass", "-e", "ssh"]);
+        assert!(!spec.to_command_line().contains("hunter2"));
+        assert!(!spec.argv().iter().any(|arg| arg.contains("hunter2")));
+    }
+
+    #[test]
+    fn test_plan_connect_output() {
+        let store = MemoryConfigStore::with_content(
+            "Host web\n    HostName 10.0.0.5\n    IdentityFile ~/.ssh/web\n\nHost plain\n    HostName 10.0.0.6\n",
+        );
+        let mut manager = memory_manager(&store);
+        manager
+            .add_host(
+                "db",
+                "10.0.0.7",
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &[],
+                Some("hunter2"),
+            )
+            .unwrap();
+
+        let plan = manager.plan_connect("web", Some("uptime"), None).unwrap();
+        assert_eq!(
+            plan.auth,
+            AuthMethod::IdentityFile("~/.ssh/web".to_string())
+        );
+        assert_eq!(
+            plan.describe(),
+            format!(
+                "{}: ssh -F /nonexistent/ssh_config -o StrictHostKeyChecking=accept-new \
+                 -o LogLevel=ERROR -o RemoteCommand=uptime -o RequestTTY=yes \
+                 -o ServerAliveInterval=60 -o ServerAliveCountMax=3 web\n{}: {}\n",
+                t("dry_run_command"),
+                t("dry_run_auth"),
+                t("dry_run_auth_key").replace("{}", "~/.ssh/web")
+            )
+        );
+
+        let plan = manager.plan_connect("db", None, None).unwrap();
+        assert_eq!(plan.auth, AuthMethod::StoredPassword);
+        assert_eq!(
+            plan.describe(),
+            format!(
+                "{}: sshpass -e ssh -F /nonexistent/ssh_config -o StrictHostKeyChecking=accept-new \
+                 -o LogLevel=ERROR -o ServerAliveInterval=60 -o ServerAliveCountMax=3 db\n{}: {}\n",
+                t("dry_run_command"),
+                t("dry_run_auth"),
+                t("dry_run_auth_password")
+            )
+        );
+        assert!(!plan.describe().contains("hunter2"));
+
+        let plan = manager.plan_connect("plain", None, None).unwrap();
+        assert_eq!(plan.auth, AuthMethod::SshDefault);
+
+        // 与connect不同，试运行要求主机存在于配置中
+        assert!(matches!(
+            manager.plan_connect("missing", None, None),
+            Err(SshConnError::HostNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_run_with_retry() {
+        let policy = RetryPolicy {
+            retries: 3,
+            delay: Duration::from_secs(2),
+            quick_failure: Duration::from_secs(10),
+        };
+        let quick = Duration::from_millis(300);
+
+        // 前两次很快以255退出，第三次成功；等待时间逐次递增
+        let mut outcomes =
+            vec![(Some(255), quick), (Some(255), quick), (Some(0), quick)].into_iter();
+        let mut sleeps = Vec::new();
+        run_with_retry(&policy, || Ok(outcomes.next().unwrap()), |d| sleeps.push(d)).unwrap();
+        assert_eq!(sleeps, [Duration::from_secs(2), Duration::from_secs(4)]);
+
+        // 重试次数用完后返回连接失败
+        let mut attempts = 0;
+        let mut sleeps = Vec::new();
+        let result = run_with_retry(
+            &policy,
+            || {
+                attempts += 1;
+                Ok((Some(255), quick))
+            },
+            |d| sleeps.push(d),
+        );
+        assert!(matches!(result, Err(SshConnError::SshConnectionError(_))));
+        assert_eq!(attempts, 4);
+        assert_eq!(sleeps.len(), 3);
+
+        // 会话已经开始（持续时间超过阈值）后退出不重试
+        let mut attempts = 0;
+        let result = run_with_retry(
+            &policy,
+            || {
+                attempts += 1;
+                Ok((Some(255), Duration::from_secs(60)))
+            },
+            |_| panic!("must not sleep"),
+        );
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+
+        // 远程命令本身的非零退出码不是连接失败
+        let mut attempts = 0;
+        run_with_retry(
+            &policy,
+            || {
+                attempts += 1;
+                Ok((Some(1), quick))
+            },
+            |_| panic!("must not sleep"),
+        )
+        .unwrap();
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn test_connect_command_user_override() {
+        let store = MemoryConfigStore::with_content(
+            "Host web\n    HostName 10.0.0.5\n    # ssh-conn-password-cmd: printf 's3cret'\n    User deploy\n",
+        );
+        let mut manager = memory_manager(&store);
+
+        // `-l` 在目标主机之前，优先于配置中的User
+        let spec = manager
+            .connect_command("web", Some("uptime"), Some("admin"))
+            .unwrap();
+        let args = &spec.args;
+        assert_eq!(args[args.len() - 3..], ["-l", "admin", "web"]);
+        assert!(args.contains(&"RemoteCommand=uptime".to_string()));
+        // 密码命令属于配置中的用户，换用其他用户时不使用
+        assert!(!spec.use_password && spec.password_command.is_none());
+        let plan = manager.plan_connect("web", None, Some("admin")).unwrap();
+        assert_eq!(plan.auth, AuthMethod::SshDefault);
+
+        // 与配置相同的用户仍使用密码命令
+        let spec = manager
+            .connect_command("web", None, Some("deploy"))
+            .unwrap();
+        assert!(
+            spec.args
+                .ends_with(&["-l".to_string(), "deploy".to_string(), "web".to_string()])
+        );
+        assert!(spec.use_password);
+
+        assert!(matches!(
+            manager.connect_command("web", None, Some("bad user")),
+            Err(SshConnError::InvalidUsername { .. })
+        ));
+    }
+
+    #[test]
+    fn test_password_command_directive() {
+        let store = MemoryConfigStore::with_content(
+            "Host web\n    HostName 10.0.0.5\n    # ssh-conn-password-cmd: printf 's3cret'\n    User deploy\n",
+        );
+        let mut manager = memory_manager(&store);
+        let host = manager.get_host("web").unwrap().unwrap();
+        assert_eq!(host.password_command.as_deref(), Some("printf 's3cret'"));
+        assert!(host.custom_options.is_empty());
+        assert!(
+            host.to_config_format()
+                .ends_with("    # ssh-conn-password-cmd: printf 's3cret'")
+        );
+
+        // 没有存储的密码也经由sshpass，探测时不使用
+        let spec = manager.build_ssh_command(&host, &CommandMode::Connect);
+        assert!(spec.use_password);
+        assert_eq!(spec.password_command.as_deref(), Some("printf 's3cret'"));
+        let spec = manager.build_ssh_command(&host, &CommandMode::Probe);
+        assert!(!spec.use_password && spec.password_command.is_none());
+
+        let plan = manager.plan_connect("web", None, None).unwrap();
+        assert_eq!(
+            plan.auth,
+            AuthMethod::PasswordCommand("printf 's3cret'".to_string())
+        );
+
+        let password = manager
+            .connection_password("web", true, plan.spec.password_command.as_deref())
+            .unwrap()
+            .unwrap();
+        assert_eq!(password.as_str(), "s3cret");
+        assert!(
+            manager
+                .connection_password("web", true, Some("exit 1"))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_edit_host_clears_each_optional_field() {
+        let full = "Host web\n    HostName 10.0.0.5\n    User deploy\n    Port 2222\n    ProxyCommand ssh -W %h:%p bastion\n    IdentityFile ~/.ssh/web\n    RemoteCommand tmux attach\n    RequestTTY yes\n    AddressFamily inet\n    BindAddress 192.168.1.10\n    PermitLocalCommand yes\n    LocalCommand echo connected\n    SetEnv LANG=C\n";
+
+        for field in OptionalField::ALL {
+            let store = MemoryConfigStore::with_content(full);
+            let mut manager = memory_manager(&store);
+            manager
+                .edit_host(
+                    "web",
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    &[field],
+                )
+                .unwrap();
+
+            let content = store.content();
+            assert!(
+                !content.contains(&format!("    {} ", field.keyword())),
+                "{} not cleared:\n{}",
+                field.name(),
+                content
+            );
+            // 其他字段保持不变
+            let others = OptionalField::ALL.into_iter().filter(|f| *f != field);
+            for other in others {
+                assert!(
+                    content.contains(&format!("    {} ", other.keyword())),
+                    "{} lost when clearing {}",
+                    other.name(),
+                    field.name()
+                );
+            }
+            assert!(content.contains("    HostName 10.0.0.5"));
+        }
+
+        // 同时提供新值时以新值为准
+        let store = MemoryConfigStore::with_content(full);
+        let mut manager = memory_manager(&store);
+        manager
+            .edit_host(
+                "web",
+                None,
+                Some("ops"),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &[OptionalField::User, OptionalField::Port],
+            )
+            .unwrap();
+        let host = manager.get_host("web").unwrap().unwrap();
+        assert_eq!(host.user.as_deref(), Some("ops"));
+        assert_eq!(host.port, None);
+    }
+
+    #[test]
+    fn test_edit_host_keeps_password_command_and_other_options() {
+        let content = "Host web\n    HostName 10.0.0.5\n    # 生产环境\n    ConnectTimeout 7\n    ProxyJump bastion\n    SetEnv LANG=C\n    # ssh-conn-password-cmd: pass show web\n\nHost db\n    HostName 10.0.0.7\n";
+        let store = MemoryConfigStore::with_content(content);
+        let mut manager = memory_manager(&store);
+
+        let preview = manager
+            .preview_edit_host(
+                "web",
+                None,
+                Some("admin"),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &[],
+                &[],
+            )
+            .unwrap();
+        assert_eq!(preview.password_command.as_deref(), Some("pass show web"));
+        assert_eq!(preview.connect_timeout.as_deref(), Some("7"));
+
+        manager
+            .edit_host(
+                "web",
+                None,
+                Some("admin"),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &[],
+            )
+            .unwrap();
+        // 只追加User，其余行（含注释和密码命令）原样保留
+        assert_eq!(
+            store.content(),
+            "Host web\n    HostName 10.0.0.5\n    # 生产环境\n    ConnectTimeout 7\n    ProxyJump bastion\n    SetEnv LANG=C\n    # ssh-conn-password-cmd: pass show web\n    User admin\n\nHost db\n    HostName 10.0.0.7\n"
+        );
+        let host = manager.get_host("web").unwrap().unwrap();
+        assert_eq!(host.password_command.as_deref(), Some("pass show web"));
+        assert_eq!(host.get_custom_option("ProxyJump"), Some("bastion"));
+    }
+
+    #[test]
+    fn test_address_options_round_trip() {
+        let store = MemoryConfigStore::with_content(
+            "Host v4only\n    HostName example.com\n    AddressFamily inet\n    BindAddress 192.168.1.10\n",
+        );
+        let mut manager = memory_manager(&store);
+
+        let host = manager.get_host("v4only").unwrap().unwrap();
+        assert_eq!(host.address_family.as_deref(), Some("inet"));
+        assert_eq!(host.bind_address.as_deref(), Some("192.168.1.10"));
+        assert!(host.custom_options.is_empty());
+        let config = host.to_config_format();
+        assert!(config.lines().any(|l| l == "    AddressFamily inet"));
+        assert!(config.lines().any(|l| l == "    BindAddress 192.168.1.10"));
+
+        // 编辑其他字段时保留，--clear 可单独删除
+        manager
+            .edit_host(
+                "v4only",
+                None,
+                Some("admin"),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &[OptionalField::BindAddress],
+            )
+            .unwrap();
+        let host = manager.get_host("v4only").unwrap().unwrap();
+        assert_eq!(host.address_family.as_deref(), Some("inet"));
+        assert_eq!(host.bind_address, None);
+
+        // 添加时校验并统一为小写
+        let mut entry = SshHost::new("v6only".to_string());
+        entry.hostname = Some("example.com".to_string());
+        entry.address_family = Some("IPv6".to_string());
+        assert!(manager.add_host_entry(&entry, None).is_err());
+        entry.address_family = Some("INET6".to_string());
+        entry.bind_address = Some("eth0".to_string());
+        manager.add_host_entry(&entry, None).unwrap();
+        let host = manager.get_host("v6only").unwrap().unwrap();
+        assert_eq!(host.address_family.as_deref(), Some("inet6"));
+        assert_eq!(host.bind_address.as_deref(), Some("eth0"));
+    }
+
+    #[test]
+    fn test_local_command_options_round_trip() {
+        let store = MemoryConfigStore::with_content(
+            "Host web\n    HostName example.com\n    PermitLocalCommand yes\n    LocalCommand notify-send \"connected to %n\"\n",
+        );
+        let mut manager = memory_manager(&store);
+
+        let host = manager.get_host("web").unwrap().unwrap();
+        assert_eq!(host.permit_local_command.as_deref(), Some("yes"));
+        assert_eq!(
+            host.local_command.as_deref(),
+            Some("notify-send \"connected to %n\"")
+        );
+        assert!(host.custom_options.is_empty());
+        let reparsed = parse_ssh_config_str(&host.to_config_format()).unwrap();
+        assert_eq!(reparsed.len(), 1);
+        assert_eq!(reparsed[0].permit_local_command, host.permit_local_command);
+        assert_eq!(reparsed[0].local_command, host.local_command);
+
+        // 编辑其他字段时保留，--clear 可单独删除
+        manager
+            .edit_host(
+                "web",
+                None,
+                Some("admin"),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &[OptionalField::LocalCommand],
+            )
+            .unwrap();
+        let host = manager.get_host("web").unwrap().unwrap();
+        assert_eq!(host.permit_local_command.as_deref(), Some("yes"));
+        assert_eq!(host.local_command, None);
+
+        // 添加时校验：PermitLocalCommand统一为小写，LocalCommand不能跨行
+        let mut entry = SshHost::new("db".to_string());
+        entry.hostname = Some("example.com".to_string());
+        entry.permit_local_command = Some("maybe".to_string());
+        assert!(manager.add_host_entry(&entry, None).is_err());
+        entry.permit_local_command = Some("No".to_string());
+        entry.local_command = Some("echo one\necho two".to_string());
+        assert!(manager.add_host_entry(&entry, None).is_err());
+        entry.local_command = Some("echo connected".to_string());
+        manager.add_host_entry(&entry, None).unwrap();
+        let host = manager.get_host("db").unwrap().unwrap();
+        assert_eq!(host.permit_local_command.as_deref(), Some("no"));
+        assert_eq!(host.local_command.as_deref(), Some("echo connected"));
+    }
+
+    #[test]
+    fn test_request_tty_round_trip() {
+        let store = MemoryConfigStore::new();
+        let mut manager = memory_manager(&store);
+
+        assert!(
+            manager
+                .add_host(
+                    "tunnel",
+                    "10.0.0.5",
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    Some("maybe"),
+                    &[],
+                    None
+                )
+                .is_err()
+        );
+        manager
+            .add_host(
+                "tunnel",
+                "10.0.0.5",
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some("NO"),
+                &[],
+                None,
+            )
+            .unwrap();
+        assert!(store.content().contains("    RequestTTY no\n"));
+        let host = manager.get_host("tunnel").unwrap().unwrap();
+        assert_eq!(host.request_tty.as_deref(), Some("no"));
+        assert!(host.to_config_format().contains("RequestTTY no"));
+        assert_eq!(host.get_full_ssh_command(), "ssh -T 10.0.0.5");
+
+        // 编辑时未指定则保留原值
+        manager
+            .edit_host(
+                "tunnel",
+                None,
+                Some("ops"),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &[],
+            )
+            .unwrap();
+        let host = manager.get_host("tunnel").unwrap().unwrap();
+        assert_eq!(host.request_tty.as_deref(), Some("no"));
+    }
+
+    #[test]
+    fn test_set_option_in_block_preserves_comments_and_options() {
+        let content = "# 生产环境\nHost web db\n    # 部署用户\n    user deploy\n    Compression yes\n\nHost other\n    User deploy\n";
+
+        // 关键字不区分大小写
+        let updated = set_option_in_block(content, "db", "User", "svc-deploy").unwrap();
+        assert_eq!(
+            updated,
+            "# 生产环境\nHost web db\n    # 部署用户\n    User svc-deploy\n    Compression yes\n\nHost other\n    User deploy\n"
+        );
+
+        // 不存在的选项追加到块尾，空行仍留在块之间
+        let updated = set_option_in_block(content, "web", "Port", "2222").unwrap();
+        assert!(updated.contains("    Compression yes\n    Port 2222\n\nHost other"));
+
+        assert!(set_option_in_block(content, "missing", "Port", "22").is_none());
+    }
+
+    #[test]
+    fn test_build_jump_options() {
+        let base = default_settings().ssh_options.tui_args();
+        let options = build_jump_options(build_connect_options(&base, None, None), "bastion");
+        assert_eq!(argv(&options[base.len()..]), ["-J", "bastion"]);
+    }
+
+    #[test]
+    fn test_build_sftp_options_with_nonstandard_port() {
+        let mut host = SshHost::new("files".to_string());
+        host.hostname = Some("files.example.com".to_string());
+        host.port = Some("2222".to_string());
+        host.identity_file = Some("~/.ssh/id_files".to_string());
+
+        let base = default_settings().ssh_options.tui_args();
+        let options = build_transfer_options(&host, &base);
+        assert_eq!(
+            argv(&options),
+            [
+                "-o",
+                "StrictHostKeyChecking=accept-new",
+                "-o",
+                "LogLevel=ERROR",
+                "-o",
+                "RemoteCommand=none",
+                "-P",
+                "2222",
+                "-i",
+                "~/.ssh/id_files"
+            ]
+        );
+    }
+
+    fn scp_host() -> SshHost {
+        let mut host = SshHost::new("files".to_string());
+        host.hostname = Some("10.0.0.5".to_string());
+        host.user = Some("deploy".to_string());
+        host.port = Some("2222".to_string());
+        host.identity_file = Some("~/.ssh/id_files".to_string());
+        host
+    }
+
+    #[test]
+    fn test_scp_endpoint_parse() {
+        assert_eq!(
+            ScpEndpoint::parse("files:/var/log/app.log"),
+            ScpEndpoint::Remote {
+                host: "files".to_string(),
+                path: "/var/log/app.log".to_string()
+            }
+        );
+        assert_eq!(
+            ScpEndpoint::parse("files:"),
+            ScpEndpoint::Remote {
+                host: "files".to_string(),
+                path: String::new()
+            }
+        );
+        assert_eq!(
+            ScpEndpoint::parse("./a:b"),
+            ScpEndpoint::Local("./a:b".to_string())
+        );
+        assert_eq!(
+            ScpEndpoint::parse("report.txt"),
+            ScpEndpoint::Local("report.txt".to_string())
+        );
+    }
+
+    #[test]
+    fn test_build_scp_args_download() {
+        let args = build_scp_args(
+            &scp_host(),
+            &[],
+            &ScpEndpoint::parse("files:/var/log/app.log"),
+            &ScpEndpoint::parse("./logs/"),
+            false,
+        );
+        assert_eq!(
+            argv(&args),
+            [
+                "-o",
+                "RemoteCommand=none",
+                "-P",
+                "2222",
+                "-i",
+                "~/.ssh/id_files",
+                "files:/var/log/app.log",
+                "./logs/"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_scp_args_upload() {
+        let mut host = scp_host();
+        host.user = None;
+        host.identity_file = None;
+        host.hostname = Some("fe80::1".to_string());
+
+        let args = build_scp_args(
+            &host,
+            &[],
+            &ScpEndpoint::parse("dist"),
+            &ScpEndpoint::parse("files:/srv/www"),
+            true,
+        );
+        assert_eq!(
+            argv(&args),
+            [
+                "-o",
+                "RemoteCommand=none",
+                "-P",
+                "2222",
+                "-r",
+                "dist",
+                "files:/srv/www"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scp_keeps_alias_for_proxy_jump_host() {
+        let store = MemoryConfigStore::with_content(
+            "Host bastion\n    HostName 203.0.113.1\n\nHost inner\n    HostName 10.0.0.9\n    User deploy\n    ProxyJump bastion\n",
+        );
+        let mut manager = memory_manager(&store);
+        let host = manager.get_host("inner").unwrap().unwrap();
+        assert_eq!(host.get_custom_option("ProxyJump"), Some("bastion"));
+
+        let args = manager.with_config_file(build_scp_args(
+            &host,
+            &manager.settings.ssh_options.connect_args(),
+            &ScpEndpoint::parse("inner:/var/log/app.log"),
+            &ScpEndpoint::parse("./logs/"),
+            false,
+        ));
+        // 通过 `-F` 指定的配置解析别名，ProxyJump才会生效
+        assert!(args.contains(&"-F".to_string()));
+        assert_eq!(
+            args[args.len() - 2..],
+            ["inner:/var/log/app.log", "./logs/"]
+        );
+        assert!(!args.iter().any(|arg| arg.contains("10.0.0.9")));
+    }
+
+    #[test]
+    fn test_with_path_uses_given_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config_path = dir.path().join("config");
+        std::fs::write(&config_path, "Host web\n    HostName 10.0.0.5\n").unwrap();
+        let passwords = PasswordManager::with_path(dir.path().join("passwords.db")).unwrap();
+        let mut manager = ConfigManager::with_path(&config_path, passwords).unwrap();
+
+        assert_eq!(manager.config_path(), config_path.to_str().unwrap());
+        assert_eq!(manager.get_hosts().unwrap()[0].host, "web");
+        manager
+            .add_host(
+                "db",
+                "10.0.0.7",
+                Some("root"),
+                None,
+                None,
+                None,
+                None,
+                None,
+                &[],
+                None,
+            )
+            .unwrap();
+        let content = std::fs::read_to_string(&config_path).unwrap();
+        assert!(content.contains("Host db\n"));
+    }
+
+    #[test]
+    fn test_parse_ssh_config_str() {
+        let content = "# comment\nHost web web-alias\n    HostName 10.0.0.5\n    Port 2222\n\nHost *\n    User root\n\nHost db\n    SetEnv LANG=C\n";
+        let hosts = parse_ssh_config_str(content).unwrap();
+        let names: Vec<&str> = hosts.iter().map(|h| h.host.as_str()).collect();
+        assert_eq!(names, ["web", "db"]);
+        assert_eq!(hosts[0].port.as_deref(), Some("2222"));
+        assert_eq!(hosts[1].set_env, [("LANG".to_string(), "C".to_string())]);
+        assert!(hosts.iter().all(|h| h.source_file.is_none()));
+        assert!(parse_ssh_config_str("").unwrap().is_empty());
+
+        let from_reader = parse_ssh_config_reader(content.as_bytes()).unwrap();
+        assert_eq!(from_reader, hosts);
+
+        // 任意位置、任意大小写的Include都无法在内存中解析
+        for content in [
+            "Include conf.d/*\nHost web\n",
+            "Host web\n    include ~/.ssh/extra\n",
+            "Include=other\n",
+        ] {
+            let err = parse_ssh_config_str(content).unwrap_err();
+            assert!(matches!(err, SshConnError::ConfigParse(_)), "{content}");
+        }
+    }
+
+    #[test]
+    fn test_host_inherits_wildcard_defaults() {
+        let content = "Host web\n    HostName 10.0.0.5\n\nHost db\n    HostName 10.0.0.7\n    ServerAliveInterval 120\n\nHost *\n    ServerAliveInterval 30\n    User admin\n";
+        let store = MemoryConfigStore::with_content(content);
+        let mut manager = memory_manager(&store);
+
+        let web = manager.get_host("web").unwrap().unwrap();
+        assert_eq!(web.server_alive_interval, None);
+        assert_eq!(
+            manager
+                .wildcard_defaults()
+                .and_then(|d| d.server_alive_interval.as_deref()),
+            Some("30")
+        );
+
+        let effective = manager.effective_host(&web);
+        assert_eq!(effective.server_alive_interval.as_deref(), Some("30"));
+        assert_eq!(effective.user.as_deref(), Some("admin"));
+        assert_eq!(
+            web.inherited_options(manager.wildcard_defaults().unwrap()),
+            [
+                ("User".to_string(), "admin".to_string()),
+                ("ServerAliveInterval".to_string(), "30".to_string()),
+            ]
+        );
+
+        // 主机自己的设置优先
+        let db = manager.get_host("db").unwrap().unwrap();
+        assert_eq!(
+            manager.effective_host(&db).server_alive_interval.as_deref(),
+            Some("120")
+        );
+
+        // 默认值不写入主机的配置块
+        assert_eq!(store.content(), content);
+        assert_eq!(
+            manager
+                .get_hosts()
+                .unwrap()
+                .iter()
+                .map(|h| h.host.as_str())
+                .collect::<Vec<_>>(),
+            ["web", "db"]
+        );
+    }
+
+    #[test]
+    fn test_include_records_source_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let main = dir.path().join("config");
+        let conf_d = dir.path().join("conf.d");
+        std::fs::create_dir(&conf_d).unwrap();
+        std::fs::write(
+            &main,
+            format!(
+                "Include {}/*.conf\n\nHost web\n    HostName 10.0.0.5\n",
+                conf_d.display()
+            ),
+        )
+        .unwrap();
+        std::fs::write(
+            conf_d.join("b.conf"),
+            format!(
+                "Host db\n    HostName 10.0.0.7\n\nInclude {}\n",
+                dir.path().join("nested").display()
+            ),
+        )
+        .unwrap();
+        std::fs::write(conf_d.join("a.conf"), "Host cache\n    HostName 10.0.0.8\n").unwrap();
+        std::fs::write(conf_d.join("notes.txt"), "Host ignored\n").unwrap();
+        std::fs::write(dir.path().join("nested"), "Host deep\n    User root\n").unwrap();
+
+        let passwords = PasswordManager::with_path(dir.path().join("passwords.db")).unwrap();
+        let mut manager = ConfigManager::with_path(&main, passwords).unwrap();
+        let sources: Vec<(String, PathBuf)> = manager
+            .get_hosts()
+            .unwrap()
+            .iter()
+            .map(|h| (h.host.clone(), h.source_file.clone().unwrap()))
+            .collect();
+        assert_eq!(
+            sources,
+            [
+                ("web".to_string(), main.clone()),
+                ("cache".to_string(), conf_d.join("a.conf")),
+                ("db".to_string(), conf_d.join("b.conf")),
+                ("deep".to_string(), dir.path().join("nested")),
+            ]
+        );
+
+        // 编辑和删除写回主机所在的文件，主配置不变
+        let main_before = std::fs::read_to_string(&main).unwrap();
+        manager.set_host_option("db", "Port", "2222").unwrap();
+        manager
+            .edit_host(
+                "cache",
+                None,
+                Some("admin"),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &[],
+            )
+            .unwrap();
+        manager.delete_host("deep").unwrap();
+        assert_eq!(std::fs::read_to_string(&main).unwrap(), main_before);
+        assert!(
+            std::fs::read_to_string(conf_d.join("b.conf"))
+                .unwrap()
+                .contains("Port 2222")
+        );
+        assert!(
+            std::fs::read_to_string(conf_d.join("a.conf"))
+                .unwrap()
+                .contains("User admin")
+        );
+        assert!(
+            !std::fs::read_to_string(dir.path().join("nested"))
+                .unwrap()
+                .contains("Host deep")
+        );
+        assert!(manager.get_host("deep").unwrap().is_none());
+    }
+
+    fn manager_with(settings: Settings, dir: &tempfile::TempDir) -> ConfigManager {
+        let mut settings = settings;
+        settings.password_db_path = Some(dir.path().join("passwords.db"));
+        let password_manager = PasswordManager::new(&settings).unwrap();
+        ConfigManager::new(password_manager, settings).unwrap()
+    }
+
+    fn manager_in(dir: &tempfile::TempDir) -> ConfigManager {
+        let settings = Settings {
+            ssh_config_path: Some(dir.path().join("config")),
+            ..Settings::default()
+        };
+        manager_with(settings, dir)
+    }
+
+    /// 配置和密码都只保存在内存中的管理器
+    fn memory_manager(store: &MemoryConfigStore) -> ConfigManager {
+        let settings = Settings {
+            ssh_config_path: Some(std::path::PathBuf::from("/nonexistent/ssh_config")),
+            password_db_path: Some(std::path::PathBuf::from(":memory:")),
+            auto_backup: false,
+            audit_log: false,
+            ..Settings::default()
+        };
+        let password_manager = PasswordManager::new(&settings).unwrap();
+        ConfigManager::with_store(password_manager, settings, Arc::new(store.clone())).unwrap()
+    }
+
+    #[test]
+    fn test_memory_store_add_list_delete() {
+        let store = MemoryConfigStore::new();
+        let mut manager = memory_manager(&store);
+        assert!(manager.list_hosts().unwrap().is_empty());
+
+        manager
+            .add_host(
+                "web",
+                "10.0.0.1",
+                Some("deploy"),
+                Some(2222),
+                None,
+                None,
+                None,
+                None,
+                &[],
+                None,
+            )
+            .unwrap();
+        manager
+            .add_host(
+                "db",
+                "10.0.0.2",
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &[],
+                Some("secret"),
+            )
+            .unwrap();
+        assert_eq!(manager.list_hosts().unwrap(), ["web", "db"]);
+        assert!(
+            store
+                .content()
+                .contains("Host web\n    HostName 10.0.0.1\n    User deploy\n    Port 2222\n")
+        );
+        assert_eq!(manager.stored_password_hosts(), ["db"]);
+
+        // 重复添加被拒绝
+        assert!(matches!(
+            manager.add_host(
+                "web",
+                "10.0.0.9",
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &[],
+                None
+            ),
+            Err(SshConnError::HostAlreadyExists { .. })
+        ));
+
+        manager.delete_host("web").unwrap();
+        assert_eq!(manager.list_hosts().unwrap(), ["db"]);
+        assert!(!store.content().contains("Host web"));
+
+        manager.delete_host("db").unwrap();
+        assert!(manager.list_hosts().unwrap().is_empty());
+        assert!(manager.stored_password_hosts().is_empty());
+        assert!(matches!(
+            manager.delete_host("db"),
+            Err(SshConnError::HostNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_add_host_entry_writes_custom_options() {
+        let store = MemoryConfigStore::new();
+        let mut manager = memory_manager(&store);
+
+        let mut entry = SshHost::new("newbox".to_string());
+        entry.hostname = Some("10.1.2.3".to_string());
+        entry.user = Some("ops".to_string());
+        entry.server_alive_interval = Some("30".to_string());
+        entry
+            .custom_options
+            .insert("ProxyJump".to_string(), "bastion".to_string());
+        entry
+            .custom_options
+            .insert("Compression".to_string(), "yes".to_string());
+        manager.add_host_entry(&entry, None).unwrap();
+
+        assert_eq!(
+            store.content(),
+            "\nHost newbox\n    HostName 10.1.2.3\n    User ops\n    ServerAliveInterval 30\n    Compression yes\n    ProxyJump bastion\n"
+        );
+        let parsed = manager.get_host("newbox").unwrap().unwrap();
+        assert_eq!(parsed.get_custom_option("ProxyJump"), Some("bastion"));
+
+        // 缺少HostName时拒绝
+        let bare = SshHost::new("bare".to_string());
+        assert!(manager.add_host_entry(&bare, None).is_err());
+    }
+
+    #[test]
+    fn test_mutations_append_audit_entries() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut manager = manager_in(&dir);
+        let log_path = dir.path().join(crate::audit::AUDIT_LOG_FILE_NAME);
+        assert_eq!(manager.audit_log_path(), log_path);
+
+        let mut entry = SshHost::new("web".to_string());
+        entry.hostname = Some("10.0.0.5".to_string());
+        entry.user = Some("deploy".to_string());
+        manager.add_host_entry(&entry, Some("secret")).unwrap();
+        let log = std::fs::read_to_string(&log_path).unwrap();
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(lines.len(), 1);
+        assert!(
+            lines[0]
+                .ends_with(" op=add host=web HostName=10.0.0.5, User=deploy, Password=(stored)"),
+            "{}",
+            lines[0]
+        );
+        assert!(!log.contains("secret"));
+
+        manager
+            .edit_host(
+                "web",
+                None,
+                Some("admin"),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &[],
+            )
+            .unwrap();
+        manager.delete_host("web").unwrap();
+        let log = std::fs::read_to_string(&log_path).unwrap();
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].ends_with(" op=edit host=web User: deploy -> admin"));
+        assert!(lines[2].ends_with(" op=delete host=web"));
+    }
+
+    #[test]
+    fn test_audit_log_can_be_disabled() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let log_path = dir.path().join("audit.log");
+        let settings = Settings {
+            ssh_config_path: Some(dir.path().join("config")),
+            audit_log: false,
+            audit_log_path: Some(log_path.clone()),
+            ..Settings::default()
+        };
+        let mut manager = manager_with(settings, &dir);
+        let mut entry = SshHost::new("web".to_string());
+        entry.hostname = Some("10.0.0.5".to_string());
+        manager.add_host_entry(&entry, None).unwrap();
+        assert!(!log_path.exists());
+        assert!(!dir.path().join(crate::audit::AUDIT_LOG_FILE_NAME).exists());
+    }
+
+    #[test]
+    fn test_memory_store_edit_and_parse() {
+        let store = MemoryConfigStore::with_content(
+            "Host *\n    ServerAliveInterval 30\n\nHost web\n    HostName 10.0.0.1\n    User root\n\nHost db\n    HostName 10.0.0.2\n",
+        );
+        let mut manager = memory_manager(&store);
+        assert_eq!(manager.list_hosts().unwrap(), ["web", "db"]);
+
+        manager
+            .edit_host(
+                "web",
+                None,
+                Some("deploy"),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &[],
+            )
+            .unwrap();
+        let web = manager.get_host("web").unwrap().unwrap();
+        assert_eq!(web.hostname.as_deref(), Some("10.0.0.1"));
+        assert_eq!(web.user.as_deref(), Some("deploy"));
+        // 通配符块和其他主机保持不变
+        assert!(
+            store
+                .content()
+                .starts_with("Host *\n    ServerAliveInterval 30\n")
+        );
+        assert!(manager.get_host("db").unwrap().is_some());
+    }
+
+    fn env(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_backup_compression_round_trip() {
+        use std::io::Read;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let original = "Host web\n    HostName 10.0.0.1\n    User deploy\n";
+        std::fs::write(dir.path().join("config"), original).unwrap();
+        let manager = manager_in(&dir);
+
+        // 上级目录不存在时自动创建；`--compress` 时补上 .gz 扩展名
+        let options = BackupOptions {
+            compress: true,
+            force: false,
+        };
+        let outcome = manager
+            .backup_config_to(&dir.path().join("nas/ssh-config"), &options)
+            .unwrap();
+        assert_eq!(outcome.path, dir.path().join("nas/ssh-config.gz"));
+        assert_eq!(
+            outcome.size,
+            std::fs::metadata(&outcome.path).unwrap().len()
+        );
+
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(std::fs::File::open(&outcome.path).unwrap())
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, original);
+
+        // 扩展名为 .gz 时即使没有 `--compress` 也压缩
+        let outcome = manager
+            .backup_config_to(&dir.path().join("implied.GZ"), &BackupOptions::default())
+            .unwrap();
+        assert_eq!(outcome.path, dir.path().join("implied.GZ"));
+        let bytes = std::fs::read(&outcome.path).unwrap();
+        assert_eq!(bytes[..2], [0x1f, 0x8b]);
+
+        let outcome = manager
+            .backup_config_to(&dir.path().join("plain"), &BackupOptions::default())
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&outcome.path).unwrap(), original);
+    }
+
+    #[test]
+    fn test_backup_refuses_overwrite_without_force() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("config"), "Host web\n").unwrap();
+        let dest = dir.path().join("backup");
+        std::fs::write(&dest, "keep me").unwrap();
+        let manager = manager_in(&dir);
+
+        let err = manager
+            .backup_config_to(&dest, &BackupOptions::default())
+            .unwrap_err();
+        assert!(matches!(err, SshConnError::InvalidArgument(_)));
+        assert_eq!(std::fs::read_to_string(&dest).unwrap(), "keep me");
+
+        let options = BackupOptions {
+            compress: false,
+            force: true,
+        };
+        manager.backup_config_to(&dest, &options).unwrap();
+        assert_eq!(std::fs::read_to_string(&dest).unwrap(), "Host web\n");
+    }
+
+    #[test]
+    fn test_auto_backup_once_per_process() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let original = "Host web\n    HostName 10.0.0.1\n";
+        std::fs::write(dir.path().join("config"), original).unwrap();
+
+        let mut manager = manager_in(&dir);
+        manager.set_host_option("web", "User", "deploy").unwrap();
+        manager.set_host_option("web", "Port", "2222").unwrap();
+        manager.delete_host("web").unwrap();
+
+        // 多次修改只在第一次写入前备份一次，内容是修改前的配置
+        let backups = manager.auto_backups();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(std::fs::read_to_string(&backups[0]).unwrap(), original);
+        assert_eq!(manager.latest_auto_backup(), Some(backups[0].clone()));
+
+        // 配置内容与最近一次自动备份相同时不生成新文件
+        std::fs::write(dir.path().join("config"), original).unwrap();
+        let mut manager = manager_in(&dir);
+        manager.delete_host("web").unwrap();
+        assert_eq!(manager.auto_backups().len(), 1);
+
+        // 内容不同时新一次运行再备份一次；配置为空时没有需要备份的内容，不生成文件
+        let changed = "Host db\n    HostName 10.0.0.2\n";
+        std::fs::write(dir.path().join("config"), changed).unwrap();
+        let mut manager = manager_in(&dir);
+        manager.delete_host("db").unwrap();
+        manager
+            .add_host(
+                "db",
+                "10.0.0.3",
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &[],
+                None,
+            )
+            .unwrap();
+        let backups = manager.auto_backups();
+        assert_eq!(backups.len(), 2);
+        assert_eq!(std::fs::read_to_string(&backups[1]).unwrap(), changed);
+
+        let mut manager = manager_in(&dir);
+        manager.delete_host("db").unwrap();
+        assert_eq!(manager.auto_backups().len(), 3);
+        let mut manager = manager_in(&dir);
+        manager
+            .add_host(
+                "db",
+                "10.0.0.4",
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &[],
+                None,
+            )
+            .unwrap();
+        assert_eq!(manager.auto_backups().len(), 3);
+    }
+
+    #[test]
+    fn test_auto_backup_pruned_and_disabled() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let settings = Settings {
+            ssh_config_path: Some(dir.path().join("config")),
+            auto_backup_keep: 2,
+            ..Settings::default()
+        };
+        for i in 0..4 {
+            std::fs::write(
+                dir.path().join("config"),
+                format!("Host web\n    HostName 10.0.0.{}\n", i),
+            )
+            .unwrap();
+            let mut manager = manager_with(settings.clone(), &dir);
+            manager.set_host_option("web", "User", "deploy").unwrap();
+        }
+        let manager = manager_with(settings.clone(), &dir);
+        let backups = manager.auto_backups();
+        assert_eq!(backups.len(), 2);
+        assert!(
+            std::fs::read_to_string(&backups[1])
+                .unwrap()
+                .contains("10.0.0.3")
+        );
+
+        let mut manager = manager_with(
+            Settings {
+                auto_backup: false,
+                ..settings
+            },
+            &dir,
+        );
+        manager.set_host_option("web", "Port", "2222").unwrap();
+        assert_eq!(manager.auto_backups(), backups);
+    }
+
+    #[test]
+    fn test_set_env_parsed_from_multiple_lines() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("config"),
+            "Host web\n    HostName 10.0.0.1\n    SetEnv LANG=en_US.UTF-8 TERM=xterm-256color\n    SetEnv GREETING=\"hello world\" bogus\n",
+        )
+        .unwrap();
+        let mut manager = manager_in(&dir);
+
+        let host = manager.get_host("web").unwrap().unwrap();
+        assert_eq!(
+            host.set_env,
+            env(&[
+                ("LANG", "en_US.UTF-8"),
+                ("TERM", "xterm-256color"),
+                ("GREETING", "hello world")
+            ])
+        );
+    }
+
+    #[test]
+    fn test_set_env_round_trip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut manager = manager_in(&dir);
+        let entries = env(&[
+            ("LANG", "C.UTF-8"),
+            ("PS1", "\\u@\\h \"$\" "),
+            ("EMPTY", ""),
+        ]);
+
+        manager
+            .add_host(
+                "web", "10.0.0.1", None, None, None, None, None, None, &entries, None,
+            )
+            .unwrap();
+        let host = manager.get_host("web").unwrap().unwrap();
+        assert_eq!(host.set_env, entries);
+
+        // to_config_format 的输出可以被重新解析为相同的条目
+        let text = host.to_config_format();
+        assert_eq!(text.matches("SetEnv ").count(), 3);
+        std::fs::write(dir.path().join("config"), &text).unwrap();
+        manager.clear_cache();
+        assert_eq!(manager.get_host("web").unwrap().unwrap().set_env, entries);
+
+        // 编辑时不传SetEnv保留原值，传入空列表则清除
+        manager
+            .edit_host(
+                "web",
+                None,
+                Some("root"),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &[],
+            )
+            .unwrap();
+        assert_eq!(manager.get_host("web").unwrap().unwrap().set_env, entries);
+        manager
+            .edit_host(
+                "web",
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(&[]),
+                None,
+                &[],
+            )
+            .unwrap();
+        assert!(manager.get_host("web").unwrap().unwrap().set_env.is_empty());
+    }
+
+    fn host_named(name: &str, hostname: &str) -> SshHost {
+        let mut host = SshHost::new(name.to_string());
+        host.hostname = Some(hostname.to_string());
+        host
+    }
+
+    fn names(hosts: &[SshHost]) -> Vec<&str> {
+        hosts.iter().map(|h| h.host.as_str()).collect()
+    }
+
+    #[test]
+    fn test_rank_hosts_ordering() {
+        let mut admin = host_named("bastion", "10.0.0.9");
+        admin.user = Some("dbadmin".to_string());
+        let hosts = vec![
+            host_named("old-db-backup", "10.0.0.1"),
+            host_named("web1", "db-proxy.example.com"),
+            host_named("db10", "10.0.0.3"),
+            admin,
+            host_named("DB", "10.0.0.4"),
+            host_named("db2", "10.0.0.5"),
+            host_named("cache", "10.0.0.6"),
+        ];
+
+        assert_eq!(
+            names(&rank_hosts(&hosts, "db", SearchFields::all())),
+            ["DB", "db10", "db2", "old-db-backup", "web1", "bastion"]
+        );
+        assert_eq!(
+            names(&rank_hosts(&hosts, "db2", SearchFields::all())),
+            ["db2"]
+        );
+        assert_eq!(
+            names(&rank_hosts(&hosts, "10.0.0", SearchFields::all())).len(),
+            6
+        );
+        assert!(rank_hosts(&hosts, "nothing", SearchFields::all()).is_empty());
+    }
+
+    fn field_hosts() -> Vec<SshHost> {
+        let mut web = host_named("web22", "10.0.1.5");
+        web.user = Some("deploy".to_string());
+        web.port = Some("2200".to_string());
+        let mut db = host_named("db", "db22.example.com");
+        db.user = Some("admin".to_string());
+        db.port = Some("22".to_string());
+        let mut cache = host_named("cache", "10.0.0.22");
+        cache.user = Some("user22".to_string());
+        vec![web, db, cache]
+    }
+
+    fn search_in(fields: &str, query: &str) -> Vec<String> {
+        let fields = SearchFields::parse(fields).unwrap();
+        rank_hosts(&field_hosts(), query, fields)
+            .into_iter()
+            .map(|h| h.host)
+            .collect()
+    }
+
+    #[test]
+    fn test_rank_hosts_field_restrictions() {
+        assert_eq!(search_in("all", "22"), ["web22", "db", "cache"]);
+        assert_eq!(search_in("host", "22"), ["web22"]);
+        assert_eq!(search_in("hostname", "22"), ["db", "cache"]);
+        assert_eq!(search_in("user", "22"), ["cache"]);
+        assert_eq!(search_in("port", "22"), ["web22", "db"]);
+        assert_eq!(search_in("host,user", "22"), ["web22", "cache"]);
+        // 排除词同样只检查选定字段
+        assert_eq!(search_in("port", "22 -2200"), ["db"]);
+        // 未选择Host字段时不按Host名称提升排序
+        assert_eq!(search_in("hostname", "db"), ["db"]);
+    }
+
+    #[test]
+    fn test_search_fields_parse() {
+        let fields = SearchFields::parse(" Host , PORT ").unwrap();
+        assert!(fields.contains(SearchField::Host));
+        assert!(fields.contains(SearchField::Port));
+        assert!(!fields.contains(SearchField::User));
+        assert_eq!(SearchFields::parse("all").unwrap(), SearchFields::all());
+
+        for invalid in ["tags", "host,tags", "", ","] {
+            assert!(
+                matches!(
+                    SearchFields::parse(invalid),
+                    Err(SshConnError::InvalidArgument(_))
+                ),
+                "{invalid:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_config_file_flag_uses_default_path() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let manager = manager_with(default_settings(), &dir);
+        let default_path = get_ssh_config_path().unwrap();
+
+        let args = manager.with_config_file(manager.settings.ssh_options.connect_args());
+        assert_eq!(args[0], "-F");
+        assert_eq!(args[1], default_path.to_string_lossy());
+        assert_eq!(args[2..], manager.settings.ssh_options.connect_args()[..]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_open_in_terminal_passes_password_via_env() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let output = dir.path().join("terminal.out");
+        let script = dir.path().join("fake-terminal");
+        std::fs::write(
+            &script,
+            format!(
+                "#!/bin/sh\necho \"$@\" > {0}.tmp\necho \"SSHPASS=$SSHPASS\" >> {0}.tmp\nmv {0}.tmp {0}\n",
+                output.display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let settings = Settings {
+            ssh_config_path: Some(dir.path().join("config")),
+            terminal_command: Some(format!("{} --exec {{cmd}}", script.display())),
+            ..Settings::default()
+        };
+        let mut manager = manager_with(settings, &dir);
+        manager
+            .add_host(
+                "web",
+                "10.0.0.5",
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &[],
+                Some("s3cret"),
+            )
+            .unwrap();
+        manager.open_in_terminal("web").unwrap();
+
+        let mut content = String::new();
+        for _ in 0..100 {
+            if let Ok(text) = std::fs::read_to_string(&output) {
+                content = text;
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        let mut lines = content.lines();
+        let args = lines.next().unwrap();
+        assert!(args.starts_with("--exec sshpass -e ssh -F "), "{args}");
+        assert!(args.ends_with(" web"), "{args}");
+        assert!(!args.contains("s3cret"));
+        assert_eq!(lines.next(), Some("SSHPASS=s3cret"));
+    }
+
+    #[test]
+    fn test_switch_profile_reloads_hosts() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let work = dir.path().join("work_config");
+        let home = dir.path().join("home_config");
+        std::fs::write(&work, "Host build\n    HostName 10.0.0.1\n").unwrap();
+        std::fs::write(&home, "Host nas\n    HostName 192.168.1.2\n").unwrap();
+        let settings = Settings {
+            profiles: [
+                ("work".to_string(), work),
+                ("home".to_string(), home.clone()),
+            ]
+            .into_iter()
+            .collect(),
+            ..Settings::default()
+        };
+        let mut manager = manager_with(settings, &dir);
+        manager.switch_profile("work").unwrap();
+        assert_eq!(manager.list_hosts().unwrap(), ["build"]);
+
+        manager.switch_profile("home").unwrap();
+        assert_eq!(manager.list_hosts().unwrap(), ["nas"]);
+        assert_eq!(manager.config_path(), home.to_string_lossy());
+        assert_eq!(manager.settings().active_profile.as_deref(), Some("home"));
+
+        assert!(manager.switch_profile("clients").is_err());
+        assert_eq!(manager.list_hosts().unwrap(), ["nas"]);
+    }
+
+    #[test]
+    fn test_config_file_flag_uses_overridden_path() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config_path = dir.path().join("custom_config");
+        let settings = Settings {
+            ssh_config_path: Some(config_path.clone()),
+            ..Settings::default()
+        };
+        let manager = manager_with(settings, &dir);
+
+        let test_args = build_test_args(
+            &manager.with_config_file(manager.settings.ssh_options.test_args()),
+            "web",
+        );
+        assert_eq!(
+            argv(&test_args[..2]),
+            ["-F", config_path.to_string_lossy().as_ref()]
+        );
+        assert_eq!(argv(&test_args[test_args.len() - 2..]), ["web", "exit"]);
+
+        let host = SshHost::new("web".to_string());
+        let sftp_args = manager.with_config_file(build_transfer_options(&host, &[]));
+        assert_eq!(sftp_args[0], "-F");
+        assert_eq!(sftp_args[1], config_path.to_string_lossy());
+    }
+
+    #[test]
+    fn test_config_file_flag_disabled_by_use_system_config() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let settings = Settings {
+            use_system_config: true,
+            ..Settings::default()
+        };
+        let manager = manager_with(settings, &dir);
+
+        let args = manager.with_config_file(manager.settings.ssh_options.tui_args());
+        assert!(!args.contains(&"-F".to_string()));
+        assert_eq!(args, manager.settings.ssh_options.tui_args());
+    }
+
+    #[test]
+    fn test_default_ssh_argv() {
+        let options = default_settings().ssh_options;
+        assert_eq!(
+            argv(&options.connect_args()),
+            [
+                "-o",
+                "StrictHostKeyChecking=accept-new",
+                "-o",
+                "LogLevel=ERROR"
+            ]
+        );
+        assert_eq!(
+            argv(&options.tui_args()),
+            [
+                "-o",
+                "StrictHostKeyChecking=accept-new",
+                "-o",
+                "LogLevel=ERROR",
+                "-o",
+                "RequestTTY=force",
+                "-tt"
+            ]
+        );
+        assert_eq!(
+            argv(&build_test_args(&options.test_args(), "web")),
+            [
+                "-o",
+                "ConnectTimeout=10",
+                "-o",
+                "StrictHostKeyChecking=yes",
+                "-o",
+                "RemoteCommand=none",
+                "web",
+                "exit"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_appended_ssh_argv_overrides_same_key() {
+        let settings = Settings::from_toml_str(
+            "[ssh_options.connect]\nextra = [\"-o LogLevel=VERBOSE\", \"-A\"]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            argv(&settings.ssh_options.connect_args()),
+            [
+                "-o",
+                "StrictHostKeyChecking=accept-new",
+                "-o",
+                "LogLevel=VERBOSE",
+                "-A"
+            ]
+        );
+        // 其他场景不受影响
+        assert_eq!(
+            settings.ssh_options.tui_args(),
+            default_settings().ssh_options.tui_args()
+        );
+    }
+
+    #[test]
+    fn test_replaced_ssh_argv() {
+        let settings = Settings::from_toml_str(
+            "[ssh_options.tui]\noptions = [\"-o\", \"StrictHostKeyChecking=accept-new\"]\n\n\
+             [ssh_options.test]\noptions = []\nextra = [\"-o\", \"ConnectTimeout=3\"]\n",
+        )
+        .unwrap();
+        let tui = settings.ssh_options.tui_args();
+        assert_eq!(argv(&tui), ["-o", "StrictHostKeyChecking=accept-new"]);
+        assert!(!tui.contains(&"-tt".to_string()));
+
+        assert_eq!(
+            argv(&build_test_args(&settings.ssh_options.test_args(), "web")),
+            [
+                "-o",
+                "ConnectTimeout=3",
+                "-o",
+                "RemoteCommand=none",
+                "web",
+                "exit"
+            ]
+        );
     }
 }
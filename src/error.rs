@@ -2,6 +2,7 @@
 
 use std::fmt;
 use std::io;
+use std::path::PathBuf;
 
 /// 应用程序错误类型
 #[derive(Debug)]
@@ -9,13 +10,54 @@ pub enum SshConnError {
     Io(io::Error),
     Database(rusqlite::Error),
     ConfigParse(String),
-    HostNotFound { host: String },
-    HostAlreadyExists { host: String },
-    InvalidPort { port: String },
+    HostNotFound {
+        host: String,
+    },
+    HostAlreadyExists {
+        host: String,
+    },
+    /// 端口号无效
+    #[deprecated(note = "使用 `SshConnError::validation(\"port\", ...)`，校验函数已不再返回该变体")]
+    InvalidPort {
+        port: String,
+    },
+    /// 输入校验失败，`field` 为出错的字段名（如 `port`、`hostname`、`user`），`message` 为本地化的说明
+    Validation {
+        field: String,
+        message: String,
+    },
     PasswordError(String),
     SshConnectionError(String),
     TuiError(String),
     Connection(String),
+    InvalidSetting {
+        key: String,
+        message: String,
+    },
+    InvalidArgument(String),
+    /// HostName无效，`reason` 为本地化的原因
+    InvalidHostname {
+        value: String,
+        reason: String,
+    },
+    /// 用户名无效（为空、含空白或 `@`、`:`）
+    InvalidUsername {
+        value: String,
+    },
+    /// 私钥文件不存在
+    IdentityFileNotFound {
+        path: PathBuf,
+    },
+    /// 没有读写文件的权限
+    PermissionDenied {
+        path: PathBuf,
+    },
+    /// 找不到需要的外部程序（如 `ssh`、`sshpass`）
+    BinaryNotFound {
+        name: String,
+    },
+    /// 配置文件被其他程序锁定，暂时无法写入
+    ConfigLocked,
 }
 
 impl fmt::Display for SshConnError {
@@ -48,6 +90,7 @@ impl From<rusqlite::Error> for SshConnError {
 
 impl SshConnError {
     /// 获取本地化的错误消息
+    #[allow(deprecated)]
     pub fn localized_message(&self) -> String {
         use crate::i18n::t;
 
@@ -61,13 +104,188 @@ impl SshConnError {
             SshConnError::HostAlreadyExists { host } => {
                 format!("{}: '{}'", t("error_host_exists"), host)
             }
-            SshConnError::InvalidPort { port } => format!("{}: {}", t("error_invalid_port"), port),
+            SshConnError::InvalidPort { port } => {
+                format!("{}: {}", t("error.error_invalid_port"), port)
+            }
+            SshConnError::Validation { message, .. } => {
+                format!("{}: {}", t("error.error_validation"), message)
+            }
             SshConnError::PasswordError(msg) => format!("{}: {}", t("error_password"), msg),
             SshConnError::SshConnectionError(msg) => {
                 format!("{}: {}", t("error_ssh_connection"), msg)
             }
             SshConnError::TuiError(msg) => format!("{}: {}", t("error_tui"), msg),
             SshConnError::Connection(msg) => format!("{}: {}", t("error_connection"), msg),
+            SshConnError::InvalidSetting { key, message } => {
+                format!(
+                    "{} '{}': {}",
+                    t("error.error_invalid_setting"),
+                    key,
+                    message
+                )
+            }
+            SshConnError::InvalidArgument(msg) => {
+                format!("{}: {}", t("error.error_invalid_argument"), msg)
+            }
+            SshConnError::InvalidHostname { reason, .. } => {
+                format!("{}: {}", t("error.error_validation"), reason)
+            }
+            SshConnError::InvalidUsername { value } => {
+                let reason = if value.is_empty() {
+                    t("error.username_empty")
+                } else if value.contains([' ', '\t']) {
+                    t("error.username_no_spaces")
+                } else {
+                    t("error.username_invalid_chars")
+                };
+                format!("{}: {}", t("error.error_validation"), reason)
+            }
+            SshConnError::IdentityFileNotFound { path } => {
+                t("error.key_file_not_found").replace("{}", &path.display().to_string())
+            }
+            SshConnError::PermissionDenied { path } => {
+                t("error.file_permission_denied").replace("{}", &path.display().to_string())
+            }
+            SshConnError::BinaryNotFound { name } => {
+                t("error.binary_not_found").replace("{}", name)
+            }
+            SshConnError::ConfigLocked => t("error.config_locked"),
+        }
+    }
+}
+
+impl SshConnError {
+    /// 错误种类名称，与枚举变体同名，供 `--json-errors` 等机器可读输出使用
+    #[allow(deprecated)]
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SshConnError::Io(_) => "Io",
+            SshConnError::Database(_) => "Database",
+            SshConnError::ConfigParse(_) => "ConfigParse",
+            SshConnError::HostNotFound { .. } => "HostNotFound",
+            SshConnError::HostAlreadyExists { .. } => "HostAlreadyExists",
+            SshConnError::InvalidPort { .. } => "InvalidPort",
+            SshConnError::Validation { .. } => "Validation",
+            SshConnError::PasswordError(_) => "PasswordError",
+            SshConnError::SshConnectionError(_) => "SshConnectionError",
+            SshConnError::TuiError(_) => "TuiError",
+            SshConnError::Connection(_) => "Connection",
+            SshConnError::InvalidSetting { .. } => "InvalidSetting",
+            SshConnError::InvalidArgument(_) => "InvalidArgument",
+            SshConnError::InvalidHostname { .. } => "InvalidHostname",
+            SshConnError::InvalidUsername { .. } => "InvalidUsername",
+            SshConnError::IdentityFileNotFound { .. } => "IdentityFileNotFound",
+            SshConnError::PermissionDenied { .. } => "PermissionDenied",
+            SshConnError::BinaryNotFound { .. } => "BinaryNotFound",
+            SshConnError::ConfigLocked => "ConfigLocked",
+        }
+    }
+
+    /// 输入校验错误对应的字段名（如 `hostname`、`user`），供界面定位到出错的输入框
+    #[allow(deprecated)]
+    pub fn field(&self) -> Option<&str> {
+        match self {
+            SshConnError::Validation { field, .. } => Some(field),
+            SshConnError::InvalidPort { .. } => Some("port"),
+            SshConnError::InvalidHostname { .. } => Some("hostname"),
+            SshConnError::InvalidUsername { .. } => Some("user"),
+            _ => None,
+        }
+    }
+
+    /// 进程退出码，每种错误对应一个固定值，供脚本区分失败原因
+    ///
+    /// 2 与 clap 的参数错误一致；新增变体时必须分配新的值，已有的值不能改变
+    #[allow(deprecated)]
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            SshConnError::InvalidArgument(_) => 2,
+            SshConnError::HostNotFound { .. } => 3,
+            SshConnError::Validation { .. } => 4,
+            SshConnError::SshConnectionError(_) => 5,
+            SshConnError::Connection(_) => 6,
+            SshConnError::HostAlreadyExists { .. } => 7,
+            SshConnError::ConfigParse(_) => 8,
+            SshConnError::InvalidSetting { .. } => 9,
+            SshConnError::PasswordError(_) => 10,
+            SshConnError::Database(_) => 11,
+            SshConnError::Io(_) => 12,
+            SshConnError::TuiError(_) => 13,
+            SshConnError::InvalidHostname { .. } => 14,
+            SshConnError::InvalidUsername { .. } => 15,
+            SshConnError::IdentityFileNotFound { .. } => 16,
+            SshConnError::PermissionDenied { .. } => 17,
+            SshConnError::BinaryNotFound { .. } => 18,
+            SshConnError::ConfigLocked => 19,
+            SshConnError::InvalidPort { .. } => 20,
+        }
+    }
+
+    /// 转换为 `{"error":{"kind":...,"message":...}}` 形式的JSON
+    ///
+    /// 带有结构化字段的变体会额外输出 `host`、`field`（以及 `value`）、`key`、`path` 或 `name`
+    #[allow(deprecated)]
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut error = serde_json::Map::new();
+        error.insert("kind".to_string(), self.kind().into());
+        if let Some(field) = self.field() {
+            error.insert("field".to_string(), field.into());
+        }
+        match self {
+            SshConnError::HostNotFound { host } | SshConnError::HostAlreadyExists { host } => {
+                error.insert("host".to_string(), host.as_str().into());
+            }
+            SshConnError::InvalidSetting { key, .. } => {
+                error.insert("key".to_string(), key.as_str().into());
+            }
+            SshConnError::InvalidHostname { value, .. }
+            | SshConnError::InvalidUsername { value }
+            | SshConnError::InvalidPort { port: value } => {
+                error.insert("value".to_string(), value.as_str().into());
+            }
+            SshConnError::IdentityFileNotFound { path }
+            | SshConnError::PermissionDenied { path } => {
+                error.insert("path".to_string(), path.to_string_lossy().into());
+            }
+            SshConnError::BinaryNotFound { name } => {
+                error.insert("name".to_string(), name.as_str().into());
+            }
+            _ => {}
+        }
+        error.insert("message".to_string(), self.localized_message().into());
+        serde_json::json!({ "error": error })
+    }
+
+    /// 构造输入校验错误
+    pub fn validation(field: &str, message: impl Into<String>) -> Self {
+        SshConnError::Validation {
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+
+    /// 启动外部程序 `name` 失败时的错误：程序不存在时为 [`SshConnError::BinaryNotFound`]，
+    /// 其余情况交给 `other` 构造
+    pub fn spawn_failed(name: &str, err: io::Error, other: impl FnOnce(io::Error) -> Self) -> Self {
+        if err.kind() == io::ErrorKind::NotFound {
+            SshConnError::BinaryNotFound {
+                name: name.to_string(),
+            }
+        } else {
+            other(err)
+        }
+    }
+
+    /// 读写文件 `path` 失败时的错误：没有权限时为 [`SshConnError::PermissionDenied`]，
+    /// 文件被其他程序锁定时为 [`SshConnError::ConfigLocked`]，其余为 [`SshConnError::Io`]
+    pub fn file_access(path: impl Into<PathBuf>, err: io::Error) -> Self {
+        // Windows上文件被其他进程占用时返回 ERROR_SHARING_VIOLATION(32)/ERROR_LOCK_VIOLATION(33)
+        let locked = err.kind() == io::ErrorKind::ResourceBusy
+            || (cfg!(windows) && matches!(err.raw_os_error(), Some(32 | 33)));
+        match err.kind() {
+            io::ErrorKind::PermissionDenied => SshConnError::PermissionDenied { path: path.into() },
+            _ if locked => SshConnError::ConfigLocked,
+            _ => SshConnError::Io(err),
         }
     }
 }
@@ -83,3 +301,264 @@ impl From<SshConnError> for io::Error {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 每个变体各一个实例
+    #[allow(deprecated)]
+    fn every_variant() -> Vec<SshConnError> {
+        vec![
+            SshConnError::Io(io::Error::other("disk")),
+            SshConnError::Database(rusqlite::Error::InvalidQuery),
+            SshConnError::ConfigParse("bad".to_string()),
+            SshConnError::HostNotFound {
+                host: "web".to_string(),
+            },
+            SshConnError::HostAlreadyExists {
+                host: "web".to_string(),
+            },
+            SshConnError::validation("port", "bad port"),
+            SshConnError::PasswordError("locked".to_string()),
+            SshConnError::SshConnectionError("refused".to_string()),
+            SshConnError::TuiError("tty".to_string()),
+            SshConnError::Connection("timeout".to_string()),
+            SshConnError::InvalidSetting {
+                key: "theme".to_string(),
+                message: "unknown".to_string(),
+            },
+            SshConnError::InvalidArgument("oops".to_string()),
+            SshConnError::InvalidHostname {
+                value: "bad..host".to_string(),
+                reason: "consecutive dots".to_string(),
+            },
+            SshConnError::InvalidUsername {
+                value: "a@b".to_string(),
+            },
+            SshConnError::IdentityFileNotFound {
+                path: PathBuf::from("/keys/id_web"),
+            },
+            SshConnError::PermissionDenied {
+                path: PathBuf::from("/etc/ssh/ssh_config"),
+            },
+            SshConnError::BinaryNotFound {
+                name: "sshpass".to_string(),
+            },
+            SshConnError::ConfigLocked,
+            SshConnError::InvalidPort {
+                port: "99999".to_string(),
+            },
+        ]
+    }
+
+    /// 穷举匹配：新增变体时这里编译失败，提醒同时补上 `every_variant` 中的实例和退出码
+    #[allow(deprecated)]
+    fn variant_index(err: &SshConnError) -> usize {
+        match err {
+            SshConnError::Io(_) => 0,
+            SshConnError::Database(_) => 1,
+            SshConnError::ConfigParse(_) => 2,
+            SshConnError::HostNotFound { .. } => 3,
+            SshConnError::HostAlreadyExists { .. } => 4,
+            SshConnError::Validation { .. } => 5,
+            SshConnError::PasswordError(_) => 6,
+            SshConnError::SshConnectionError(_) => 7,
+            SshConnError::TuiError(_) => 8,
+            SshConnError::Connection(_) => 9,
+            SshConnError::InvalidSetting { .. } => 10,
+            SshConnError::InvalidArgument(_) => 11,
+            SshConnError::InvalidHostname { .. } => 12,
+            SshConnError::InvalidUsername { .. } => 13,
+            SshConnError::IdentityFileNotFound { .. } => 14,
+            SshConnError::PermissionDenied { .. } => 15,
+            SshConnError::BinaryNotFound { .. } => 16,
+            SshConnError::ConfigLocked => 17,
+            SshConnError::InvalidPort { .. } => 18,
+        }
+    }
+
+    #[test]
+    fn test_every_variant_is_covered() {
+        let indices: Vec<usize> = every_variant().iter().map(variant_index).collect();
+        assert_eq!(indices, (0..=18).collect::<Vec<_>>());
+        // 每个变体都有非空的本地化消息
+        assert!(
+            every_variant()
+                .iter()
+                .all(|e| !e.localized_message().is_empty())
+        );
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        let codes: Vec<(&str, i32)> = every_variant()
+            .iter()
+            .map(|e| (e.kind(), e.exit_code()))
+            .collect();
+        assert_eq!(
+            codes,
+            [
+                ("Io", 12),
+                ("Database", 11),
+                ("ConfigParse", 8),
+                ("HostNotFound", 3),
+                ("HostAlreadyExists", 7),
+                ("Validation", 4),
+                ("PasswordError", 10),
+                ("SshConnectionError", 5),
+                ("TuiError", 13),
+                ("Connection", 6),
+                ("InvalidSetting", 9),
+                ("InvalidArgument", 2),
+                ("InvalidHostname", 14),
+                ("InvalidUsername", 15),
+                ("IdentityFileNotFound", 16),
+                ("PermissionDenied", 17),
+                ("BinaryNotFound", 18),
+                ("ConfigLocked", 19),
+                ("InvalidPort", 20),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_exit_codes_are_distinct_and_nonzero() {
+        let variants = every_variant();
+        let kinds: std::collections::HashSet<&str> = variants.iter().map(|e| e.kind()).collect();
+        let codes: std::collections::HashSet<i32> =
+            variants.iter().map(|e| e.exit_code()).collect();
+        // 每个变体都覆盖到，且退出码互不相同，也不与成功(0)或未分类失败(1)混淆
+        assert_eq!(kinds.len(), variants.len());
+        assert_eq!(codes.len(), variants.len());
+        assert!(codes.iter().all(|&code| code > 1));
+    }
+
+    #[test]
+    fn test_json_host_not_found() {
+        let json = SshConnError::HostNotFound {
+            host: "web".to_string(),
+        }
+        .to_json();
+        assert_eq!(json["error"]["kind"], "HostNotFound");
+        assert_eq!(json["error"]["host"], "web");
+        assert!(json["error"]["message"].as_str().unwrap().contains("web"));
+    }
+
+    #[test]
+    fn test_json_validation_includes_field() {
+        let json = SshConnError::validation("port", "bad port").to_json();
+        assert_eq!(json["error"]["kind"], "Validation");
+        assert_eq!(json["error"]["field"], "port");
+        assert!(json["error"].get("host").is_none());
+    }
+
+    #[test]
+    fn test_json_invalid_setting_includes_key() {
+        let json = SshConnError::InvalidSetting {
+            key: "theme".to_string(),
+            message: "unknown".to_string(),
+        }
+        .to_json();
+        assert_eq!(json["error"]["kind"], "InvalidSetting");
+        assert_eq!(json["error"]["key"], "theme");
+    }
+
+    #[test]
+    fn test_json_structured_variants() {
+        let json = SshConnError::InvalidHostname {
+            value: "bad..host".to_string(),
+            reason: "consecutive dots".to_string(),
+        }
+        .to_json();
+        assert_eq!(json["error"]["kind"], "InvalidHostname");
+        assert_eq!(json["error"]["field"], "hostname");
+        assert_eq!(json["error"]["value"], "bad..host");
+        assert!(
+            json["error"]["message"]
+                .as_str()
+                .unwrap()
+                .contains("consecutive dots")
+        );
+
+        let json = SshConnError::InvalidUsername {
+            value: "a@b".to_string(),
+        }
+        .to_json();
+        assert_eq!(json["error"]["field"], "user");
+        assert_eq!(json["error"]["value"], "a@b");
+
+        let json = SshConnError::IdentityFileNotFound {
+            path: PathBuf::from("/keys/id_web"),
+        }
+        .to_json();
+        assert_eq!(json["error"]["path"], "/keys/id_web");
+
+        #[allow(deprecated)]
+        let json = SshConnError::InvalidPort {
+            port: "99999".to_string(),
+        }
+        .to_json();
+        assert_eq!(json["error"]["kind"], "InvalidPort");
+        assert_eq!(json["error"]["field"], "port");
+        assert_eq!(json["error"]["value"], "99999");
+
+        let json = SshConnError::BinaryNotFound {
+            name: "sshpass".to_string(),
+        }
+        .to_json();
+        assert_eq!(json["error"]["name"], "sshpass");
+        assert!(
+            json["error"]["message"]
+                .as_str()
+                .unwrap()
+                .contains("sshpass")
+        );
+
+        let json = SshConnError::ConfigLocked.to_json();
+        assert_eq!(json["error"]["kind"], "ConfigLocked");
+        assert_eq!(json["error"].as_object().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_spawn_failed_and_file_access() {
+        let missing = io::Error::from(io::ErrorKind::NotFound);
+        assert!(matches!(
+            SshConnError::spawn_failed("sshpass", missing, SshConnError::Io),
+            SshConnError::BinaryNotFound { name } if name == "sshpass"
+        ));
+        let other = io::Error::other("boom");
+        assert!(matches!(
+            SshConnError::spawn_failed("ssh", other, SshConnError::Io),
+            SshConnError::Io(_)
+        ));
+
+        let denied = io::Error::from(io::ErrorKind::PermissionDenied);
+        assert!(matches!(
+            SshConnError::file_access("/etc/ssh/ssh_config", denied),
+            SshConnError::PermissionDenied { path } if path == std::path::Path::new("/etc/ssh/ssh_config")
+        ));
+        let busy = io::Error::from(io::ErrorKind::ResourceBusy);
+        assert!(matches!(
+            SshConnError::file_access("config", busy),
+            SshConnError::ConfigLocked
+        ));
+        let missing = io::Error::from(io::ErrorKind::NotFound);
+        assert!(matches!(
+            SshConnError::file_access("config", missing),
+            SshConnError::Io(_)
+        ));
+    }
+
+    #[test]
+    fn test_json_plain_variants() {
+        let io = SshConnError::Io(io::Error::other("disk"));
+        let json = io.to_json();
+        assert_eq!(json["error"]["kind"], "Io");
+        assert_eq!(json["error"].as_object().unwrap().len(), 2);
+
+        let json = SshConnError::InvalidArgument("oops".to_string()).to_json();
+        assert_eq!(json["error"]["kind"], "InvalidArgument");
+        assert!(json["error"]["message"].as_str().unwrap().contains("oops"));
+    }
+}
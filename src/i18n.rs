@@ -67,10 +67,10 @@ impl Language {
     /// 从环境变量检测语言
     pub fn from_env() -> Self {
         // 检查 SSH_CONN_LANG 环境变量
-        if let Ok(ssh_conn_lang) = env::var("SSH_CONN_LANG") {
-            if let Some(lang) = Self::from_code(&ssh_conn_lang) {
-                return lang;
-            }
+        if let Ok(ssh_conn_lang) = env::var("SSH_CONN_LANG")
+            && let Some(lang) = Self::from_code(&ssh_conn_lang)
+        {
+            return lang;
         }
 
         // 检查其他环境变量
@@ -218,10 +218,10 @@ impl YamlTranslationLoader {
             }
 
             // 错误消息
-            if let Some(error_translations) = &translation_file.error {
-                if let Some(value) = error_translations.get("io_error") {
-                    all_translations.insert("error".to_string(), value.clone());
-                }
+            if let Some(error_translations) = &translation_file.error
+                && let Some(value) = error_translations.get("io_error")
+            {
+                all_translations.insert("error".to_string(), value.clone());
             }
 
             // 现在直接从YAML的根级别读取兼容性键
@@ -231,28 +231,28 @@ impl YamlTranslationLoader {
                 Language::English => include_str!("../locales/en.yaml"),
             };
 
-            if let Ok(raw_yaml) = serde_yaml::from_str::<serde_yaml::Value>(yaml_content) {
-                if let Some(mapping) = raw_yaml.as_mapping() {
-                    for (key, value) in mapping {
-                        if let (Some(key_str), Some(value_str)) = (key.as_str(), value.as_str()) {
-                            // 只添加不是结构体的键
-                            if ![
-                                "language",
-                                "ui",
-                                "form",
-                                "help",
-                                "error",
-                                "success",
-                                "cli",
-                                "cli_labels",
-                                "validation",
-                                "bench",
-                                "host_key_confirm",
-                            ]
-                            .contains(&key_str)
-                            {
-                                all_translations.insert(key_str.to_string(), value_str.to_string());
-                            }
+            if let Ok(raw_yaml) = serde_yaml::from_str::<serde_yaml::Value>(yaml_content)
+                && let Some(mapping) = raw_yaml.as_mapping()
+            {
+                for (key, value) in mapping {
+                    if let (Some(key_str), Some(value_str)) = (key.as_str(), value.as_str()) {
+                        // 只添加不是结构体的键
+                        if ![
+                            "language",
+                            "ui",
+                            "form",
+                            "help",
+                            "error",
+                            "success",
+                            "cli",
+                            "cli_labels",
+                            "validation",
+                            "bench",
+                            "host_key_confirm",
+                        ]
+                        .contains(&key_str)
+                        {
+                            all_translations.insert(key_str.to_string(), value_str.to_string());
                         }
                     }
                 }
@@ -307,10 +307,10 @@ impl I18n {
             self.cache.insert(self.current_language, translations);
         }
 
-        if let Some(translations) = self.cache.get(&self.current_language) {
-            if let Some(text) = translations.get(key) {
-                return text.clone();
-            }
+        if let Some(translations) = self.cache.get(&self.current_language)
+            && let Some(text) = translations.get(key)
+        {
+            return text.clone();
         }
 
         // 回退到英文
@@ -322,10 +322,10 @@ impl I18n {
                 self.cache.insert(Language::English, translations);
             }
 
-            if let Some(translations) = self.cache.get(&Language::English) {
-                if let Some(text) = translations.get(key) {
-                    return text.clone();
-                }
+            if let Some(translations) = self.cache.get(&Language::English)
+                && let Some(text) = translations.get(key)
+            {
+                return text.clone();
             }
         }
 
@@ -338,10 +338,10 @@ impl I18n {
                 self.cache.insert(Language::Chinese, translations);
             }
 
-            if let Some(translations) = self.cache.get(&Language::Chinese) {
-                if let Some(text) = translations.get(key) {
-                    return text.clone();
-                }
+            if let Some(translations) = self.cache.get(&Language::Chinese)
+                && let Some(text) = translations.get(key)
+            {
+                return text.clone();
             }
         }
 
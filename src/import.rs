@@ -0,0 +1,519 @@
+//! 从其他工具导入主机配置
+
+use std::path::Path;
+
+use crate::error::Result;
+use crate::i18n::t;
+use crate::models::SshHost;
+
+/// 解析导入来源得到的主机和提示信息
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportResult {
+    /// 可导入的主机
+    pub hosts: Vec<SshHost>,
+    /// 跳过的会话、需要手动处理的事项等提示
+    pub notes: Vec<String>,
+}
+
+/// PuTTY 会话的原始键值
+#[derive(Debug, Default)]
+struct PuttySession {
+    name: String,
+    values: Vec<(String, String)>,
+}
+
+impl PuttySession {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+            .filter(|v| !v.is_empty())
+    }
+}
+
+/// 读取 PuTTY 会话：`.reg` 注册表导出文件，或便携版的 sessions 目录（每个会话一个文件）
+pub fn load_putty(path: &Path) -> Result<ImportResult> {
+    if path.is_dir() {
+        let mut entries: Vec<_> = std::fs::read_dir(path)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_file())
+            .collect();
+        entries.sort_by_key(|entry| entry.file_name());
+
+        let mut sessions = Vec::new();
+        for entry in entries {
+            let content = decode_text(&std::fs::read(entry.path())?);
+            let name = entry.file_name().to_string_lossy().to_string();
+            sessions.push(parse_putty_session_file(&name, &content));
+        }
+        Ok(convert_sessions(sessions))
+    } else {
+        let content = decode_text(&std::fs::read(path)?);
+        Ok(parse_putty_reg(&content))
+    }
+}
+
+/// 解析 `regedit` 导出的 PuTTY 会话（`HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\...`）
+pub fn parse_putty_reg(content: &str) -> ImportResult {
+    const SESSIONS_KEY: &str = "\\software\\simontatham\\putty\\sessions\\";
+
+    let mut sessions = Vec::new();
+    let mut current: Option<PuttySession> = None;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            sessions.extend(current.take());
+            // 只处理 Sessions 下的直接子键
+            let lower = section.to_lowercase();
+            if let Some(pos) = lower.find(SESSIONS_KEY) {
+                let name = &section[pos + SESSIONS_KEY.len()..];
+                if !name.is_empty() && !name.contains('\\') {
+                    current = Some(PuttySession {
+                        name: url_decode(name),
+                        values: Vec::new(),
+                    });
+                }
+            }
+        } else if let Some(session) = current.as_mut()
+            && let Some((key, value)) = parse_reg_value(line)
+        {
+            session.values.push((key, value));
+        }
+    }
+    sessions.extend(current);
+
+    convert_sessions(sessions)
+}
+
+/// 解析便携版 PuTTY 的会话文件，每行形如 `HostName\example.com\`
+fn parse_putty_session_file(file_name: &str, content: &str) -> PuttySession {
+    let values = content
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim_end_matches(['\r', '\n']);
+            let (key, value) = line.split_once('\\')?;
+            let value = value.strip_suffix('\\').unwrap_or(value);
+            Some((key.to_string(), url_decode(value)))
+        })
+        .collect();
+
+    PuttySession {
+        name: url_decode(file_name),
+        values,
+    }
+}
+
+/// 解析注册表导出中的一行值：`"Key"="string"` 或 `"Key"=dword:00000016`
+fn parse_reg_value(line: &str) -> Option<(String, String)> {
+    let rest = line.strip_prefix('"')?;
+    let (key, rest) = read_reg_string(rest)?;
+    let value = rest.strip_prefix('=')?;
+
+    let value = if let Some(quoted) = value.strip_prefix('"') {
+        read_reg_string(quoted)?.0
+    } else if let Some(hex) = value.strip_prefix("dword:") {
+        u32::from_str_radix(hex.trim(), 16).ok()?.to_string()
+    } else {
+        // 其他类型（hex: 等）不需要
+        return None;
+    };
+    Some((key, value))
+}
+
+/// 读取引号内的字符串（开头引号已去掉），处理 `\\` 和 `\"` 转义，返回内容和剩余部分
+fn read_reg_string(input: &str) -> Option<(String, &str)> {
+    let mut value = String::new();
+    let mut chars = input.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => value.push(chars.next()?.1),
+            '"' => return Some((value, &input[i + 1..])),
+            _ => value.push(c),
+        }
+    }
+    None
+}
+
+/// 解码 `%XX` 形式的URL编码（PuTTY 会话名和便携版的值使用这种编码）
+fn url_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(byte) = input
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            decoded.push(byte);
+            i += 3;
+            continue;
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// 注册表导出文件通常是带BOM的UTF-16LE，也兼容UTF-8
+fn decode_text(bytes: &[u8]) -> String {
+    if let Some(rest) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+        let units: Vec<u16> = rest
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        return String::from_utf16_lossy(&units);
+    }
+    let bytes = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+    String::from_utf8_lossy(bytes).to_string()
+}
+
+/// 将 PuTTY 会话转换为主机配置，跳过非SSH会话和缺少主机地址的会话
+fn convert_sessions(sessions: Vec<PuttySession>) -> ImportResult {
+    let mut result = ImportResult::default();
+
+    for session in sessions {
+        // "Default Settings" 是 PuTTY 的默认模板，不是实际会话
+        if session.name == "Default Settings" {
+            continue;
+        }
+
+        // 未设置协议时 PuTTY 默认使用SSH
+        let protocol = session.get("Protocol").unwrap_or("ssh");
+        if !protocol.eq_ignore_ascii_case("ssh") {
+            result.notes.push(
+                t("import_skip_protocol")
+                    .replacen("{}", &session.name, 1)
+                    .replacen("{}", protocol, 1),
+            );
+            continue;
+        }
+
+        // HostName 可能写成 user@host
+        let (user, hostname) = match session.get("HostName") {
+            Some(value) => match value.rsplit_once('@') {
+                Some((user, host)) => (Some(user), host),
+                None => (None, value),
+            },
+            None => {
+                result
+                    .notes
+                    .push(t("import_skip_no_hostname").replace("{}", &session.name));
+                continue;
+            }
+        };
+
+        // Host 别名不能包含空白
+        let alias: String = session
+            .name
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join("-");
+        let mut host = SshHost::new(alias);
+        host.hostname = Some(hostname.to_string());
+        host.user = session.get("UserName").or(user).map(str::to_string);
+        host.port = session
+            .get("PortNumber")
+            .filter(|port| *port != "22")
+            .map(str::to_string);
+
+        if let Some(key) = session.get("PublicKeyFile") {
+            match key
+                .strip_suffix(".ppk")
+                .or_else(|| key.strip_suffix(".PPK"))
+            {
+                Some(stem) => {
+                    // .ppk 需要用 puttygen 转换为 OpenSSH 格式，这里指向转换后的文件
+                    result.notes.push(
+                        t("import_ppk_warning")
+                            .replacen("{}", &session.name, 1)
+                            .replacen("{}", key, 1)
+                            .replacen("{}", stem, 1),
+                    );
+                    host.identity_file = Some(stem.to_string());
+                }
+                None => host.identity_file = Some(key.to_string()),
+            }
+        }
+
+        result.hosts.push(host);
+    }
+
+    result
+}
+
+/// 从 known_hosts 内容中找出配置里还没有的主机
+///
+/// 每行的主机名/IP列表视为同一台机器，优先用域名作为HostName；`[host]:port` 保留端口。
+/// 已在 `existing` 中（按Host别名或HostName，不区分大小写）出现的主机、通配符模式、
+/// `@cert-authority`/`@revoked` 行均跳过。哈希过的条目无法还原主机名，只统计数量。
+/// 生成的Host别名取域名第一段或把IP中的分隔符换成 `-`，重名时追加序号
+pub fn parse_known_hosts(content: &str, existing: &[SshHost]) -> ImportResult {
+    let mut result = ImportResult::default();
+    let mut taken: Vec<String> = existing
+        .iter()
+        .flat_map(|h| std::iter::once(&h.host).chain(h.hostname.as_ref()))
+        .map(|name| name.to_lowercase())
+        .collect();
+    let mut hashed = 0;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('@') {
+            continue;
+        }
+        let Some(patterns) = line.split_whitespace().next() else {
+            continue;
+        };
+        if patterns.starts_with("|1|") {
+            hashed += 1;
+            continue;
+        }
+
+        let entries: Vec<(String, Option<String>)> = patterns
+            .split(',')
+            .filter(|p| !p.contains(['*', '?', '!']))
+            .filter_map(split_known_host)
+            .collect();
+        if entries.is_empty() || entries.iter().any(|(name, _)| taken.contains(name)) {
+            continue;
+        }
+
+        let (hostname, port) = entries
+            .iter()
+            .find(|(name, _)| name.parse::<std::net::IpAddr>().is_err())
+            .unwrap_or(&entries[0])
+            .clone();
+
+        let base = match hostname.parse::<std::net::IpAddr>() {
+            Ok(_) => hostname.replace(['.', ':'], "-"),
+            Err(_) => hostname.split('.').next().unwrap_or(&hostname).to_string(),
+        };
+        let mut alias = base.clone();
+        let mut suffix = 2;
+        while taken.contains(&alias) {
+            alias = format!("{}-{}", base, suffix);
+            suffix += 1;
+        }
+
+        taken.push(alias.clone());
+        taken.extend(entries.into_iter().map(|(name, _)| name));
+
+        let mut host = SshHost::new(alias);
+        host.hostname = Some(hostname);
+        host.port = port;
+        result.hosts.push(host);
+    }
+
+    if hashed > 0 {
+        result
+            .notes
+            .push(t("import_known_hosts_hashed").replace("{}", &hashed.to_string()));
+    }
+    result
+}
+
+/// 拆分 known_hosts 中的单个主机模式：`host` 或 `[host]:port`，名称转为小写
+fn split_known_host(pattern: &str) -> Option<(String, Option<String>)> {
+    let (name, port) = match pattern.strip_prefix('[') {
+        Some(rest) => {
+            let (name, port) = rest.split_once("]:")?;
+            (name, Some(port.to_string()).filter(|p| p != "22"))
+        }
+        None => (pattern, None),
+    };
+    (!name.is_empty()).then(|| (name.to_lowercase(), port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KNOWN_HOSTS_FIXTURE: &str = "\
+# comment line
+web01.example.com,10.0.0.11 ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIA web01
+10.0.0.12 ecdsa-sha2-nistp256 AAAAE2VjZHNh
+[git.example.com]:2222,[10.0.0.13]:2222 ssh-ed25519 AAAAC3Nza
+[ssh.example.com]:22 ssh-rsa AAAAB3Nza
+|1|F1E1KeoE/eEWhi10WpGv4OdiO6Y=|3988QV0VE8wmZL7suNrYQLITLCg= ssh-rsa AAAAB3Nza
+|1|Sa2rbm/2WzxRpTcWFAPvhYHUOqU=|8pzTqXPa0bFMsSXj0c1yJZa+5fk= ssh-ed25519 AAAAC3Nza
+@cert-authority *.example.com ssh-rsa AAAAB3Nza
+*.internal ssh-rsa AAAAB3Nza
+db.example.com ssh-ed25519 AAAAC3Nza
+web01.other.net ssh-ed25519 AAAAC3Nza
+
+WEB01.EXAMPLE.COM ssh-rsa AAAAB3Nza
+";
+
+    #[test]
+    fn test_parse_known_hosts() {
+        let mut existing = SshHost::new("database".to_string());
+        existing.hostname = Some("DB.example.com".to_string());
+        let result = parse_known_hosts(KNOWN_HOSTS_FIXTURE, &[existing]);
+
+        let summary: Vec<(&str, &str, Option<&str>)> = result
+            .hosts
+            .iter()
+            .map(|h| {
+                (
+                    h.host.as_str(),
+                    h.hostname.as_deref().unwrap(),
+                    h.port.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                // 同一行中优先使用域名
+                ("web01", "web01.example.com", None),
+                ("10-0-0-12", "10.0.0.12", None),
+                // 带端口的条目保留端口，默认端口不写入
+                ("git", "git.example.com", Some("2222")),
+                ("ssh", "ssh.example.com", None),
+                // 别名重复时追加序号
+                ("web01-2", "web01.other.net", None),
+            ]
+        );
+
+        // 两条哈希条目只给出提示
+        assert_eq!(result.notes.len(), 1);
+        assert!(result.notes[0].contains('2'));
+    }
+
+    #[test]
+    fn test_parse_known_hosts_dedupes_against_config_aliases() {
+        let existing = SshHost::new("git".to_string());
+        let result = parse_known_hosts("[git.example.com]:2222 ssh-ed25519 AAAA\n", &[existing]);
+        assert_eq!(result.hosts.len(), 1);
+        assert_eq!(result.hosts[0].host, "git-2");
+
+        let mut existing = SshHost::new("gitlab".to_string());
+        existing.hostname = Some("10.0.0.13".to_string());
+        let result = parse_known_hosts(
+            "[git.example.com]:2222,[10.0.0.13]:2222 ssh-ed25519 AAAA\n",
+            &[existing],
+        );
+        assert!(result.hosts.is_empty());
+    }
+
+    const REG_FIXTURE: &str = r#"Windows Registry Editor Version 5.00
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions]
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\Default%20Settings]
+"HostName"=""
+"PortNumber"=dword:00000016
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\Prod%20Web%2001]
+"Present"=dword:00000001
+"HostName"="web01.example.com"
+"Protocol"="ssh"
+"PortNumber"=dword:00000b3a
+"UserName"="deploy"
+"PublicKeyFile"="C:\\Users\\me\\keys\\prod.ppk"
+"Colour0"="187,187,187"
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\router]
+"HostName"="192.168.1.1"
+"Protocol"="telnet"
+"PortNumber"=dword:00000017
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\db]
+"HostName"="admin@db.internal"
+"PortNumber"=dword:00000016
+"PublicKeyFile"="C:\\keys\\id_ed25519"
+"WinTitle"="say \"hi\""
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\empty]
+"Protocol"="ssh"
+"#;
+
+    #[test]
+    fn test_parse_putty_reg() {
+        let result = parse_putty_reg(REG_FIXTURE);
+        let names: Vec<&str> = result.hosts.iter().map(|h| h.host.as_str()).collect();
+        assert_eq!(names, ["Prod-Web-01", "db"]);
+
+        let web = &result.hosts[0];
+        assert_eq!(web.hostname.as_deref(), Some("web01.example.com"));
+        assert_eq!(web.port.as_deref(), Some("2874"));
+        assert_eq!(web.user.as_deref(), Some("deploy"));
+        assert_eq!(
+            web.identity_file.as_deref(),
+            Some("C:\\Users\\me\\keys\\prod")
+        );
+
+        // user@host 拆分，默认端口不写入
+        let db = &result.hosts[1];
+        assert_eq!(db.hostname.as_deref(), Some("db.internal"));
+        assert_eq!(db.user.as_deref(), Some("admin"));
+        assert_eq!(db.port, None);
+        assert_eq!(db.identity_file.as_deref(), Some("C:\\keys\\id_ed25519"));
+
+        // ppk警告、telnet会话、缺少HostName的会话各一条提示
+        assert_eq!(result.notes.len(), 3, "{:?}", result.notes);
+        assert!(result.notes.iter().any(|n| n.contains("prod.ppk")));
+        assert!(result.notes.iter().any(|n| n.contains("router")));
+        assert!(result.notes.iter().any(|n| n.contains("empty")));
+    }
+
+    #[test]
+    fn test_parse_reg_value_escapes() {
+        assert_eq!(
+            parse_reg_value(r#""WinTitle"="say \"hi\" \\ bye""#),
+            Some(("WinTitle".to_string(), "say \"hi\" \\ bye".to_string()))
+        );
+        assert_eq!(
+            parse_reg_value(r#""PortNumber"=dword:00000016"#),
+            Some(("PortNumber".to_string(), "22".to_string()))
+        );
+        assert_eq!(parse_reg_value(r#""Blob"=hex:01,02"#), None);
+        assert_eq!(parse_reg_value(r#""Broken"="unterminated"#), None);
+    }
+
+    #[test]
+    fn test_url_decode() {
+        assert_eq!(url_decode("My%20Server%2Fprod"), "My Server/prod");
+        assert_eq!(url_decode("%E4%B8%AD%E6%96%87"), "中文");
+        // 不完整的转义保持原样
+        assert_eq!(url_decode("100%"), "100%");
+        assert_eq!(url_decode("%zz"), "%zz");
+    }
+
+    #[test]
+    fn test_decode_utf16_reg_export() {
+        let mut bytes = vec![0xFF, 0xFE];
+        for unit in REG_FIXTURE.encode_utf16() {
+            bytes.extend_from_slice(&unit.to_le_bytes());
+        }
+        assert_eq!(decode_text(&bytes), REG_FIXTURE);
+        assert_eq!(parse_putty_reg(&decode_text(&bytes)).hosts.len(), 2);
+    }
+
+    #[test]
+    fn test_load_putty_sessions_folder() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("Build%20Box"),
+            "HostName\\build.example.com\\\nPortNumber\\2222\\\nUserName\\ci\\\nProtocol\\ssh\\\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("serial"),
+            "Protocol\\serial\\\nSerialLine\\COM1\\\n",
+        )
+        .unwrap();
+
+        let result = load_putty(dir.path()).unwrap();
+        assert_eq!(result.hosts.len(), 1);
+        let host = &result.hosts[0];
+        assert_eq!(host.host, "Build-Box");
+        assert_eq!(host.hostname.as_deref(), Some("build.example.com"));
+        assert_eq!(host.port.as_deref(), Some("2222"));
+        assert_eq!(host.user.as_deref(), Some("ci"));
+        assert_eq!(result.notes.len(), 1);
+    }
+}
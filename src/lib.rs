@@ -1,14 +1,27 @@
 //! SSH连接管理工具库
 
+pub mod agent;
+pub mod audit;
+pub mod bench;
 pub mod cli;
 pub mod config;
 pub mod error;
 pub mod i18n;
+pub mod import;
+pub mod logfile;
+pub mod metrics;
 pub mod models;
 pub mod network;
+pub mod output;
 pub mod password;
+pub mod permissions;
+pub mod prompt;
+pub mod settings;
+pub mod store;
 pub mod ui;
 pub mod utils;
+pub mod version;
+pub mod watch;
 
 // 重新导出常用类型
 pub use error::{Result, SshConnError};
@@ -51,6 +64,104 @@ mod tests {
         assert_eq!(host.get_connection_string(), "192.168.1.100:2222");
     }
 
+    #[test]
+    fn test_ssh_host_full_ssh_command() {
+        let mut host = SshHost::new("web".to_string());
+
+        // 只有Host别名
+        assert_eq!(host.get_full_ssh_command(), "ssh web");
+
+        // 用户名但没有HostName时使用别名
+        host.user = Some("admin".to_string());
+        assert_eq!(host.get_full_ssh_command(), "ssh admin@web");
+
+        // HostName和端口
+        host.hostname = Some("10.0.0.5".to_string());
+        host.port = Some("2222".to_string());
+        assert_eq!(host.get_full_ssh_command(), "ssh -p 2222 admin@10.0.0.5");
+
+        // 身份文件保持 ~ 不被引号包裹
+        host.identity_file = Some("~/.ssh/id_ed25519".to_string());
+        assert_eq!(
+            host.get_full_ssh_command(),
+            "ssh -i ~/.ssh/id_ed25519 -p 2222 admin@10.0.0.5"
+        );
+
+        // ProxyJump 来自自定义选项（大小写不敏感）
+        host.custom_options
+            .insert("proxyjump".to_string(), "bastion".to_string());
+        assert_eq!(
+            host.get_full_ssh_command(),
+            "ssh -i ~/.ssh/id_ed25519 -p 2222 -J bastion admin@10.0.0.5"
+        );
+
+        // 包含空格的参数需要引号
+        let mut proxied = SshHost::new("db".to_string());
+        proxied.hostname = Some("db.internal".to_string());
+        proxied.proxy_command = Some("ssh -W %h:%p jump".to_string());
+        proxied.remote_command = Some("tmux new -A -s main".to_string());
+        assert_eq!(
+            proxied.get_full_ssh_command(),
+            "ssh -o 'ProxyCommand=ssh -W %h:%p jump' -t db.internal 'tmux new -A -s main'"
+        );
+    }
+
+    #[test]
+    fn test_search_query_parse() {
+        use models::SearchQuery;
+
+        let query = SearchQuery::parse("  Prod -EU  \"web 01\" -\"us east\" ");
+        assert_eq!(query.include, ["prod", "web 01"]);
+        assert_eq!(query.exclude, ["eu", "us east"]);
+
+        // 词中间的 `-` 不是排除，单独的 `-` 按字面量匹配
+        let query = SearchQuery::parse("old-db -");
+        assert_eq!(query.include, ["old-db", "-"]);
+        assert!(query.exclude.is_empty());
+
+        // 未闭合的引号吞掉剩余内容
+        let query = SearchQuery::parse("\"a b -c");
+        assert_eq!(query.include, ["a b -c"]);
+
+        assert!(SearchQuery::parse("   ").is_empty());
+    }
+
+    #[test]
+    fn test_search_query_over_fixture_hosts() {
+        let fixture = [
+            ("prod-eu-web", "10.1.0.1", "deploy"),
+            ("prod-us-web", "10.2.0.1", "deploy"),
+            ("prod-us-db", "10.2.0.2", "postgres"),
+            ("staging-eu", "10.3.0.1", "deploy"),
+            ("web 01", "192.168.1.1", "root"),
+        ];
+        let hosts: Vec<SshHost> = fixture
+            .iter()
+            .map(|(name, hostname, user)| {
+                let mut host = SshHost::new(name.to_string());
+                host.hostname = Some(hostname.to_string());
+                host.user = Some(user.to_string());
+                host
+            })
+            .collect();
+        let search = |query: &str| -> Vec<&str> {
+            hosts
+                .iter()
+                .filter(|h| h.matches_query(query))
+                .map(|h| h.host.as_str())
+                .collect()
+        };
+
+        assert_eq!(search("prod -eu"), ["prod-us-web", "prod-us-db"]);
+        assert_eq!(search("prod us web"), ["prod-us-web"]);
+        assert_eq!(search("deploy -prod"), ["staging-eu"]);
+        assert_eq!(search("-prod -staging"), ["web 01"]);
+        assert_eq!(search("\"web 01\""), ["web 01"]);
+        assert_eq!(search("web 01"), ["web 01"]);
+        assert_eq!(search("prod -postgres -10.1"), ["prod-us-web"]);
+        assert_eq!(search("").len(), 5);
+    }
+
     #[test]
     fn test_ssh_host_matches_query() {
         let mut host = SshHost::new("web-server".to_string());
@@ -103,6 +214,23 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_ssh_host_config_diff() {
+        let mut old = SshHost::new("web".to_string());
+        old.hostname = Some("10.0.0.5".to_string());
+        old.user = Some("deploy".to_string());
+        old.port = Some("2222".to_string());
+        let mut new = old.clone();
+        new.user = Some("admin".to_string());
+        new.port = None;
+
+        assert_eq!(
+            old.config_diff(&new),
+            "--- a/web\n+++ b/web\n@@ -1,4 +1,3 @@\n Host web\n     HostName 10.0.0.5\n-    User deploy\n-    Port 2222\n+    User admin\n"
+        );
+        assert_eq!(old.config_diff(&old.clone()), "");
+    }
+
     #[test]
     fn test_ssh_host_with_custom_options() {
         let mut host = SshHost::new("custom-server".to_string());
@@ -160,6 +288,29 @@ mod tests {
         assert!(empty_port.validate().is_ok());
     }
 
+    #[test]
+    fn test_form_field_select_cycles_options() {
+        let mut field = FormField::new("模板", "").with_type(FormFieldType::Select(vec![
+            "dc-a".to_string(),
+            "dc-b".to_string(),
+        ]));
+
+        field.cycle_option(true);
+        assert_eq!(field.value, "dc-a");
+        field.cycle_option(true);
+        assert_eq!(field.value, "dc-b");
+        // 最后一个选项之后回到未选择
+        field.cycle_option(true);
+        assert_eq!(field.value, "");
+        field.cycle_option(false);
+        assert_eq!(field.value, "dc-b");
+
+        // 非选择字段不受影响
+        let mut text = FormField::new("主机名", "web");
+        text.cycle_option(true);
+        assert_eq!(text.value, "web");
+    }
+
     #[test]
     fn test_ssh_host_serialization() {
         let mut host = SshHost::new("test-server".to_string());
@@ -245,11 +396,309 @@ mod tests {
         // 验证搜索文本被正确恢复
         assert_eq!(new_search_input, "redis");
     }
+
+    #[test]
+    fn test_troubleshooting_hint() {
+        use models::ConnectionStatus;
+
+        let hint =
+            |message: &str| ConnectionStatus::Failed(message.to_string()).troubleshooting_hint();
+        assert_eq!(
+            hint("Connection failed: Connection refused (os error 111)"),
+            Some(t("hint.refused"))
+        );
+        assert_eq!(hint("Connection timeout after 5s"), Some(t("hint.timeout")));
+        assert_eq!(
+            hint("DNS resolution failed: failed to lookup address information"),
+            Some(t("hint.dns"))
+        );
+        assert_eq!(
+            hint("Connection failed: No route to host (os error 113)"),
+            Some(t("hint.unreachable"))
+        );
+        assert_eq!(
+            hint("ssh: Permission denied (publickey,password)."),
+            Some(t("hint.auth"))
+        );
+        assert_eq!(
+            hint("ssh: Host key verification failed."),
+            Some(t("hint.host_key"))
+        );
+        assert_eq!(hint("something unexpected"), None);
+        assert_eq!(ConnectionStatus::Unknown.troubleshooting_hint(), None);
+        assert_eq!(
+            ConnectionStatus::Connected(std::time::Duration::from_millis(3)).troubleshooting_hint(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_ssh_host_from_str_errors() {
+        assert!(matches!(
+            "".parse::<SshHost>(),
+            Err(SshConnError::ConfigParse(_))
+        ));
+        assert!(matches!(
+            "Host a\nHost b\n".parse::<SshHost>(),
+            Err(SshConnError::ConfigParse(_))
+        ));
+        assert!(matches!(
+            "Include other\nHost a\n".parse::<SshHost>(),
+            Err(SshConnError::ConfigParse(_))
+        ));
+        // 通配符块不计入
+        let host: SshHost = "Host *\n    User root\nHost a\n".parse().unwrap();
+        assert_eq!(host.host, "a");
+    }
+
+    /// 取值不以空白开头或结尾，也不含换行
+    fn option_value() -> impl proptest::strategy::Strategy<Value = String> {
+        "[A-Za-z0-9_./:@-]([A-Za-z0-9_./:@ -]{0,20}[A-Za-z0-9_./:@-])?"
+    }
+
+    fn optional_value() -> impl proptest::strategy::Strategy<Value = Option<String>> {
+        proptest::option::of(option_value())
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn prop_display_from_str_round_trip(
+            host in "[a-z][a-z0-9.-]{0,15}",
+            hostname in optional_value(),
+            user in proptest::option::of("[a-z_][a-z0-9_-]{0,15}"),
+            port in proptest::option::of(1u16..),
+            identity_file in optional_value(),
+            remote_command in optional_value(),
+            ciphers in optional_value(),
+            password_command in optional_value(),
+            // 自定义关键字不与内置字段重名
+            custom_options in proptest::collection::hash_map("X[A-Za-z]{1,12}", option_value(), 0..5),
+        ) {
+            let mut original = SshHost::new(host);
+            original.hostname = hostname;
+            original.user = user;
+            original.port = port.map(|p| p.to_string());
+            original.identity_file = identity_file;
+            original.remote_command = remote_command;
+            original.ciphers = ciphers;
+            original.password_command = password_command;
+            original.custom_options = custom_options;
+
+            let parsed: SshHost = original.to_string().parse().unwrap();
+            proptest::prop_assert_eq!(parsed, original);
+        }
+    }
 }
 
 #[cfg(test)]
 mod utils_tests {
     use super::utils::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_expand_path_tilde() {
+        let home = dirs::home_dir().unwrap_or_default();
+        assert_eq!(expand_path("~"), home);
+        assert_eq!(expand_path("~/"), home);
+        assert_eq!(
+            expand_path("~/.ssh/id_ed25519"),
+            home.join(".ssh/id_ed25519")
+        );
+        // 只展开开头的 ~
+        assert_eq!(expand_path("keys/~/id"), PathBuf::from("keys/~/id"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_expand_path_tilde_user() {
+        let root = expand_path("~root/.ssh/id_rsa");
+        assert!(root.is_absolute());
+        assert!(root.ends_with(".ssh/id_rsa"));
+        // 不存在的用户原样保留
+        assert_eq!(
+            expand_path("~no-such-user-ssh-conn/id"),
+            PathBuf::from("~no-such-user-ssh-conn/id")
+        );
+    }
+
+    #[test]
+    fn test_expand_path_env_vars() {
+        let value = std::env::var("PATH").unwrap();
+        assert_eq!(
+            expand_path("$PATH/id"),
+            PathBuf::from(format!("{}/id", value))
+        );
+        assert_eq!(
+            expand_path("${PATH}_suffix"),
+            PathBuf::from(format!("{}_suffix", value))
+        );
+        // 变量名到第一个非字母数字、非下划线字符为止
+        assert_eq!(
+            expand_path("/a/$PATH.b"),
+            PathBuf::from(format!("/a/{}.b", value))
+        );
+        // 未设置的变量和不完整的写法原样保留
+        for literal in [
+            "$SSH_CONN_UNSET_VAR/id",
+            "${SSH_CONN_UNSET_VAR}/id",
+            "${PATH/id",
+            "cost$",
+            "a$/b",
+            "${}",
+        ] {
+            assert_eq!(expand_path(literal), PathBuf::from(literal));
+        }
+        // ~ 之后的部分同样展开环境变量
+        let home = dirs::home_dir().unwrap_or_default();
+        assert_eq!(
+            expand_path("~/${PATH}"),
+            home.join(value.trim_start_matches(std::path::is_separator))
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_expand_path_windows_vars() {
+        let value = std::env::var("PATH").unwrap();
+        assert_eq!(
+            expand_path("%PATH%\\id"),
+            PathBuf::from(format!("{}\\id", value))
+        );
+        assert_eq!(
+            expand_path("%SSH_CONN_UNSET_VAR%\\id"),
+            PathBuf::from("%SSH_CONN_UNSET_VAR%\\id")
+        );
+        assert_eq!(expand_path("100%"), PathBuf::from("100%"));
+    }
+
+    #[test]
+    fn test_expand_path_without_expansion() {
+        for path in ["/etc/keys/id_rsa", "relative/id", "", "C:\\keys\\id"] {
+            assert_eq!(expand_path(path), PathBuf::from(path));
+        }
+    }
+
+    #[test]
+    fn test_parse_set_env() {
+        assert_eq!(
+            parse_set_env("LANG=C.UTF-8 MSG=\"a b\" EMPTY=").unwrap(),
+            vec![
+                ("LANG".to_string(), "C.UTF-8".to_string()),
+                ("MSG".to_string(), "a b".to_string()),
+                ("EMPTY".to_string(), String::new()),
+            ]
+        );
+        assert!(parse_set_env("").unwrap().is_empty());
+
+        assert!(validate_env_var("_PRIVATE=1").is_ok());
+        assert!(validate_env_var("LANG").is_err());
+        assert!(validate_env_var("=value").is_err());
+        assert!(validate_env_var("1ABC=x").is_err());
+        assert!(validate_env_var("MY-VAR=x").is_err());
+
+        assert_eq!(format_env_var("LANG", "C"), "LANG=C");
+        assert_eq!(format_env_var("MSG", "a \"b\""), "MSG=\"a \\\"b\\\"\"");
+    }
+
+    #[test]
+    fn test_host_domain() {
+        assert_eq!(
+            host_domain("db1.eu.example.com").as_deref(),
+            Some("example.com")
+        );
+        assert_eq!(
+            host_domain("Web.EU.Example.COM.").as_deref(),
+            Some("example.com")
+        );
+        assert_eq!(host_domain("example.com").as_deref(), Some("example.com"));
+        assert_eq!(host_domain("api.shop.co.uk").as_deref(), Some("shop.co.uk"));
+        assert_eq!(
+            host_domain("www.example.com.cn").as_deref(),
+            Some("example.com.cn")
+        );
+
+        // IP地址和单段主机名无法分组
+        assert_eq!(host_domain("192.168.1.10"), None);
+        assert_eq!(host_domain("2001:db8::1"), None);
+        assert_eq!(host_domain("[::1]"), None);
+        assert_eq!(host_domain("localhost"), None);
+        assert_eq!(host_domain("bad..name"), None);
+    }
+
+    #[test]
+    fn test_group_hosts() {
+        use crate::models::SshHost;
+
+        let host = |name: &str, hostname: Option<&str>| {
+            let mut h = SshHost::new(name.to_string());
+            h.hostname = hostname.map(str::to_string);
+            h
+        };
+        let hosts = vec![
+            host("web", Some("web.eu.example.com")),
+            host("nas", Some("192.168.1.10")),
+            host("db", Some("DB.US.Example.com")),
+            host("blog", Some("blog.acme.org")),
+            host("alias", None),
+            host("router", Some("router")),
+        ];
+
+        let groups = group_hosts(&hosts, |h| h.hostname.as_deref().and_then(host_domain));
+        let summary: Vec<(Option<&str>, Vec<&str>)> = groups
+            .iter()
+            .map(|(name, members)| {
+                (
+                    name.as_deref(),
+                    members.iter().map(|h| h.host.as_str()).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (Some("acme.org"), vec!["blog"]),
+                (Some("example.com"), vec!["web", "db"]),
+                (None, vec!["nas", "alias", "router"]),
+            ]
+        );
+
+        assert!(group_hosts(&[], |_| None).is_empty());
+    }
+
+    #[test]
+    fn test_natural_cmp() {
+        use std::cmp::Ordering;
+
+        let mut names = vec![
+            "web10", "web2", "Web1", "web", "web-1", "db01", "db1", "db001",
+        ];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            names,
+            [
+                "db1", "db01", "db001", "web", "web-1", "Web1", "web2", "web10"
+            ]
+        );
+
+        // 纯数字与超出u64范围的数字
+        assert_eq!(natural_cmp("9", "10"), Ordering::Less);
+        assert_eq!(natural_cmp("100", "099"), Ordering::Greater);
+        assert_eq!(
+            natural_cmp("99999999999999999999999", "100000000000000000000000"),
+            Ordering::Less
+        );
+
+        // 前缀相同时短的在前，有后缀的在后
+        assert_eq!(natural_cmp("node", "node1"), Ordering::Less);
+        assert_eq!(natural_cmp("node1", "node1a"), Ordering::Less);
+        assert_eq!(natural_cmp("node1a", "node2"), Ordering::Less);
+
+        // 不区分大小写，包括非ASCII字符
+        assert_eq!(natural_cmp("Ärger2", "ärger10"), Ordering::Less);
+        assert_eq!(natural_cmp("服务器2", "服务器10"), Ordering::Less);
+        assert_eq!(natural_cmp("ÉCOLE", "école"), Ordering::Less);
+        assert_eq!(natural_cmp("école", "école"), Ordering::Equal);
+    }
 
     #[test]
     fn test_validate_port() {
@@ -283,6 +732,99 @@ mod utils_tests {
         assert!(validate_hostname("invalid..domain").is_err());
     }
 
+    #[test]
+    fn test_validate_hostname_table() {
+        use crate::error::SshConnError;
+        use crate::i18n::t;
+
+        let label_63 = "a".repeat(63);
+        let label_64 = "a".repeat(64);
+        // 4段63字符加3个点共255字符
+        let long_name = [label_63.as_str(); 4].join(".");
+        let max_name = format!("{}.{}", [label_63.as_str(); 3].join("."), "a".repeat(61));
+        assert_eq!(max_name.len(), 253);
+
+        // (输入, 期望的错误键；None 表示有效)
+        let cases: Vec<(&str, Option<&str>)> = vec![
+            // IPv4
+            ("10.0.0.1", None),
+            ("255.255.255.255", None),
+            // IPv6，可带方括号和区域ID
+            ("2001:db8::1", None),
+            ("::1", None),
+            ("[2001:db8::1]", None),
+            ("fe80::1%eth0", None),
+            ("[fe80::1%eth0]", None),
+            ("::ffff:192.0.2.1", None),
+            ("2001:db8::g", Some("validation.hostname_invalid_ipv6")),
+            ("2001:db8:::1", Some("validation.hostname_invalid_ipv6")),
+            ("[2001:db8::1", Some("validation.hostname_invalid_ipv6")),
+            ("2001:db8::1]", Some("validation.hostname_invalid_ipv6")),
+            ("[example.com]", Some("validation.hostname_invalid_ipv6")),
+            ("fe80::1%", Some("validation.hostname_invalid_ipv6")),
+            ("host:22", Some("validation.hostname_invalid_ipv6")),
+            // DNS名称
+            ("example.com", None),
+            ("a", None),
+            ("xn--bcher-kva.example", None),
+            ("0day.example", None),
+            (label_63.as_str(), None),
+            (max_name.as_str(), None),
+            (
+                label_64.as_str(),
+                Some("validation.hostname_label_too_long"),
+            ),
+            (long_name.as_str(), Some("validation.hostname_too_long")),
+            ("my_host.example", Some("validation.hostname_invalid_chars")),
+            ("host!", Some("validation.hostname_invalid_chars")),
+            ("-web.example", Some("validation.hostname_label_hyphen")),
+            ("web-.example", Some("validation.hostname_label_hyphen")),
+            ("web.-example", Some("validation.hostname_label_hyphen")),
+            // 原有规则
+            ("", Some("validation.hostname_empty")),
+            (" host", Some("validation.hostname_whitespace")),
+            ("host\t", Some("validation.hostname_whitespace")),
+            ("my host", Some("validation.hostname_spaces")),
+            (
+                "invalid..domain",
+                Some("validation.hostname_consecutive_dots"),
+            ),
+            (
+                ".example.com",
+                Some("validation.hostname_starts_or_ends_with_dot"),
+            ),
+            (
+                "example.com.",
+                Some("validation.hostname_starts_or_ends_with_dot"),
+            ),
+        ];
+
+        for (input, expected) in cases {
+            match (validate_hostname(input), expected) {
+                (Ok(()), None) => {}
+                (Err(SshConnError::InvalidHostname { value, reason }), Some(key)) => {
+                    assert_eq!(value, input);
+                    assert_eq!(reason, t(key), "input {:?}", input);
+                }
+                (result, expected) => {
+                    panic!(
+                        "input {:?}: expected {:?}, got {:?}",
+                        input, expected, result
+                    )
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("user@host"), "user@host");
+        assert_eq!(shell_quote("~/.ssh/id_rsa"), "~/.ssh/id_rsa");
+        assert_eq!(shell_quote("a b"), "'a b'");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
     #[test]
     fn test_validate_username() {
         // 测试有效用户名
@@ -296,4 +838,245 @@ mod utils_tests {
         assert!(validate_username(" ").is_err());
         assert!(validate_username("user name").is_err()); // 包含空格
     }
+
+    #[test]
+    fn test_validation_errors_carry_field() {
+        fn field_of(result: crate::error::Result<impl std::fmt::Debug>) -> String {
+            match result {
+                Err(e) => {
+                    assert!(!e.to_string().is_empty());
+                    e.field()
+                        .expect("validation error without field")
+                        .to_string()
+                }
+                Ok(v) => panic!("expected a validation error, got {:?}", v),
+            }
+        }
+
+        assert_eq!(field_of(validate_port("0")), "port");
+        assert_eq!(field_of(validate_port("65536")), "port");
+        assert_eq!(field_of(validate_port("")), "port");
+        assert_eq!(field_of(validate_hostname("bad..host")), "hostname");
+        assert_eq!(field_of(validate_hostname(" host")), "hostname");
+        assert_eq!(field_of(validate_username("user@host")), "user");
+        assert_eq!(field_of(validate_host("two words")), "host");
+        assert_eq!(field_of(validate_host("-foo")), "host");
+        assert_eq!(field_of(validate_host("*")), "host");
+        assert_eq!(field_of(validate_request_tty("maybe")), "request_tty");
+        assert_eq!(field_of(validate_env_var("1BAD=x")), "set_env");
+        assert_eq!(field_of(validate_address_family("ipv4")), "address_family");
+        assert_eq!(
+            field_of(validate_bind_address("10.0.0.300")),
+            "bind_address"
+        );
+        assert_eq!(
+            field_of(validate_permit_local_command("always")),
+            "permit_local_command"
+        );
+        assert_eq!(field_of(validate_local_command("a\nb")), "local_command");
+    }
+
+    #[test]
+    fn test_validate_host() {
+        assert!(validate_host("web-1").is_ok());
+        assert!(validate_host("db.internal").is_ok());
+        // 含通配符的模式只警告
+        assert!(validate_host("web-*").is_ok());
+
+        assert!(validate_host("").is_err());
+        assert!(validate_host("-foo").is_err());
+        assert!(validate_host("-").is_err());
+        assert!(validate_host("*").is_err());
+        assert!(validate_host("*?").is_err());
+    }
+
+    #[test]
+    fn test_validate_address_family() {
+        assert_eq!(validate_address_family("any").unwrap(), "any");
+        assert_eq!(validate_address_family("INET").unwrap(), "inet");
+        assert_eq!(validate_address_family(" inet6 ").unwrap(), "inet6");
+        assert!(validate_address_family("").is_err());
+        assert!(validate_address_family("inet4").is_err());
+    }
+
+    #[test]
+    fn test_validate_local_command_options() {
+        assert_eq!(validate_permit_local_command("YES").unwrap(), "yes");
+        assert_eq!(validate_permit_local_command(" no ").unwrap(), "no");
+        assert!(validate_permit_local_command("true").is_err());
+        assert!(validate_permit_local_command("").is_err());
+
+        assert!(validate_local_command("notify-send 'connected to %n'").is_ok());
+        assert!(validate_local_command("").is_err());
+        assert!(validate_local_command("echo one\necho two").is_err());
+        assert!(validate_local_command("echo one\r").is_err());
+    }
+
+    #[test]
+    fn test_validate_bind_address() {
+        for valid in [
+            "192.168.1.10",
+            "::1",
+            "fe80::1",
+            "eth0",
+            "enp0s31f6",
+            "wg-vpn",
+            "br.100",
+        ] {
+            assert!(validate_bind_address(valid).is_ok(), "{valid} rejected");
+        }
+        for invalid in [
+            "",
+            "10.0.0.300",
+            "1.2.3",
+            "eth 0",
+            "a-very-long-interface",
+            "10.0.0.1/24",
+        ] {
+            assert!(
+                validate_bind_address(invalid).is_err(),
+                "{invalid} accepted"
+            );
+        }
+    }
+
+    #[test]
+    fn test_expand_terminal_command() {
+        let command: Vec<String> = ["ssh", "-F", "/tmp/my config", "web"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        // 单独的 {cmd} 展开为多个参数，不经过shell
+        assert_eq!(
+            expand_terminal_command("alacritty -e {cmd}", &command).unwrap(),
+            ["alacritty", "-e", "ssh", "-F", "/tmp/my config", "web"]
+        );
+
+        // 嵌在脚本文本中时替换为转义后的命令行
+        assert_eq!(
+            expand_terminal_command(
+                r#"osascript -e "tell application \"Terminal\" to do script \"{cmd}\"""#,
+                &command
+            )
+            .unwrap(),
+            [
+                "osascript",
+                "-e",
+                "tell application \"Terminal\" to do script \"ssh -F '/tmp/my config' web\""
+            ]
+        );
+
+        assert!(expand_terminal_command("xterm -e", &command).is_err());
+    }
+
+    #[test]
+    fn test_expand_range() {
+        assert_eq!(expand_range("node[01-03]"), ["node01", "node02", "node03"]);
+        assert_eq!(
+            expand_range("10.0.0.[8-11]"),
+            ["10.0.0.8", "10.0.0.9", "10.0.0.10", "10.0.0.11"]
+        );
+        // 按起始值的宽度补零
+        assert_eq!(expand_range("db[008-010]").len(), 3);
+        assert_eq!(expand_range("db[008-010]")[2], "db010");
+        assert_eq!(expand_range("n[9-10]x"), ["n9x", "n10x"]);
+        assert_eq!(
+            expand_range("r[1-2]n[1-2]"),
+            ["r1n1", "r1n2", "r2n1", "r2n2"]
+        );
+
+        // 没有区间或格式不正确时原样返回
+        assert_eq!(expand_range("web"), ["web"]);
+        assert_eq!(expand_range("web[a-c]"), ["web[a-c]"]);
+        assert_eq!(expand_range("web[3-1]"), ["web[3-1]"]);
+        assert_eq!(expand_range("web[1-"), ["web[1-"]);
+    }
+
+    #[test]
+    fn test_try_expand_range_limit() {
+        assert_eq!(try_expand_range("n[1-4]", 4).unwrap().len(), 4);
+        assert_eq!(try_expand_range("n[1-5]", 4), None);
+        // 多个区间按乘积计算
+        assert_eq!(try_expand_range("r[1-2]n[1-3]", 6).unwrap().len(), 6);
+        assert_eq!(try_expand_range("r[1-2]n[1-3]", 5), None);
+        // 不会溢出
+        assert_eq!(
+            try_expand_range("n[0-18446744073709551615]", MAX_RANGE_EXPANSION),
+            None
+        );
+        assert_eq!(try_expand_range("web", 0).unwrap(), ["web"]);
+
+        // 超过上限时 expand_range 原样返回
+        assert_eq!(expand_range("n[1-100000]"), ["n[1-100000]"]);
+    }
+
+    #[test]
+    fn test_diff_lines() {
+        use DiffLine::*;
+        assert_eq!(
+            diff_lines("a\nb\nc\n", "a\nx\nc\nd\n"),
+            [Same("a"), Removed("b"), Added("x"), Same("c"), Added("d")]
+        );
+        assert_eq!(diff_lines("", "a"), [Added("a")]);
+        assert_eq!(diff_lines("a", ""), [Removed("a")]);
+        assert!(diff_lines("", "").is_empty());
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n", "old", "new", 3), "");
+
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n";
+        let new = "1\n2\nthree\n4\n5\n6\n7\n8\n9\n10\n11\n";
+        assert_eq!(
+            unified_diff(old, new, "current", "backup", 1),
+            "--- current\n+++ backup\n\
+             @@ -2,3 +2,3 @@\n 2\n-3\n+three\n 4\n\
+             @@ -10 +10,2 @@\n 10\n+11\n"
+        );
+        // 上下文重叠的变更合并为一个块
+        let diff = unified_diff(old, new, "current", "backup", 3);
+        assert_eq!(diff.matches("@@ -").count(), 2);
+        let diff = unified_diff(old, new, "current", "backup", 4);
+        assert_eq!(diff.matches("@@ -").count(), 1);
+        assert!(diff.contains("@@ -1,10 +1,11 @@"));
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("staging-*", "staging-web1"));
+        assert!(glob_match("staging-*", "staging-"));
+        assert!(!glob_match("staging-*", "prod-staging-web"));
+        assert!(glob_match("web?", "web1"));
+        assert!(!glob_match("web?", "web10"));
+        assert!(glob_match("*-db-*", "eu-db-01"));
+        assert!(glob_match("*a*b", "xaxxab"));
+        assert!(!glob_match("*a*b", "xaxxa"));
+        assert!(glob_match("exact", "exact"));
+        assert!(!glob_match("Exact", "exact"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("?", ""));
+        assert!(glob_match("节点-?", "节点-一"));
+    }
+
+    #[test]
+    fn test_weak_algorithms() {
+        assert!(weak_algorithms("aes256-gcm@openssh.com,aes128-ctr").is_empty());
+        assert_eq!(weak_algorithms("+ssh-rsa"), ["ssh-rsa"]);
+        assert_eq!(
+            weak_algorithms("aes128-ctr,3des-cbc,hmac-md5"),
+            ["3des-cbc", "hmac-md5"]
+        );
+        // `-` 前缀表示从默认列表中移除，不算启用
+        assert!(weak_algorithms("-ssh-rsa,ssh-dss").is_empty());
+
+        let mut host = crate::models::SshHost::new("legacy".to_string());
+        host.host_key_algorithms = Some("+ssh-rsa".to_string());
+        host.macs = Some("hmac-sha2-256".to_string());
+        assert_eq!(
+            host.legacy_crypto_warnings(),
+            ["HostKeyAlgorithms: ssh-rsa"]
+        );
+    }
 }
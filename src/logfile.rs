@@ -0,0 +1,215 @@
+//! 日志文件输出（`--log-file` 或设置 `[log] enabled = true`）
+//!
+//! 按大小轮转：当前文件写满 `max_size_mb` 后依次改名为 `.1`、`.2` …，
+//! 最多保留 `keep` 个旧文件，最旧的直接删除。
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::error::Result;
+use crate::settings::LogSettings;
+
+/// 默认日志文件名
+pub const LOG_FILE_NAME: &str = "ssh-conn.log";
+
+static ACTIVE_LOG_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// 当前写入的日志文件，未启用日志文件时为None
+pub fn active_log_path() -> Option<&'static Path> {
+    ACTIVE_LOG_PATH.get().map(PathBuf::as_path)
+}
+
+/// 记录当前写入的日志文件，只在启动时设置一次
+pub fn set_active_log_path(path: PathBuf) {
+    let _ = ACTIVE_LOG_PATH.set(path);
+}
+
+/// 确定日志文件路径：`--log-file PATH` 优先，其次为设置中的路径，最后为默认日志目录
+///
+/// 既没有 `--log-file` 也没有在设置中启用时返回None
+pub fn resolve_log_path(
+    flag: Option<&Option<PathBuf>>,
+    settings: &LogSettings,
+) -> Result<Option<PathBuf>> {
+    match flag {
+        Some(Some(path)) => Ok(Some(path.clone())),
+        Some(None) => default_or_configured(settings).map(Some),
+        None if settings.enabled => default_or_configured(settings).map(Some),
+        None => Ok(None),
+    }
+}
+
+fn default_or_configured(settings: &LogSettings) -> Result<PathBuf> {
+    match &settings.path {
+        Some(path) => Ok(path.clone()),
+        None => Ok(crate::utils::get_log_dir()?.join(LOG_FILE_NAME)),
+    }
+}
+
+/// 第 `index` 个旧日志文件的路径，如 `ssh-conn.log.1`
+pub fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+/// 按大小轮转的日志文件
+#[derive(Debug)]
+pub struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: usize,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    /// 以追加方式打开日志文件，目录不存在时自动创建
+    pub fn open(path: impl Into<PathBuf>, max_bytes: u64, keep: usize) -> io::Result<Self> {
+        let path = path.into();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = Self::open_append(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            max_bytes: max_bytes.max(1),
+            keep,
+            file,
+            size,
+        })
+    }
+
+    /// 日志文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn open_append(path: &Path) -> io::Result<File> {
+        OpenOptions::new().create(true).append(true).open(path)
+    }
+
+    /// 把当前文件改名为 `.1`，已有的旧文件序号依次加一，超出 `keep` 的删除
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.keep == 0 {
+            std::fs::remove_file(&self.path)?;
+        } else {
+            let _ = std::fs::remove_file(rotated_path(&self.path, self.keep));
+            for index in (1..self.keep).rev() {
+                let from = rotated_path(&self.path, index);
+                if from.exists() {
+                    std::fs::rename(&from, rotated_path(&self.path, index + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        }
+        self.file = Self::open_append(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // 单条记录不拆分到两个文件；空文件写入超长记录时不轮转
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        self.file.write_all(buf)?;
+        self.size += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(path: &Path) -> String {
+        std::fs::read_to_string(path).unwrap_or_default()
+    }
+
+    #[test]
+    fn test_rotated_path() {
+        assert_eq!(
+            rotated_path(Path::new("/tmp/logs/ssh-conn.log"), 2),
+            PathBuf::from("/tmp/logs/ssh-conn.log.2")
+        );
+    }
+
+    #[test]
+    fn test_resolve_log_path() {
+        let mut settings = LogSettings::default();
+        assert_eq!(resolve_log_path(None, &settings).unwrap(), None);
+
+        let explicit = Some(PathBuf::from("/tmp/explicit.log"));
+        assert_eq!(
+            resolve_log_path(Some(&explicit), &settings).unwrap(),
+            explicit
+        );
+
+        settings.enabled = true;
+        settings.path = Some(PathBuf::from("/var/log/ssh-conn.log"));
+        assert_eq!(
+            resolve_log_path(None, &settings).unwrap(),
+            Some(PathBuf::from("/var/log/ssh-conn.log"))
+        );
+        assert_eq!(
+            resolve_log_path(Some(&None), &settings).unwrap(),
+            Some(PathBuf::from("/var/log/ssh-conn.log"))
+        );
+    }
+
+    #[test]
+    fn test_rotation_keeps_limited_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("logs").join(LOG_FILE_NAME);
+        let mut file = RotatingFile::open(&path, 10, 2).unwrap();
+
+        for line in ["first\n", "second\n", "third\n", "fourth\n"] {
+            file.write_all(line.as_bytes()).unwrap();
+        }
+        file.flush().unwrap();
+
+        assert_eq!(read(&path), "fourth\n");
+        assert_eq!(read(&rotated_path(&path, 1)), "third\n");
+        assert_eq!(read(&rotated_path(&path, 2)), "second\n");
+        assert!(!rotated_path(&path, 3).exists());
+    }
+
+    #[test]
+    fn test_rotation_appends_until_limit() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(LOG_FILE_NAME);
+        std::fs::write(&path, "old\n").unwrap();
+
+        let mut file = RotatingFile::open(&path, 10, 1).unwrap();
+        file.write_all(b"new\n").unwrap();
+        assert_eq!(read(&path), "old\nnew\n");
+
+        // 超出上限时轮转，超长的单条记录仍完整写入新文件
+        file.write_all(b"a long record\n").unwrap();
+        assert_eq!(read(&path), "a long record\n");
+        assert_eq!(read(&rotated_path(&path, 1)), "old\nnew\n");
+    }
+
+    #[test]
+    fn test_rotation_without_keep_discards_old_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(LOG_FILE_NAME);
+        let mut file = RotatingFile::open(&path, 4, 0).unwrap();
+        file.write_all(b"abc\n").unwrap();
+        file.write_all(b"def\n").unwrap();
+
+        assert_eq!(read(&path), "def\n");
+        assert!(!rotated_path(&path, 1).exists());
+    }
+}
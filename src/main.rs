@@ -1,34 +1,104 @@
 use clap::Parser;
 use std::process;
 
-use ssh_conn::cli::{Cli, CliApp};
+use ssh_conn::cli::{Cli, CliApp, Commands};
 use ssh_conn::config::ConfigManager;
 use ssh_conn::error::Result;
-use ssh_conn::i18n::t;
+use ssh_conn::i18n::{self, t};
+use ssh_conn::logfile::{self, RotatingFile};
+use ssh_conn::output;
 use ssh_conn::password::PasswordManager;
+use ssh_conn::settings::Settings;
 
 fn main() {
-    // 初始化日志系统
-    env_logger::init();
+    // 作为 ssh-add 的 SSH_ASKPASS 程序运行时只输出存储的私钥口令
+    if let Some(passphrase) = ssh_conn::agent::askpass_response() {
+        println!("{}", passphrase.as_str());
+        return;
+    }
+
+    // 解析命令行参数
+    let cli = Cli::parse();
+    let json_errors = cli.json_errors;
+
+    // version 不加载设置和密码数据库，便于在配置有问题时报告版本
+    if let Some(Commands::Version) = cli.command {
+        let _ = ssh_conn::cli::version_command(cli.verbose > 0);
+        return;
+    }
+
+    // 先加载设置以确定日志输出位置，设置有误时在run中报告
+    let settings = Settings::load();
+    init_logger(&cli, settings.as_ref().ok());
+    output::set_quiet(cli.quiet);
 
-    if let Err(e) = run() {
-        eprintln!("{}: {}", t("error"), e.localized_message());
-        process::exit(1);
+    if let Err(e) = settings.and_then(|settings| run(cli, settings)) {
+        // TUI异常退出时终端可能仍处于raw mode
+        let _ = ssh_conn::ui::restore_terminal();
+        if json_errors {
+            eprintln!("{}", e.to_json());
+        } else {
+            eprintln!("{}: {}", t("error"), e.localized_message());
+        }
+        process::exit(e.exit_code());
     }
 }
 
+/// 初始化日志系统，-v/-vv 优先于 RUST_LOG
+///
+/// 启用日志文件时写入按大小轮转的文件，未指定级别时默认记录Info；
+/// 日志文件无法打开时给出警告并继续输出到标准错误
+fn init_logger(cli: &Cli, settings: Option<&Settings>) {
+    let mut logger = env_logger::Builder::from_default_env();
+    let level = output::log_level(cli.verbose);
+
+    let log_settings = settings.map(|s| s.log.clone()).unwrap_or_default();
+    match logfile::resolve_log_path(cli.log_file.as_ref(), &log_settings) {
+        Ok(Some(path)) => {
+            match RotatingFile::open(&path, log_settings.max_bytes(), log_settings.keep) {
+                Ok(file) => {
+                    logger.target(env_logger::Target::Pipe(Box::new(file)));
+                    if level.is_none() && std::env::var_os("RUST_LOG").is_none() {
+                        logger.filter_level(log::LevelFilter::Info);
+                    }
+                    logfile::set_active_log_path(path);
+                }
+                Err(e) => eprintln!("{}: {}: {}", t("log_file_open_failed"), path.display(), e),
+            }
+        }
+        Ok(None) => {}
+        Err(e) => eprintln!("{}: {}", t("log_file_open_failed"), e.localized_message()),
+    }
+
+    if let Some(level) = level {
+        logger.filter_level(level);
+    }
+    logger.init();
+}
+
 /// 主运行函数
 ///
 /// 初始化所有组件并运行命令行应用
-fn run() -> Result<()> {
-    // 解析命令行参数
-    let cli = Cli::parse();
+fn run(cli: Cli, mut settings: Settings) -> Result<()> {
+    // 命令行指定的配置文件或配置档案优先
+    if let Some(config) = &cli.config {
+        settings.ssh_config_path = Some(config.clone());
+    } else if let Some(profile) = &cli.profile {
+        settings.use_profile(profile)?;
+    }
+
+    // 设置文件中的语言仅在未通过 SSH_CONN_LANG 指定时生效
+    if std::env::var("SSH_CONN_LANG").is_err()
+        && let Some(language) = settings.language()
+    {
+        i18n::set_language(language);
+    }
 
     // 初始化密码管理器
-    let password_manager = PasswordManager::new()?;
+    let password_manager = PasswordManager::new(&settings)?;
 
     // 初始化配置管理器
-    let config_manager = ConfigManager::new(password_manager)?;
+    let config_manager = ConfigManager::new(password_manager, settings)?;
 
     // 创建并运行命令行应用
     let mut app = CliApp::new(config_manager);
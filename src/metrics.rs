@@ -0,0 +1,208 @@
+//! 批量测试结果的指标输出（`test-all --metrics-out`）
+//!
+//! 支持node_exporter textfile采集器使用的Prometheus文本格式和JSON，
+//! 格式化函数只依赖测试结果，写文件时先写临时文件再重命名，采集器不会读到写了一半的文件。
+
+use std::io::Write;
+use std::path::Path;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::error::Result;
+use crate::models::ConnectionStatus;
+
+/// 指标文件格式
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum MetricsFormat {
+    /// Prometheus text exposition format (node_exporter textfile collector)
+    Prometheus,
+    /// JSON object with one entry per host
+    Json,
+}
+
+/// 单台主机的测试结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HostMetric {
+    pub host: String,
+    pub up: bool,
+    /// 连接耗时（毫秒），不可用时为None
+    pub latency_ms: Option<u64>,
+}
+
+impl HostMetric {
+    /// 由连接测试结果得到指标，未完成的测试返回None
+    pub fn from_status(host: &str, status: &ConnectionStatus) -> Option<Self> {
+        let (up, latency_ms) = match status {
+            ConnectionStatus::Connected(d) | ConnectionStatus::SshReachable(d) => {
+                (true, Some(d.as_millis() as u64))
+            }
+            ConnectionStatus::Failed(_) => (false, None),
+            ConnectionStatus::Unknown | ConnectionStatus::Connecting => return None,
+        };
+        Some(Self {
+            host: host.to_string(),
+            up,
+            latency_ms,
+        })
+    }
+}
+
+/// 按Prometheus文本格式转义标签值：反斜杠、双引号和换行
+pub fn escape_label_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// 输出Prometheus文本格式，延迟只对可用的主机输出
+pub fn format_prometheus(results: &[HostMetric]) -> String {
+    let mut out = String::new();
+    out.push_str(
+        "# HELP ssh_conn_host_up Whether the host accepted a TCP connection (1) or not (0).\n",
+    );
+    out.push_str("# TYPE ssh_conn_host_up gauge\n");
+    for result in results {
+        out.push_str(&format!(
+            "ssh_conn_host_up{{host=\"{}\"}} {}\n",
+            escape_label_value(&result.host),
+            u8::from(result.up)
+        ));
+    }
+
+    out.push_str(
+        "# HELP ssh_conn_host_latency_ms Time to establish the connection in milliseconds.\n",
+    );
+    out.push_str("# TYPE ssh_conn_host_latency_ms gauge\n");
+    for result in results {
+        if let Some(latency) = result.latency_ms {
+            out.push_str(&format!(
+                "ssh_conn_host_latency_ms{{host=\"{}\"}} {}\n",
+                escape_label_value(&result.host),
+                latency
+            ));
+        }
+    }
+    out
+}
+
+/// 输出JSON：`{"hosts": [{"host", "up", "latency_ms"}]}`
+pub fn format_json(results: &[HostMetric]) -> String {
+    let mut out = serde_json::to_string_pretty(&serde_json::json!({ "hosts": results }))
+        .expect("host metrics are always serializable");
+    out.push('\n');
+    out
+}
+
+/// 按格式输出指标
+pub fn format_metrics(results: &[HostMetric], format: MetricsFormat) -> String {
+    match format {
+        MetricsFormat::Prometheus => format_prometheus(results),
+        MetricsFormat::Json => format_json(results),
+    }
+}
+
+/// 原子地写入指标文件：写到同目录下的临时文件后重命名
+///
+/// 临时文件名以 `.tmp` 结尾，不会被只读取 `*.prom` 的textfile采集器读到
+pub fn write_atomic(path: &Path, content: &str) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let tmp = path.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()));
+
+    let written = std::fs::File::create(&tmp).and_then(|mut file| {
+        file.write_all(content.as_bytes())?;
+        file.sync_all()
+    });
+    if let Err(e) = written.and_then(|()| std::fs::rename(&tmp, path)) {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn results() -> Vec<HostMetric> {
+        [
+            (
+                "web1",
+                ConnectionStatus::Connected(Duration::from_millis(23)),
+            ),
+            ("db", ConnectionStatus::Failed("refused".to_string())),
+            (
+                "lab \"b\"\\x",
+                ConnectionStatus::SshReachable(Duration::from_millis(7)),
+            ),
+            ("pending", ConnectionStatus::Connecting),
+        ]
+        .iter()
+        .filter_map(|(host, status)| HostMetric::from_status(host, status))
+        .collect()
+    }
+
+    #[test]
+    fn test_escape_label_value() {
+        assert_eq!(escape_label_value("web1"), "web1");
+        assert_eq!(escape_label_value(r#"a"b\c"#), r#"a\"b\\c"#);
+        assert_eq!(escape_label_value("a\nb"), "a\\nb");
+    }
+
+    #[test]
+    fn test_prometheus_snapshot() {
+        assert_eq!(
+            format_prometheus(&results()),
+            "# HELP ssh_conn_host_up Whether the host accepted a TCP connection (1) or not (0).\n\
+             # TYPE ssh_conn_host_up gauge\n\
+             ssh_conn_host_up{host=\"web1\"} 1\n\
+             ssh_conn_host_up{host=\"db\"} 0\n\
+             ssh_conn_host_up{host=\"lab \\\"b\\\"\\\\x\"} 1\n\
+             # HELP ssh_conn_host_latency_ms Time to establish the connection in milliseconds.\n\
+             # TYPE ssh_conn_host_latency_ms gauge\n\
+             ssh_conn_host_latency_ms{host=\"web1\"} 23\n\
+             ssh_conn_host_latency_ms{host=\"lab \\\"b\\\"\\\\x\"} 7\n"
+        );
+    }
+
+    #[test]
+    fn test_json_snapshot() {
+        let value: serde_json::Value = serde_json::from_str(&format_json(&results())).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "hosts": [
+                    { "host": "web1", "up": true, "latency_ms": 23 },
+                    { "host": "db", "up": false, "latency_ms": null },
+                    { "host": "lab \"b\"\\x", "up": true, "latency_ms": 7 },
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_write_atomic_replaces_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("ssh_conn.prom");
+        std::fs::write(&path, "old").unwrap();
+
+        write_atomic(&path, "new\n").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new\n");
+        // 不留下临时文件
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        let missing = dir.path().join("no-such-dir").join("x.prom");
+        assert!(write_atomic(&missing, "x").is_err());
+    }
+}
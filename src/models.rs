@@ -1,38 +1,434 @@
 //! 数据模型定义
 
+use crate::error::{Result, SshConnError};
 use crate::i18n::t;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
+/// 可参与搜索匹配的字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchField {
+    Host,
+    HostName,
+    User,
+    Port,
+}
+
+impl SearchField {
+    /// 全部可搜索字段
+    pub const ALL: [SearchField; 4] = [
+        SearchField::Host,
+        SearchField::HostName,
+        SearchField::User,
+        SearchField::Port,
+    ];
+
+    /// 命令行中使用的字段名
+    pub fn name(self) -> &'static str {
+        match self {
+            SearchField::Host => "host",
+            SearchField::HostName => "hostname",
+            SearchField::User => "user",
+            SearchField::Port => "port",
+        }
+    }
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// 搜索字段掩码，默认包含全部字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchFields(u8);
+
+impl Default for SearchFields {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+impl SearchFields {
+    /// 包含全部字段
+    pub fn all() -> Self {
+        SearchField::ALL.into_iter().collect()
+    }
+
+    /// 是否包含指定字段
+    pub fn contains(self, field: SearchField) -> bool {
+        self.0 & field.bit() != 0
+    }
+
+    /// 解析逗号分隔的字段列表，如 `host,port`；`all` 表示全部字段
+    ///
+    /// 字段名不区分大小写，未知字段名返回错误
+    pub fn parse(value: &str) -> Result<Self> {
+        let mut fields = SearchFields(0);
+        for name in value.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            let name = name.to_lowercase();
+            if name == "all" {
+                return Ok(Self::all());
+            }
+            let field = SearchField::ALL
+                .into_iter()
+                .find(|f| f.name() == name)
+                .ok_or_else(|| {
+                    let known: Vec<&str> = SearchField::ALL.iter().map(|f| f.name()).collect();
+                    SshConnError::InvalidArgument(
+                        t("error.unknown_search_field")
+                            .replacen("{}", &name, 1)
+                            .replacen("{}", &known.join(", "), 1),
+                    )
+                })?;
+            fields.0 |= field.bit();
+        }
+
+        if fields.0 == 0 {
+            return Err(SshConnError::InvalidArgument(t(
+                "error.empty_search_fields",
+            )));
+        }
+        Ok(fields)
+    }
+}
+
+impl FromIterator<SearchField> for SearchFields {
+    fn from_iter<I: IntoIterator<Item = SearchField>>(iter: I) -> Self {
+        SearchFields(iter.into_iter().fold(0, |mask, f| mask | f.bit()))
+    }
+}
+
+/// 可以通过 `bulk-edit --set` 批量修改的主机字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditableField {
+    User,
+    Port,
+    IdentityFile,
+    ProxyJump,
+    ConnectTimeout,
+}
+
+impl EditableField {
+    /// 全部可修改的字段
+    pub const ALL: [EditableField; 5] = [
+        EditableField::User,
+        EditableField::Port,
+        EditableField::IdentityFile,
+        EditableField::ProxyJump,
+        EditableField::ConnectTimeout,
+    ];
+
+    /// 命令行中使用的字段名
+    pub fn name(self) -> &'static str {
+        match self {
+            EditableField::User => "user",
+            EditableField::Port => "port",
+            EditableField::IdentityFile => "identity_file",
+            EditableField::ProxyJump => "proxy_jump",
+            EditableField::ConnectTimeout => "connect_timeout",
+        }
+    }
+
+    /// 对应的SSH配置关键字
+    pub fn keyword(self) -> &'static str {
+        match self {
+            EditableField::User => "User",
+            EditableField::Port => "Port",
+            EditableField::IdentityFile => "IdentityFile",
+            EditableField::ProxyJump => "ProxyJump",
+            EditableField::ConnectTimeout => "ConnectTimeout",
+        }
+    }
+
+    /// 主机当前的取值
+    pub fn value(self, host: &SshHost) -> Option<String> {
+        match self {
+            EditableField::User => host.user.clone(),
+            EditableField::Port => host.port.clone(),
+            EditableField::IdentityFile => host.identity_file.clone(),
+            EditableField::ProxyJump => host.get_custom_option("ProxyJump").map(str::to_string),
+            EditableField::ConnectTimeout => host.connect_timeout.clone(),
+        }
+    }
+
+    /// 校验新值，返回写入配置的形式
+    pub fn validate(self, value: &str) -> Result<String> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Err(SshConnError::InvalidArgument(
+                t("error.field_value_empty").replace("{}", self.name()),
+            ));
+        }
+
+        match self {
+            EditableField::User => crate::utils::validate_username(value)?,
+            EditableField::Port => {
+                crate::utils::validate_port(value)?;
+            }
+            EditableField::ConnectTimeout => {
+                if !value.parse::<u32>().is_ok_and(|secs| secs > 0) {
+                    return Err(SshConnError::InvalidArgument(
+                        t("error.connect_timeout_invalid").replace("{}", value),
+                    ));
+                }
+            }
+            EditableField::IdentityFile | EditableField::ProxyJump => {}
+        }
+        Ok(value.to_string())
+    }
+}
+
+/// 编辑主机时可以清除（删除对应指令）的可选字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionalField {
+    User,
+    Port,
+    ProxyCommand,
+    IdentityFile,
+    RemoteCommand,
+    RequestTty,
+    SetEnv,
+    AddressFamily,
+    BindAddress,
+    PermitLocalCommand,
+    LocalCommand,
+}
+
+impl OptionalField {
+    /// 全部可清除的字段
+    pub const ALL: [OptionalField; 11] = [
+        OptionalField::User,
+        OptionalField::Port,
+        OptionalField::ProxyCommand,
+        OptionalField::IdentityFile,
+        OptionalField::RemoteCommand,
+        OptionalField::RequestTty,
+        OptionalField::SetEnv,
+        OptionalField::AddressFamily,
+        OptionalField::BindAddress,
+        OptionalField::PermitLocalCommand,
+        OptionalField::LocalCommand,
+    ];
+
+    /// 命令行中使用的字段名
+    pub fn name(self) -> &'static str {
+        match self {
+            OptionalField::User => "user",
+            OptionalField::Port => "port",
+            OptionalField::ProxyCommand => "proxy_command",
+            OptionalField::IdentityFile => "identity_file",
+            OptionalField::RemoteCommand => "remote_command",
+            OptionalField::RequestTty => "request_tty",
+            OptionalField::SetEnv => "set_env",
+            OptionalField::AddressFamily => "address_family",
+            OptionalField::BindAddress => "bind_address",
+            OptionalField::PermitLocalCommand => "permit_local_command",
+            OptionalField::LocalCommand => "local_command",
+        }
+    }
+
+    /// 对应的SSH配置关键字
+    pub fn keyword(self) -> &'static str {
+        match self {
+            OptionalField::User => "User",
+            OptionalField::Port => "Port",
+            OptionalField::ProxyCommand => "ProxyCommand",
+            OptionalField::IdentityFile => "IdentityFile",
+            OptionalField::RemoteCommand => "RemoteCommand",
+            OptionalField::RequestTty => "RequestTTY",
+            OptionalField::SetEnv => "SetEnv",
+            OptionalField::AddressFamily => "AddressFamily",
+            OptionalField::BindAddress => "BindAddress",
+            OptionalField::PermitLocalCommand => "PermitLocalCommand",
+            OptionalField::LocalCommand => "LocalCommand",
+        }
+    }
+
+    /// 按字段名查找，不区分大小写，`-` 与 `_` 等价
+    pub fn parse(name: &str) -> Result<Self> {
+        let name = name.trim().to_lowercase().replace('-', "_");
+        Self::ALL
+            .into_iter()
+            .find(|f| f.name() == name)
+            .ok_or_else(|| {
+                let known: Vec<&str> = Self::ALL.iter().map(|f| f.name()).collect();
+                SshConnError::InvalidArgument(
+                    t("error.unknown_clearable_field")
+                        .replacen("{}", &name, 1)
+                        .replacen("{}", &known.join(", "), 1),
+                )
+            })
+    }
+}
+
+/// 一项字段赋值，命令行写法为 `field=value`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldAssignment {
+    pub field: EditableField,
+    pub value: String,
+}
+
+impl FieldAssignment {
+    /// 解析 `field=value`，字段名不区分大小写，`-` 与 `_` 等价
+    pub fn parse(text: &str) -> Result<Self> {
+        let (name, value) = text.split_once('=').ok_or_else(|| {
+            SshConnError::InvalidArgument(t("error.field_assignment_invalid").replace("{}", text))
+        })?;
+        let name = name.trim().to_lowercase().replace('-', "_");
+        let field = EditableField::ALL
+            .into_iter()
+            .find(|f| f.name() == name)
+            .ok_or_else(|| {
+                let known: Vec<&str> = EditableField::ALL.iter().map(|f| f.name()).collect();
+                SshConnError::InvalidArgument(
+                    t("error.unknown_editable_field")
+                        .replacen("{}", &name, 1)
+                        .replacen("{}", &known.join(", "), 1),
+                )
+            })?;
+        let value = field.validate(value)?;
+        Ok(Self { field, value })
+    }
+}
+
+/// 解析后的搜索条件
+///
+/// 以空白分隔搜索词：普通词必须全部匹配，以 `-` 开头的词表示排除，
+/// 双引号内的空白作为字面量保留，如 `prod -eu "web 01"`。匹配不区分大小写。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchQuery {
+    /// 必须全部匹配的词
+    pub include: Vec<String>,
+    /// 不能匹配的词
+    pub exclude: Vec<String>,
+}
+
+impl SearchQuery {
+    /// 解析搜索文本
+    pub fn parse(query: &str) -> Self {
+        let mut parsed = SearchQuery::default();
+        let mut chars = query.chars().peekable();
+
+        loop {
+            while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                chars.next();
+            }
+            let Some(&first) = chars.peek() else {
+                break;
+            };
+
+            // 只有引号外、词首的 `-` 表示排除
+            let exclude = first == '-';
+            if exclude {
+                chars.next();
+            }
+
+            let mut term = String::new();
+            let mut in_quotes = false;
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() && !in_quotes {
+                    break;
+                }
+                chars.next();
+                if c == '"' {
+                    in_quotes = !in_quotes;
+                } else {
+                    term.push(c);
+                }
+            }
+
+            let term = term.to_lowercase();
+            match (exclude, term.is_empty()) {
+                (true, true) => parsed.include.push("-".to_string()),
+                (true, false) => parsed.exclude.push(term),
+                (false, true) => {}
+                (false, false) => parsed.include.push(term),
+            }
+        }
+
+        parsed
+    }
+
+    /// 是否没有任何搜索条件
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// 检查主机是否满足搜索条件
+    pub fn matches(&self, host: &SshHost) -> bool {
+        self.matches_in(host, SearchFields::all())
+    }
+
+    /// 只在指定字段中检查主机是否满足搜索条件
+    pub fn matches_in(&self, host: &SshHost, fields: SearchFields) -> bool {
+        self.include
+            .iter()
+            .all(|term| host.matches_term(term, fields))
+            && !self
+                .exclude
+                .iter()
+                .any(|term| host.matches_term(term, fields))
+    }
+}
+
+/// 会话类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SessionKind {
+    /// 交互式shell（ssh）
+    #[default]
+    Shell,
+    /// 文件传输（sftp）
+    Sftp,
+}
+
+/// 连接状态的显示方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StatusPalette {
+    /// 彩色圆点（🟢/🔴等）
+    #[default]
+    Default,
+    /// 色盲友好：用形状不同的符号 ✓/✗/~/? 区分状态，配合蓝色/橙色而不是绿色/红色
+    ColorBlind,
+}
+
 /// 连接状态
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 pub enum ConnectionStatus {
     /// 未检测
+    #[default]
     Unknown,
     /// 连接中
     Connecting,
     /// 连接成功
     Connected(Duration), // 包含延迟时间
+    /// 经由ssh（包括配置中的跳板机/代理）探测成功
+    SshReachable(Duration),
     /// 连接失败
     Failed(String), // 包含错误信息
 }
 
-impl Default for ConnectionStatus {
-    fn default() -> Self {
-        Self::Unknown
-    }
-}
-
 impl ConnectionStatus {
     /// 获取状态显示字符串
     pub fn display_string(&self) -> String {
+        self.display_string_with(StatusPalette::Default)
+    }
+
+    /// 按指定的显示方式获取状态显示字符串
+    pub fn display_string_with(&self, palette: StatusPalette) -> String {
+        let (unknown, connecting, up, failed) = match palette {
+            StatusPalette::Default => ("⚪", "🟡", "🟢", "🔴"),
+            StatusPalette::ColorBlind => ("?", "~", "✓", "✗"),
+        };
         match self {
-            ConnectionStatus::Unknown => "⚪".to_string(),
-            ConnectionStatus::Connecting => "🟡".to_string(),
+            ConnectionStatus::Unknown => unknown.to_string(),
+            ConnectionStatus::Connecting => connecting.to_string(),
             ConnectionStatus::Connected(duration) => {
-                format!("🟢 {}ms", duration.as_millis())
+                format!("{} {}ms", up, duration.as_millis())
             }
-            ConnectionStatus::Failed(_) => "🔴".to_string(),
+            ConnectionStatus::SshReachable(duration) => {
+                format!("{} ssh {}ms", up, duration.as_millis())
+            }
+            ConnectionStatus::Failed(_) => failed.to_string(),
         }
     }
 
@@ -44,13 +440,60 @@ impl ConnectionStatus {
             ConnectionStatus::Connected(duration) => {
                 format!("{} ({}ms)", t("status.connected"), duration.as_millis())
             }
+            ConnectionStatus::SshReachable(duration) => {
+                format!("{} ({}ms)", t("status.ssh_reachable"), duration.as_millis())
+            }
             ConnectionStatus::Failed(error) => {
                 format!("{}: {}", t("status.failed"), error)
             }
         }
     }
+
+    /// 根据失败原因给出排查建议，无法识别的原因或非失败状态返回None
+    pub fn troubleshooting_hint(&self) -> Option<String> {
+        const HINTS: [(&[&str], &str); 7] = [
+            (&["connection refused"], "hint.refused"),
+            (&["timed out", "timeout"], "hint.timeout"),
+            (
+                &[
+                    "dns resolution failed",
+                    "failed to lookup",
+                    "name or service not known",
+                    "could not resolve",
+                    "nodename nor servname",
+                ],
+                "hint.dns",
+            ),
+            (
+                &[
+                    "network is unreachable",
+                    "no route to host",
+                    "host is unreachable",
+                ],
+                "hint.unreachable",
+            ),
+            (&["host key verification failed"], "hint.host_key"),
+            (&["permission denied"], "hint.auth"),
+            (
+                &["connection reset", "connection closed", "broken pipe"],
+                "hint.reset",
+            ),
+        ];
+
+        let ConnectionStatus::Failed(error) = self else {
+            return None;
+        };
+        let error = error.to_lowercase();
+        HINTS
+            .iter()
+            .find(|(patterns, _)| patterns.iter().any(|p| error.contains(p)))
+            .map(|(_, key)| t(key))
+    }
 }
 
+/// 在主机块中以注释形式保存密码命令的指令，ssh会忽略这一行
+pub const PASSWORD_COMMAND_DIRECTIVE: &str = "# ssh-conn-password-cmd:";
+
 /// SSH主机配置结构体
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct SshHost {
@@ -70,11 +513,40 @@ pub struct SshHost {
     pub connect_timeout: Option<String>,
     /// 服务器存活间隔（ServerAliveInterval字段）
     pub server_alive_interval: Option<String>,
+    /// 远程命令（RemoteCommand字段）
+    pub remote_command: Option<String>,
+    /// 是否分配TTY（RequestTTY字段：auto/yes/force/no）
+    pub request_tty: Option<String>,
+    /// 地址族（AddressFamily字段：any/inet/inet6）
+    pub address_family: Option<String>,
+    /// 本机源地址或网卡（BindAddress字段）
+    pub bind_address: Option<String>,
+    /// 是否允许执行本地命令（PermitLocalCommand字段：yes/no）
+    pub permit_local_command: Option<String>,
+    /// 连接成功后在本机执行的命令（LocalCommand字段，需PermitLocalCommand为yes）
+    pub local_command: Option<String>,
+    /// 对称加密算法（Ciphers字段）
+    pub ciphers: Option<String>,
+    /// 消息认证算法（MACs字段）
+    pub macs: Option<String>,
+    /// 主机密钥算法（HostKeyAlgorithms字段）
+    pub host_key_algorithms: Option<String>,
+    /// 公钥认证算法（PubkeyAcceptedAlgorithms字段）
+    pub pubkey_accepted_algorithms: Option<String>,
+    /// 发送给服务器的环境变量（SetEnv字段，可有多行）
+    #[serde(default)]
+    pub set_env: Vec<(String, String)>,
     /// 其他自定义配置
     pub custom_options: std::collections::HashMap<String, String>,
+    /// 连接时获取密码的外部命令（注释指令 `# ssh-conn-password-cmd:`），优先于存储的密码
+    #[serde(default)]
+    pub password_command: Option<String>,
     /// 连接状态（不序列化到配置文件）
     #[serde(skip)]
     pub connection_status: ConnectionStatus,
+    /// 主机所在的配置文件，经Include引入时为被引入的文件
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_file: Option<std::path::PathBuf>,
 }
 
 impl SshHost {
@@ -89,11 +561,85 @@ impl SshHost {
             identity_file: None,
             connect_timeout: None,
             server_alive_interval: None,
+            remote_command: None,
+            request_tty: None,
+            address_family: None,
+            bind_address: None,
+            permit_local_command: None,
+            local_command: None,
+            ciphers: None,
+            macs: None,
+            host_key_algorithms: None,
+            pubkey_accepted_algorithms: None,
+            set_env: Vec::new(),
             custom_options: std::collections::HashMap::new(),
+            password_command: None,
             connection_status: ConnectionStatus::default(),
+            source_file: None,
         }
     }
 
+    /// 合并 `Host *` 中的默认选项后的实际配置：本主机已设置的选项优先，
+    /// 未设置的选项取自 `defaults`（与ssh“先出现的值生效”一致，`Host *` 通常写在最后）
+    ///
+    /// 密码命令是本工具的指令，不继承
+    pub fn with_defaults(&self, defaults: &SshHost) -> SshHost {
+        let inherit = |own: &Option<String>, default: &Option<String>| {
+            own.clone().or_else(|| default.clone())
+        };
+        let mut merged = self.clone();
+        merged.hostname = inherit(&self.hostname, &defaults.hostname);
+        merged.user = inherit(&self.user, &defaults.user);
+        merged.port = inherit(&self.port, &defaults.port);
+        merged.proxy_command = inherit(&self.proxy_command, &defaults.proxy_command);
+        merged.identity_file = inherit(&self.identity_file, &defaults.identity_file);
+        merged.connect_timeout = inherit(&self.connect_timeout, &defaults.connect_timeout);
+        merged.server_alive_interval =
+            inherit(&self.server_alive_interval, &defaults.server_alive_interval);
+        merged.remote_command = inherit(&self.remote_command, &defaults.remote_command);
+        merged.request_tty = inherit(&self.request_tty, &defaults.request_tty);
+        merged.address_family = inherit(&self.address_family, &defaults.address_family);
+        merged.bind_address = inherit(&self.bind_address, &defaults.bind_address);
+        merged.permit_local_command =
+            inherit(&self.permit_local_command, &defaults.permit_local_command);
+        merged.local_command = inherit(&self.local_command, &defaults.local_command);
+        merged.ciphers = inherit(&self.ciphers, &defaults.ciphers);
+        merged.macs = inherit(&self.macs, &defaults.macs);
+        merged.host_key_algorithms =
+            inherit(&self.host_key_algorithms, &defaults.host_key_algorithms);
+        merged.pubkey_accepted_algorithms = inherit(
+            &self.pubkey_accepted_algorithms,
+            &defaults.pubkey_accepted_algorithms,
+        );
+        if merged.set_env.is_empty() {
+            merged.set_env = defaults.set_env.clone();
+        }
+        for (key, value) in &defaults.custom_options {
+            if !self
+                .custom_options
+                .keys()
+                .any(|k| k.eq_ignore_ascii_case(key))
+            {
+                merged.custom_options.insert(key.clone(), value.clone());
+            }
+        }
+        merged
+    }
+
+    /// 从 `defaults` 继承的选项（配置关键字, 取值），即本主机未设置、由 `Host *` 提供的选项
+    pub fn inherited_options(&self, defaults: &SshHost) -> Vec<(String, String)> {
+        let own: Vec<String> = self
+            .config_options()
+            .into_iter()
+            .map(|(keyword, _)| keyword.to_lowercase())
+            .collect();
+        defaults
+            .config_options()
+            .into_iter()
+            .filter(|(keyword, _)| !own.contains(&keyword.to_lowercase()))
+            .collect()
+    }
+
     /// 获取连接字符串
     pub fn get_connection_string(&self) -> String {
         match (&self.user, &self.hostname, &self.port) {
@@ -111,62 +657,302 @@ impl SshHost {
         }
     }
 
+    /// 按名称获取自定义选项（SSH配置关键字不区分大小写）
+    pub fn get_custom_option(&self, key: &str) -> Option<&str> {
+        self.custom_options
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// 本机网络相关的选项（配置关键字, 取值），顺序与写入配置文件的顺序一致
+    pub fn address_options(&self) -> [(&'static str, Option<&str>); 2] {
+        [
+            ("AddressFamily", self.address_family.as_deref()),
+            ("BindAddress", self.bind_address.as_deref()),
+        ]
+    }
+
+    /// 本地命令相关的选项（配置关键字, 取值），顺序与写入配置文件的顺序一致
+    pub fn local_command_options(&self) -> [(&'static str, Option<&str>); 2] {
+        [
+            ("PermitLocalCommand", self.permit_local_command.as_deref()),
+            ("LocalCommand", self.local_command.as_deref()),
+        ]
+    }
+
+    /// 加密算法相关的选项（配置关键字, 取值），顺序与写入配置文件的顺序一致
+    pub fn crypto_options(&self) -> [(&'static str, Option<&str>); 4] {
+        [
+            ("Ciphers", self.ciphers.as_deref()),
+            ("MACs", self.macs.as_deref()),
+            ("HostKeyAlgorithms", self.host_key_algorithms.as_deref()),
+            (
+                "PubkeyAcceptedAlgorithms",
+                self.pubkey_accepted_algorithms.as_deref(),
+            ),
+        ]
+    }
+
+    /// 加密算法选项中启用的弱算法，格式为 `关键字: 算法`
+    pub fn legacy_crypto_warnings(&self) -> Vec<String> {
+        self.crypto_options()
+            .into_iter()
+            .filter_map(|(keyword, value)| value.map(|v| (keyword, v)))
+            .flat_map(|(keyword, value)| {
+                crate::utils::weak_algorithms(value)
+                    .into_iter()
+                    .map(move |alg| format!("{}: {}", keyword, alg))
+            })
+            .collect()
+    }
+
+    /// 获取完整的ssh命令
+    ///
+    /// 根据主机字段展开身份文件、端口、跳板机等参数，
+    /// 生成不依赖ssh config即可执行的命令，例如 `ssh -i ~/.ssh/key -p 2222 user@host`
+    pub fn get_full_ssh_command(&self) -> String {
+        use crate::utils::shell_quote;
+
+        let mut parts = vec!["ssh".to_string()];
+
+        if let Some(identity_file) = &self.identity_file {
+            parts.push("-i".to_string());
+            parts.push(shell_quote(identity_file));
+        }
+
+        if let Some(port) = &self.port {
+            parts.push("-p".to_string());
+            parts.push(shell_quote(port));
+        }
+
+        if let Some(proxy_jump) = self.get_custom_option("ProxyJump") {
+            parts.push("-J".to_string());
+            parts.push(shell_quote(proxy_jump));
+        }
+
+        if let Some(proxy_command) = &self.proxy_command {
+            parts.push("-o".to_string());
+            parts.push(shell_quote(&format!("ProxyCommand={}", proxy_command)));
+        }
+
+        match self.request_tty.as_deref() {
+            Some("yes") => parts.push("-t".to_string()),
+            Some("force") => parts.push("-tt".to_string()),
+            Some("no") => parts.push("-T".to_string()),
+            Some(_) => {}
+            None if self.remote_command.is_some() => parts.push("-t".to_string()),
+            None => {}
+        }
+
+        let target = match (&self.user, &self.hostname) {
+            (Some(user), Some(hostname)) => format!("{}@{}", user, hostname),
+            (Some(user), None) => format!("{}@{}", user, self.host),
+            (None, Some(hostname)) => hostname.clone(),
+            (None, None) => self.host.clone(),
+        };
+        parts.push(shell_quote(&target));
+
+        if let Some(remote_command) = &self.remote_command {
+            parts.push(shell_quote(remote_command));
+        }
+
+        parts.join(" ")
+    }
+
     /// 检查是否匹配搜索查询
+    ///
+    /// 查询语法见 [`SearchQuery`]：空白分隔的词全部匹配，`-词` 表示排除，双引号保留空格
     pub fn matches_query(&self, query: &str) -> bool {
-        let query = query.to_lowercase();
+        SearchQuery::parse(query).matches(self)
+    }
 
-        self.host.to_lowercase().contains(&query)
-            || self
-                .hostname
-                .as_ref()
-                .is_some_and(|h| h.to_lowercase().contains(&query))
-            || self
-                .user
-                .as_ref()
-                .is_some_and(|u| u.to_lowercase().contains(&query))
-            || self.port.as_ref().is_some_and(|p| p.contains(&query))
+    /// 检查单个搜索词是否出现在选定的字段中（词需已转为小写）
+    fn matches_term(&self, term: &str, fields: SearchFields) -> bool {
+        SearchField::ALL
+            .into_iter()
+            .filter(|&field| fields.contains(field))
+            .any(|field| match field {
+                SearchField::Host => self.host.to_lowercase().contains(term),
+                SearchField::HostName => self
+                    .hostname
+                    .as_ref()
+                    .is_some_and(|h| h.to_lowercase().contains(term)),
+                SearchField::User => self
+                    .user
+                    .as_ref()
+                    .is_some_and(|u| u.to_lowercase().contains(term)),
+                SearchField::Port => self.port.as_ref().is_some_and(|p| p.contains(term)),
+            })
     }
 
-    /// 转换为配置文件格式
-    pub fn to_config_format(&self) -> String {
-        let mut lines = vec![format!("Host {}", self.host)];
+    /// 计算搜索结果的排序等级，不匹配时返回None
+    ///
+    /// 0：Host名称完全匹配；1：Host名称前缀匹配；2：其他字段（或Host名称中间）包含查询词
+    ///
+    /// 多个搜索词时以第一个包含词判断完全匹配和前缀匹配
+    pub fn match_rank(&self, query: &str) -> Option<u8> {
+        self.match_rank_in(query, SearchFields::all())
+    }
 
-        if let Some(hostname) = &self.hostname {
-            lines.push(format!("    HostName {}", hostname));
+    /// 只在指定字段中匹配时的排序等级；未选择Host字段时不区分完全匹配和前缀匹配
+    pub fn match_rank_in(&self, query: &str, fields: SearchFields) -> Option<u8> {
+        let query = SearchQuery::parse(query);
+        if !query.matches_in(self, fields) {
+            return None;
+        }
+        if !fields.contains(SearchField::Host) {
+            return Some(2);
         }
 
-        if let Some(user) = &self.user {
-            lines.push(format!("    User {}", user));
+        let host = self.host.to_lowercase();
+        match query.include.first() {
+            Some(term) if host == *term => Some(0),
+            Some(term) if host.starts_with(term.as_str()) => Some(1),
+            _ => Some(2),
         }
+    }
 
-        if let Some(port) = &self.port {
-            lines.push(format!("    Port {}", port));
+    /// 配置块中的全部指令（不含Host行），SetEnv等可重复的指令各占一项
+    pub fn config_options(&self) -> Vec<(String, String)> {
+        let mut options = Vec::new();
+        let fields = [
+            ("HostName", &self.hostname),
+            ("User", &self.user),
+            ("Port", &self.port),
+            ("ProxyCommand", &self.proxy_command),
+            ("IdentityFile", &self.identity_file),
+            ("ConnectTimeout", &self.connect_timeout),
+            ("ServerAliveInterval", &self.server_alive_interval),
+            ("RemoteCommand", &self.remote_command),
+            ("RequestTTY", &self.request_tty),
+        ];
+        for (keyword, value) in fields {
+            if let Some(value) = value {
+                options.push((keyword.to_string(), value.clone()));
+            }
         }
 
-        if let Some(proxy_command) = &self.proxy_command {
-            lines.push(format!("    ProxyCommand {}", proxy_command));
+        for (keyword, value) in self
+            .address_options()
+            .into_iter()
+            .chain(self.local_command_options())
+            .chain(self.crypto_options())
+        {
+            if let Some(value) = value {
+                options.push((keyword.to_string(), value.to_string()));
+            }
         }
 
-        if let Some(identity_file) = &self.identity_file {
-            lines.push(format!("    IdentityFile {}", identity_file));
+        for (key, value) in &self.set_env {
+            options.push((
+                "SetEnv".to_string(),
+                crate::utils::format_env_var(key, value),
+            ));
         }
 
-        if let Some(connect_timeout) = &self.connect_timeout {
-            lines.push(format!("    ConnectTimeout {}", connect_timeout));
+        // 自定义选项按关键字排序保证输出稳定
+        let mut custom_options: Vec<_> = self.custom_options.iter().collect();
+        custom_options.sort();
+        for (key, value) in custom_options {
+            options.push((key.clone(), value.clone()));
         }
+        options
+    }
 
-        if let Some(server_alive_interval) = &self.server_alive_interval {
-            lines.push(format!("    ServerAliveInterval {}", server_alive_interval));
+    /// 与 `other` 相比取值不同的指令：`(关键字, 当前的值, other中的值)`
+    ///
+    /// 关键字不区分大小写，可重复的指令（如SetEnv）按整组比较
+    pub fn changed_options(&self, other: &SshHost) -> Vec<(String, Vec<String>, Vec<String>)> {
+        let old = self.config_options();
+        let new = other.config_options();
+        let values = |options: &[(String, String)], keyword: &str| -> Vec<String> {
+            options
+                .iter()
+                .filter(|(k, _)| k.eq_ignore_ascii_case(keyword))
+                .map(|(_, v)| v.clone())
+                .collect()
+        };
+
+        let mut keywords: Vec<&String> = Vec::new();
+        for (keyword, _) in old.iter().chain(&new) {
+            if !keywords.iter().any(|k| k.eq_ignore_ascii_case(keyword)) {
+                keywords.push(keyword);
+            }
         }
 
-        // 添加自定义选项
-        for (key, value) in &self.custom_options {
-            lines.push(format!("    {} {}", key, value));
+        keywords
+            .into_iter()
+            .map(|keyword| {
+                (
+                    keyword.clone(),
+                    values(&old, keyword),
+                    values(&new, keyword),
+                )
+            })
+            .filter(|(_, old, new)| old != new)
+            .collect()
+    }
+
+    /// 改为 `other` 的差异摘要，每行形如 `- 关键字 旧值` 或 `+ 关键字 新值`
+    pub fn diff_lines(&self, other: &SshHost) -> Vec<String> {
+        let mut lines = Vec::new();
+        for (keyword, old, new) in self.changed_options(other) {
+            lines.extend(old.iter().map(|value| format!("- {} {}", keyword, value)));
+            lines.extend(new.iter().map(|value| format!("+ {} {}", keyword, value)));
         }
+        lines
+    }
+
+    /// 改为 `other` 后配置块的统一格式差异，保存编辑前预览；没有变化时返回空字符串
+    pub fn config_diff(&self, other: &SshHost) -> String {
+        crate::utils::unified_diff(
+            &format!("{}\n", self.to_config_format()),
+            &format!("{}\n", other.to_config_format()),
+            &format!("a/{}", self.host),
+            &format!("b/{}", other.host),
+            3,
+        )
+    }
 
+    /// 转换为配置文件格式
+    pub fn to_config_format(&self) -> String {
+        let mut lines = vec![format!("Host {}", self.host)];
+        for (keyword, value) in self.config_options() {
+            lines.push(format!("    {} {}", keyword, value));
+        }
+        if let Some(command) = &self.password_command {
+            lines.push(format!("    {} {}", PASSWORD_COMMAND_DIRECTIVE, command));
+        }
         lines.join("\n")
     }
 
+    /// 清除一个可选字段
+    pub fn clear_field(&mut self, field: OptionalField) {
+        match field {
+            OptionalField::User => self.user = None,
+            OptionalField::Port => self.port = None,
+            OptionalField::ProxyCommand => self.proxy_command = None,
+            OptionalField::IdentityFile => self.identity_file = None,
+            OptionalField::RemoteCommand => self.remote_command = None,
+            OptionalField::RequestTty => self.request_tty = None,
+            OptionalField::SetEnv => self.set_env.clear(),
+            OptionalField::AddressFamily => self.address_family = None,
+            OptionalField::BindAddress => self.bind_address = None,
+            OptionalField::PermitLocalCommand => self.permit_local_command = None,
+            OptionalField::LocalCommand => self.local_command = None,
+        }
+    }
+
+    /// SetEnv条目格式化为以空格分隔的 `KEY=value` 文本（用于表单和显示）
+    pub fn set_env_string(&self) -> String {
+        self.set_env
+            .iter()
+            .map(|(key, value)| crate::utils::format_env_var(key, value))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
     /// 获取实际的主机名和端口
     pub fn get_host_and_port(&self) -> (String, u16) {
         let hostname = self.hostname.as_ref().unwrap_or(&self.host).clone();
@@ -179,13 +965,13 @@ impl SshHost {
     }
 
     /// 异步测试端口连通性
+    ///
+    /// 连接完成后立即返回，不额外等待；界面需要让🟡状态保持可见时由调用方自行处理
     pub async fn test_connection(&mut self) -> crate::error::Result<()> {
-        use tokio::net::TcpStream;
-        use tokio::time::{Instant, sleep, timeout};
+        use tokio::time::{Instant, timeout};
 
         // 只有在状态不是Connecting时才设置为Connecting
         // 这样可以避免UI中已经设置的Connecting状态被覆盖
-        let connecting_start = Instant::now();
         if !matches!(self.connection_status, ConnectionStatus::Connecting) {
             self.connection_status = ConnectionStatus::Connecting;
         }
@@ -202,35 +988,69 @@ impl SshHost {
 
         let start_time = Instant::now();
 
-        let result =
-            match timeout(Duration::from_secs(timeout_secs), TcpStream::connect(&addr)).await {
-                Ok(Ok(_stream)) => {
-                    let duration = start_time.elapsed();
-                    self.connection_status = ConnectionStatus::Connected(duration);
-                    log::debug!("Connection to {} successful in {:?}", addr, duration);
-                    Ok(())
-                }
-                Ok(Err(e)) => {
-                    let error_msg = format!("Connection failed: {}", e);
-                    self.connection_status = ConnectionStatus::Failed(error_msg.clone());
-                    log::warn!("Connection to {} failed: {}", addr, e);
-                    Err(crate::error::SshConnError::Connection(error_msg))
-                }
-                Err(_) => {
-                    let error_msg = format!("Connection timeout after {}s", timeout_secs);
-                    self.connection_status = ConnectionStatus::Failed(error_msg.clone());
-                    log::warn!("Connection to {} timed out", addr);
-                    Err(crate::error::SshConnError::Connection(error_msg))
-                }
-            };
-
-        // 确保Connecting状态至少显示200ms，这样用户能看到🟡状态
-        let elapsed = connecting_start.elapsed();
-        if elapsed < Duration::from_millis(200) {
-            sleep(Duration::from_millis(200) - elapsed).await;
+        // 按AddressFamily只尝试对应地址族的地址
+        let connect = crate::network::connect_tcp(&hostname, port, self.address_family.as_deref());
+        match timeout(Duration::from_secs(timeout_secs), connect).await {
+            Ok(Ok(_stream)) => {
+                let duration = start_time.elapsed();
+                self.connection_status = ConnectionStatus::Connected(duration);
+                log::debug!("Connection to {} successful in {:?}", addr, duration);
+                Ok(())
+            }
+            Ok(Err(e)) => {
+                let error_msg = format!("Connection failed: {}", e);
+                self.connection_status = ConnectionStatus::Failed(error_msg.clone());
+                log::warn!("Connection to {} failed: {}", addr, e);
+                Err(crate::error::SshConnError::Connection(error_msg))
+            }
+            Err(_) => {
+                let error_msg = format!("Connection timeout after {}s", timeout_secs);
+                self.connection_status = ConnectionStatus::Failed(error_msg.clone());
+                log::warn!("Connection to {} timed out", addr);
+                Err(crate::error::SshConnError::Connection(error_msg))
+            }
         }
+    }
+}
+
+/// 输出配置块，与 [`SshHost::to_config_format`] 相同
+///
+/// ```
+/// use ssh_conn::SshHost;
+///
+/// let mut host = SshHost::new("web".to_string());
+/// host.hostname = Some("10.0.0.5".to_string());
+/// assert_eq!(host.to_string(), "Host web\n    HostName 10.0.0.5");
+/// ```
+impl std::fmt::Display for SshHost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_config_format())
+    }
+}
+
+/// 解析单个Host块（规则同 [`parse_ssh_config_str`](crate::config::parse_ssh_config_str)），
+/// 没有或有多个非通配符主机时返回 [`SshConnError::ConfigParse`]
+///
+/// ```
+/// use ssh_conn::SshHost;
+///
+/// let host: SshHost = "Host web\n  HostName 10.0.0.5\n  Compression yes".parse().unwrap();
+/// assert_eq!(host.hostname.as_deref(), Some("10.0.0.5"));
+/// assert_eq!(host.custom_options["Compression"], "yes");
+///
+/// assert!("HostName 10.0.0.5".parse::<SshHost>().is_err());
+/// ```
+impl std::str::FromStr for SshHost {
+    type Err = SshConnError;
 
-        result
+    fn from_str(s: &str) -> Result<Self> {
+        let mut hosts = crate::config::parse_ssh_config_str(s)?;
+        if hosts.len() != 1 {
+            return Err(SshConnError::ConfigParse(
+                t("error.expected_single_host").replace("{}", &hosts.len().to_string()),
+            ));
+        }
+        Ok(hosts.remove(0))
     }
 }
 
@@ -256,6 +1076,8 @@ pub enum FormFieldType {
     Number,
     Password,
     Path,
+    /// 从给定选项中选择，空值表示未选择
+    Select(Vec<String>),
 }
 
 impl FormField {
@@ -296,23 +1118,42 @@ impl FormField {
             ));
         }
 
-        match self.field_type {
-            FormFieldType::Number => {
-                if !self.value.is_empty() {
-                    crate::utils::validate_port(&self.value)?;
-                }
+        match &self.field_type {
+            FormFieldType::Number if !self.value.is_empty() => {
+                crate::utils::validate_port(&self.value)?;
             }
-            FormFieldType::Path => {
-                if !self.value.is_empty() {
-                    let path = std::path::Path::new(&self.value);
-                    if !path.exists() {
-                        log::warn!("{}", t("path_not_exists").replace("{}", &self.value));
-                    }
-                }
+            FormFieldType::Path
+                if !self.value.is_empty() && !crate::utils::expand_path(&self.value).exists() =>
+            {
+                log::warn!("{}", t("path_not_exists").replace("{}", &self.value));
             }
             _ => {}
         }
 
         Ok(())
     }
+
+    /// 选择字段切换到下一个（`forward` 为false时上一个）选项，首尾之间经过“未选择”
+    pub fn cycle_option(&mut self, forward: bool) {
+        let FormFieldType::Select(options) = &self.field_type else {
+            return;
+        };
+
+        // 位置0表示未选择，选项从1开始
+        let count = options.len() + 1;
+        let current = options
+            .iter()
+            .position(|o| *o == self.value)
+            .map_or(0, |i| i + 1);
+        let next = if forward {
+            (current + 1) % count
+        } else {
+            (current + count - 1) % count
+        };
+        self.value = if next == 0 {
+            String::new()
+        } else {
+            options[next - 1].clone()
+        };
+    }
 }
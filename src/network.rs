@@ -6,6 +6,27 @@ use std::time::Duration;
 use tokio::net::TcpStream;
 use tokio::time::{Instant, timeout};
 
+/// 服务器接受的认证方式（见 [`NetworkProbe::probe_auth_methods`]）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthMethods {
+    /// 不需要认证，以 `none` 方式即可登录
+    NoneRequired,
+    /// 服务器提供的认证方式，如 `publickey`、`password`、`keyboard-interactive`
+    Offered(Vec<String>),
+}
+
+impl AuthMethods {
+    /// 服务器是否提供密码登录（`password` 或 `keyboard-interactive`）
+    pub fn offers_password(&self) -> bool {
+        match self {
+            AuthMethods::NoneRequired => false,
+            AuthMethods::Offered(methods) => methods
+                .iter()
+                .any(|m| m == "password" || m == "keyboard-interactive"),
+        }
+    }
+}
+
 /// 网络检测器
 pub struct NetworkProbe {
     /// 默认超时时间（秒）
@@ -24,6 +45,17 @@ impl NetworkProbe {
         self
     }
 
+    /// 用于 [`probe_auth_methods`](Self::probe_auth_methods) 的检测器：
+    /// 在主机的ConnectTimeout（默认5秒）之外再留5秒完成密钥交换
+    pub fn for_auth_probe(host: &SshHost) -> Self {
+        let connect_timeout = host
+            .connect_timeout
+            .as_deref()
+            .and_then(|t| t.parse::<u64>().ok())
+            .unwrap_or(5);
+        Self::new().with_timeout(connect_timeout + 5)
+    }
+
     /// 测试单个主机的连接
     pub async fn test_host(&self, host: &mut SshHost) -> Result<()> {
         host.test_connection().await
@@ -126,6 +158,211 @@ impl NetworkProbe {
         let average = total_duration / successful_count;
         Ok((average, results))
     }
+
+    /// 探测服务器接受的认证方式
+    ///
+    /// `argv` 为 [`CommandMode::AuthProbe`](crate::config::CommandMode::AuthProbe) 构建的命令
+    /// （`ssh -o BatchMode=yes -o PreferredAuthentications=none <host> exit`），服务器拒绝时在
+    /// `Permission denied (publickey,password).` 中列出可用的方式；超过默认超时时间时终止ssh并返回错误
+    pub async fn probe_auth_methods(&self, argv: &[String]) -> Result<AuthMethods> {
+        let (program, args) = argv
+            .split_first()
+            .ok_or_else(|| SshConnError::Connection("empty command".to_string()))?;
+        let output = tokio::process::Command::new(program)
+            .args(args)
+            .stdin(std::process::Stdio::null())
+            .kill_on_drop(true)
+            .output();
+        let output = timeout(Duration::from_secs(self.default_timeout), output)
+            .await
+            .map_err(|_| {
+                SshConnError::Connection(format!(
+                    "Auth probe timeout after {}s",
+                    self.default_timeout
+                ))
+            })?
+            .map_err(|e| {
+                SshConnError::spawn_failed(program, e, |e| {
+                    SshConnError::Connection(format!("{}: {}", program, e))
+                })
+            })?;
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        parse_auth_methods(output.status.success(), &stderr)
+    }
+
+    /// 解析主机名并按AddressFamily筛选，返回耗时和解析出的地址
+    pub async fn timed_resolve(
+        &self,
+        hostname: &str,
+        port: u16,
+        address_family: Option<&str>,
+    ) -> Result<(Duration, Vec<std::net::SocketAddr>)> {
+        let start_time = Instant::now();
+        let resolved = timeout(
+            Duration::from_secs(self.default_timeout),
+            tokio::net::lookup_host((hostname, port)),
+        )
+        .await
+        .map_err(|_| {
+            SshConnError::Connection(format!(
+                "DNS resolution timeout after {}s",
+                self.default_timeout
+            ))
+        })?
+        .map_err(|e| SshConnError::Connection(format!("DNS resolution failed: {}", e)))?;
+        let duration = start_time.elapsed();
+
+        let addrs = filter_address_family(resolved, address_family);
+        if addrs.is_empty() {
+            return Err(SshConnError::Connection(format!(
+                "no {} address for {}",
+                address_family.unwrap_or("any"),
+                hostname
+            )));
+        }
+        Ok((duration, addrs))
+    }
+
+    /// 连接已解析的地址，返回建立TCP连接的耗时
+    pub async fn timed_connect(&self, addr: std::net::SocketAddr) -> Result<Duration> {
+        let start_time = Instant::now();
+        match timeout(
+            Duration::from_secs(self.default_timeout),
+            TcpStream::connect(addr),
+        )
+        .await
+        {
+            Ok(Ok(_stream)) => Ok(start_time.elapsed()),
+            Ok(Err(e)) => Err(SshConnError::Connection(format!(
+                "Connection failed: {}",
+                e
+            ))),
+            Err(_) => Err(SshConnError::Connection(format!(
+                "Connection timeout after {}s",
+                self.default_timeout
+            ))),
+        }
+    }
+}
+
+/// 执行命令并计时，标准输入为空，返回耗时和输出
+pub fn timed_command(argv: &[String]) -> Result<(Duration, std::process::Output)> {
+    let (program, args) = argv
+        .split_first()
+        .ok_or_else(|| SshConnError::Connection("empty command".to_string()))?;
+    let start_time = std::time::Instant::now();
+    let output = std::process::Command::new(program)
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .map_err(|e| {
+            SshConnError::spawn_failed(program, e, |e| {
+                SshConnError::Connection(format!("{}: {}", program, e))
+            })
+        })?;
+    Ok((start_time.elapsed(), output))
+}
+
+/// 运行ssh探测命令（见 [`CommandMode::Probe`](crate::config::CommandMode::Probe)），返回耗时
+///
+/// 与TCP探测不同，会经过主机配置的ProxyJump/ProxyCommand；服务器拒绝非交互认证
+/// （`Permission denied`）时说明已经连到了sshd，同样视为可达
+pub fn ssh_probe(argv: &[String]) -> Result<Duration> {
+    let (duration, output) = timed_command(argv)?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    probe_result(output.status.success(), &stderr, duration)
+}
+
+/// 根据ssh的退出状态和标准错误判断探测结果
+fn probe_result(success: bool, stderr: &str, duration: Duration) -> Result<Duration> {
+    if success || stderr.contains("Permission denied") {
+        return Ok(duration);
+    }
+    Err(ssh_failure(stderr))
+}
+
+/// 以ssh标准错误的最后一个非空行作为失败原因
+fn ssh_failure(stderr: &str) -> SshConnError {
+    let reason = stderr
+        .lines()
+        .rev()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or("ssh exited with an error");
+    SshConnError::Connection(format!("ssh: {}", reason))
+}
+
+/// 根据 `PreferredAuthentications=none` 探测的退出状态和标准错误得到服务器接受的认证方式
+///
+/// 成功退出说明服务器不需要认证；否则从 `[user@host: ]Permission denied (m1,m2,...).` 中取出方式列表，
+/// 没有这一行时（如连接超时、被重置）返回ssh报告的错误
+pub fn parse_auth_methods(success: bool, stderr: &str) -> Result<AuthMethods> {
+    const DENIED: &str = "Permission denied (";
+
+    if success {
+        return Ok(AuthMethods::NoneRequired);
+    }
+    stderr
+        .lines()
+        .find_map(|line| {
+            let rest = &line[line.find(DENIED)? + DENIED.len()..];
+            let methods = &rest[..rest.find(')')?];
+            Some(
+                methods
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|m| !m.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+            )
+        })
+        .map(AuthMethods::Offered)
+        .ok_or_else(|| ssh_failure(stderr))
+}
+
+/// 按AddressFamily（inet只保留IPv4，inet6只保留IPv6，其他取值不过滤）筛选解析出的地址
+pub fn filter_address_family(
+    addrs: impl IntoIterator<Item = std::net::SocketAddr>,
+    address_family: Option<&str>,
+) -> Vec<std::net::SocketAddr> {
+    let family = address_family.map(str::to_ascii_lowercase);
+    addrs
+        .into_iter()
+        .filter(|addr| match family.as_deref() {
+            Some("inet") => addr.is_ipv4(),
+            Some("inet6") => addr.is_ipv6(),
+            _ => true,
+        })
+        .collect()
+}
+
+/// 解析主机名并按AddressFamily筛选后依次尝试连接，与ssh选择地址的方式一致
+pub async fn connect_tcp(
+    hostname: &str,
+    port: u16,
+    address_family: Option<&str>,
+) -> std::io::Result<TcpStream> {
+    let resolved = tokio::net::lookup_host((hostname, port)).await?;
+    let addrs = filter_address_family(resolved, address_family);
+    if addrs.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!(
+                "no {} address for {}",
+                address_family.unwrap_or("any"),
+                hostname
+            ),
+        ));
+    }
+
+    let mut last_error = None;
+    for addr in addrs {
+        match TcpStream::connect(addr).await {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.expect("at least one address was tried"))
 }
 
 impl Default for NetworkProbe {
@@ -139,6 +376,54 @@ mod tests {
     use super::*;
     use crate::models::{ConnectionStatus, SshHost};
 
+    #[test]
+    fn test_missing_program_is_binary_not_found() {
+        let argv = vec!["ssh-conn-no-such-program".to_string()];
+        assert!(matches!(
+            timed_command(&argv),
+            Err(SshConnError::BinaryNotFound { name }) if name == "ssh-conn-no-such-program"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_no_min_connecting_returns_promptly() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut host = SshHost::new("local".to_string());
+        host.hostname = Some("127.0.0.1".to_string());
+        host.port = Some(listener.local_addr().unwrap().port().to_string());
+
+        let start = std::time::Instant::now();
+        NetworkProbe::new().test_host(&mut host).await.unwrap();
+        assert!(start.elapsed() < Duration::from_millis(150));
+        assert!(matches!(
+            host.connection_status,
+            ConnectionStatus::Connected(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_connection_adds_no_artificial_delay() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut host = SshHost::new("local".to_string());
+        host.hostname = Some("127.0.0.1".to_string());
+        host.port = Some(listener.local_addr().unwrap().port().to_string());
+
+        // 本机连接远快于原先固定等待的200ms，SshHost本身不再等待
+        let start = std::time::Instant::now();
+        host.test_connection().await.unwrap();
+        assert!(start.elapsed() < Duration::from_millis(150));
+        assert!(matches!(
+            host.connection_status,
+            ConnectionStatus::Connected(_)
+        ));
+
+        let mut hosts = vec![host.clone(), host];
+        let start = std::time::Instant::now();
+        let results = NetworkProbe::new().test_hosts(&mut hosts).await;
+        assert!(results.iter().all(Result::is_ok));
+        assert!(start.elapsed() < Duration::from_millis(150));
+    }
+
     #[tokio::test]
     async fn test_probe_creation() {
         let probe = NetworkProbe::new();
@@ -163,6 +448,129 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_probe_result() {
+        let duration = Duration::from_millis(40);
+        assert_eq!(probe_result(true, "", duration).unwrap(), duration);
+        // 连到了sshd但不允许非交互认证
+        assert!(
+            probe_result(
+                false,
+                "web: Permission denied (publickey,password).\n",
+                duration
+            )
+            .is_ok()
+        );
+
+        let err = probe_result(
+            false,
+            "channel 0: open failed: connect failed: Name or service not known\nstdio forwarding failed\n",
+            duration,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            SshConnError::Connection("ssh: stdio forwarding failed".to_string()).to_string()
+        );
+    }
+
+    #[test]
+    fn test_parse_auth_methods() {
+        let offered = |methods: &[&str]| {
+            AuthMethods::Offered(methods.iter().map(|m| m.to_string()).collect())
+        };
+        // 常见的OpenSSH输出：带 `user@host:` 前缀，Windows版以CRLF结尾
+        assert_eq!(
+            parse_auth_methods(
+                false,
+                "deploy@10.0.0.5: Permission denied (publickey,password).\r\n"
+            )
+            .unwrap(),
+            offered(&["publickey", "password"])
+        );
+        // 首次连接时先输出known_hosts警告，其中也带括号
+        assert_eq!(
+            parse_auth_methods(
+                false,
+                "Warning: Permanently added '10.0.0.5' (ED25519) to the list of known hosts.\n\
+                 root@web: Permission denied (publickey,gssapi-keyex,gssapi-with-mic,keyboard-interactive).\n"
+            )
+            .unwrap(),
+            offered(&[
+                "publickey",
+                "gssapi-keyex",
+                "gssapi-with-mic",
+                "keyboard-interactive"
+            ])
+        );
+        // 旧版本没有前缀
+        let methods = parse_auth_methods(false, "Permission denied (publickey).\n").unwrap();
+        assert_eq!(methods, offered(&["publickey"]));
+        assert!(!methods.offers_password());
+        assert!(
+            parse_auth_methods(false, "Permission denied (keyboard-interactive).")
+                .unwrap()
+                .offers_password()
+        );
+
+        // 服务器接受none认证
+        assert_eq!(
+            parse_auth_methods(true, "").unwrap(),
+            AuthMethods::NoneRequired
+        );
+
+        // 没连到sshd：返回ssh的错误
+        for (stderr, reason) in [
+            (
+                "ssh: connect to host 10.0.0.5 port 22: Connection timed out\r\n",
+                "ssh: ssh: connect to host 10.0.0.5 port 22: Connection timed out",
+            ),
+            (
+                "Connection timed out during banner exchange\nConnection to 10.0.0.5 port 22 timed out\n",
+                "ssh: Connection to 10.0.0.5 port 22 timed out",
+            ),
+            (
+                "kex_exchange_identification: read: Connection reset by peer\n",
+                "ssh: kex_exchange_identification: read: Connection reset by peer",
+            ),
+            ("", "ssh: ssh exited with an error"),
+        ] {
+            match parse_auth_methods(false, stderr) {
+                Err(SshConnError::Connection(message)) => assert_eq!(message, reason),
+                other => panic!("expected Connection error, got {:?}", other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_probe_auth_methods_times_out() {
+        let probe = NetworkProbe::new().with_timeout(1);
+        let argv: Vec<String> = ["sleep", "5"].iter().map(|s| s.to_string()).collect();
+        if cfg!(unix) {
+            let err = probe.probe_auth_methods(&argv).await.unwrap_err();
+            assert!(err.to_string().contains("timeout"), "{err}");
+        }
+        assert!(probe.probe_auth_methods(&[]).await.is_err());
+    }
+
+    #[test]
+    fn test_filter_address_family() {
+        let v4: std::net::SocketAddr = "10.0.0.5:22".parse().unwrap();
+        let v6: std::net::SocketAddr = "[2001:db8::5]:22".parse().unwrap();
+        assert_eq!(filter_address_family([v4, v6], Some("inet")), [v4]);
+        assert_eq!(filter_address_family([v4, v6], Some("INET6")), [v6]);
+        assert_eq!(filter_address_family([v4, v6], Some("any")), [v4, v6]);
+        assert_eq!(filter_address_family([v4, v6], None), [v4, v6]);
+    }
+
+    #[tokio::test]
+    async fn test_connect_tcp_without_matching_family() {
+        let err = connect_tcp("127.0.0.1", 22, Some("inet6"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    }
+
     #[tokio::test]
     async fn test_invalid_connection() {
         let probe = NetworkProbe::new();
@@ -0,0 +1,47 @@
+//! 命令行输出控制
+//!
+//! `-q/--quiet` 时不输出提示性信息（如“使用已存储的密码”、操作成功提示），
+//! 命令的结果和错误照常输出；`-v/-vv` 对应的日志级别也在这里换算
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// 设置是否静默提示性输出
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// 当前是否为静默模式
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// 输出一行提示性信息，静默模式下忽略
+pub fn info(message: impl std::fmt::Display) {
+    if !is_quiet() {
+        println!("{}", message);
+    }
+}
+
+/// `-v` 的次数对应的日志级别：一次为Info，两次及以上为Debug；未指定时返回None，沿用 `RUST_LOG`
+pub fn log_level(verbose: u8) -> Option<log::LevelFilter> {
+    match verbose {
+        0 => None,
+        1 => Some(log::LevelFilter::Info),
+        _ => Some(log::LevelFilter::Debug),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_level_from_verbosity() {
+        assert_eq!(log_level(0), None);
+        assert_eq!(log_level(1), Some(log::LevelFilter::Info));
+        assert_eq!(log_level(2), Some(log::LevelFilter::Debug));
+        assert_eq!(log_level(5), Some(log::LevelFilter::Debug));
+    }
+}
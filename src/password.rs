@@ -1,9 +1,142 @@
 //! 密码管理模块
+//!
+//! 同一数据库中另有 `key_passphrases` 表保存私钥的口令，以展开后的私钥路径为键，
+//! 供载入ssh-agent时使用；私钥可能被多台主机共用，删除主机不会删除口令。
 
 use crate::error::{Result, SshConnError};
+use crate::i18n::t;
+use crate::settings::Settings;
 use crate::utils::get_password_db_path;
-use rusqlite::{Connection, params};
-use std::collections::HashMap;
+use rusqlite::{Connection, ErrorCode, params};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use zeroize::{Zeroize, Zeroizing};
+
+/// 运行主机的密码命令（`# ssh-conn-password-cmd:`），取标准输出的第一行作为密码
+///
+/// 命令由 `sh -c` 执行，标准输入和标准错误沿用当前终端，便于密码管理器提示解锁；
+/// 命令失败或没有输出时返回错误，不回退到存储的密码
+pub fn run_password_command(command: &str) -> Result<Zeroizing<String>> {
+    let failed = |reason: String| {
+        SshConnError::PasswordError(
+            t("error.password_command_failed")
+                .replacen("{}", command, 1)
+                .replacen("{}", &reason, 1),
+        )
+    };
+
+    let output = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stderr(std::process::Stdio::inherit())
+        .output()
+        .map_err(|e| failed(e.to_string()))?;
+    let stdout = Zeroizing::new(output.stdout);
+    if !output.status.success() {
+        return Err(failed(output.status.to_string()));
+    }
+
+    let text = std::str::from_utf8(&stdout).map_err(|e| failed(e.to_string()))?;
+    let password = text.lines().next().unwrap_or_default();
+    if password.is_empty() {
+        return Err(failed(t("error.password_command_empty")));
+    }
+    Ok(Zeroizing::new(password.to_string()))
+}
+
+/// 数据库被其他进程锁定时，SQLite内部等待的时长
+const BUSY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// busy_timeout 之后仍然锁定时，每次重试前的等待时间
+const RETRY_DELAYS: [Duration; 3] = [
+    Duration::from_millis(50),
+    Duration::from_millis(150),
+    Duration::from_millis(400),
+];
+
+/// 加密导出文件开头的格式标记
+const EXPORT_MAGIC: &[u8] = b"SSHCONN-PW1";
+/// 派生密钥使用的盐长度
+const EXPORT_SALT_LEN: usize = 16;
+/// XChaCha20-Poly1305 的随机数长度
+const EXPORT_NONCE_LEN: usize = 24;
+
+/// 用Argon2id从口令派生加密密钥
+fn derive_export_key(passphrase: &str, salt: &[u8]) -> Result<Zeroizing<[u8; 32]>> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    argon2::Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, key.as_mut())
+        .map_err(|e| SshConnError::PasswordError(e.to_string()))?;
+    Ok(key)
+}
+
+/// 解密 [`PasswordManager::export_encrypted`] 导出的数据，返回 主机 → 密码
+///
+/// 口令错误或数据损坏时返回错误
+pub fn decrypt_password_export(data: &[u8], passphrase: &str) -> Result<BTreeMap<String, String>> {
+    use chacha20poly1305::aead::{Aead, KeyInit};
+    use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+
+    let invalid = || SshConnError::PasswordError(t("error.password_export_invalid"));
+    let body = data.strip_prefix(EXPORT_MAGIC).ok_or_else(invalid)?;
+    if body.len() < EXPORT_SALT_LEN + EXPORT_NONCE_LEN {
+        return Err(invalid());
+    }
+    let (salt, rest) = body.split_at(EXPORT_SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(EXPORT_NONCE_LEN);
+
+    let key = derive_export_key(passphrase, salt)?;
+    let cipher = XChaCha20Poly1305::new(key.as_ref().into());
+    let plaintext = Zeroizing::new(
+        cipher
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map_err(|_| invalid())?,
+    );
+    serde_json::from_slice(&plaintext).map_err(|_| invalid())
+}
+
+/// 是否为数据库被锁定（`SQLITE_BUSY`/`SQLITE_LOCKED`）导致的暂时性错误
+fn is_busy(err: &SshConnError) -> bool {
+    matches!(
+        err,
+        SshConnError::Database(rusqlite::Error::SqliteFailure(e, _))
+            if matches!(e.code, ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked)
+    )
+}
+
+/// 执行数据库操作，遇到锁定错误时按 `delays` 退避重试
+///
+/// 重试用尽后返回说明数据库被占用的错误
+fn retry_on_busy<T>(delays: &[Duration], mut op: impl FnMut() -> Result<T>) -> Result<T> {
+    let mut delays = delays.iter();
+    loop {
+        match op() {
+            Err(e) if is_busy(&e) => match delays.next() {
+                Some(delay) => {
+                    log::warn!("Password database is busy, retrying in {:?}", delay);
+                    std::thread::sleep(*delay);
+                }
+                None => {
+                    return Err(SshConnError::PasswordError(format!(
+                        "{} ({})",
+                        t("error.password_db_busy"),
+                        e
+                    )));
+                }
+            },
+            result => return result,
+        }
+    }
+}
+
+/// 私钥口令的键：展开 `~` 和环境变量后的私钥路径
+pub fn key_passphrase_id(identity_file: &str) -> String {
+    crate::utils::expand_path(identity_file)
+        .to_string_lossy()
+        .into_owned()
+}
 
 /// 密码管理器
 #[derive(Clone)]
@@ -12,39 +145,102 @@ pub struct PasswordManager {
     db_path: String,
     /// 数据库密码
     db_password: String,
-    /// 密码缓存
+    /// 是否在内存中缓存密码
+    cache_passwords: bool,
+    /// 密码缓存，关闭缓存时始终为空
     password_cache: HashMap<String, String>,
+    /// 存储了非空密码的主机名，不含密码本身，关闭缓存时也会维护
+    stored_hosts: HashSet<String>,
+    /// 数据库连接，首次使用时打开并一直复用，克隆出的管理器共享同一连接
+    conn: Arc<Mutex<Option<Connection>>>,
 }
 
 impl PasswordManager {
     /// 创建一个新的密码管理器
-    pub fn new() -> Result<Self> {
-        let db_path = get_password_db_path()?.to_string_lossy().to_string();
+    ///
+    /// 数据库路径优先使用设置中的 `password_db_path`；`cache_passwords = false` 时
+    /// 启动时不载入密码，每次使用才查询数据库
+    pub fn new(settings: &Settings) -> Result<Self> {
+        let db_path = match &settings.password_db_path {
+            Some(path) => path.to_string_lossy().to_string(),
+            None => get_password_db_path()?.to_string_lossy().to_string(),
+        };
 
         // 初始化密码管理器
         let mut manager = Self {
             db_path,
             db_password: String::new(), // 默认为空密码
+            cache_passwords: settings.cache_passwords,
             password_cache: HashMap::new(),
+            stored_hosts: HashSet::new(),
+            conn: Arc::new(Mutex::new(None)),
         };
 
-        // 加载所有密码到缓存
+        // 加载所有密码到缓存（关闭缓存时只加载主机名）
         manager.load_all_passwords()?;
 
         Ok(manager)
     }
 
+    /// 使用指定的数据库文件创建密码管理器，其余设置取默认值
+    ///
+    /// 供库的使用者和测试操作临时文件，`":memory:"` 表示只在内存中保存
+    pub fn with_path(path: impl Into<PathBuf>) -> Result<Self> {
+        let settings = Settings {
+            password_db_path: Some(path.into()),
+            ..Settings::default()
+        };
+        Self::new(&settings)
+    }
+
+    /// 密码数据库的路径
+    pub fn db_path(&self) -> &str {
+        &self.db_path
+    }
+
     /// 设置数据库密码
     pub fn set_db_password(&mut self, password: &str) -> Result<()> {
         self.db_password = password.to_string();
+        // 密码在打开连接时设置，需要重新打开
+        *self.lock_conn() = None;
         // 重新加载密码
         self.load_all_passwords()?;
         Ok(())
     }
 
-    /// 打开密码数据库连接
+    fn lock_conn(&self) -> std::sync::MutexGuard<'_, Option<Connection>> {
+        // 持有锁时不会panic，遇到中毒的锁直接取出连接
+        self.conn.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 在共享的数据库连接上执行操作，连接尚未打开时先打开
+    ///
+    /// 遇到数据库被锁定的错误时按退避重试
+    fn with_db<T>(&self, mut op: impl FnMut(&Connection) -> Result<T>) -> Result<T> {
+        retry_on_busy(&RETRY_DELAYS, || {
+            let mut conn = self.lock_conn();
+            if conn.is_none() {
+                *conn = Some(self.open_db()?);
+            }
+            op(conn.as_ref().expect("connection opened above"))
+        })
+    }
+
+    /// 打开密码数据库连接，建表只在这里执行一次
     fn open_db(&self) -> Result<Connection> {
+        // 数据库中保存着密码，新建时先以600创建空文件，SQLite会沿用该文件的权限
+        let path = Path::new(&self.db_path);
+        if self.db_path != ":memory:" && !path.exists() {
+            crate::permissions::private_open_options()
+                .write(true)
+                .create_new(true)
+                .open(path)
+                .map_err(|e| SshConnError::file_access(path, e))?;
+        }
         let conn = Connection::open(&self.db_path).map_err(SshConnError::Database)?;
+        // 另一个实例正在写入时等待锁释放，而不是立即失败
+        conn.busy_timeout(BUSY_TIMEOUT)
+            .map_err(SshConnError::Database)?;
 
         // 如果有设置密码，则使用密码
         if !self.db_password.is_empty() {
@@ -58,6 +254,11 @@ impl PasswordManager {
             [],
         )
         .map_err(SshConnError::Database)?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS key_passphrases (key_path TEXT PRIMARY KEY, passphrase TEXT)",
+            [],
+        )
+        .map_err(SshConnError::Database)?;
 
         Ok(conn)
     }
@@ -65,21 +266,33 @@ impl PasswordManager {
     /// 保存密码
     pub fn save_password(&mut self, host: &str, password: &str) -> Result<()> {
         // 更新缓存
-        self.password_cache
-            .insert(host.to_string(), password.to_string());
+        if self.cache_passwords
+            && let Some(mut old) = self
+                .password_cache
+                .insert(host.to_string(), password.to_string())
+        {
+            old.zeroize();
+        }
+        if password.is_empty() {
+            self.stored_hosts.remove(host);
+        } else {
+            self.stored_hosts.insert(host.to_string());
+        }
 
         // 保存到数据库
-        let conn = self.open_db()?;
-        conn.execute(
-            "INSERT OR REPLACE INTO passwords (host, password) VALUES (?1, ?2)",
-            params![host, password],
-        )
-        .map_err(SshConnError::Database)?;
-
-        Ok(())
+        self.with_db(|conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO passwords (host, password) VALUES (?1, ?2)",
+                params![host, password],
+            )
+            .map_err(SshConnError::Database)?;
+            Ok(())
+        })
     }
 
     /// 获取密码
+    ///
+    /// 调用方应在用完后清零返回的密码（如包装为 `zeroize::Zeroizing`）
     pub fn get_password(&self, host: &str) -> Option<String> {
         // 先从缓存中查找
         if let Some(password) = self.password_cache.get(host) {
@@ -87,72 +300,454 @@ impl PasswordManager {
         }
 
         // 如果缓存中没有，尝试从数据库加载
-        match self.open_db() {
-            Ok(conn) => {
-                let mut stmt = match conn.prepare("SELECT password FROM passwords WHERE host = ?1")
-                {
-                    Ok(stmt) => stmt,
-                    Err(_) => return None,
-                };
-
-                let mut rows = match stmt.query(params![host]) {
-                    Ok(rows) => rows,
-                    Err(_) => return None,
-                };
-
-                if let Ok(Some(row)) = rows.next() {
-                    if let Ok(password) = row.get::<_, String>(0) {
-                        return Some(password);
-                    }
-                }
-
-                None
+        let result = self.with_db(|conn| {
+            let mut stmt = conn
+                .prepare("SELECT password FROM passwords WHERE host = ?1")
+                .map_err(SshConnError::Database)?;
+            let mut rows = stmt.query(params![host]).map_err(SshConnError::Database)?;
+            match rows.next().map_err(SshConnError::Database)? {
+                Some(row) => Ok(row.get::<_, String>(0).ok()),
+                None => Ok(None),
             }
-            Err(_) => None,
-        }
+        });
+
+        result.unwrap_or_else(|e| {
+            log::warn!("Failed to read password for {}: {}", host, e);
+            None
+        })
+    }
+
+    /// 是否存储了非空密码，只查内存中的主机名（启动时已加载），可在每帧渲染时调用
+    pub fn has_password(&self, host: &str) -> bool {
+        self.stored_hosts.contains(host)
     }
 
     /// 删除密码
     pub fn delete_password(&mut self, host: &str) -> Result<()> {
         // 从缓存中删除
-        self.password_cache.remove(host);
+        if let Some(mut password) = self.password_cache.remove(host) {
+            password.zeroize();
+        }
+        self.stored_hosts.remove(host);
 
         // 从数据库中删除
-        let conn = self.open_db()?;
-        conn.execute("DELETE FROM passwords WHERE host = ?1", params![host])
-            .map_err(SshConnError::Database)?;
-
-        Ok(())
+        self.with_db(|conn| {
+            conn.execute("DELETE FROM passwords WHERE host = ?1", params![host])
+                .map_err(SshConnError::Database)?;
+            Ok(())
+        })
     }
 
     /// 加载所有密码到缓存
     fn load_all_passwords(&mut self) -> Result<()> {
+        self.clear_cache();
+        self.stored_hosts.clear();
+
+        // 先确保连接已打开：数据库持续被占用时报告错误，其他错误（如数据库不存在）忽略
+        match self.with_db(|_| Ok(())) {
+            Ok(()) => {}
+            Err(e @ SshConnError::PasswordError(_)) => return Err(e),
+            Err(_) => return Ok(()),
+        }
+
+        let entries = self.with_db(|conn| {
+            let mut stmt = conn
+                .prepare("SELECT host, password FROM passwords")
+                .map_err(SshConnError::Database)?;
+            let rows = stmt
+                .query_map([], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+                })
+                .map_err(SshConnError::Database)?;
+            Ok(rows.flatten().collect::<Vec<_>>())
+        })?;
+
+        for (host, mut password) in entries {
+            if !password.is_empty() {
+                self.stored_hosts.insert(host.clone());
+            }
+            if self.cache_passwords {
+                self.password_cache.insert(host, password);
+            } else {
+                password.zeroize();
+            }
+        }
+        Ok(())
+    }
+
+    /// 清零并清空密码缓存
+    fn clear_cache(&mut self) {
+        for password in self.password_cache.values_mut() {
+            password.zeroize();
+        }
         self.password_cache.clear();
+    }
 
-        let conn = match self.open_db() {
-            Ok(conn) => conn,
-            Err(_) => return Ok(()), // 如果数据库不存在，忽略错误
-        };
+    /// 获取所有缓存的密码，关闭缓存时为空
+    pub fn get_all_passwords(&self) -> &HashMap<String, String> {
+        &self.password_cache
+    }
 
-        let mut stmt = conn
-            .prepare("SELECT host, password FROM passwords")
-            .map_err(SshConnError::Database)?;
+    /// 用口令加密导出全部已存储的密码
+    ///
+    /// 格式为 标记 + 16字节盐 + 24字节随机数 + 密文，密文是 `{主机: 密码}` 的JSON，
+    /// 密钥由Argon2id从口令派生，用XChaCha20-Poly1305加密；关闭缓存时逐个从数据库读取
+    pub fn export_encrypted(&self, passphrase: &str) -> Result<Vec<u8>> {
+        use chacha20poly1305::XChaCha20Poly1305;
+        use chacha20poly1305::aead::rand_core::RngCore;
+        use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
 
-        let rows = stmt
-            .query_map([], |row| {
-                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        let entries: Vec<(String, Zeroizing<String>)> = self
+            .list_hosts()
+            .into_iter()
+            .filter_map(|host| {
+                let password = self.get_password(&host)?;
+                Some((host, Zeroizing::new(password)))
             })
+            .collect();
+        let passwords: BTreeMap<&str, &str> = entries
+            .iter()
+            .map(|(host, password)| (host.as_str(), password.as_str()))
+            .collect();
+        let plaintext = Zeroizing::new(
+            serde_json::to_vec(&passwords)
+                .map_err(|e| SshConnError::PasswordError(e.to_string()))?,
+        );
+
+        let mut salt = [0u8; EXPORT_SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let key = derive_export_key(passphrase, &salt)?;
+        let cipher = XChaCha20Poly1305::new(key.as_ref().into());
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext.as_slice())
+            .map_err(|e| SshConnError::PasswordError(e.to_string()))?;
+
+        let mut data = EXPORT_MAGIC.to_vec();
+        data.extend_from_slice(&salt);
+        data.extend_from_slice(&nonce);
+        data.extend_from_slice(&ciphertext);
+        Ok(data)
+    }
+
+    /// 列出存储了非空密码的主机名（按名称排序）
+    pub fn list_hosts(&self) -> Vec<String> {
+        let mut hosts: Vec<String> = self.stored_hosts.iter().cloned().collect();
+        hosts.sort();
+        hosts
+    }
+
+    /// 保存私钥的口令，`identity_file` 中开头的 `~` 会被展开
+    pub fn save_key_passphrase(&mut self, identity_file: &str, passphrase: &str) -> Result<()> {
+        let key_path = key_passphrase_id(identity_file);
+        self.with_db(|conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO key_passphrases (key_path, passphrase) VALUES (?1, ?2)",
+                params![key_path, passphrase],
+            )
             .map_err(SshConnError::Database)?;
+            Ok(())
+        })
+    }
+
+    /// 获取私钥的口令，口令不在内存中缓存，每次从数据库读取
+    ///
+    /// 调用方应在用完后清零返回的口令（如包装为 `zeroize::Zeroizing`）
+    pub fn get_key_passphrase(&self, identity_file: &str) -> Option<String> {
+        let key_path = key_passphrase_id(identity_file);
+        let result = self.with_db(|conn| {
+            let mut stmt = conn
+                .prepare("SELECT passphrase FROM key_passphrases WHERE key_path = ?1")
+                .map_err(SshConnError::Database)?;
+            let mut rows = stmt
+                .query(params![key_path])
+                .map_err(SshConnError::Database)?;
+            match rows.next().map_err(SshConnError::Database)? {
+                Some(row) => Ok(row.get::<_, String>(0).ok()),
+                None => Ok(None),
+            }
+        });
+
+        result.unwrap_or_else(|e| {
+            log::warn!("Failed to read passphrase for {}: {}", key_path, e);
+            None
+        })
+    }
+
+    /// 删除私钥的口令，返回是否存在过
+    pub fn delete_key_passphrase(&mut self, identity_file: &str) -> Result<bool> {
+        let key_path = key_passphrase_id(identity_file);
+        self.with_db(|conn| {
+            let deleted = conn
+                .execute(
+                    "DELETE FROM key_passphrases WHERE key_path = ?1",
+                    params![key_path],
+                )
+                .map_err(SshConnError::Database)?;
+            Ok(deleted > 0)
+        })
+    }
+
+    /// 列出存储了口令的私钥路径（按路径排序）
+    pub fn list_key_passphrases(&self) -> Vec<String> {
+        let result = self.with_db(|conn| {
+            let mut stmt = conn
+                .prepare("SELECT key_path FROM key_passphrases ORDER BY key_path")
+                .map_err(SshConnError::Database)?;
+            let rows = stmt
+                .query_map([], |row| row.get::<_, String>(0))
+                .map_err(SshConnError::Database)?;
+            Ok(rows.flatten().collect::<Vec<_>>())
+        });
+
+        result.unwrap_or_else(|e| {
+            log::warn!("Failed to list key passphrases: {}", e);
+            Vec::new()
+        })
+    }
+}
+
+impl Drop for PasswordManager {
+    fn drop(&mut self) {
+        self.clear_cache();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    fn busy_error() -> SshConnError {
+        SshConnError::Database(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_BUSY),
+            Some("database is locked".to_string()),
+        ))
+    }
+
+    #[test]
+    fn test_run_password_command() {
+        let password = run_password_command("printf 'hunter2\\nsecond line\\n'").unwrap();
+        assert_eq!(password.as_str(), "hunter2");
+
+        let err = run_password_command("exit 3").unwrap_err().to_string();
+        assert!(err.contains("exit 3"), "{err}");
+        let err = run_password_command("true").unwrap_err().to_string();
+        assert!(err.contains(&t("error.password_command_empty")), "{err}");
+    }
 
-        for (host, password) in rows.flatten() {
-            self.password_cache.insert(host, password);
+    #[test]
+    fn test_retry_on_busy_until_success() {
+        let attempts = Cell::new(0);
+        let result = retry_on_busy(&[Duration::ZERO; 3], || {
+            attempts.set(attempts.get() + 1);
+            if attempts.get() < 3 {
+                Err(busy_error())
+            } else {
+                Ok("saved")
+            }
+        });
+        assert_eq!(result.unwrap(), "saved");
+        assert_eq!(attempts.get(), 3);
+    }
+
+    #[test]
+    fn test_retry_on_busy_gives_up_with_clear_error() {
+        let attempts = Cell::new(0);
+        let result: Result<()> = retry_on_busy(&[Duration::ZERO; 2], || {
+            attempts.set(attempts.get() + 1);
+            Err(busy_error())
+        });
+        assert_eq!(attempts.get(), 3);
+        match result {
+            Err(SshConnError::PasswordError(msg)) => {
+                assert!(msg.contains(&t("error.password_db_busy")))
+            }
+            other => panic!("unexpected result: {:?}", other.err()),
         }
 
-        Ok(())
+        // 其他错误不重试
+        let attempts = Cell::new(0);
+        let result: Result<()> = retry_on_busy(&[Duration::ZERO; 2], || {
+            attempts.set(attempts.get() + 1);
+            Err(SshConnError::ConfigParse("broken".to_string()))
+        });
+        assert!(matches!(result, Err(SshConnError::ConfigParse(_))));
+        assert_eq!(attempts.get(), 1);
     }
 
-    /// 获取所有密码
-    pub fn get_all_passwords(&self) -> &HashMap<String, String> {
-        &self.password_cache
+    #[test]
+    fn test_encrypted_export_round_trip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let settings = Settings {
+            password_db_path: Some(dir.path().join("passwords.db")),
+            cache_passwords: false,
+            ..Settings::default()
+        };
+        let mut manager = PasswordManager::new(&settings).unwrap();
+        manager.save_password("web", "secret").unwrap();
+        manager.save_password("db", "hunter2").unwrap();
+        manager.save_password("empty", "").unwrap();
+
+        let data = manager.export_encrypted("correct horse").unwrap();
+        assert!(data.starts_with(EXPORT_MAGIC));
+        assert!(!String::from_utf8_lossy(&data).contains("hunter2"));
+
+        let passwords = decrypt_password_export(&data, "correct horse").unwrap();
+        assert_eq!(
+            passwords.into_iter().collect::<Vec<_>>(),
+            [
+                ("db".to_string(), "hunter2".to_string()),
+                ("web".to_string(), "secret".to_string()),
+            ]
+        );
+
+        // 口令错误、数据被改动或格式不对时都拒绝
+        assert!(decrypt_password_export(&data, "wrong").is_err());
+        let mut tampered = data.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(decrypt_password_export(&tampered, "correct horse").is_err());
+        assert!(decrypt_password_export(b"not an export", "correct horse").is_err());
+    }
+
+    #[test]
+    fn test_cache_disabled_keeps_cache_empty() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let settings = Settings {
+            password_db_path: Some(dir.path().join("passwords.db")),
+            ..Settings::default()
+        };
+        let mut cached = PasswordManager::new(&settings).unwrap();
+        cached.save_password("web", "secret").unwrap();
+        cached.save_password("empty", "").unwrap();
+        assert_eq!(cached.get_all_passwords().len(), 2);
+
+        let settings = Settings {
+            cache_passwords: false,
+            ..settings
+        };
+        let mut manager = PasswordManager::new(&settings).unwrap();
+        assert!(manager.get_all_passwords().is_empty());
+        // 只记录主机名，仍可判断是否存储了密码
+        assert!(manager.has_password("web"));
+        assert!(!manager.has_password("empty"));
+        assert_eq!(manager.list_hosts(), ["web"]);
+
+        assert_eq!(manager.get_password("web").as_deref(), Some("secret"));
+        manager.save_password("db", "hunter2").unwrap();
+        assert!(manager.get_all_passwords().is_empty());
+        assert_eq!(manager.get_password("db").as_deref(), Some("hunter2"));
+        assert_eq!(manager.list_hosts(), ["db", "web"]);
+
+        manager.delete_password("web").unwrap();
+        assert!(!manager.has_password("web"));
+        assert_eq!(manager.get_password("web"), None);
+    }
+
+    #[test]
+    fn test_key_passphrases_are_separate_from_host_passwords() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut manager = PasswordManager::with_path(dir.path().join("passwords.db")).unwrap();
+        manager.save_password("web", "host-secret").unwrap();
+        manager
+            .save_key_passphrase("/keys/id_ed25519", "key-secret")
+            .unwrap();
+        manager
+            .save_key_passphrase("/keys/id_rsa", "other")
+            .unwrap();
+
+        assert_eq!(
+            manager.get_key_passphrase("/keys/id_ed25519").as_deref(),
+            Some("key-secret")
+        );
+        assert_eq!(manager.get_key_passphrase("/keys/missing"), None);
+        assert_eq!(
+            manager.list_key_passphrases(),
+            ["/keys/id_ed25519", "/keys/id_rsa"]
+        );
+        // 主机密码与私钥口令互不可见
+        assert_eq!(manager.list_hosts(), ["web"]);
+        assert_eq!(manager.get_password("/keys/id_ed25519"), None);
+        assert_eq!(manager.get_key_passphrase("web"), None);
+
+        // 删除主机密码不影响私钥口令，重新打开数据库后仍在
+        manager.delete_password("web").unwrap();
+        let mut reopened = PasswordManager::with_path(dir.path().join("passwords.db")).unwrap();
+        assert_eq!(
+            reopened.get_key_passphrase("/keys/id_ed25519").as_deref(),
+            Some("key-secret")
+        );
+
+        assert!(reopened.delete_key_passphrase("/keys/id_rsa").unwrap());
+        assert!(!reopened.delete_key_passphrase("/keys/id_rsa").unwrap());
+        assert_eq!(reopened.list_key_passphrases(), ["/keys/id_ed25519"]);
+    }
+
+    #[test]
+    fn test_key_passphrase_is_keyed_by_expanded_path() {
+        let mut manager = PasswordManager::with_path(":memory:").unwrap();
+        manager
+            .save_key_passphrase("~/.ssh/id_ed25519", "secret")
+            .unwrap();
+        let expanded = dirs::home_dir().unwrap_or_default().join(".ssh/id_ed25519");
+        assert_eq!(
+            manager
+                .get_key_passphrase(&expanded.to_string_lossy())
+                .as_deref(),
+            Some("secret")
+        );
+        assert_eq!(
+            manager.list_key_passphrases(),
+            [expanded.to_string_lossy().into_owned()]
+        );
+    }
+
+    #[test]
+    fn test_clones_share_one_connection() {
+        // 每个新打开的内存数据库都是空的，能读回数据说明始终使用同一连接
+        let settings = Settings {
+            password_db_path: Some(std::path::PathBuf::from(":memory:")),
+            ..Settings::default()
+        };
+        let mut manager = PasswordManager::new(&settings).unwrap();
+        manager.save_password("web", "one").unwrap();
+        manager.save_password("db", "two").unwrap();
+        manager.delete_password("db").unwrap();
+
+        let mut clone = manager.clone();
+        assert!(Arc::ptr_eq(&manager.conn, &clone.conn));
+        clone.save_password("cache", "three").unwrap();
+
+        manager.load_all_passwords().unwrap();
+        assert_eq!(manager.list_hosts(), ["cache", "web"]);
+
+        // 绕过缓存直接查询数据库
+        manager.password_cache.clear();
+        assert_eq!(manager.get_password("cache").as_deref(), Some("three"));
+        assert_eq!(manager.get_password("db"), None);
+    }
+
+    #[test]
+    fn test_save_password_waits_for_lock() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let settings = Settings {
+            password_db_path: Some(dir.path().join("passwords.db")),
+            ..Settings::default()
+        };
+        let mut manager = PasswordManager::new(&settings).unwrap();
+
+        // 另一个连接持有写锁，稍后释放
+        let other = Connection::open(dir.path().join("passwords.db")).unwrap();
+        other.execute_batch("BEGIN EXCLUSIVE").unwrap();
+        let holder = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            other.execute_batch("COMMIT").unwrap();
+        });
+
+        manager.save_password("web", "secret").unwrap();
+        holder.join().unwrap();
+
+        let reloaded = PasswordManager::new(&settings).unwrap();
+        assert_eq!(reloaded.get_password("web").as_deref(), Some("secret"));
     }
 }
@@ -0,0 +1,199 @@
+//! `~/.ssh` 及其中文件的权限
+//!
+//! OpenSSH 拒绝使用权限过宽的配置文件和私钥。启动时以及 `doctor`/`validate` 中检查
+//! `~/.ssh` 是否为700、SSH配置和密码数据库是否为600（或更严格），`doctor --fix` 确认后修正。
+//! 本程序创建的目录和文件（配置、密码数据库、备份）在Unix上一开始就使用这些权限。
+//! 其他平台没有Unix权限位，检查总是通过。
+
+use std::fs::OpenOptions;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::i18n::t;
+
+/// 目录的权限：只有所有者可以访问
+pub const PRIVATE_DIR_MODE: u32 = 0o700;
+/// 文件的权限：只有所有者可以读写
+pub const PRIVATE_FILE_MODE: u32 = 0o600;
+
+/// 权限过宽的文件或目录
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PermissionIssue {
+    pub path: PathBuf,
+    /// 当前的权限位
+    pub mode: u32,
+    /// 允许的最宽权限
+    pub expected: u32,
+}
+
+impl PermissionIssue {
+    /// 本地化的警告，如 `/home/me/.ssh/config 的权限为 644，应为 600 或更严格`
+    pub fn message(&self) -> String {
+        t("permissions_too_open")
+            .replacen("{}", &self.path.display().to_string(), 1)
+            .replacen("{}", &format!("{:o}", self.mode), 1)
+            .replacen("{}", &format!("{:o}", self.expected), 1)
+    }
+
+    /// 去掉允许范围之外的权限位
+    pub fn fix(&self) -> io::Result<()> {
+        set_mode(&self.path, self.mode & self.expected)
+    }
+}
+
+/// 检查 `path` 的权限是否在 `expected` 之内；不存在或不是Unix时返回None
+pub fn check_mode(path: &Path, expected: u32) -> Option<PermissionIssue> {
+    let mode = mode_of(path)?;
+    (mode & !expected != 0).then(|| PermissionIssue {
+        path: path.to_path_buf(),
+        mode,
+        expected,
+    })
+}
+
+/// 检查SSH目录（700）、SSH配置文件和密码数据库（600），不存在的跳过
+pub fn check_ssh_permissions(
+    ssh_dir: Option<&Path>,
+    config_path: &Path,
+    password_db_path: &Path,
+) -> Vec<PermissionIssue> {
+    let dir = ssh_dir.and_then(|dir| check_mode(dir, PRIVATE_DIR_MODE));
+    let files = [config_path, password_db_path]
+        .into_iter()
+        .filter_map(|path| check_mode(path, PRIVATE_FILE_MODE));
+    dir.into_iter().chain(files).collect()
+}
+
+/// 写入时以600创建文件的 [`OpenOptions`]（已存在的文件保持原有权限）
+pub fn private_open_options() -> OpenOptions {
+    #[allow(unused_mut)]
+    let mut options = OpenOptions::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(PRIVATE_FILE_MODE);
+    }
+    options
+}
+
+/// 与 [`std::fs::write`] 相同，但新建的文件为600
+pub fn write_private(path: &Path, content: impl AsRef<[u8]>) -> io::Result<()> {
+    use std::io::Write;
+
+    private_open_options()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)?
+        .write_all(content.as_ref())
+}
+
+/// 与 [`std::fs::create_dir_all`] 相同，但新建的目录为700
+pub fn create_private_dir_all(path: &Path) -> io::Result<()> {
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(PRIVATE_DIR_MODE);
+    }
+    builder.create(path)
+}
+
+#[cfg(unix)]
+fn mode_of(path: &Path) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path)
+        .ok()
+        .map(|metadata| metadata.permissions().mode() & 0o777)
+}
+
+#[cfg(not(unix))]
+fn mode_of(_path: &Path) -> Option<u32> {
+    None
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: u32) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::TempDir;
+
+    fn mode(path: &Path) -> u32 {
+        std::fs::metadata(path).unwrap().permissions().mode() & 0o777
+    }
+
+    #[test]
+    fn test_created_files_and_dirs_are_private() {
+        let dir = TempDir::new().unwrap();
+        let nested = dir.path().join("a").join(".ssh");
+        create_private_dir_all(&nested).unwrap();
+        assert_eq!(mode(&nested), 0o700);
+
+        let file = nested.join("config");
+        write_private(&file, "Host web\n").unwrap();
+        assert_eq!(mode(&file), 0o600);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "Host web\n");
+
+        // 已存在的文件保持原有权限
+        set_mode(&file, 0o640).unwrap();
+        write_private(&file, "Host db\n").unwrap();
+        assert_eq!(mode(&file), 0o640);
+    }
+
+    #[test]
+    fn test_check_and_fix_permissions() {
+        let dir = TempDir::new().unwrap();
+        let ssh_dir = dir.path().join(".ssh");
+        std::fs::create_dir(&ssh_dir).unwrap();
+        set_mode(&ssh_dir, 0o755).unwrap();
+        let config = ssh_dir.join("config");
+        std::fs::write(&config, "").unwrap();
+        set_mode(&config, 0o644).unwrap();
+        let db = ssh_dir.join("ssh_conn_passwords.db");
+        std::fs::write(&db, "").unwrap();
+        set_mode(&db, 0o400).unwrap();
+
+        let issues = check_ssh_permissions(Some(&ssh_dir), &config, &db);
+        assert_eq!(
+            issues,
+            vec![
+                PermissionIssue {
+                    path: ssh_dir.clone(),
+                    mode: 0o755,
+                    expected: 0o700,
+                },
+                PermissionIssue {
+                    path: config.clone(),
+                    mode: 0o644,
+                    expected: 0o600,
+                },
+            ]
+        );
+        assert!(issues[1].message().contains("644"));
+
+        for issue in &issues {
+            issue.fix().unwrap();
+        }
+        assert_eq!(mode(&ssh_dir), 0o700);
+        assert_eq!(mode(&config), 0o600);
+        // 比要求更严格的权限保持不变
+        assert_eq!(mode(&db), 0o400);
+        assert!(check_ssh_permissions(Some(&ssh_dir), &config, &db).is_empty());
+
+        // 不存在的路径跳过
+        let missing = dir.path().join("missing");
+        assert!(check_ssh_permissions(Some(&missing), &missing, &missing).is_empty());
+    }
+}
@@ -0,0 +1,519 @@
+//! 命令行交互输入模块
+//!
+//! 不启动TUI，逐项提示输入主机信息，适合网络较差、TUI刷新缓慢的场景。
+//! 输入通过 [`Prompter`] 读取，测试中可换成预设答案
+
+use std::io::{BufRead, IsTerminal, Write};
+
+use crate::error::{Result, SshConnError};
+use crate::i18n::t;
+use crate::models::EditableField;
+use crate::models::SshHost;
+use crate::utils::{
+    format_env_var, parse_set_env, validate_address_family, validate_bind_address, validate_host,
+    validate_hostname, validate_port, validate_request_tty, validate_username,
+};
+
+/// 交互输入接口
+pub trait Prompter {
+    /// 显示提示并读取一行输入，输入结束（Ctrl-D）时返回None
+    fn read_line(&mut self, prompt: &str) -> Result<Option<String>>;
+
+    /// 读取不回显的输入（如密码），Ctrl-C/Ctrl-D/Esc 时返回None
+    fn read_hidden(&mut self, prompt: &str) -> Result<Option<String>>;
+
+    /// 显示一行信息
+    fn message(&mut self, text: &str);
+}
+
+/// 从终端读取输入
+#[derive(Debug, Default)]
+pub struct TerminalPrompter;
+
+impl Prompter for TerminalPrompter {
+    fn read_line(&mut self, prompt: &str) -> Result<Option<String>> {
+        print!("{}", prompt);
+        std::io::stdout().flush()?;
+
+        let mut line = String::new();
+        if std::io::stdin().lock().read_line(&mut line)? == 0 {
+            println!();
+            return Ok(None);
+        }
+        Ok(Some(line.trim_end_matches(['\r', '\n']).to_string()))
+    }
+
+    fn read_hidden(&mut self, prompt: &str) -> Result<Option<String>> {
+        use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+        use crossterm::terminal;
+
+        // 输入来自管道时无法关闭回显，按普通输入读取
+        if !std::io::stdin().is_terminal() {
+            return self.read_line(prompt);
+        }
+
+        print!("{}", prompt);
+        std::io::stdout().flush()?;
+
+        // 原始模式下Ctrl-C不会产生SIGINT，由下面的按键处理取消
+        terminal::enable_raw_mode()?;
+        let mut value = String::new();
+        let result = loop {
+            let key = match event::read() {
+                Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => key,
+                Ok(_) => continue,
+                Err(e) => break Err(e.into()),
+            };
+            let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+            match key.code {
+                KeyCode::Enter => break Ok(Some(std::mem::take(&mut value))),
+                KeyCode::Esc => break Ok(None),
+                KeyCode::Char('c' | 'd') if ctrl => break Ok(None),
+                KeyCode::Backspace => {
+                    value.pop();
+                }
+                KeyCode::Char(c) if !ctrl => value.push(c),
+                _ => {}
+            }
+        };
+        terminal::disable_raw_mode()?;
+        println!();
+        result
+    }
+
+    fn message(&mut self, text: &str) {
+        println!("{}", text);
+    }
+}
+
+/// 向导的结果：主机配置和可选的密码
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostAnswers {
+    pub host: SshHost,
+    pub password: Option<String>,
+}
+
+/// 逐项询问 Host、HostName、User、Port、IdentityFile 和密码
+///
+/// `defaults` 中已有的值作为默认值显示在方括号中，直接回车即采用；
+/// 未询问的字段（如RemoteCommand）原样保留。每项输入立即校验，出错时重新询问。
+/// 最后显示摘要并确认，输入结束、取消或不确认时返回None，调用方不应写入任何内容
+pub fn host_wizard(
+    prompter: &mut dyn Prompter,
+    defaults: &SshHost,
+    is_taken: impl Fn(&str) -> bool,
+) -> Result<Option<HostAnswers>> {
+    let mut host = defaults.clone();
+
+    let Some(name) = ask(prompter, &t("form.host"), Some(&host.host), true, |value| {
+        validate_host(value)?;
+        if is_taken(value) {
+            return Err(SshConnError::HostAlreadyExists {
+                host: value.to_string(),
+            });
+        }
+        Ok(())
+    })?
+    else {
+        return Ok(None);
+    };
+    host.host = name;
+
+    let answered = ask_into(
+        prompter,
+        &t("form.hostname"),
+        &mut host.hostname,
+        true,
+        validate_hostname,
+    )? && ask_into(
+        prompter,
+        &t("form.user"),
+        &mut host.user,
+        false,
+        validate_username,
+    )? && ask_into(prompter, &t("form.port"), &mut host.port, false, |value| {
+        validate_port(value).map(|_| ())
+    })? && ask_into(
+        prompter,
+        &t("form.identity_file"),
+        &mut host.identity_file,
+        false,
+        |_| Ok(()),
+    )?;
+    if !answered {
+        return Ok(None);
+    }
+
+    let Some(password) = prompter.read_hidden(&format!("{}: ", t("form.password")))? else {
+        return Ok(None);
+    };
+    let password = (!password.is_empty()).then_some(password);
+
+    prompter.message(&format!("\n{}:", t("wizard_summary")));
+    for line in summary_lines(&host, password.is_some()) {
+        prompter.message(&format!("  {}", line));
+    }
+
+    if confirm(prompter)? {
+        Ok(Some(HostAnswers { host, password }))
+    } else {
+        Ok(None)
+    }
+}
+
+/// 逐项修改已有主机，当前值作为默认值，直接回车保留，输入 `-` 清除
+///
+/// 除添加向导中的字段外，还会询问ConnectTimeout、ServerAliveInterval、RemoteCommand、
+/// RequestTTY、AddressFamily、BindAddress、SetEnv 以及每个自定义选项。有修改时显示差异摘要并确认；
+/// 返回的主机与当前相同且没有新密码时表示没有修改
+pub fn edit_wizard(prompter: &mut dyn Prompter, current: &SshHost) -> Result<Option<HostAnswers>> {
+    let mut host = current.clone();
+    prompter.message(&t("wizard_edit_hint"));
+
+    let answered = ask_into(
+        prompter,
+        "HostName",
+        &mut host.hostname,
+        true,
+        validate_hostname,
+    )? && ask_into(prompter, "User", &mut host.user, false, validate_username)?
+        && ask_into(prompter, "Port", &mut host.port, false, |value| {
+            validate_port(value).map(|_| ())
+        })?
+        && ask_into(
+            prompter,
+            "ProxyCommand",
+            &mut host.proxy_command,
+            false,
+            |_| Ok(()),
+        )?
+        && ask_into(
+            prompter,
+            "IdentityFile",
+            &mut host.identity_file,
+            false,
+            |_| Ok(()),
+        )?
+        && ask_into(
+            prompter,
+            "ConnectTimeout",
+            &mut host.connect_timeout,
+            false,
+            |value| EditableField::ConnectTimeout.validate(value).map(|_| ()),
+        )?
+        && ask_into(
+            prompter,
+            "ServerAliveInterval",
+            &mut host.server_alive_interval,
+            false,
+            |value| match value.parse::<u32>() {
+                Ok(_) => Ok(()),
+                Err(_) => Err(SshConnError::validation(
+                    "server_alive_interval",
+                    t("error.server_alive_interval_invalid").replace("{}", value),
+                )),
+            },
+        )?
+        && ask_into(
+            prompter,
+            "RemoteCommand",
+            &mut host.remote_command,
+            false,
+            |_| Ok(()),
+        )?
+        && ask_into(
+            prompter,
+            "RequestTTY",
+            &mut host.request_tty,
+            false,
+            |value| validate_request_tty(value).map(|_| ()),
+        )?
+        && ask_into(
+            prompter,
+            "AddressFamily",
+            &mut host.address_family,
+            false,
+            |value| validate_address_family(value).map(|_| ()),
+        )?
+        && ask_into(
+            prompter,
+            "BindAddress",
+            &mut host.bind_address,
+            false,
+            validate_bind_address,
+        )?;
+    if !answered {
+        return Ok(None);
+    }
+    host.request_tty = host
+        .request_tty
+        .as_deref()
+        .map(validate_request_tty)
+        .transpose()?;
+    host.address_family = host
+        .address_family
+        .as_deref()
+        .map(validate_address_family)
+        .transpose()?;
+
+    let set_env = host
+        .set_env
+        .iter()
+        .map(|(key, value)| format_env_var(key, value))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let Some(set_env) = ask(prompter, "SetEnv", Some(&set_env), false, |value| {
+        parse_set_env(value).map(|_| ())
+    })?
+    else {
+        return Ok(None);
+    };
+    host.set_env = parse_set_env(&set_env)?;
+
+    // 自定义选项逐个询问，按关键字排序；块内的注释也解析在其中，不询问
+    let mut custom_options: Vec<(String, String)> = current
+        .custom_options
+        .iter()
+        .filter(|(k, _)| !k.starts_with('#'))
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    custom_options.sort();
+    for (keyword, value) in custom_options {
+        let Some(answer) = ask(prompter, &keyword, Some(&value), false, |_| Ok(()))? else {
+            return Ok(None);
+        };
+        if answer.is_empty() {
+            host.custom_options.remove(&keyword);
+        } else {
+            host.custom_options.insert(keyword, answer);
+        }
+    }
+
+    let Some(password) = prompter.read_hidden(&format!("{}: ", t("form.password")))? else {
+        return Ok(None);
+    };
+    let password = (!password.is_empty()).then_some(password);
+
+    let mut diff = current.diff_lines(&host);
+    if password.is_some() {
+        diff.push(format!("+ {}: ********", t("field_password")));
+    }
+    if diff.is_empty() {
+        return Ok(Some(HostAnswers { host, password }));
+    }
+
+    prompter.message(&format!("\n{} {}:", t("wizard_summary"), host.host));
+    for line in diff {
+        prompter.message(&format!("  {}", line));
+    }
+    if confirm(prompter)? {
+        Ok(Some(HostAnswers { host, password }))
+    } else {
+        Ok(None)
+    }
+}
+
+/// 询问是否保存，直接回车视为确认
+fn confirm(prompter: &mut dyn Prompter) -> Result<bool> {
+    let Some(answer) = prompter.read_line(&format!("{} [Y/n] ", t("wizard_confirm")))? else {
+        return Ok(false);
+    };
+    Ok(matches!(
+        answer.trim().to_lowercase().as_str(),
+        "" | "y" | "yes"
+    ))
+}
+
+/// 询问一个字段并写回 `field`，可选字段留空时清除；返回false表示取消
+fn ask_into(
+    prompter: &mut dyn Prompter,
+    label: &str,
+    field: &mut Option<String>,
+    required: bool,
+    validate: impl Fn(&str) -> Result<()>,
+) -> Result<bool> {
+    match ask(prompter, label, field.as_deref(), required, validate)? {
+        Some(value) => {
+            *field = (!value.is_empty()).then_some(value);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// 询问一项输入，返回None表示取消
+///
+/// 直接回车采用默认值，输入 `-` 表示清除；可选字段留空或清除时返回空字符串
+fn ask(
+    prompter: &mut dyn Prompter,
+    label: &str,
+    default: Option<&str>,
+    required: bool,
+    validate: impl Fn(&str) -> Result<()>,
+) -> Result<Option<String>> {
+    let default = default.filter(|value| !value.is_empty());
+    let prompt = match default {
+        Some(value) => format!("{} [{}]: ", label, value),
+        None => format!("{}: ", label),
+    };
+
+    loop {
+        let Some(input) = prompter.read_line(&prompt)? else {
+            return Ok(None);
+        };
+        let value = match input.trim() {
+            "" => default.unwrap_or_default(),
+            "-" => "",
+            value => value,
+        };
+
+        if value.is_empty() {
+            if !required {
+                return Ok(Some(String::new()));
+            }
+            prompter.message(&format!("✗ {}", t("wizard_required")));
+            continue;
+        }
+
+        match validate(value) {
+            Ok(()) => return Ok(Some(value.to_string())),
+            Err(e) => prompter.message(&format!("✗ {}", e)),
+        }
+    }
+}
+
+/// 摘要中显示的字段，密码只显示是否设置
+fn summary_lines(host: &SshHost, has_password: bool) -> Vec<String> {
+    let mut lines = vec![format!("Host: {}", host.host)];
+    let fields = [
+        ("HostName", &host.hostname),
+        ("User", &host.user),
+        ("Port", &host.port),
+        ("ProxyCommand", &host.proxy_command),
+        ("IdentityFile", &host.identity_file),
+        ("RemoteCommand", &host.remote_command),
+        ("RequestTTY", &host.request_tty),
+        ("AddressFamily", &host.address_family),
+        ("BindAddress", &host.bind_address),
+        ("PermitLocalCommand", &host.permit_local_command),
+        ("LocalCommand", &host.local_command),
+    ];
+    for (keyword, value) in fields {
+        if let Some(value) = value {
+            lines.push(format!("{}: {}", keyword, value));
+        }
+    }
+    for (key, value) in &host.set_env {
+        lines.push(format!("SetEnv: {}={}", key, value));
+    }
+    if has_password {
+        lines.push(format!("{}: ********", t("field_password")));
+    }
+    lines
+}
+
+/// 按顺序返回预设答案的输入，记录显示过的提示和信息
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub(crate) struct ScriptedPrompter {
+    answers: std::collections::VecDeque<String>,
+    pub output: Vec<String>,
+}
+
+#[cfg(test)]
+impl ScriptedPrompter {
+    /// 答案用尽时相当于输入结束（Ctrl-D）
+    pub fn new(answers: &[&str]) -> Self {
+        Self {
+            answers: answers.iter().map(|a| a.to_string()).collect(),
+            output: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl Prompter for ScriptedPrompter {
+    fn read_line(&mut self, prompt: &str) -> Result<Option<String>> {
+        self.output.push(prompt.to_string());
+        Ok(self.answers.pop_front())
+    }
+
+    fn read_hidden(&mut self, prompt: &str) -> Result<Option<String>> {
+        self.read_line(prompt)
+    }
+
+    fn message(&mut self, text: &str) {
+        self.output.push(text.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_wizard_reprompts_on_invalid_input() {
+        let mut prompter = ScriptedPrompter::new(&[
+            "web",         // 已存在
+            "web2",        // Host
+            "",            // HostName 必填
+            "bad host",    // 含空格
+            "10.0.0.5",    // HostName
+            "",            // User 采用默认值
+            "70000",       // 端口超出范围
+            "2222",        // Port
+            "~/.ssh/web2", // IdentityFile
+            "secret",      // 密码
+            "",            // 确认
+        ]);
+        let mut defaults = SshHost::new(String::new());
+        defaults.user = Some("deploy".to_string());
+        defaults.remote_command = Some("tmux attach".to_string());
+
+        let answers = host_wizard(&mut prompter, &defaults, |name| name == "web")
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(answers.host.host, "web2");
+        assert_eq!(answers.host.hostname.as_deref(), Some("10.0.0.5"));
+        assert_eq!(answers.host.user.as_deref(), Some("deploy"));
+        assert_eq!(answers.host.port.as_deref(), Some("2222"));
+        assert_eq!(answers.host.identity_file.as_deref(), Some("~/.ssh/web2"));
+        // 未询问的字段保留
+        assert_eq!(answers.host.remote_command.as_deref(), Some("tmux attach"));
+        assert_eq!(answers.password.as_deref(), Some("secret"));
+
+        let errors = prompter
+            .output
+            .iter()
+            .filter(|line| line.starts_with('✗'))
+            .count();
+        assert_eq!(errors, 4);
+        assert!(
+            prompter
+                .output
+                .contains(&format!("{} [deploy]: ", t("form.user")))
+        );
+        // 摘要中不显示密码
+        assert!(!prompter.output.iter().any(|line| line.contains("secret")));
+    }
+
+    #[test]
+    fn test_host_wizard_cancel_returns_none() {
+        let defaults = SshHost::new(String::new());
+
+        // 输入中途结束（Ctrl-D）
+        let mut prompter = ScriptedPrompter::new(&["web", "10.0.0.5"]);
+        assert_eq!(
+            host_wizard(&mut prompter, &defaults, |_| false).unwrap(),
+            None
+        );
+
+        // 不确认
+        let mut prompter = ScriptedPrompter::new(&["web", "10.0.0.5", "", "", "", "", "n"]);
+        assert_eq!(
+            host_wizard(&mut prompter, &defaults, |_| false).unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,1000 @@
+//! 应用设置模块
+//!
+//! 从 `~/.config/ssh-conn/settings.toml` 加载应用设置（遵循 `XDG_CONFIG_HOME`，
+//! 可通过 `SSH_CONN_CONFIG_DIR` 覆盖目录）。文件或字段缺失时使用默认值，行为与未配置时一致。
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Result, SshConnError};
+use crate::i18n::Language;
+use crate::models::SshHost;
+
+/// 应用设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// 界面语言（zh/en），未设置时根据环境变量检测
+    pub language: Option<String>,
+    /// SSH配置文件路径，未设置时使用 ~/.ssh/config
+    pub ssh_config_path: Option<PathBuf>,
+    /// 密码数据库路径，未设置时使用 ~/.ssh/ssh_conn_passwords.db
+    pub password_db_path: Option<PathBuf>,
+    /// 为true时调用ssh不带 `-F`，由ssh按默认规则查找配置文件
+    pub use_system_config: bool,
+    /// 启动时把全部密码载入内存缓存；为false时每次使用才从数据库读取，用后清零
+    pub cache_passwords: bool,
+    /// 每次运行中第一次修改SSH配置前自动备份，备份文件为 `<配置>.backup.<时间>.auto`
+    pub auto_backup: bool,
+    /// 保留的自动备份数量，超出时删除最旧的，0表示不删除
+    pub auto_backup_keep: usize,
+    /// `watch` 检测到主机状态切换时执行的命令，通过环境变量 `HOST`、`STATUS` 等获取变化
+    pub watch_hook: Option<String>,
+    /// 在新终端窗口中连接时使用的命令模板，如 `alacritty -e {cmd}`，未设置时自动检测
+    pub terminal_command: Option<String>,
+    /// 主机未设置ServerAliveInterval时连接使用的保活间隔（秒），0表示不添加
+    pub default_server_alive_interval: u64,
+    /// 连接前把主机的IdentityFile载入ssh-agent（不在agent中时运行 `ssh-add`）
+    pub add_keys_to_agent: bool,
+    /// 新增、编辑、删除主机时向审计日志追加一行记录
+    pub audit_log: bool,
+    /// 审计日志路径，未设置时为SSH配置所在目录下的 `ssh_conn_audit.log`
+    pub audit_log_path: Option<PathBuf>,
+    /// TUI界面设置
+    pub ui: UiSettings,
+    /// 各场景传给ssh的参数
+    pub ssh_options: SshOptionSettings,
+    /// 日志文件设置
+    pub log: LogSettings,
+    /// 新建主机时可选用的模板，如 `[templates.datacenter-a]`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub templates: BTreeMap<String, HostTemplate>,
+    /// 命名的配置档案：名称 → SSH配置文件路径，通过 `--profile` 或TUI切换
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, PathBuf>,
+    /// 当前使用的配置档案（运行时状态，不写入设置文件）
+    #[serde(skip)]
+    pub active_profile: Option<String>,
+}
+
+/// 主机模板：新建主机时补全未明确指定的字段
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HostTemplate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy_command: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identity_file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connect_timeout: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server_alive_interval: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote_command: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_tty: Option<String>,
+    /// SetEnv环境变量
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub set_env: BTreeMap<String, String>,
+    /// 其他SSH选项，如 `ProxyJump = "bastion"`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub options: BTreeMap<String, String>,
+}
+
+impl HostTemplate {
+    /// 用模板补全主机中未设置的字段，已有的值（命令行参数或表单中填写的）保持不变
+    ///
+    /// SetEnv按变量名合并，其他选项按关键字（不区分大小写）合并
+    pub fn apply(&self, host: &mut SshHost) {
+        fn fill(field: &mut Option<String>, value: Option<String>) {
+            if field.is_none() {
+                *field = value;
+            }
+        }
+
+        fill(&mut host.user, self.user.clone());
+        fill(&mut host.port, self.port.map(|p| p.to_string()));
+        fill(&mut host.proxy_command, self.proxy_command.clone());
+        fill(&mut host.identity_file, self.identity_file.clone());
+        fill(
+            &mut host.connect_timeout,
+            self.connect_timeout.map(|t| t.to_string()),
+        );
+        fill(
+            &mut host.server_alive_interval,
+            self.server_alive_interval.map(|i| i.to_string()),
+        );
+        fill(&mut host.remote_command, self.remote_command.clone());
+        fill(&mut host.request_tty, self.request_tty.clone());
+
+        for (key, value) in &self.set_env {
+            if !host.set_env.iter().any(|(k, _)| k == key) {
+                host.set_env.push((key.clone(), value.clone()));
+            }
+        }
+        for (key, value) in &self.options {
+            if host.get_custom_option(key).is_none() {
+                host.custom_options.insert(key.clone(), value.clone());
+            }
+        }
+    }
+
+    /// 校验模板字段，`name` 为模板名，用于错误信息中的键名
+    fn validate(&self, name: &str) -> Result<()> {
+        let invalid = |field: &str, message: String| SshConnError::InvalidSetting {
+            key: format!("templates.{}.{}", name, field),
+            message,
+        };
+
+        if self.port == Some(0) {
+            return Err(invalid(
+                "port",
+                "port must be between 1 and 65535".to_string(),
+            ));
+        }
+        if let Some(mode) = &self.request_tty {
+            crate::utils::validate_request_tty(mode)
+                .map_err(|_| invalid("request_tty", format!("unsupported value '{}'", mode)))?;
+        }
+        for key in self.set_env.keys() {
+            crate::utils::validate_env_var(&format!("{}=", key))
+                .map_err(|_| invalid("set_env", format!("invalid variable name '{}'", key)))?;
+        }
+        Ok(())
+    }
+}
+
+/// TUI界面设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UiSettings {
+    /// 启动时自动测试所有主机的连通性
+    pub auto_test_on_start: bool,
+    /// 主机列表显示的列及顺序，如 `["status", "host", "hostname"]`，未设置时使用默认列
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub columns: Option<Vec<String>>,
+    /// 紧凑模式：去掉选中行前的标记、收窄列宽，未设置 `columns` 时只显示主要的列
+    pub compact: bool,
+    /// 色盲友好的状态显示：用 ✓/✗/~/? 符号和蓝色/橙色代替绿色/红色圆点
+    pub color_blind: bool,
+    /// 连接测试时🟡（连接中）状态的最短显示时间（毫秒），0 表示不等待
+    pub min_connecting_ms: u64,
+    /// 测试后连接（`C`）时，延迟不超过该值（毫秒）则直接连接，否则显示结果并请求确认；未设置时总是确认
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_connect_max_latency_ms: Option<u64>,
+}
+
+/// 日志文件设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogSettings {
+    /// 把日志写入文件，也可以通过 `--log-file` 临时开启
+    pub enabled: bool,
+    /// 日志文件路径，未设置时为 `~/.local/share/ssh-conn/logs/ssh-conn.log`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    /// 单个日志文件的大小上限（MB），超出时轮转
+    pub max_size_mb: u64,
+    /// 保留的旧日志文件数量
+    pub keep: usize,
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: None,
+            max_size_mb: 5,
+            keep: 3,
+        }
+    }
+}
+
+impl LogSettings {
+    /// 单个日志文件的大小上限（字节）
+    pub fn max_bytes(&self) -> u64 {
+        self.max_size_mb.saturating_mul(1024 * 1024)
+    }
+}
+
+/// CLI连接的内置SSH参数
+const DEFAULT_CONNECT_OPTIONS: &[&str] = &[
+    "-o",
+    "StrictHostKeyChecking=accept-new",
+    "-o",
+    "LogLevel=ERROR",
+];
+
+/// TUI连接的内置SSH参数
+const DEFAULT_TUI_OPTIONS: &[&str] = &[
+    "-o",
+    "StrictHostKeyChecking=accept-new",
+    "-o",
+    "LogLevel=ERROR",
+    "-o",
+    "RequestTTY=force",
+    "-tt",
+];
+
+/// 连接测试的内置SSH参数
+const DEFAULT_TEST_OPTIONS: &[&str] =
+    &["-o", "ConnectTimeout=10", "-o", "StrictHostKeyChecking=yes"];
+
+/// 各场景的SSH参数设置
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SshOptionSettings {
+    /// `ssh-conn connect` 使用的参数
+    pub connect: SshOptionSet,
+    /// TUI中连接使用的参数
+    pub tui: SshOptionSet,
+    /// 连接测试使用的参数
+    pub test: SshOptionSet,
+}
+
+/// 单个场景的SSH参数
+///
+/// 每一项可以是 `-o` 与 `Key=Value` 分开的两项、合并的 `-o Key=Value`，
+/// 或不带值的单独标志（如 `-tt`、`-4`）。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SshOptionSet {
+    /// 替换内置参数，未设置时使用内置参数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<Vec<String>>,
+    /// 追加到参数末尾，同名的 `-o Key=...` 会覆盖前面的对应项
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub extra: Vec<String>,
+}
+
+impl SshOptionSettings {
+    /// CLI连接的最终参数
+    pub fn connect_args(&self) -> Vec<String> {
+        self.connect.resolve(DEFAULT_CONNECT_OPTIONS)
+    }
+
+    /// TUI连接的最终参数
+    pub fn tui_args(&self) -> Vec<String> {
+        self.tui.resolve(DEFAULT_TUI_OPTIONS)
+    }
+
+    /// 连接测试的最终参数
+    pub fn test_args(&self) -> Vec<String> {
+        self.test.resolve(DEFAULT_TEST_OPTIONS)
+    }
+
+    /// 校验所有场景的参数语法
+    fn validate(&self) -> Result<()> {
+        for (name, set) in [
+            ("connect", &self.connect),
+            ("tui", &self.tui),
+            ("test", &self.test),
+        ] {
+            if let Some(options) = &set.options {
+                normalize_ssh_options(options).map_err(|message| SshConnError::InvalidSetting {
+                    key: format!("ssh_options.{}.options", name),
+                    message,
+                })?;
+            }
+            normalize_ssh_options(&set.extra).map_err(|message| SshConnError::InvalidSetting {
+                key: format!("ssh_options.{}.extra", name),
+                message,
+            })?;
+        }
+        Ok(())
+    }
+}
+
+impl SshOptionSet {
+    /// 合并内置参数、替换参数和追加参数，得到传给ssh的argv
+    pub fn resolve(&self, builtin: &[&str]) -> Vec<String> {
+        let builtin: Vec<String> = builtin.iter().map(|o| o.to_string()).collect();
+        let base = match &self.options {
+            Some(options) => normalize_ssh_options(options).unwrap_or_else(|e| {
+                log::warn!("Ignoring invalid ssh options: {}", e);
+                builtin
+            }),
+            None => builtin,
+        };
+        let extra = normalize_ssh_options(&self.extra).unwrap_or_else(|e| {
+            log::warn!("Ignoring invalid ssh options: {}", e);
+            Vec::new()
+        });
+
+        // ssh对同一个 -o 选项只采用第一次出现的值，因此追加项需要先移除同名的基础项
+        let mut groups = group_ssh_options(&base);
+        for group in group_ssh_options(&extra) {
+            if let Some(key) = option_key(&group) {
+                groups.retain(|g| option_key(g).as_deref() != Some(key.as_str()));
+            }
+            groups.push(group);
+        }
+        groups.concat()
+    }
+}
+
+/// 将设置中的SSH参数规范化为argv，语法不合法时返回错误描述
+pub fn normalize_ssh_options(entries: &[String]) -> std::result::Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut iter = entries.iter().map(|e| e.trim());
+
+    while let Some(entry) = iter.next() {
+        let key_value = if entry == "-o" {
+            Some(
+                iter.next()
+                    .ok_or_else(|| "'-o' must be followed by Key=Value".to_string())?,
+            )
+        } else {
+            entry.strip_prefix("-o").map(str::trim)
+        };
+
+        match key_value {
+            Some(kv) => {
+                let valid = kv.split_once('=').is_some_and(|(key, value)| {
+                    !key.is_empty()
+                        && key.chars().all(|c| c.is_ascii_alphanumeric())
+                        && !value.trim().is_empty()
+                });
+                if !valid {
+                    return Err(format!("expected '-o Key=Value', got '-o {}'", kv));
+                }
+                args.push("-o".to_string());
+                args.push(kv.to_string());
+            }
+            None => {
+                let valid = entry
+                    .strip_prefix('-')
+                    .is_some_and(|f| !f.is_empty() && f.chars().all(|c| c.is_ascii_alphanumeric()));
+                if !valid {
+                    return Err(format!("invalid ssh option '{}'", entry));
+                }
+                args.push(entry.to_string());
+            }
+        }
+    }
+
+    Ok(args)
+}
+
+/// 将规范化后的argv按选项分组（`-o Key=Value` 为一组，单独标志为一组）
+fn group_ssh_options(args: &[String]) -> Vec<Vec<String>> {
+    let mut groups = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "-o"
+            && let Some(value) = iter.next()
+        {
+            groups.push(vec![arg.clone(), value.clone()]);
+        } else {
+            groups.push(vec![arg.clone()]);
+        }
+    }
+    groups
+}
+
+/// 参数中是否已有 `-o Key=...`（键名不区分大小写）
+pub fn has_ssh_option(args: &[String], key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    group_ssh_options(args)
+        .iter()
+        .any(|group| option_key(group).as_deref() == Some(key.as_str()))
+}
+
+/// 获取 `-o Key=Value` 分组的键名（小写），单独标志返回None
+fn option_key(group: &[String]) -> Option<String> {
+    match group {
+        [flag, value] if flag == "-o" => value
+            .split_once('=')
+            .map(|(key, _)| key.to_ascii_lowercase()),
+        _ => None,
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            language: None,
+            ssh_config_path: None,
+            password_db_path: None,
+            use_system_config: false,
+            cache_passwords: true,
+            auto_backup: true,
+            auto_backup_keep: 10,
+            watch_hook: None,
+            terminal_command: None,
+            default_server_alive_interval: 60,
+            add_keys_to_agent: false,
+            audit_log: true,
+            audit_log_path: None,
+            ui: UiSettings::default(),
+            ssh_options: SshOptionSettings::default(),
+            log: LogSettings::default(),
+            templates: BTreeMap::new(),
+            profiles: BTreeMap::new(),
+            active_profile: None,
+        }
+    }
+}
+
+impl Default for UiSettings {
+    fn default() -> Self {
+        Self {
+            auto_test_on_start: true,
+            columns: None,
+            compact: false,
+            color_blind: false,
+            min_connecting_ms: 200,
+            auto_connect_max_latency_ms: None,
+        }
+    }
+}
+
+impl Settings {
+    /// 从默认位置加载设置
+    pub fn load() -> Result<Self> {
+        Self::load_from(&crate::utils::get_settings_path()?)
+    }
+
+    /// 从指定文件加载设置，文件不存在时返回默认设置
+    pub fn load_from(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => Self::from_toml_str(&content),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// 从TOML文本解析设置
+    pub fn from_toml_str(content: &str) -> Result<Self> {
+        let deserializer = toml::Deserializer::new(content);
+        let settings: Settings = serde_path_to_error::deserialize(deserializer).map_err(|e| {
+            SshConnError::InvalidSetting {
+                key: setting_key_from_path(&e.path().to_string()),
+                message: e.inner().message().to_string(),
+            }
+        })?;
+
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// 校验字段取值
+    pub fn validate(&self) -> Result<()> {
+        if let Some(code) = &self.language
+            && Language::from_code(code).is_none()
+        {
+            return Err(SshConnError::InvalidSetting {
+                key: "language".to_string(),
+                message: format!("unsupported language '{}'", code),
+            });
+        }
+
+        for (key, path) in [
+            ("ssh_config_path", &self.ssh_config_path),
+            ("password_db_path", &self.password_db_path),
+            ("log.path", &self.log.path),
+        ] {
+            if path.as_ref().is_some_and(|p| p.as_os_str().is_empty()) {
+                return Err(SshConnError::InvalidSetting {
+                    key: key.to_string(),
+                    message: "path cannot be empty".to_string(),
+                });
+            }
+        }
+
+        if self.log.max_size_mb == 0 {
+            return Err(SshConnError::InvalidSetting {
+                key: "log.max_size_mb".to_string(),
+                message: "must be at least 1".to_string(),
+            });
+        }
+
+        if let Some(template) = &self.terminal_command
+            && !template.contains("{cmd}")
+        {
+            return Err(SshConnError::InvalidSetting {
+                key: "terminal_command".to_string(),
+                message: "must contain the {cmd} placeholder".to_string(),
+            });
+        }
+
+        if let Some(columns) = &self.ui.columns {
+            crate::ui::parse_columns(columns).map_err(|message| SshConnError::InvalidSetting {
+                key: "ui.columns".to_string(),
+                message,
+            })?;
+        }
+
+        for (name, template) in &self.templates {
+            template.validate(name)?;
+        }
+
+        for (name, path) in &self.profiles {
+            let invalid = |message: &str| SshConnError::InvalidSetting {
+                key: format!("profiles.{}", name),
+                message: message.to_string(),
+            };
+            if !is_valid_profile_name(name) {
+                return Err(invalid(
+                    "profile names may only contain letters, digits, '-' and '_'",
+                ));
+            }
+            if path.as_os_str().is_empty() {
+                return Err(invalid("path cannot be empty"));
+            }
+        }
+
+        self.ssh_options.validate()
+    }
+
+    /// 按名称查找主机模板
+    pub fn template(&self, name: &str) -> Result<&HostTemplate> {
+        self.templates.get(name).ok_or_else(|| {
+            let known: Vec<&str> = self.templates.keys().map(String::as_str).collect();
+            SshConnError::InvalidArgument(
+                crate::i18n::t("error.template_not_found")
+                    .replacen("{}", name, 1)
+                    .replacen("{}", &known.join(", "), 1),
+            )
+        })
+    }
+
+    /// 按名称查找配置档案对应的SSH配置文件路径
+    pub fn profile(&self, name: &str) -> Result<&Path> {
+        self.profiles
+            .get(name)
+            .map(PathBuf::as_path)
+            .ok_or_else(|| {
+                let known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+                SshConnError::InvalidArgument(
+                    crate::i18n::t("error.profile_not_found")
+                        .replacen("{}", name, 1)
+                        .replacen("{}", &known.join(", "), 1),
+                )
+            })
+    }
+
+    /// 切换到指定的配置档案，之后 `ssh_config_path` 指向该档案的配置文件
+    pub fn use_profile(&mut self, name: &str) -> Result<()> {
+        let path = self.profile(name)?.to_path_buf();
+        self.ssh_config_path = Some(path);
+        self.active_profile = Some(name.to_string());
+        Ok(())
+    }
+
+    /// 当前档案之后的下一个档案（按名称排序循环），没有档案时返回None
+    pub fn next_profile(&self) -> Option<&str> {
+        let current = self.active_profile.as_deref();
+        self.profiles
+            .keys()
+            .find(|name| current.is_some_and(|c| name.as_str() > c))
+            .or_else(|| self.profiles.keys().next())
+            .map(String::as_str)
+    }
+
+    /// 在设置文件中新增或修改配置档案并保存
+    pub fn add_profile(path: &Path, name: &str, config_path: &Path) -> Result<Self> {
+        if !is_valid_profile_name(name) {
+            return Err(SshConnError::InvalidArgument(
+                crate::i18n::t("error.profile_name_invalid").replace("{}", name),
+            ));
+        }
+        Self::set_value(
+            path,
+            &format!("profiles.{}", name),
+            &config_path.to_string_lossy(),
+        )
+    }
+
+    /// 获取设置中指定的语言
+    pub fn language(&self) -> Option<Language> {
+        self.language.as_deref().and_then(Language::from_code)
+    }
+
+    /// 序列化为TOML文本
+    pub fn to_toml_string(&self) -> Result<String> {
+        toml::to_string_pretty(self).map_err(|e| SshConnError::InvalidSetting {
+            key: String::new(),
+            message: e.to_string(),
+        })
+    }
+
+    /// 修改设置文件中的单个键并保存
+    ///
+    /// `key` 使用点号分隔的路径（如 `ui.auto_test_on_start`），
+    /// `value` 优先按TOML字面量解析（布尔、数字等），不合法时按字符串处理。
+    /// 修改后的完整设置通过校验才会写入文件。
+    pub fn set_value(path: &Path, key: &str, value: &str) -> Result<Self> {
+        let mut table = match std::fs::read_to_string(path) {
+            Ok(content) => {
+                content
+                    .parse::<toml::Table>()
+                    .map_err(|e| SshConnError::InvalidSetting {
+                        key: key.to_string(),
+                        message: e.message().to_string(),
+                    })?
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => toml::Table::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut candidates = Vec::new();
+        if let Some(literal) = parse_toml_literal(value) {
+            candidates.push(literal);
+        }
+        candidates.push(toml::Value::String(value.to_string()));
+
+        let mut first_error = None;
+        for candidate in candidates {
+            insert_dotted(&mut table, key, candidate)?;
+            let content =
+                toml::to_string_pretty(&table).map_err(|e| SshConnError::InvalidSetting {
+                    key: key.to_string(),
+                    message: e.to_string(),
+                })?;
+
+            match Self::from_toml_str(&content) {
+                Ok(settings) => {
+                    if let Some(parent) = path.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    std::fs::write(path, content)?;
+                    return Ok(settings);
+                }
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        Err(first_error.expect("at least one candidate value"))
+    }
+}
+
+/// 档案名只允许字母、数字、`-` 和 `_`，可直接用作设置键名
+fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// 将serde路径转换为设置键名，根路径表示文件本身的语法错误
+fn setting_key_from_path(path: &str) -> String {
+    if path == "." {
+        "settings.toml".to_string()
+    } else {
+        path.to_string()
+    }
+}
+
+/// 尝试将文本解析为TOML字面量
+fn parse_toml_literal(value: &str) -> Option<toml::Value> {
+    format!("value = {}", value)
+        .parse::<toml::Table>()
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+}
+
+/// 按点号路径插入值，必要时创建中间表
+fn insert_dotted(table: &mut toml::Table, key: &str, value: toml::Value) -> Result<()> {
+    let parts: Vec<&str> = key.split('.').collect();
+    if parts.iter().any(|p| p.is_empty()) {
+        return Err(SshConnError::InvalidSetting {
+            key: key.to_string(),
+            message: "invalid key".to_string(),
+        });
+    }
+
+    let (last, parents) = parts.split_last().expect("key has at least one part");
+    let mut current = table;
+    for part in parents {
+        let entry = current
+            .entry(part.to_string())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        current = entry
+            .as_table_mut()
+            .ok_or_else(|| SshConnError::InvalidSetting {
+                key: key.to_string(),
+                message: format!("'{}' is not a table", part),
+            })?;
+    }
+
+    current.insert(last.to_string(), value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn invalid_key(result: Result<Settings>) -> String {
+        match result {
+            Err(SshConnError::InvalidSetting { key, .. }) => key,
+            other => panic!("expected InvalidSetting, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_missing_file_uses_defaults() {
+        let dir = TempDir::new().unwrap();
+        let settings = Settings::load_from(&dir.path().join("settings.toml")).unwrap();
+        assert_eq!(settings, Settings::default());
+        assert!(settings.ui.auto_test_on_start);
+        assert_eq!(settings.language, None);
+        assert_eq!(settings.default_server_alive_interval, 60);
+    }
+
+    #[test]
+    fn test_partial_file_keeps_other_defaults() {
+        let settings = Settings::from_toml_str("[ui]\nauto_test_on_start = false\n").unwrap();
+        assert!(!settings.ui.auto_test_on_start);
+        assert_eq!(settings.ui.auto_connect_max_latency_ms, None);
+        assert_eq!(settings.ui.min_connecting_ms, 200);
+        assert_eq!(settings.language, None);
+        assert_eq!(settings.ssh_config_path, None);
+
+        let settings = Settings::from_toml_str("language = \"en\"\n").unwrap();
+        assert_eq!(settings.language(), Some(Language::English));
+        assert!(settings.ui.auto_test_on_start);
+    }
+
+    #[test]
+    fn test_invalid_file_reports_key_path() {
+        let key = invalid_key(Settings::from_toml_str(
+            "[ui]\nauto_test_on_start = \"yes\"\n",
+        ));
+        assert_eq!(key, "ui.auto_test_on_start");
+
+        let key = invalid_key(Settings::from_toml_str("[ui]\nunknown_key = 1\n"));
+        assert_eq!(key, "ui.unknown_key");
+
+        let key = invalid_key(Settings::from_toml_str("language = \"klingon\"\n"));
+        assert_eq!(key, "language");
+
+        let key = invalid_key(Settings::from_toml_str("language = \n"));
+        assert_eq!(key, "settings.toml");
+
+        let key = invalid_key(Settings::from_toml_str("terminal_command = \"xterm -e\"\n"));
+        assert_eq!(key, "terminal_command");
+
+        let key = invalid_key(Settings::from_toml_str(
+            "[ui]\ncolumns = [\"host\", \"Host\"]\n",
+        ));
+        assert_eq!(key, "ui.columns");
+
+        let key = invalid_key(Settings::from_toml_str("[log]\nmax_size_mb = 0\n"));
+        assert_eq!(key, "log.max_size_mb");
+    }
+
+    #[test]
+    fn test_log_settings() {
+        let settings = Settings::default();
+        assert!(!settings.log.enabled);
+        assert_eq!(settings.log.max_bytes(), 5 * 1024 * 1024);
+
+        let settings =
+            Settings::from_toml_str("[log]\nenabled = true\nmax_size_mb = 1\nkeep = 7\n").unwrap();
+        assert!(settings.log.enabled);
+        assert_eq!(settings.log.max_bytes(), 1024 * 1024);
+        assert_eq!(settings.log.keep, 7);
+        assert_eq!(settings.log.path, None);
+    }
+
+    #[test]
+    fn test_set_value_writes_and_validates() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("nested").join("settings.toml");
+
+        let settings = Settings::set_value(&path, "ui.auto_test_on_start", "false").unwrap();
+        assert!(!settings.ui.auto_test_on_start);
+
+        let settings = Settings::set_value(&path, "language", "en").unwrap();
+        assert_eq!(settings.language.as_deref(), Some("en"));
+        assert!(!settings.ui.auto_test_on_start);
+
+        // 非法值不会写入文件
+        let key = invalid_key(Settings::set_value(&path, "ui.auto_test_on_start", "maybe"));
+        assert_eq!(key, "ui.auto_test_on_start");
+        let key = invalid_key(Settings::set_value(&path, "no_such_key", "1"));
+        assert_eq!(key, "no_such_key");
+
+        let reloaded = Settings::load_from(&path).unwrap();
+        assert_eq!(reloaded.language.as_deref(), Some("en"));
+        assert!(!reloaded.ui.auto_test_on_start);
+    }
+
+    #[test]
+    fn test_normalize_ssh_options() {
+        let entries: Vec<String> = [
+            "-o",
+            "LogLevel=VERBOSE",
+            "-o ConnectTimeout=5",
+            "-oBatchMode=yes",
+            "-4",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        assert_eq!(
+            normalize_ssh_options(&entries).unwrap(),
+            vec![
+                "-o",
+                "LogLevel=VERBOSE",
+                "-o",
+                "ConnectTimeout=5",
+                "-o",
+                "BatchMode=yes",
+                "-4"
+            ]
+        );
+
+        for invalid in [
+            vec!["LogLevel=VERBOSE"],
+            vec!["-o"],
+            vec!["-o", "LogLevel"],
+            vec!["-o", "Log Level=x"],
+            vec!["-p 22"],
+            vec!["-"],
+        ] {
+            let entries: Vec<String> = invalid.iter().map(|s| s.to_string()).collect();
+            assert!(normalize_ssh_options(&entries).is_err(), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_invalid_ssh_options_report_key_path() {
+        let key = invalid_key(Settings::from_toml_str(
+            "[ssh_options.tui]\nextra = [\"LogLevel=VERBOSE\"]\n",
+        ));
+        assert_eq!(key, "ssh_options.tui.extra");
+
+        let key = invalid_key(Settings::from_toml_str(
+            "[ssh_options.connect]\noptions = [\"-o\"]\n",
+        ));
+        assert_eq!(key, "ssh_options.connect.options");
+    }
+
+    const TEMPLATE_TOML: &str = r#"
+[templates.datacenter-a]
+user = "ops"
+port = 2222
+identity_file = "~/.ssh/dc_a"
+server_alive_interval = 30
+
+[templates.datacenter-a.set_env]
+LANG = "C.UTF-8"
+
+[templates.datacenter-a.options]
+ProxyJump = "bastion-a"
+Compression = "yes"
+"#;
+
+    #[test]
+    fn test_templates_parsed_from_toml() {
+        let settings = Settings::from_toml_str(TEMPLATE_TOML).unwrap();
+        let template = settings.template("datacenter-a").unwrap();
+        assert_eq!(template.user.as_deref(), Some("ops"));
+        assert_eq!(template.port, Some(2222));
+        assert_eq!(template.options["ProxyJump"], "bastion-a");
+        assert!(matches!(
+            settings.template("missing"),
+            Err(SshConnError::InvalidArgument(_))
+        ));
+
+        let key = invalid_key(Settings::from_toml_str(
+            "[templates.bad]\nrequest_tty = \"sometimes\"\n",
+        ));
+        assert_eq!(key, "templates.bad.request_tty");
+        let key = invalid_key(Settings::from_toml_str("[templates.bad]\nport = 0\n"));
+        assert_eq!(key, "templates.bad.port");
+    }
+
+    #[test]
+    fn test_template_apply_keeps_explicit_values() {
+        let settings = Settings::from_toml_str(TEMPLATE_TOML).unwrap();
+        let template = settings.template("datacenter-a").unwrap();
+
+        let mut host = SshHost::new("newbox".to_string());
+        host.hostname = Some("10.1.2.3".to_string());
+        host.port = Some("22".to_string());
+        host.set_env = vec![("LANG".to_string(), "en_US.UTF-8".to_string())];
+        host.custom_options
+            .insert("proxyjump".to_string(), "bastion-b".to_string());
+        template.apply(&mut host);
+
+        // 明确指定的字段保持不变，其余由模板补全
+        assert_eq!(host.hostname.as_deref(), Some("10.1.2.3"));
+        assert_eq!(host.port.as_deref(), Some("22"));
+        assert_eq!(host.user.as_deref(), Some("ops"));
+        assert_eq!(host.identity_file.as_deref(), Some("~/.ssh/dc_a"));
+        assert_eq!(host.server_alive_interval.as_deref(), Some("30"));
+        assert_eq!(
+            host.set_env,
+            [("LANG".to_string(), "en_US.UTF-8".to_string())]
+        );
+
+        // 自定义选项按关键字合并，不区分大小写
+        assert_eq!(host.get_custom_option("ProxyJump"), Some("bastion-b"));
+        assert_eq!(host.get_custom_option("Compression"), Some("yes"));
+        assert_eq!(host.custom_options.len(), 2);
+
+        // 空主机套用模板得到模板的全部字段
+        let mut bare = SshHost::new("bare".to_string());
+        template.apply(&mut bare);
+        assert_eq!(bare.port.as_deref(), Some("2222"));
+        assert_eq!(bare.set_env, [("LANG".to_string(), "C.UTF-8".to_string())]);
+        assert_eq!(bare.get_custom_option("ProxyJump"), Some("bastion-a"));
+    }
+
+    #[test]
+    fn test_profile_resolution() {
+        let mut settings = Settings::from_toml_str(
+            r#"
+ssh_config_path = "/etc/ssh/default_config"
+
+[profiles]
+work = "/home/me/.ssh/work_config"
+home = "/home/me/.ssh/home_config"
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            settings.profile("work").unwrap(),
+            Path::new("/home/me/.ssh/work_config")
+        );
+        assert!(settings.profile("clients").is_err());
+
+        // 未选择档案时从第一个开始，按名称循环
+        assert_eq!(settings.next_profile(), Some("home"));
+        settings.use_profile("home").unwrap();
+        assert_eq!(
+            settings.ssh_config_path.as_deref(),
+            Some(Path::new("/home/me/.ssh/home_config"))
+        );
+        assert_eq!(settings.next_profile(), Some("work"));
+        settings.use_profile("work").unwrap();
+        assert_eq!(settings.next_profile(), Some("home"));
+
+        // 当前档案是运行时状态，不写入设置文件
+        assert!(
+            !settings
+                .to_toml_string()
+                .unwrap()
+                .contains("active_profile")
+        );
+
+        let key = invalid_key(Settings::from_toml_str("[profiles]\n\"a.b\" = \"/x\"\n"));
+        assert_eq!(key, "profiles.a.b");
+    }
+
+    #[test]
+    fn test_add_and_list_profiles() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("settings.toml");
+        std::fs::write(&path, "language = \"en\"\n").unwrap();
+
+        Settings::add_profile(&path, "work", Path::new("/tmp/work_config")).unwrap();
+        let settings = Settings::add_profile(&path, "home", Path::new("/tmp/home_config")).unwrap();
+        assert_eq!(
+            settings.profiles.keys().collect::<Vec<_>>(),
+            ["home", "work"]
+        );
+
+        // 同名档案覆盖原路径，其他设置保持不变
+        Settings::add_profile(&path, "work", Path::new("/tmp/work2")).unwrap();
+        let reloaded = Settings::load_from(&path).unwrap();
+        assert_eq!(reloaded.language.as_deref(), Some("en"));
+        assert_eq!(reloaded.profile("work").unwrap(), Path::new("/tmp/work2"));
+        assert_eq!(reloaded.profiles.len(), 2);
+
+        assert!(Settings::add_profile(&path, "bad name", Path::new("/tmp/x")).is_err());
+        assert_eq!(Settings::load_from(&path).unwrap().profiles.len(), 2);
+    }
+}
@@ -0,0 +1,214 @@
+//! SSH配置文件存储模块
+//!
+//! [`ConfigManager`](crate::config::ConfigManager) 通过 [`ConfigStore`] 读写配置内容，
+//! 默认使用磁盘文件，测试中可换成内存实现，避免触碰真实的 `~/.ssh/config`
+//!
+//! 启用 `async` 特性时，[`read_async`]、[`write_async`] 和 [`append_async`]
+//! 对文件存储使用 `tokio::fs`，供异步API使用
+
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::error::{Result, SshConnError};
+use crate::permissions::{private_open_options, write_private};
+
+/// SSH配置内容的读写接口
+pub trait ConfigStore: Send + Sync {
+    /// 读取全部内容，配置不存在时返回None
+    fn read(&self) -> Result<Option<String>>;
+
+    /// 用 `content` 替换全部内容
+    fn write(&self, content: &str) -> Result<()>;
+
+    /// 在末尾追加内容，配置不存在时创建
+    fn append(&self, content: &str) -> Result<()>;
+
+    /// 存储对应的磁盘文件，不在磁盘上时为None
+    fn path(&self) -> Option<&Path> {
+        None
+    }
+}
+
+/// 基于磁盘文件的存储
+#[derive(Debug, Clone)]
+pub struct FileConfigStore {
+    path: PathBuf,
+}
+
+impl FileConfigStore {
+    /// 创建指向 `path` 的文件存储
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl ConfigStore for FileConfigStore {
+    fn read(&self) -> Result<Option<String>> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(SshConnError::file_access(&self.path, e)),
+        }
+    }
+
+    fn write(&self, content: &str) -> Result<()> {
+        write_private(&self.path, content).map_err(|e| SshConnError::file_access(&self.path, e))
+    }
+
+    fn append(&self, content: &str) -> Result<()> {
+        let mut file = private_open_options()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| SshConnError::file_access(&self.path, e))?;
+        file.write_all(content.as_bytes())
+            .map_err(|e| SshConnError::file_access(&self.path, e))
+    }
+
+    fn path(&self) -> Option<&Path> {
+        Some(&self.path)
+    }
+}
+
+/// [`ConfigStore::read`] 的异步版本：文件存储用 `tokio::fs` 读取，其他存储直接调用同步方法
+#[cfg(feature = "async")]
+pub async fn read_async(store: &dyn ConfigStore) -> Result<Option<String>> {
+    let Some(path) = store.path() else {
+        return store.read();
+    };
+    match tokio::fs::read_to_string(path).await {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(SshConnError::file_access(path, e)),
+    }
+}
+
+/// [`ConfigStore::write`] 的异步版本
+#[cfg(feature = "async")]
+pub async fn write_async(store: &dyn ConfigStore, content: &str) -> Result<()> {
+    match store.path() {
+        Some(path) => {
+            use tokio::io::AsyncWriteExt;
+
+            let mut file = tokio::fs::OpenOptions::from(private_open_options())
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(path)
+                .await
+                .map_err(|e| SshConnError::file_access(path, e))?;
+            file.write_all(content.as_bytes())
+                .await
+                .map_err(|e| SshConnError::file_access(path, e))?;
+            // tokio的文件在后台线程写入，flush之后内容才真正落盘
+            file.flush()
+                .await
+                .map_err(|e| SshConnError::file_access(path, e))
+        }
+        None => store.write(content),
+    }
+}
+
+/// [`ConfigStore::append`] 的异步版本
+#[cfg(feature = "async")]
+pub async fn append_async(store: &dyn ConfigStore, content: &str) -> Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    let Some(path) = store.path() else {
+        return store.append(content);
+    };
+    let mut file = tokio::fs::OpenOptions::from(private_open_options())
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+        .map_err(|e| SshConnError::file_access(path, e))?;
+    file.write_all(content.as_bytes())
+        .await
+        .map_err(|e| SshConnError::file_access(path, e))?;
+    file.flush()
+        .await
+        .map_err(|e| SshConnError::file_access(path, e))
+}
+
+/// 内存中的存储，克隆后共享同一份内容
+#[derive(Debug, Clone, Default)]
+pub struct MemoryConfigStore {
+    content: Arc<Mutex<Option<String>>>,
+}
+
+impl MemoryConfigStore {
+    /// 创建空的内存存储（相当于配置文件不存在）
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 创建带有初始内容的内存存储
+    pub fn with_content(content: &str) -> Self {
+        Self {
+            content: Arc::new(Mutex::new(Some(content.to_string()))),
+        }
+    }
+
+    /// 当前内容，不存在时返回空字符串
+    pub fn content(&self) -> String {
+        self.lock().clone().unwrap_or_default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<String>> {
+        // 持有锁时不会panic，遇到中毒的锁直接取出内容
+        self.content.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl ConfigStore for MemoryConfigStore {
+    fn read(&self) -> Result<Option<String>> {
+        Ok(self.lock().clone())
+    }
+
+    fn write(&self, content: &str) -> Result<()> {
+        *self.lock() = Some(content.to_string());
+        Ok(())
+    }
+
+    fn append(&self, content: &str) -> Result<()> {
+        self.lock()
+            .get_or_insert_with(String::new)
+            .push_str(content);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_store_read_write_append() {
+        let store = MemoryConfigStore::new();
+        assert_eq!(store.read().unwrap(), None);
+
+        store.append("Host a\n").unwrap();
+        store.append("Host b\n").unwrap();
+        assert_eq!(store.read().unwrap().as_deref(), Some("Host a\nHost b\n"));
+
+        // 克隆共享同一份内容
+        let clone = store.clone();
+        clone.write("Host c\n").unwrap();
+        assert_eq!(store.content(), "Host c\n");
+    }
+
+    #[test]
+    fn test_file_store_missing_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = FileConfigStore::new(dir.path().join("config"));
+        assert_eq!(store.read().unwrap(), None);
+
+        store.append("Host a\n").unwrap();
+        store.append("Host b\n").unwrap();
+        assert_eq!(store.read().unwrap().as_deref(), Some("Host a\nHost b\n"));
+        store.write("").unwrap();
+        assert_eq!(store.read().unwrap().as_deref(), Some(""));
+    }
+}
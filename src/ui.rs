@@ -1,6 +1,6 @@
 //! 终端用户界面模块
 
-use crossterm::event::{self, Event, KeyCode};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
 use crossterm::execute;
 use crossterm::terminal::{
     EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
@@ -9,18 +9,297 @@ use ratatui::Terminal;
 use ratatui::backend::CrosstermBackend;
 use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
 use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState};
+use std::collections::{HashMap, HashSet};
 use std::io;
+use std::time::{Duration, Instant};
 
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Once, mpsc};
 use std::thread;
 
-use crate::config::ConfigManager;
+use crate::agent::{self, AgentStatus, KeyStatus};
+use crate::config::{CommandMode, ConfigManager};
+use crate::error::SshConnError;
 use crate::i18n::t;
-use crate::models::{ConnectionStatus, FormField, SshHost};
+use crate::models::{
+    ConnectionStatus, FormField, FormFieldType, OptionalField, SessionKind, SshHost, StatusPalette,
+};
+use crate::network::AuthMethods;
+
+/// 连接测试结果超过该时长后视为过期并淡化显示
+const TEST_STALE_AFTER: Duration = Duration::from_secs(10 * 60);
+
+/// 等待按键的最长时间；超时后取出已完成的测试结果，并按需刷新随时间变化的内容
+const TICK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// 显示“N秒前测试”时的刷新间隔
+const TEST_AGE_REFRESH: Duration = Duration::from_secs(1);
+
+/// 测试后直接连接前，测试结果在界面上停留的时间
+const CHECKED_CONNECT_PAUSE: Duration = Duration::from_millis(600);
+
+/// 主循环的重绘判断：只在界面有变化，或随时间变化的内容到了刷新时间时重绘
+#[derive(Debug)]
+pub(crate) struct RedrawState {
+    dirty: bool,
+    last_draw: Option<Instant>,
+}
+
+impl Default for RedrawState {
+    fn default() -> Self {
+        // 启动后先绘制一次
+        Self {
+            dirty: true,
+            last_draw: None,
+        }
+    }
+}
+
+impl RedrawState {
+    /// 按键、窗口大小变化或收到测试结果后标记需要重绘
+    pub(crate) fn mark(&mut self) {
+        self.dirty = true;
+    }
+
+    /// `refresh` 为随时间变化的内容的刷新间隔，没有这类内容时为None
+    pub(crate) fn should_draw(&self, now: Instant, refresh: Option<Duration>) -> bool {
+        self.dirty
+            || refresh.is_some_and(|refresh| {
+                self.last_draw
+                    .is_none_or(|last| now.saturating_duration_since(last) >= refresh)
+            })
+    }
+
+    pub(crate) fn drawn(&mut self, now: Instant) {
+        self.dirty = false;
+        self.last_draw = Some(now);
+    }
+}
+
+/// 终端是否处于TUI模式（raw mode + 备用屏幕），保证恢复操作只执行一次
+static TERMINAL_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// 测试完成后还需等待多久，让🟡状态至少显示 `min_connecting`（`ui.min_connecting_ms`）
+///
+/// 为零或测试已经耗时更久时不等待
+fn remaining_connecting_time(min_connecting: Duration, elapsed: Duration) -> Option<Duration> {
+    min_connecting
+        .checked_sub(elapsed)
+        .filter(|remaining| !remaining.is_zero())
+}
+
+/// 前台是否正在运行ssh/sftp子进程
+///
+/// 子进程与本进程同属前台进程组，Ctrl+C产生的SIGINT会同时送达两者，
+/// 此时由子进程自行处理，TUI不能随之退出
+static CHILD_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// 信号处理器只安装一次
+static SIGNAL_HANDLER: Once = Once::new();
+
+/// 在前台运行子进程期间标记 [`CHILD_RUNNING`]，返回时（包括panic）清除
+fn with_foreground_child<T>(run: impl FnOnce() -> T) -> T {
+    struct Running;
+    impl Drop for Running {
+        fn drop(&mut self) {
+            CHILD_RUNNING.store(false, Ordering::SeqCst);
+        }
+    }
+
+    CHILD_RUNNING.store(true, Ordering::SeqCst);
+    let _running = Running;
+    run()
+}
+
+/// 收到信号时是否恢复终端并退出：前台子进程运行时忽略SIGINT，SIGTERM总是退出
+#[cfg(unix)]
+fn should_exit_on_signal(signal: i32, child_running: bool) -> bool {
+    signal != signal_hook::consts::SIGINT || !child_running
+}
+
+/// 进入TUI模式：开启raw mode并切换到备用屏幕
+fn enter_terminal_mode() -> io::Result<()> {
+    enable_raw_mode()?;
+    TERMINAL_ACTIVE.store(true, Ordering::SeqCst);
+    execute!(io::stdout(), EnterAlternateScreen)?;
+    Ok(())
+}
+
+/// 恢复终端：关闭raw mode、离开备用屏幕并显示光标
+///
+/// 可重复调用，终端不处于TUI模式时不做任何操作，
+/// 因此信号处理器和正常退出路径可以安全地各自调用。
+pub fn restore_terminal() -> io::Result<()> {
+    if !TERMINAL_ACTIVE.swap(false, Ordering::SeqCst) {
+        return Ok(());
+    }
+
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen, crossterm::cursor::Show)?;
+    Ok(())
+}
+
+/// 终端恢复的单个步骤，全部通过crossterm完成，不依赖 stty/tput/reset 等外部命令
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RecoveryStep {
+    DisableRawMode,
+    EnableRawMode,
+    LeaveAlternateScreen,
+    EnterAlternateScreen,
+    ShowCursor,
+    ResetColor,
+    /// 清屏并把光标移到左上角
+    ClearScreen,
+    /// 写入 `ESC c` 完全重置终端，只在紧急恢复时使用
+    FullReset,
+    /// 丢弃事件队列中残留的按键
+    DrainEvents,
+}
+
+/// 退出程序时在 [`restore_terminal`] 之后执行
+const EXIT_STEPS: &[RecoveryStep] = &[
+    RecoveryStep::DisableRawMode,
+    RecoveryStep::ResetColor,
+    RecoveryStep::ShowCursor,
+];
+
+/// SSH会话结束、重新进入TUI后执行，清除会话留下的颜色和按键
+const AFTER_SESSION_STEPS: &[RecoveryStep] = &[
+    RecoveryStep::ResetColor,
+    RecoveryStep::ClearScreen,
+    RecoveryStep::DisableRawMode,
+    RecoveryStep::EnableRawMode,
+    RecoveryStep::DrainEvents,
+];
+
+/// 渲染失败时的紧急恢复：完全重置终端后重新进入TUI模式
+const EMERGENCY_STEPS: &[RecoveryStep] = &[
+    RecoveryStep::DisableRawMode,
+    RecoveryStep::LeaveAlternateScreen,
+    RecoveryStep::ResetColor,
+    RecoveryStep::ShowCursor,
+    RecoveryStep::FullReset,
+    RecoveryStep::EnterAlternateScreen,
+    RecoveryStep::ClearScreen,
+    RecoveryStep::EnableRawMode,
+    RecoveryStep::DrainEvents,
+];
+
+/// 执行恢复步骤所需的终端操作，测试中替换为记录调用的实现
+pub(crate) trait TerminalOps {
+    fn set_raw_mode(&mut self, enabled: bool) -> io::Result<()>;
+    fn drain_events(&mut self) -> io::Result<()>;
+    fn writer(&mut self) -> &mut dyn io::Write;
+}
+
+/// 真实终端：raw mode和事件队列由crossterm管理，控制序列写入stdout
+struct CrosstermOps(io::Stdout);
+
+impl TerminalOps for CrosstermOps {
+    fn set_raw_mode(&mut self, enabled: bool) -> io::Result<()> {
+        if enabled {
+            enable_raw_mode()
+        } else {
+            disable_raw_mode()
+        }
+    }
+
+    fn drain_events(&mut self) -> io::Result<()> {
+        while event::poll(Duration::ZERO)? {
+            event::read()?;
+        }
+        Ok(())
+    }
+
+    fn writer(&mut self) -> &mut dyn io::Write {
+        &mut self.0
+    }
+}
+
+/// 依次执行恢复步骤，某一步失败时记录日志并继续后面的步骤；返回失败的步骤数
+pub(crate) fn run_recovery(ops: &mut dyn TerminalOps, steps: &[RecoveryStep]) -> usize {
+    let mut failures = 0;
+    for &step in steps {
+        let result = match step {
+            RecoveryStep::DisableRawMode => ops.set_raw_mode(false),
+            RecoveryStep::EnableRawMode => ops.set_raw_mode(true),
+            RecoveryStep::DrainEvents => ops.drain_events(),
+            _ => write_recovery_sequence(ops.writer(), step),
+        };
+        if let Err(e) = result {
+            log::debug!("Terminal recovery step {:?} failed: {}", step, e);
+            failures += 1;
+        }
+    }
+    failures
+}
+
+/// 写入步骤对应的控制序列
+fn write_recovery_sequence(mut out: &mut dyn io::Write, step: RecoveryStep) -> io::Result<()> {
+    use crossterm::terminal::{Clear, ClearType};
+
+    match step {
+        RecoveryStep::LeaveAlternateScreen => execute!(&mut out, LeaveAlternateScreen),
+        RecoveryStep::EnterAlternateScreen => execute!(&mut out, EnterAlternateScreen),
+        RecoveryStep::ShowCursor => execute!(&mut out, crossterm::cursor::Show),
+        RecoveryStep::ResetColor => execute!(&mut out, crossterm::style::ResetColor),
+        RecoveryStep::ClearScreen => execute!(
+            &mut out,
+            Clear(ClearType::All),
+            crossterm::cursor::MoveTo(0, 0)
+        ),
+        RecoveryStep::FullReset => out.write_all(b"\x1bc").and_then(|_| out.flush()),
+        RecoveryStep::DisableRawMode | RecoveryStep::EnableRawMode | RecoveryStep::DrainEvents => {
+            Ok(())
+        }
+    }
+}
+
+/// 安装SIGINT/SIGTERM处理器，在进程被中断时先恢复终端再退出
+///
+/// 前台运行ssh时的SIGINT交给子进程处理，见 [`should_exit_on_signal`]
+#[cfg(unix)]
+fn install_signal_handler() {
+    use signal_hook::consts::{SIGINT, SIGTERM};
+    use signal_hook::iterator::Signals;
 
-/// 连接测试结果类型别名
-type PendingConnectionTests = Arc<Mutex<Vec<(usize, Option<ConnectionStatus>)>>>;
+    SIGNAL_HANDLER.call_once(|| match Signals::new([SIGINT, SIGTERM]) {
+        Ok(mut signals) => {
+            thread::spawn(move || {
+                for signal in signals.forever() {
+                    if !should_exit_on_signal(signal, CHILD_RUNNING.load(Ordering::SeqCst)) {
+                        continue;
+                    }
+                    let _ = restore_terminal();
+                    std::process::exit(128 + signal);
+                }
+            });
+        }
+        Err(e) => log::warn!("Failed to install signal handler: {}", e),
+    });
+}
+
+/// 非Unix平台上raw mode会把Ctrl+C作为按键事件传递，无需额外处理
+#[cfg(not(unix))]
+fn install_signal_handler() {
+    SIGNAL_HANDLER.call_once(|| {});
+}
+
+/// 测试线程完成后发回的结果：(主机名, 连接状态)
+type TestResult = (String, ConnectionStatus);
+
+/// 认证方式探测线程发回的结果：(主机名, 认证方式或失败原因)
+type AuthProbeResult = (String, std::result::Result<AuthMethods, String>);
+
+/// 主机的认证方式探测（`A`）状态
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum AuthProbeState {
+    Probing,
+    Done(AuthMethods),
+    Failed(String),
+}
 
 /// 搜索状态
 #[derive(Default)]
@@ -30,6 +309,470 @@ struct SearchState {
     input: String,
 }
 
+/// 校验错误的字段名对应的表单字段下标（见 `show_add_form` 中的字段顺序）
+fn form_field_index(field: &str) -> Option<usize> {
+    match field {
+        "host" => Some(0),
+        "hostname" => Some(1),
+        "user" => Some(2),
+        "port" => Some(3),
+        "proxy_command" => Some(4),
+        "identity_file" => Some(5),
+        "remote_command" => Some(6),
+        "request_tty" => Some(7),
+        "set_env" => Some(8),
+        "address_family" => Some(9),
+        "bind_address" => Some(10),
+        "permit_local_command" => Some(11),
+        "local_command" => Some(12),
+        _ => None,
+    }
+}
+
+/// 表单中AddressFamily选择字段的选项
+fn address_family_options() -> Vec<String> {
+    crate::utils::ADDRESS_FAMILY_VALUES
+        .iter()
+        .map(|v| v.to_string())
+        .collect()
+}
+
+/// 表单中PermitLocalCommand选择字段的选项
+fn permit_local_command_options() -> Vec<String> {
+    vec!["yes".to_string(), "no".to_string()]
+}
+
+/// 主机列表的列
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HostColumn {
+    /// 序号（从1开始），用于快速连接
+    Index,
+    Host,
+    HostName,
+    User,
+    Port,
+    Status,
+    /// 认证方式：🔒 存储了密码，🔑 仅密钥
+    Auth,
+    ProxyCommand,
+    IdentityFile,
+}
+
+/// 认证方式列的标记：存储了密码为 🔒，否则为 🔑（依赖密钥或手动输入）
+pub(crate) fn auth_indicator(has_password: bool) -> &'static str {
+    if has_password { "🔒" } else { "🔑" }
+}
+
+impl HostColumn {
+    /// 默认显示的列及顺序
+    pub(crate) const DEFAULT: [HostColumn; 9] = [
+        HostColumn::Index,
+        HostColumn::Host,
+        HostColumn::HostName,
+        HostColumn::User,
+        HostColumn::Port,
+        HostColumn::Status,
+        HostColumn::Auth,
+        HostColumn::ProxyCommand,
+        HostColumn::IdentityFile,
+    ];
+
+    /// 紧凑模式下默认显示的列
+    pub(crate) const COMPACT: [HostColumn; 5] = [
+        HostColumn::Index,
+        HostColumn::Host,
+        HostColumn::HostName,
+        HostColumn::User,
+        HostColumn::Status,
+    ];
+
+    /// 设置文件 `ui.columns` 中使用的列名
+    fn name(self) -> &'static str {
+        match self {
+            HostColumn::Index => "index",
+            HostColumn::Host => "host",
+            HostColumn::HostName => "hostname",
+            HostColumn::User => "user",
+            HostColumn::Port => "port",
+            HostColumn::Status => "status",
+            HostColumn::Auth => "auth",
+            HostColumn::ProxyCommand => "proxy_command",
+            HostColumn::IdentityFile => "identity_file",
+        }
+    }
+
+    /// 表头文本
+    fn header(self) -> &'static str {
+        match self {
+            HostColumn::Index => "#",
+            HostColumn::Host => "Host",
+            HostColumn::HostName => "HostName",
+            HostColumn::User => "User",
+            HostColumn::Port => "Port",
+            HostColumn::Status => "Status",
+            HostColumn::Auth => "Auth",
+            HostColumn::ProxyCommand => "ProxyCommand",
+            HostColumn::IdentityFile => "IdentityFile",
+        }
+    }
+
+    /// 列宽约束，紧凑模式下收窄
+    fn constraint(self, compact: bool) -> Constraint {
+        match (self, compact) {
+            (HostColumn::Index, false) => Constraint::Length(4),
+            (HostColumn::Index, true) => Constraint::Length(3),
+            (HostColumn::Host | HostColumn::HostName, false) => Constraint::Min(15),
+            (HostColumn::Host | HostColumn::HostName, true) => Constraint::Min(10),
+            (HostColumn::User, _) => Constraint::Length(8),
+            (HostColumn::Port, false) => Constraint::Length(6),
+            (HostColumn::Port, true) => Constraint::Length(5),
+            (HostColumn::Status, false) => Constraint::Length(12),
+            (HostColumn::Status, true) => Constraint::Length(10),
+            (HostColumn::Auth, false) => Constraint::Length(4),
+            (HostColumn::Auth, true) => Constraint::Length(2),
+            (HostColumn::ProxyCommand | HostColumn::IdentityFile, false) => Constraint::Min(20),
+            (HostColumn::ProxyCommand | HostColumn::IdentityFile, true) => Constraint::Min(12),
+        }
+    }
+
+    /// 第 `index` 行（从0开始）主机在该列显示的文本，`has_password` 表示该主机是否存储了密码
+    fn cell_text(
+        self,
+        index: usize,
+        host: &SshHost,
+        has_password: bool,
+        palette: StatusPalette,
+    ) -> String {
+        match self {
+            HostColumn::Index => (index + 1).to_string(),
+            HostColumn::Host => host.host.clone(),
+            HostColumn::HostName => host.hostname.clone().unwrap_or_default(),
+            HostColumn::User => host.user.clone().unwrap_or_default(),
+            HostColumn::Port => host.port.clone().unwrap_or_default(),
+            HostColumn::Status => host.connection_status.display_string_with(palette),
+            HostColumn::Auth => auth_indicator(has_password).to_string(),
+            HostColumn::ProxyCommand => host.proxy_command.clone().unwrap_or_default(),
+            HostColumn::IdentityFile => host.identity_file.clone().unwrap_or_default(),
+        }
+    }
+}
+
+/// 解析设置中的列名列表（不区分大小写），返回按配置顺序排列的列
+///
+/// 列表为空、包含未知列名或重复列时返回错误描述
+pub(crate) fn parse_columns(names: &[String]) -> std::result::Result<Vec<HostColumn>, String> {
+    if names.is_empty() {
+        return Err("at least one column is required".to_string());
+    }
+    let mut columns = Vec::with_capacity(names.len());
+    for name in names {
+        let column = HostColumn::DEFAULT
+            .into_iter()
+            .find(|c| c.name().eq_ignore_ascii_case(name.trim()))
+            .ok_or_else(|| {
+                let known: Vec<&str> = HostColumn::DEFAULT.iter().map(|c| c.name()).collect();
+                format!(
+                    "unknown column '{}', expected one of: {}",
+                    name,
+                    known.join(", ")
+                )
+            })?;
+        if columns.contains(&column) {
+            return Err(format!(
+                "column '{}' is listed more than once",
+                column.name()
+            ));
+        }
+        columns.push(column);
+    }
+    Ok(columns)
+}
+
+/// 主机表格各行单元格的文本，与 [`build_host_table`] 生成的行一一对应
+///
+/// `has_password` 按Host名称判断是否存储了密码，用于认证方式列；`palette` 决定状态列的符号
+pub(crate) fn host_table_rows(
+    hosts: &[SshHost],
+    columns: &[HostColumn],
+    has_password: &dyn Fn(&str) -> bool,
+    palette: StatusPalette,
+) -> Vec<Vec<String>> {
+    hosts
+        .iter()
+        .enumerate()
+        .map(|(i, host)| {
+            let stored = columns.contains(&HostColumn::Auth) && has_password(&host.host);
+            columns
+                .iter()
+                .map(|c| c.cell_text(i, host, stored, palette))
+                .collect()
+        })
+        .collect()
+}
+
+/// 各列的宽度约束，与 [`build_host_table`] 使用的一致
+pub(crate) fn column_constraints(columns: &[HostColumn], compact: bool) -> Vec<Constraint> {
+    columns.iter().map(|c| c.constraint(compact)).collect()
+}
+
+/// 构建主机列表表格（表头、行、列宽和高亮样式），不依赖终端
+///
+/// 紧凑模式下不显示选中行前的 `▍ ` 标记，选中行只靠高亮样式区分
+pub(crate) fn build_host_table<'a>(
+    hosts: &[SshHost],
+    columns: &[HostColumn],
+    has_password: &dyn Fn(&str) -> bool,
+    title: String,
+    footer: Option<Line<'a>>,
+    compact: bool,
+    palette: StatusPalette,
+) -> Table<'a> {
+    let header = Row::new(columns.iter().map(|c| Cell::from(c.header())))
+        .style(Style::default().add_modifier(Modifier::BOLD));
+    let rows = host_table_rows(hosts, columns, has_password, palette)
+        .into_iter()
+        .zip(hosts)
+        .map(|(cells, host)| {
+            Row::new(cells.into_iter().zip(columns).map(|(text, column)| {
+                let cell = Cell::from(text);
+                match column {
+                    HostColumn::Status => {
+                        cell.style(status_style(&host.connection_status, palette))
+                    }
+                    _ => cell,
+                }
+            }))
+        });
+
+    let block = Block::default().borders(Borders::ALL).title(title);
+    let block = match footer {
+        Some(footer) => block.title_bottom(footer),
+        None => block,
+    };
+
+    Table::new(rows, column_constraints(columns, compact))
+        .header(header)
+        .block(block)
+        .row_highlight_style(
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::Yellow)
+                .add_modifier(Modifier::BOLD | Modifier::REVERSED),
+        )
+        .highlight_symbol(if compact { "" } else { "▍ " })
+}
+
+/// 状态单元格的样式：色盲模式下可用为蓝色、失败为加粗的橙色；默认的彩色圆点不另加颜色
+pub(crate) fn status_style(status: &ConnectionStatus, palette: StatusPalette) -> Style {
+    match (palette, status) {
+        (StatusPalette::Default, _) => Style::default(),
+        (
+            StatusPalette::ColorBlind,
+            ConnectionStatus::Connected(_) | ConnectionStatus::SshReachable(_),
+        ) => Style::default().fg(Color::Blue),
+        (StatusPalette::ColorBlind, ConnectionStatus::Failed(_)) => Style::default()
+            .fg(Color::Rgb(230, 159, 0))
+            .add_modifier(Modifier::BOLD),
+        (StatusPalette::ColorBlind, _) => Style::default(),
+    }
+}
+
+/// 格式化距上次连接测试的时长，如 "tested 2m ago"；返回文本以及结果是否已过期
+pub(crate) fn format_test_age(elapsed: Duration, stale_after: Duration) -> (String, bool) {
+    let secs = elapsed.as_secs();
+    let age = match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m", secs / 60),
+        3600..86400 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    };
+    let stale = elapsed >= stale_after;
+    let mut text = t("ui.tested_ago").replacen("{}", &age, 1);
+    if stale {
+        text = format!("{} ({})", text, t("ui.test_stale"));
+    }
+    (text, stale)
+}
+
+/// 重新加载后应选中的行：优先按名称找到之前选中的主机，找不到时保持原位置（不超出列表）
+pub(crate) fn reselect_by_name(
+    hosts: &[SshHost],
+    previous: Option<&str>,
+    fallback: usize,
+) -> Option<usize> {
+    if hosts.is_empty() {
+        return None;
+    }
+    previous
+        .and_then(|name| hosts.iter().position(|h| h.host == name))
+        .or(Some(fallback.min(hosts.len() - 1)))
+}
+
+/// 主机列表的整体状态统计，显示在界面底部
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) struct FleetSummary {
+    pub total: usize,
+    pub up: usize,
+    pub down: usize,
+    /// 未测试或正在测试
+    pub unknown: usize,
+    /// 可达主机的平均延迟
+    pub avg_latency: Option<Duration>,
+}
+
+impl FleetSummary {
+    /// 由各主机的连接状态统计
+    pub fn from_statuses<'a>(statuses: impl IntoIterator<Item = &'a ConnectionStatus>) -> Self {
+        let mut summary = Self::default();
+        let mut latency_total = Duration::ZERO;
+        for status in statuses {
+            summary.total += 1;
+            match status {
+                ConnectionStatus::Connected(d) | ConnectionStatus::SshReachable(d) => {
+                    summary.up += 1;
+                    latency_total += *d;
+                }
+                ConnectionStatus::Failed(_) => summary.down += 1,
+                ConnectionStatus::Unknown | ConnectionStatus::Connecting => summary.unknown += 1,
+            }
+        }
+        if summary.up > 0 {
+            summary.avg_latency = Some(latency_total / summary.up as u32);
+        }
+        summary
+    }
+
+    /// 格式化为一行，如 "42 hosts · 38 up · 3 down · 1 unknown · avg 45ms"
+    pub fn format(&self) -> String {
+        let mut parts = vec![
+            t("ui.summary_hosts").replacen("{}", &self.total.to_string(), 1),
+            t("ui.summary_up").replacen("{}", &self.up.to_string(), 1),
+            t("ui.summary_down").replacen("{}", &self.down.to_string(), 1),
+            t("ui.summary_unknown").replacen("{}", &self.unknown.to_string(), 1),
+        ];
+        if let Some(avg) = self.avg_latency {
+            parts.push(t("ui.summary_avg").replacen("{}", &avg.as_millis().to_string(), 1));
+        }
+        parts.join(" · ")
+    }
+}
+
+/// 快速连接状态
+#[derive(Default)]
+struct QuickConnectState {
+    show: bool,
+    input: String,
+    /// 解析失败时的提示（歧义候选或未找到）
+    message: Option<String>,
+}
+
+/// 快速连接输入的解析结果
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum QuickConnectMatch {
+    /// 唯一匹配，值为主机在列表中的下标
+    Found(usize),
+    /// 前缀匹配到多个主机
+    Ambiguous(Vec<String>),
+    NotFound,
+}
+
+/// 根据列表中显示的序号（从1开始）或唯一的主机名前缀查找主机
+///
+/// 完全相同的主机名优先于前缀匹配，前缀比较不区分大小写
+pub(crate) fn resolve_quick_connect(hosts: &[SshHost], input: &str) -> QuickConnectMatch {
+    let input = input.trim();
+    if input.is_empty() {
+        return QuickConnectMatch::NotFound;
+    }
+
+    if let Ok(index) = input.parse::<usize>()
+        && (1..=hosts.len()).contains(&index)
+    {
+        return QuickConnectMatch::Found(index - 1);
+    }
+
+    if let Some(index) = hosts.iter().position(|h| h.host == input) {
+        return QuickConnectMatch::Found(index);
+    }
+
+    let prefix = input.to_lowercase();
+    let matches: Vec<usize> = hosts
+        .iter()
+        .enumerate()
+        .filter(|(_, h)| h.host.to_lowercase().starts_with(&prefix))
+        .map(|(i, _)| i)
+        .collect();
+
+    match matches.as_slice() {
+        [] => QuickConnectMatch::NotFound,
+        [index] => QuickConnectMatch::Found(*index),
+        _ => QuickConnectMatch::Ambiguous(matches.iter().map(|&i| hosts[i].host.clone()).collect()),
+    }
+}
+
+/// 以指定用户连接的输入状态
+#[derive(Default)]
+struct UserOverrideState {
+    show: bool,
+    /// 要连接的目标主机
+    target: Option<String>,
+    /// 输入的用户名，初始为主机配置中的User
+    input: String,
+    /// 用户名无效时的提示
+    message: Option<String>,
+}
+
+/// 测试后连接时等待确认的状态
+#[derive(Default)]
+struct ConnectConfirmState {
+    show: bool,
+    host: Option<String>,
+    /// 测试结果的说明
+    detail: String,
+}
+
+/// 测试后连接的决定
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum CheckedConnect {
+    /// 延迟在阈值内，直接连接
+    Connect,
+    /// 连接正常但未设置阈值或延迟超出，显示结果并请求确认
+    Confirm,
+    /// 测试失败，不连接
+    Abort,
+}
+
+/// 根据测试结果决定是否连接；`threshold` 为直接连接允许的最大延迟，None表示总是确认
+///
+/// 测试尚未完成时返回None
+pub(crate) fn checked_connect_decision(
+    status: &ConnectionStatus,
+    threshold: Option<Duration>,
+) -> Option<CheckedConnect> {
+    match status {
+        ConnectionStatus::Connected(latency) | ConnectionStatus::SshReachable(latency) => {
+            Some(if threshold.is_some_and(|max| *latency <= max) {
+                CheckedConnect::Connect
+            } else {
+                CheckedConnect::Confirm
+            })
+        }
+        ConnectionStatus::Failed(_) => Some(CheckedConnect::Abort),
+        ConnectionStatus::Unknown | ConnectionStatus::Connecting => None,
+    }
+}
+
+/// 跳板机选择状态
+#[derive(Default)]
+struct JumpPickerState {
+    show: bool,
+    /// 要连接的目标主机
+    target: Option<String>,
+    /// 可作为跳板机的其他主机
+    candidates: Vec<String>,
+    selection: usize,
+}
+
 /// 删除确认状态
 #[derive(Default)]
 struct DeleteConfirmState {
@@ -48,6 +791,10 @@ struct FormState {
     editing_field: bool,
     edit_host_original: Option<SshHost>,
     error_field_index: Option<usize>,
+    /// 保存编辑前显示的配置块差异，确认后才写入
+    diff_preview: Option<String>,
+    /// 已确认差异，下一次保存直接写入
+    diff_confirmed: bool,
 }
 
 /// 错误模态框状态
@@ -63,33 +810,109 @@ struct HostKeyConfirmState {
     show: bool,
     host: Option<String>,
     selection: usize, // 0: Yes, 1: No
+    /// 确认后要建立的会话类型
+    session: SessionKind,
 }
 
 /// UI状态管理器
 #[derive(Default)]
 struct UiState {
     search: SearchState,
+    quick_connect: QuickConnectState,
+    user_override: UserOverrideState,
+    connect_confirm: ConnectConfirmState,
+    jump_picker: JumpPickerState,
     delete_confirm: DeleteConfirmState,
     form: FormState,
     error_modal: ErrorModalState,
     host_key_confirm: HostKeyConfirmState,
+    /// 底部提示信息（下一次按键时清除）
+    notice: Option<String>,
+    /// 按主机名自然排序，关闭时保持配置文件顺序
+    sort_by_name: bool,
 }
 
 /// 终端UI管理器
 pub struct UiManager {
     config_manager: ConfigManager,
     state: UiState,
-    /// 正在进行的连接测试结果
-    pending_connection_tests: PendingConnectionTests,
+    /// 测试线程通过该通道发回结果，每个线程持有一份发送端
+    test_results_tx: mpsc::Sender<TestResult>,
+    /// 主循环每帧取出已完成的测试结果
+    test_results_rx: mpsc::Receiver<TestResult>,
+    /// 已启动、尚未收到结果的连接测试
+    pending_tests: HashSet<String>,
+    /// 测试后连接（`C`）正在等待测试结果的主机
+    checked_connect: Option<String>,
+    /// 只读模式：禁用新增、编辑和删除
+    read_only: bool,
+    /// 紧凑显示主机列表，初始值取自设置 `ui.compact`
+    compact: bool,
+    /// 状态列的显示方式，初始值取自设置 `ui.color_blind`
+    palette: StatusPalette,
+    /// 各主机最近一次连接测试结果的记录时间（connection_status 不参与序列化，单独保存）
+    test_times: HashMap<String, Instant>,
+    /// 各主机最近一次连接测试的结果，过滤或重新加载主机列表后据此恢复状态
+    host_statuses: HashMap<String, ConnectionStatus>,
+    /// ssh-agent的状态，第一次选中带IdentityFile的主机时检查
+    agent_status: Option<AgentStatus>,
+    /// 各IdentityFile是否已在ssh-agent中
+    key_statuses: HashMap<String, KeyStatus>,
+    /// 探测线程通过该通道发回服务器接受的认证方式
+    auth_results_tx: mpsc::Sender<AuthProbeResult>,
+    auth_results_rx: mpsc::Receiver<AuthProbeResult>,
+    /// 各主机的认证方式探测结果，显示在底部
+    auth_probes: HashMap<String, AuthProbeState>,
 }
 
 impl UiManager {
     /// 创建一个新的UI管理器
     pub fn new(config_manager: ConfigManager) -> Self {
+        let compact = config_manager.settings().ui.compact;
+        let palette = if config_manager.settings().ui.color_blind {
+            StatusPalette::ColorBlind
+        } else {
+            StatusPalette::Default
+        };
+        let (test_results_tx, test_results_rx) = mpsc::channel();
+        let (auth_results_tx, auth_results_rx) = mpsc::channel();
         Self {
             config_manager,
             state: UiState::default(),
-            pending_connection_tests: Arc::new(Mutex::new(Vec::new())),
+            test_results_tx,
+            test_results_rx,
+            pending_tests: HashSet::new(),
+            checked_connect: None,
+            read_only: false,
+            compact,
+            palette,
+            test_times: HashMap::new(),
+            host_statuses: HashMap::new(),
+            agent_status: None,
+            key_statuses: HashMap::new(),
+            auth_results_tx,
+            auth_results_rx,
+            auth_probes: HashMap::new(),
+        }
+    }
+
+    /// 设置只读模式，开启后仍可连接、搜索和测试，但不能修改配置；
+    /// 紧凑和色盲模式的切换只在本次运行中生效，不写入设置文件
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    /// 设置紧凑显示模式（只影响本次运行，TUI中按 `c` 切换时才会保存）
+    pub fn set_compact(&mut self, compact: bool) {
+        self.compact = compact;
+    }
+
+    /// 当前模式下的快捷键帮助文本
+    fn help_text(&self) -> String {
+        if self.read_only {
+            t("help.help_navigation_read_only")
+        } else {
+            t("help.help_navigation")
         }
     }
 
@@ -119,8 +942,10 @@ impl UiManager {
         let mut terminal = self.setup_terminal()?;
         let (mut hosts, mut selected, mut table_state) = Self::initialize_state(&hosts);
 
-        // 自动触发全部服务器的连接测试
-        self.test_all_connections(&mut hosts);
+        // 自动触发全部服务器的连接测试（可在设置中关闭）
+        if self.config_manager.settings().ui.auto_test_on_start {
+            self.test_all_connections(&mut hosts);
+        }
 
         self.main_event_loop(&mut terminal, &mut hosts, &mut selected, &mut table_state)?;
 
@@ -128,12 +953,76 @@ impl UiManager {
         Ok(())
     }
 
+    /// 只打开指定主机的编辑表单（不显示主机列表），保存或取消后退出
+    ///
+    /// 主机不存在时在进入TUI之前返回错误；返回值表示是否保存了修改
+    pub fn run_edit_form(&mut self, host: &str) -> crate::error::Result<bool> {
+        let (mut hosts, mut selected) = self.open_edit_form(host)?;
+        let mut table_state = TableState::default();
+        table_state.select(Some(selected));
+
+        let mut terminal = self.setup_terminal()?;
+        let result =
+            self.edit_form_loop(&mut terminal, &mut hosts, &mut selected, &mut table_state);
+        Self::cleanup_terminal()?;
+        Ok(result?)
+    }
+
+    /// 查找主机并用其当前配置填充编辑表单，返回主机列表和该主机的位置
+    fn open_edit_form(&mut self, host: &str) -> crate::error::Result<(Vec<SshHost>, usize)> {
+        let hosts = self.config_manager.get_hosts()?.clone();
+        let index = hosts.iter().position(|h| h.host == host).ok_or_else(|| {
+            SshConnError::HostNotFound {
+                host: host.to_string(),
+            }
+        })?;
+        self.show_edit_form(&hosts[index]);
+        Ok((hosts, index))
+    }
+
+    /// 单独编辑表单的事件循环，表单关闭时结束
+    fn edit_form_loop(
+        &mut self,
+        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+        hosts: &mut Vec<SshHost>,
+        selected: &mut usize,
+        table_state: &mut TableState,
+    ) -> io::Result<bool> {
+        loop {
+            terminal.draw(|f| {
+                let size = f.area();
+                self.render_form_popup(f, size);
+                self.render_edit_diff_popup(f, size);
+                self.render_error_modal(f, size);
+            })?;
+
+            if !event::poll(std::time::Duration::from_millis(100))? {
+                continue;
+            }
+            if let Event::Key(key) = event::read()? {
+                if self.state.error_modal.show {
+                    self.handle_error_modal();
+                    continue;
+                }
+                // 非编辑状态下按 s 保存（有变化时先确认差异，按 y 或回车保存），保存成功时表单随之关闭
+                let saving = if self.state.form.diff_preview.is_some() {
+                    matches!(key.code, KeyCode::Char('y') | KeyCode::Enter)
+                } else {
+                    key.code == KeyCode::Char('s') && !self.state.form.editing_field
+                };
+                self.handle_form_event(key, hosts, selected, table_state)?;
+                if !self.state.form.show_edit {
+                    return Ok(saving);
+                }
+            }
+        }
+    }
+
     /// 设置终端
     fn setup_terminal(&self) -> io::Result<Terminal<CrosstermBackend<io::Stdout>>> {
-        enable_raw_mode()?;
-        let mut stdout = io::stdout();
-        execute!(stdout, EnterAlternateScreen)?;
-        let backend = CrosstermBackend::new(stdout);
+        install_signal_handler();
+        enter_terminal_mode()?;
+        let backend = CrosstermBackend::new(io::stdout());
         Terminal::new(backend)
     }
 
@@ -158,41 +1047,64 @@ impl UiManager {
     ) -> io::Result<()> {
         let mut error_count = 0;
         const MAX_ERRORS: u32 = 5;
+        let mut redraw = RedrawState::default();
 
         loop {
             // 检查并更新连接测试结果
-            self.update_connection_test_results(hosts);
+            if self.update_connection_test_results(hosts) {
+                redraw.mark();
+                self.resolve_checked_connect(terminal, hosts, selected, table_state)?;
+            }
+
+            if self.update_key_status(hosts, table_state.selected()) {
+                redraw.mark();
+            }
 
-            // 渲染界面，如果渲染失败则尝试恢复
-            if let Err(e) = self.render_ui(terminal, hosts, table_state) {
-                error_count += 1;
-                if error_count >= MAX_ERRORS {
-                    // 错误次数过多，执行紧急恢复
+            if self.update_auth_probe_results() {
+                redraw.mark();
+            }
+
+            // 只在有变化时渲染界面，如果渲染失败则尝试恢复
+            let now = Instant::now();
+            if redraw.should_draw(now, self.refresh_interval()) {
+                if let Err(e) = self.render_ui(terminal, hosts, table_state) {
+                    error_count += 1;
+                    if error_count >= MAX_ERRORS {
+                        // 错误次数过多，执行紧急恢复
+                        self.emergency_terminal_recovery()?;
+                        return Err(e);
+                    }
+
+                    // 尝试恢复终端并继续
                     self.emergency_terminal_recovery()?;
-                    return Err(e);
+                    // 额外重新初始化事件系统
+                    let _ = self.reinitialize_event_system();
+                    std::thread::sleep(std::time::Duration::from_millis(100));
+                    continue;
                 }
+                // 重置错误计数
+                error_count = 0;
+                redraw.drawn(now);
+            }
 
-                // 尝试恢复终端并继续
-                self.emergency_terminal_recovery()?;
-                // 额外重新初始化事件系统
-                let _ = self.reinitialize_event_system();
-                std::thread::sleep(std::time::Duration::from_millis(100));
+            // 阻塞等待事件，超时后回到循环开头处理测试结果和定时刷新
+            if !event::poll(TICK_INTERVAL)? {
                 continue;
             }
+            redraw.mark();
 
             // 处理事件，如果返回true则退出循环
             if self.process_events(terminal, hosts, selected, table_state)? {
                 break;
             }
-
-            // 重置错误计数
-            error_count = 0;
-
-            // 确保界面及时刷新，防止SSH连接后界面冻结
-            std::thread::sleep(std::time::Duration::from_millis(50));
         }
         Ok(())
     }
+
+    /// 随时间变化的内容的刷新间隔：显示了测试时长时每秒刷新，否则只在有变化时重绘
+    fn refresh_interval(&self) -> Option<Duration> {
+        (!self.test_times.is_empty()).then_some(TEST_AGE_REFRESH)
+    }
     /// 渲染UI
     fn render_ui(
         &self,
@@ -204,21 +1116,26 @@ impl UiManager {
             let size = f.area();
 
             // 渲染搜索输入框
-            let y_offset = self.render_search_popup(f, size);
+            let y_offset = self.render_search_popup(f, size)
+                + self.render_quick_connect_popup(f, size)
+                + self.render_user_override_popup(f, size);
 
             // 渲染主表格
             self.render_main_table(f, size, y_offset, hosts, table_state);
 
             // 渲染各种弹窗
             self.render_delete_confirm_popup(f, size);
+            self.render_jump_picker(f, size);
             self.render_form_popup(f, size);
+            self.render_edit_diff_popup(f, size);
             self.render_error_modal(f, size);
             self.render_host_key_confirm(f, size);
+            self.render_connect_confirm(f, size);
         })?;
         Ok(())
     }
 
-    /// 处理事件
+    /// 处理已就绪的事件（调用前已由 `event::poll` 确认）
     fn process_events(
         &mut self,
         terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
@@ -226,12 +1143,9 @@ impl UiManager {
         selected: &mut usize,
         table_state: &mut TableState,
     ) -> io::Result<bool> {
-        // 使用较短的超时时间，确保界面响应及时
-        if !event::poll(std::time::Duration::from_millis(100))? {
-            return Ok(false);
-        }
-
         if let Event::Key(key) = event::read()? {
+            self.state.notice = None;
+
             // 处理错误模态框
             if self.state.error_modal.show {
                 self.handle_error_modal();
@@ -243,6 +1157,24 @@ impl UiManager {
                 if self.handle_search_event(key.code, hosts, selected, table_state)? {
                     return Ok(false);
                 }
+            } else if self.state.jump_picker.show {
+                self.handle_jump_picker_event(key.code, terminal, hosts, selected, table_state)?;
+                return Ok(false);
+            } else if self.state.quick_connect.show {
+                self.handle_quick_connect_event(key.code, terminal, hosts, selected, table_state)?;
+                return Ok(false);
+            } else if self.state.connect_confirm.show {
+                self.handle_connect_confirm_event(
+                    key.code,
+                    terminal,
+                    hosts,
+                    selected,
+                    table_state,
+                )?;
+                return Ok(false);
+            } else if self.state.user_override.show {
+                self.handle_user_override_event(key.code, terminal, hosts, selected, table_state)?;
+                return Ok(false);
             } else if self.state.host_key_confirm.show {
                 if self.handle_host_key_event(key.code, terminal, hosts, selected, table_state)? {
                     return Ok(false);
@@ -252,7 +1184,7 @@ impl UiManager {
                     return Ok(false);
                 }
             } else if self.state.form.show_add || self.state.form.show_edit {
-                if self.handle_form_event(key.code, hosts, selected, table_state)? {
+                if self.handle_form_event(key, hosts, selected, table_state)? {
                     return Ok(false);
                 }
             } else {
@@ -274,13 +1206,10 @@ impl UiManager {
     /// 清理终端
     fn cleanup_terminal() -> io::Result<()> {
         // 执行完整的终端清理，确保程序退出时终端状态正常
-        disable_raw_mode()?;
-        execute!(io::stdout(), LeaveAlternateScreen)?;
+        restore_terminal()?;
 
         // 额外的终端恢复，确保完全清理
-        use std::process::Command;
-        let _ = Command::new("stty").args(["sane"]).status();
-        let _ = Command::new("tput").args(["cnorm"]).status(); // 恢复光标
+        run_recovery(&mut CrosstermOps(io::stdout()), EXIT_STEPS);
 
         Ok(())
     }
@@ -320,13 +1249,182 @@ impl UiManager {
         3
     }
 
-    /// 渲染删除确认弹窗
-    fn render_delete_confirm_popup(&self, f: &mut ratatui::Frame, size: Rect) {
-        if !self.state.delete_confirm.show {
-            return;
+    /// 渲染快速连接输入框
+    fn render_quick_connect_popup(&self, f: &mut ratatui::Frame, size: Rect) -> u16 {
+        if !self.state.quick_connect.show {
+            return 0;
         }
 
-        let popup_area = self.centered_rect(50, 20, size);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(t("ui.quick_connect_prompt"));
+        let area = Rect {
+            x: 0,
+            y: 0,
+            width: size.width,
+            height: 3,
+        };
+        let mut line = format!(
+            "{}: {}█",
+            t("ui.quick_connect_label"),
+            self.state.quick_connect.input
+        );
+        if let Some(message) = &self.state.quick_connect.message {
+            line.push_str("  ");
+            line.push_str(message);
+        }
+        let para = Paragraph::new(line).alignment(Alignment::Left);
+
+        f.render_widget(block, area);
+        f.render_widget(
+            para,
+            Rect {
+                x: 2,
+                y: 1,
+                width: size.width - 4,
+                height: 1,
+            },
+        );
+        3
+    }
+
+    /// 渲染保存编辑前的差异确认弹窗，删除的行为红色，新增的行为绿色
+    fn render_edit_diff_popup(&self, f: &mut ratatui::Frame, size: Rect) {
+        let Some(diff) = &self.state.form.diff_preview else {
+            return;
+        };
+
+        let popup_area = self.centered_rect(70, 60, size);
+        f.render_widget(Clear, popup_area);
+
+        let lines: Vec<Line> = diff
+            .lines()
+            .map(|line| {
+                let style = if line.starts_with("---") || line.starts_with("+++") {
+                    Style::default().add_modifier(Modifier::BOLD)
+                } else if line.starts_with('-') {
+                    Style::default().fg(Color::Red)
+                } else if line.starts_with('+') {
+                    Style::default().fg(Color::Green)
+                } else if line.starts_with("@@") {
+                    Style::default().fg(Color::Cyan)
+                } else {
+                    Style::default()
+                };
+                Line::from(Span::styled(line.to_string(), style))
+            })
+            .collect();
+        let block = Block::default()
+            .title(t("ui.edit_diff_title"))
+            .title_bottom(t("ui.edit_diff_hint"))
+            .borders(Borders::ALL);
+        f.render_widget(Paragraph::new(lines).block(block), popup_area);
+    }
+
+    /// 渲染测试后连接的确认弹窗
+    fn render_connect_confirm(&self, f: &mut ratatui::Frame, size: Rect) {
+        let confirm = &self.state.connect_confirm;
+        if !confirm.show {
+            return;
+        }
+
+        let popup_area = self.centered_rect(50, 20, size);
+        f.render_widget(Clear, popup_area);
+
+        let unknown = t("unknown");
+        let host = confirm.host.as_deref().unwrap_or(&unknown);
+        let block = Block::default()
+            .title(t("ui.checked_connect_title").replace("{}", host))
+            .title_bottom(t("ui.checked_connect_hint"))
+            .borders(Borders::ALL);
+        f.render_widget(
+            Paragraph::new(format!("\n{}", confirm.detail))
+                .alignment(Alignment::Center)
+                .block(block),
+            popup_area,
+        );
+    }
+
+    /// 渲染以指定用户连接的输入框
+    fn render_user_override_popup(&self, f: &mut ratatui::Frame, size: Rect) -> u16 {
+        let state = &self.state.user_override;
+        if !state.show {
+            return 0;
+        }
+
+        let unknown = t("unknown");
+        let target = state.target.as_deref().unwrap_or(&unknown);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(t("ui.user_override_prompt").replace("{}", target));
+        let area = Rect {
+            x: 0,
+            y: 0,
+            width: size.width,
+            height: 3,
+        };
+        let mut line = format!("{}: {}█", t("ui.user_override_label"), state.input);
+        if let Some(message) = &state.message {
+            line.push_str("  ");
+            line.push_str(message);
+        }
+        let para = Paragraph::new(line).alignment(Alignment::Left);
+
+        f.render_widget(block, area);
+        f.render_widget(
+            para,
+            Rect {
+                x: 2,
+                y: 1,
+                width: size.width - 4,
+                height: 1,
+            },
+        );
+        3
+    }
+
+    /// 渲染跳板机选择弹窗
+    fn render_jump_picker(&self, f: &mut ratatui::Frame, size: Rect) {
+        let picker = &self.state.jump_picker;
+        if !picker.show {
+            return;
+        }
+
+        let popup_area = self.centered_rect(50, 50, size);
+        f.render_widget(Clear, popup_area);
+
+        let unknown = t("unknown");
+        let target = picker.target.as_deref().unwrap_or(&unknown);
+        let block = Block::default()
+            .title(t("ui.jump_picker_title").replace("{}", target))
+            .title_bottom(t("ui.jump_picker_hint"))
+            .borders(Borders::ALL);
+
+        // 保持选中项在可见范围内
+        let visible = popup_area.height.saturating_sub(2).max(1) as usize;
+        let offset = picker.selection.saturating_sub(visible - 1);
+        let lines: Vec<String> = picker
+            .candidates
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .take(visible)
+            .map(|(i, name)| {
+                let marker = if i == picker.selection { "▍ " } else { "  " };
+                format!("{}{}", marker, name)
+            })
+            .collect();
+
+        f.render_widget(Paragraph::new(lines.join("\n")).block(block), popup_area);
+    }
+
+    /// 渲染删除确认弹窗
+    fn render_delete_confirm_popup(&self, f: &mut ratatui::Frame, size: Rect) {
+        if !self.state.delete_confirm.show {
+            return;
+        }
+
+        let popup_area = self.centered_rect(50, 20, size);
         let inner_area = Rect {
             x: popup_area.x + 1,
             y: popup_area.y + 1,
@@ -419,73 +1517,226 @@ impl UiManager {
         hosts: &[SshHost],
         table_state: &mut TableState,
     ) {
+        // 最后一行留给整体状态统计
         let table_area = Rect {
             x: 0,
             y: y_offset,
             width: size.width,
-            height: size.height - y_offset,
+            height: size.height.saturating_sub(y_offset + 1),
+        };
+        let summary_area = Rect {
+            x: 0,
+            y: size.height.saturating_sub(1),
+            width: size.width,
+            height: size.height.min(1),
         };
 
-        let header = Row::new(vec![
-            Cell::from("Host"),
-            Cell::from("HostName"),
-            Cell::from("User"),
-            Cell::from("Port"),
-            Cell::from("Status"),
-            Cell::from("ProxyCommand"),
-            Cell::from("IdentityFile"),
-        ])
-        .style(Style::default().add_modifier(Modifier::BOLD));
-
-        let rows: Vec<Row> = hosts
-            .iter()
-            .map(|h| {
-                Row::new(vec![
-                    Cell::from(h.host.clone()),
-                    Cell::from(h.hostname.clone().unwrap_or_default()),
-                    Cell::from(h.user.clone().unwrap_or_default()),
-                    Cell::from(h.port.clone().unwrap_or_default()),
-                    Cell::from(h.connection_status.display_string()),
-                    Cell::from(h.proxy_command.clone().unwrap_or_default()),
-                    Cell::from(h.identity_file.clone().unwrap_or_default()),
-                ])
-            })
-            .collect();
-
-        let title = if let Some(query) = &self.state.search.query {
+        let mut title = if let Some(query) = &self.state.search.query {
             format!(
                 "{} ({}: {}) ({})",
                 t("ui.server_list"),
                 t("ui.search_result"),
                 query,
-                t("help.help_navigation")
+                self.help_text()
             )
         } else {
-            format!("{} ({})", t("ui.server_list"), t("help.help_navigation"))
+            format!("{} ({})", t("ui.server_list"), self.help_text())
         };
+        if let Some(profile) = &self.config_manager.settings().active_profile {
+            title = format!("[{}] {}", profile, title);
+        }
+        if self.read_only {
+            title = format!("[{}] {}", t("ui.read_only_badge"), title);
+        }
 
-        let table = Table::new(
-            rows,
-            &[
-                Constraint::Min(15),    // Host 列 - 最小15字符
-                Constraint::Min(15),    // HostName 列 - 最小15字符
-                Constraint::Length(8),  // User 列
-                Constraint::Length(6),  // Port 列
-                Constraint::Length(12), // Status 列
-                Constraint::Min(20),    // ProxyCommand 列 - 最小20字符
-                Constraint::Min(20),    // IdentityFile 列 - 最小20字符
-            ],
-        )
-        .header(header)
-        .block(Block::default().borders(Borders::ALL).title(title))
-        .row_highlight_style(
-            Style::default()
-                .fg(Color::Black)
-                .bg(Color::Yellow)
-                .add_modifier(Modifier::BOLD | Modifier::REVERSED),
-        )
-        .highlight_symbol("▍ ");
+        let footer = match &self.state.notice {
+            Some(notice) => Some(Line::from(notice.as_str())),
+            None => self.selected_host_footer(hosts, table_state.selected()),
+        };
+        let table = build_host_table(
+            hosts,
+            &self.host_columns(),
+            &|host| self.config_manager.has_stored_password(host),
+            title,
+            footer,
+            self.compact,
+            self.palette,
+        );
         f.render_stateful_widget(table, table_area, table_state);
+
+        let summary = FleetSummary::from_statuses(hosts.iter().map(|h| &h.connection_status));
+        f.render_widget(
+            Paragraph::new(format!(" {}", summary.format()))
+                .style(Style::default().fg(Color::DarkGray)),
+            summary_area,
+        );
+    }
+
+    /// 主机列表显示的列，取自设置 `ui.columns`，未设置时为默认列（紧凑模式下为主要的列）
+    fn host_columns(&self) -> Vec<HostColumn> {
+        self.config_manager
+            .settings()
+            .ui
+            .columns
+            .as_deref()
+            .and_then(|names| parse_columns(names).ok())
+            .unwrap_or_else(|| {
+                if self.compact {
+                    HostColumn::COMPACT.to_vec()
+                } else {
+                    HostColumn::DEFAULT.to_vec()
+                }
+            })
+    }
+
+    /// 把界面开关写入设置文件，只读模式下不写入，只在本次运行中生效
+    fn save_ui_toggle(&self, key: &str, value: bool) -> crate::error::Result<()> {
+        if self.read_only {
+            return Ok(());
+        }
+        let path = crate::utils::get_settings_path()?;
+        crate::settings::Settings::set_value(&path, key, &value.to_string()).map(|_| ())
+    }
+
+    /// 切换紧凑显示模式并保存到设置文件 `ui.compact`
+    fn toggle_compact(&mut self) {
+        self.compact = !self.compact;
+        let saved = self.save_ui_toggle("ui.compact", self.compact);
+        self.state.notice = Some(match saved {
+            Ok(_) if self.compact => t("ui.compact_on"),
+            Ok(_) => t("ui.compact_off"),
+            Err(e) => t("ui.compact_save_failed").replace("{}", &e.to_string()),
+        });
+    }
+
+    /// 切换色盲友好的状态显示并保存到设置文件 `ui.color_blind`
+    fn toggle_color_blind(&mut self) {
+        self.palette = match self.palette {
+            StatusPalette::Default => StatusPalette::ColorBlind,
+            StatusPalette::ColorBlind => StatusPalette::Default,
+        };
+        let color_blind = self.palette == StatusPalette::ColorBlind;
+        let saved = self.save_ui_toggle("ui.color_blind", color_blind);
+        self.state.notice = Some(match saved {
+            Ok(_) if color_blind => t("ui.color_blind_on"),
+            Ok(_) => t("ui.color_blind_off"),
+            Err(e) => t("ui.color_blind_save_failed").replace("{}", &e.to_string()),
+        });
+    }
+
+    /// 选中主机（含 `Host *` 中）的IdentityFile
+    fn identity_file_of(&self, host: &SshHost) -> Option<String> {
+        self.config_manager.effective_host(host).identity_file
+    }
+
+    /// 检查选中主机的私钥是否在ssh-agent中，每个IdentityFile只检查一次；有新结果时返回true
+    fn update_key_status(&mut self, hosts: &[SshHost], selected: Option<usize>) -> bool {
+        let Some(identity_file) = selected
+            .and_then(|i| hosts.get(i))
+            .and_then(|host| self.identity_file_of(host))
+        else {
+            return false;
+        };
+        if self.key_statuses.contains_key(&identity_file) {
+            return false;
+        }
+        let agent = self.agent_status.get_or_insert_with(agent::agent_status);
+        let status = agent::key_status(&identity_file, agent);
+        self.key_statuses.insert(identity_file, status);
+        true
+    }
+
+    /// 选中主机的底部提示：经Include引入的主机显示所在文件；
+    /// 测试过的主机显示上次测试时间，过期结果淡化显示，测试失败时附上排查建议
+    fn selected_host_footer(
+        &self,
+        hosts: &[SshHost],
+        selected: Option<usize>,
+    ) -> Option<Line<'static>> {
+        let host = hosts.get(selected?)?;
+        let mut spans = Vec::new();
+        if let Some(source) = host
+            .source_file
+            .as_deref()
+            .filter(|path| *path != std::path::Path::new(self.config_manager.config_path()))
+        {
+            spans.push(Span::styled(
+                t("ui.source_file").replace("{}", &source.display().to_string()),
+                Style::default().fg(Color::Cyan),
+            ));
+        }
+
+        if let Some(defaults) = self.config_manager.wildcard_defaults() {
+            let inherited: Vec<String> = host
+                .inherited_options(defaults)
+                .into_iter()
+                .map(|(keyword, value)| format!("{} {}", keyword, value))
+                .collect();
+            if !inherited.is_empty() {
+                if !spans.is_empty() {
+                    spans.push(Span::raw(" · "));
+                }
+                spans.push(Span::styled(
+                    t("ui.inherited_defaults").replace("{}", &inherited.join(", ")),
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+        }
+
+        if let Some(status) = self
+            .identity_file_of(host)
+            .and_then(|identity_file| self.key_statuses.get(&identity_file))
+        {
+            let (text, color) = match status {
+                KeyStatus::Loaded => (t("ui.agent_key_loaded"), Color::Green),
+                KeyStatus::NotLoaded => (t("ui.agent_key_not_loaded"), Color::Yellow),
+                KeyStatus::NoAgent => (t("ui.agent_key_no_agent"), Color::DarkGray),
+            };
+            if !spans.is_empty() {
+                spans.push(Span::raw(" · "));
+            }
+            spans.push(Span::styled(text, Style::default().fg(color)));
+        }
+
+        if let Some(probe) = self.auth_probes.get(&host.host) {
+            let (text, color) = match probe {
+                AuthProbeState::Probing => (t("ui.auth_probing"), Color::DarkGray),
+                AuthProbeState::Done(AuthMethods::NoneRequired) => {
+                    (t("ui.auth_none_required"), Color::Yellow)
+                }
+                AuthProbeState::Done(AuthMethods::Offered(methods)) => (
+                    t("ui.auth_methods").replace("{}", &methods.join(",")),
+                    Color::Cyan,
+                ),
+                AuthProbeState::Failed(reason) => {
+                    (t("ui.auth_probe_failed").replace("{}", reason), Color::Red)
+                }
+            };
+            if !spans.is_empty() {
+                spans.push(Span::raw(" · "));
+            }
+            spans.push(Span::styled(text, Style::default().fg(color)));
+        }
+
+        if let Some(tested_at) = self.test_times.get(&host.host) {
+            let (text, stale) = format_test_age(tested_at.elapsed(), TEST_STALE_AFTER);
+            let style = if stale {
+                Style::default()
+                    .fg(Color::DarkGray)
+                    .add_modifier(Modifier::DIM)
+            } else {
+                Style::default()
+            };
+            if !spans.is_empty() {
+                spans.push(Span::raw(" · "));
+            }
+            spans.push(Span::styled(text, style));
+            if let Some(hint) = host.connection_status.troubleshooting_hint() {
+                spans.push(Span::raw(" · "));
+                spans.push(Span::styled(hint, Style::default().fg(Color::Yellow)));
+            }
+        }
+        (!spans.is_empty()).then(|| Line::from(spans))
     }
 
     /// 构建表单文本
@@ -527,6 +1778,20 @@ impl UiManager {
         let is_focused = index == self.state.form.focus_index;
         let is_editing = self.state.form.editing_field && is_focused;
 
+        // 选择字段显示为 ◀ 选项 ▶，用左右方向键或Enter切换
+        let display;
+        let field = if matches!(field.field_type, FormFieldType::Select(_)) {
+            let value = if field.value.is_empty() {
+                t("form.template_none")
+            } else {
+                field.value.clone()
+            };
+            display = FormField::new(field.label.clone(), format!("◀ {} ▶", value));
+            &display
+        } else {
+            field
+        };
+
         match (is_focused, is_editing, is_readonly, is_error) {
             (true, true, false, false) => format!("▶ {}: {}█", field.label, field.value),
             (true, true, false, true) => format!("▶ ❌ {}: {}█", field.label, field.value),
@@ -566,13 +1831,18 @@ impl UiManager {
         f.render_widget(error_block, popup_area);
 
         let press_any_key_text = t("press_any_key");
-        let error_text = [
-            "",
-            &self.state.error_modal.message,
-            "",
-            &press_any_key_text,
-            "",
+        let mut error_text = vec![
+            String::new(),
+            self.state.error_modal.message.clone(),
+            String::new(),
         ];
+        // 启用了日志文件时提示详细信息的位置
+        if let Some(path) = crate::logfile::active_log_path() {
+            error_text.push(format!("{}: {}", t("ui.log_file_hint"), path.display()));
+            error_text.push(String::new());
+        }
+        error_text.push(press_any_key_text.to_string());
+        error_text.push(String::new());
         let error_paragraph = Paragraph::new(error_text.join("\n"))
             .alignment(Alignment::Center)
             .style(Style::default().fg(Color::White));
@@ -723,65 +1993,197 @@ impl UiManager {
             }
         };
 
+        // 验证RequestTTY
+        let request_tty = if self.state.form.fields[7].value.trim().is_empty() {
+            None
+        } else {
+            match crate::utils::validate_request_tty(&self.state.form.fields[7].value) {
+                Ok(value) => Some(value),
+                Err(e) => {
+                    self.show_error_with_field(&e.to_string(), 7)?;
+                    self.state.form.focus_index = 7;
+                    self.state.form.editing_field = true;
+                    return Ok(false);
+                }
+            }
+        };
+
+        // 验证SetEnv
+        let set_env = match crate::utils::parse_set_env(&self.state.form.fields[8].value) {
+            Ok(entries) => entries,
+            Err(e) => {
+                self.show_error_with_field(&e.to_string(), 8)?;
+                self.state.form.focus_index = 8;
+                self.state.form.editing_field = true;
+                return Ok(false);
+            }
+        };
+
+        // 验证BindAddress
+        let bind_address = self.state.form.fields[10].value.trim().to_string();
+        if !bind_address.is_empty()
+            && let Err(e) = crate::utils::validate_bind_address(&bind_address)
+        {
+            self.show_error_with_field(&e.to_string(), 10)?;
+            self.state.form.focus_index = 10;
+            self.state.form.editing_field = true;
+            return Ok(false);
+        }
+        let address_family = self.state.form.fields[9].value.clone();
+        let permit_local_command = self.state.form.fields[11].value.clone();
+
+        // 验证LocalCommand
+        let local_command = self.state.form.fields[12].value.trim().to_string();
+        if !local_command.is_empty()
+            && let Err(e) = crate::utils::validate_local_command(&local_command)
+        {
+            self.show_error_with_field(&e.to_string(), 12)?;
+            self.state.form.focus_index = 12;
+            self.state.form.editing_field = true;
+            return Ok(false);
+        }
+
         // 保存数据
         let result = if self.state.form.show_add {
-            // 添加主机
-            self.config_manager.add_host(
-                &self.state.form.fields[0].value,
-                &self.state.form.fields[1].value,
-                if self.state.form.fields[2].value.is_empty() {
-                    None
-                } else {
-                    Some(&self.state.form.fields[2].value)
-                },
-                port,
-                if self.state.form.fields[4].value.is_empty() {
-                    None
-                } else {
-                    Some(&self.state.form.fields[4].value)
-                },
-                if self.state.form.fields[5].value.is_empty() {
-                    None
-                } else {
-                    Some(&self.state.form.fields[5].value)
-                },
-                if self.state.form.fields[6].value.is_empty() {
-                    None
-                } else {
-                    Some(&self.state.form.fields[6].value)
-                },
-            )
+            // 添加主机，表单中填写的字段优先于所选模板
+            let field = |i: usize| {
+                let value = &self.state.form.fields[i].value;
+                (!value.is_empty()).then(|| value.clone())
+            };
+            let mut entry = SshHost::new(self.state.form.fields[0].value.clone());
+            entry.hostname = field(1);
+            entry.user = field(2);
+            entry.port = port.map(|p| p.to_string());
+            entry.proxy_command = field(4);
+            entry.identity_file = field(5);
+            entry.remote_command = field(6);
+            entry.request_tty = request_tty;
+            entry.set_env = set_env;
+            entry.address_family = (!address_family.is_empty()).then_some(address_family);
+            entry.bind_address = (!bind_address.is_empty()).then_some(bind_address);
+            entry.permit_local_command =
+                (!permit_local_command.is_empty()).then_some(permit_local_command);
+            entry.local_command = (!local_command.is_empty()).then_some(local_command);
+            let password = field(13);
+            // 模板选择字段只在配置了模板时出现在添加表单末尾
+            let template = self
+                .state
+                .form
+                .fields
+                .get(14)
+                .map(|f| f.value.clone())
+                .filter(|name| !name.is_empty());
+
+            match template {
+                Some(name) => self
+                    .config_manager
+                    .settings()
+                    .template(&name)
+                    .map(|template| template.apply(&mut entry)),
+                None => Ok(()),
+            }
+            .and_then(|()| {
+                self.config_manager
+                    .add_host_entry(&entry, password.as_deref())
+            })
         } else {
-            // 编辑主机
-            self.config_manager.edit_host(
-                &self.state.form.fields[0].value,
-                if self.state.form.fields[1].value.is_empty() {
-                    None
-                } else {
-                    Some(&self.state.form.fields[1].value)
-                },
-                if self.state.form.fields[2].value.is_empty() {
-                    None
-                } else {
-                    Some(&self.state.form.fields[2].value)
-                },
-                port,
-                if self.state.form.fields[4].value.is_empty() {
-                    None
-                } else {
-                    Some(&self.state.form.fields[4].value)
-                },
-                if self.state.form.fields[5].value.is_empty() {
-                    None
-                } else {
-                    Some(&self.state.form.fields[5].value)
-                },
-                if self.state.form.fields[6].value.is_empty() {
-                    None
-                } else {
-                    Some(&self.state.form.fields[6].value)
-                },
-            )
+            // 编辑主机：表单预填了当前值，清空的字段表示删除对应指令
+            let field = |i: usize| {
+                let value = &self.state.form.fields[i].value;
+                (!value.is_empty()).then(|| value.clone())
+            };
+            let host = self.state.form.fields[0].value.clone();
+            let hostname = field(1);
+            let user = field(2);
+            let proxy_command = field(4);
+            let identity_file = field(5);
+            let remote_command = field(6);
+            let password = field(13);
+            let cleared: Vec<OptionalField> = [
+                (2, OptionalField::User),
+                (3, OptionalField::Port),
+                (4, OptionalField::ProxyCommand),
+                (5, OptionalField::IdentityFile),
+                (6, OptionalField::RemoteCommand),
+                (7, OptionalField::RequestTty),
+                (9, OptionalField::AddressFamily),
+                (10, OptionalField::BindAddress),
+                (11, OptionalField::PermitLocalCommand),
+                (12, OptionalField::LocalCommand),
+            ]
+            .into_iter()
+            .filter(|(i, _)| self.state.form.fields[*i].value.trim().is_empty())
+            .map(|(_, field)| field)
+            .collect();
+            let network: Vec<(&str, &str)> = [
+                ("AddressFamily", address_family.as_str()),
+                ("BindAddress", bind_address.as_str()),
+                ("PermitLocalCommand", permit_local_command.as_str()),
+                ("LocalCommand", local_command.as_str()),
+            ]
+            .into_iter()
+            .filter(|(_, value)| !value.is_empty())
+            .collect();
+
+            // 先显示配置块的差异，确认后再次进入这里才写入；没有变化时直接保存（如只修改密码）
+            let confirmed = std::mem::take(&mut self.state.form.diff_confirmed);
+            let preview = if confirmed {
+                Ok(None)
+            } else {
+                self.config_manager
+                    .preview_edit_host(
+                        &host,
+                        hostname.as_deref(),
+                        user.as_deref(),
+                        port,
+                        proxy_command.as_deref(),
+                        identity_file.as_deref(),
+                        remote_command.as_deref(),
+                        request_tty.as_deref(),
+                        // 表单中预填了当前的SetEnv，清空即表示删除
+                        Some(&set_env),
+                        &cleared,
+                        &network,
+                    )
+                    .map(|updated| {
+                        let diff = self
+                            .state
+                            .form
+                            .edit_host_original
+                            .as_ref()
+                            .map(|original| original.config_diff(&updated))
+                            .unwrap_or_default();
+                        (!diff.is_empty()).then_some(diff)
+                    })
+            };
+
+            match preview {
+                Ok(Some(diff)) => {
+                    self.state.form.diff_preview = Some(diff);
+                    return Ok(false);
+                }
+                Ok(None) => self
+                    .config_manager
+                    .edit_host(
+                        &host,
+                        hostname.as_deref(),
+                        user.as_deref(),
+                        port,
+                        proxy_command.as_deref(),
+                        identity_file.as_deref(),
+                        remote_command.as_deref(),
+                        request_tty.as_deref(),
+                        Some(&set_env),
+                        password.as_deref(),
+                        &cleared,
+                    )
+                    .and_then(|()| {
+                        network.iter().try_for_each(|(keyword, value)| {
+                            self.config_manager.set_host_option(&host, keyword, value)
+                        })
+                    }),
+                Err(e) => Err(e),
+            }
         };
 
         match result {
@@ -789,6 +2191,8 @@ impl UiManager {
                 // 保存成功，重新加载主机列表
                 self.config_manager.clear_cache();
                 *hosts = self.config_manager.get_hosts()?.clone();
+                self.restore_statuses(hosts);
+                self.sort_hosts(hosts);
 
                 if self.state.form.show_add {
                     *selected = 0;
@@ -805,7 +2209,14 @@ impl UiManager {
                 Ok(true)
             }
             Err(e) => {
-                self.show_error_message(&e.to_string())?;
+                // 输入校验错误定位到对应的表单字段
+                if let Some(index) = e.field().and_then(form_field_index) {
+                    self.show_error_with_field(&e.to_string(), index)?;
+                    self.state.form.focus_index = index;
+                    self.state.form.editing_field = true;
+                } else {
+                    self.show_error_message(&e.to_string())?;
+                }
                 Ok(false)
             }
         }
@@ -827,8 +2238,10 @@ impl UiManager {
                     *hosts = self.config_manager.get_hosts()?.clone();
                 } else {
                     self.state.search.query = Some(query.clone());
-                    *hosts = self.config_manager.search_hosts(&query)?;
+                    *hosts = self.config_manager.search_hosts_ranked(&query)?;
                 }
+                self.restore_statuses(hosts);
+                self.sort_hosts(hosts);
                 *selected = 0;
                 if !hosts.is_empty() {
                     table_state.select(Some(*selected));
@@ -858,47 +2271,318 @@ impl UiManager {
         }
     }
 
-    /// 更新搜索结果
-    fn update_search_results(
+    /// 处理快速连接输入
+    fn handle_quick_connect_event(
         &mut self,
+        key: KeyCode,
+        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
         hosts: &mut Vec<SshHost>,
         selected: &mut usize,
         table_state: &mut TableState,
     ) -> io::Result<()> {
-        let query = self.state.search.input.trim();
-        if query.is_empty() {
-            self.state.search.query = None;
-            *hosts = self.config_manager.get_hosts()?.clone();
-        } else {
-            self.state.search.query = Some(query.to_string());
-            *hosts = self.config_manager.search_hosts(query)?;
-        }
-        *selected = 0;
-        if !hosts.is_empty() {
-            table_state.select(Some(*selected));
-        } else {
-            table_state.select(None);
+        match key {
+            KeyCode::Enter => match resolve_quick_connect(hosts, &self.state.quick_connect.input) {
+                QuickConnectMatch::Found(index) => {
+                    self.reset_quick_connect();
+                    *selected = index;
+                    table_state.select(Some(index));
+                    let host = hosts[index].host.clone();
+                    self.handle_connect_request(
+                        &host,
+                        SessionKind::Shell,
+                        terminal,
+                        hosts,
+                        selected,
+                        table_state,
+                    )?;
+                }
+                QuickConnectMatch::Ambiguous(candidates) => {
+                    self.state.quick_connect.message =
+                        Some(t("ui.quick_connect_ambiguous").replace("{}", &candidates.join(", ")));
+                }
+                QuickConnectMatch::NotFound => {
+                    self.state.quick_connect.message = Some(
+                        t("ui.quick_connect_not_found")
+                            .replace("{}", self.state.quick_connect.input.trim()),
+                    );
+                }
+            },
+            KeyCode::Esc => self.reset_quick_connect(),
+            KeyCode::Char(c) => {
+                self.state.quick_connect.input.push(c);
+                self.state.quick_connect.message = None;
+            }
+            KeyCode::Backspace => {
+                self.state.quick_connect.input.pop();
+                self.state.quick_connect.message = None;
+            }
+            _ => {}
         }
         Ok(())
     }
 
-    /// 处理删除确认事件
-    fn handle_delete_confirm_event(
+    /// 测试后连接：等待的测试有结果后按 [`checked_connect_decision`] 直接连接、请求确认或报告失败
+    fn resolve_checked_connect(
         &mut self,
-        key: KeyCode,
+        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
         hosts: &mut Vec<SshHost>,
         selected: &mut usize,
         table_state: &mut TableState,
-    ) -> io::Result<bool> {
-        match key {
-            KeyCode::Enter => {
-                if self.state.delete_confirm.input.trim().to_lowercase() == "yes" {
-                    if let Some(host_to_delete) = &self.state.delete_confirm.host {
-                        let _ = self.config_manager.delete_host(host_to_delete);
-                        self.reset_delete_confirm();
-                        self.reload_hosts(hosts, selected, table_state)?;
-                    }
-                }
+    ) -> io::Result<()> {
+        let Some(host) = self
+            .checked_connect
+            .take_if(|host| !self.pending_tests.contains(host))
+        else {
+            return Ok(());
+        };
+        let Some(status) = self.host_statuses.get(&host).cloned() else {
+            return Ok(());
+        };
+
+        let threshold = self
+            .config_manager
+            .settings()
+            .ui
+            .auto_connect_max_latency_ms
+            .map(Duration::from_millis);
+        match checked_connect_decision(&status, threshold) {
+            Some(CheckedConnect::Connect) => {
+                // 先显示测试结果，再退出界面连接
+                self.state.notice = Some(status.detail_string());
+                self.render_ui(terminal, hosts, table_state)?;
+                thread::sleep(CHECKED_CONNECT_PAUSE);
+                self.handle_connect_request(
+                    &host,
+                    SessionKind::Shell,
+                    terminal,
+                    hosts,
+                    selected,
+                    table_state,
+                )?;
+            }
+            Some(CheckedConnect::Confirm) => {
+                self.state.connect_confirm = ConnectConfirmState {
+                    show: true,
+                    host: Some(host),
+                    detail: status.detail_string(),
+                };
+            }
+            Some(CheckedConnect::Abort) => {
+                let mut message = status.detail_string();
+                if let Some(hint) = status.troubleshooting_hint() {
+                    message = format!("{}\n{}", message, hint);
+                }
+                self.show_error_message(&message)?;
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// 处理测试后连接的确认，y/回车连接，n/Esc取消
+    fn handle_connect_confirm_event(
+        &mut self,
+        key: KeyCode,
+        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+        hosts: &mut Vec<SshHost>,
+        selected: &mut usize,
+        table_state: &mut TableState,
+    ) -> io::Result<()> {
+        match key {
+            KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
+                let confirm = std::mem::take(&mut self.state.connect_confirm);
+                if let Some(host) = confirm.host {
+                    self.handle_connect_request(
+                        &host,
+                        SessionKind::Shell,
+                        terminal,
+                        hosts,
+                        selected,
+                        table_state,
+                    )?;
+                }
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc | KeyCode::Char('q') => {
+                self.state.connect_confirm = ConnectConfirmState::default();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// 打开以指定用户连接的输入框，用户名初始为主机配置中的User
+    fn show_user_override(&mut self, host: &SshHost) {
+        self.state.user_override = UserOverrideState {
+            show: true,
+            target: Some(host.host.clone()),
+            input: host.user.clone().unwrap_or_default(),
+            message: None,
+        };
+    }
+
+    /// 处理用户名输入，回车后以该用户连接目标主机（仅本次连接，不修改配置）
+    fn handle_user_override_event(
+        &mut self,
+        key: KeyCode,
+        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+        hosts: &mut Vec<SshHost>,
+        selected: &mut usize,
+        table_state: &mut TableState,
+    ) -> io::Result<()> {
+        let state = &mut self.state.user_override;
+        match key {
+            KeyCode::Enter => {
+                let user = state.input.trim().to_string();
+                if let Err(e) = crate::utils::validate_username(&user) {
+                    state.message = Some(e.to_string());
+                    return Ok(());
+                }
+                let state = std::mem::take(&mut self.state.user_override);
+                if let Some(target) = state.target {
+                    self.exit_and_connect(
+                        &target,
+                        SessionKind::Shell,
+                        None,
+                        Some(&user),
+                        terminal,
+                        hosts,
+                        selected,
+                        table_state,
+                    )?;
+                }
+            }
+            KeyCode::Esc => self.state.user_override = UserOverrideState::default(),
+            KeyCode::Char(c) => {
+                state.input.push(c);
+                state.message = None;
+            }
+            KeyCode::Backspace => {
+                state.input.pop();
+                state.message = None;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// 打开跳板机选择弹窗，候选为配置中的其他主机
+    fn show_jump_picker(&mut self, target: &str) -> io::Result<()> {
+        let candidates: Vec<String> = self
+            .config_manager
+            .get_hosts()?
+            .iter()
+            .map(|h| h.host.clone())
+            .filter(|name| name != target)
+            .collect();
+
+        if candidates.is_empty() {
+            self.state.notice = Some(t("ui.jump_picker_empty"));
+            return Ok(());
+        }
+
+        self.state.jump_picker = JumpPickerState {
+            show: true,
+            target: Some(target.to_string()),
+            candidates,
+            selection: 0,
+        };
+        Ok(())
+    }
+
+    /// 处理跳板机选择事件，回车后经选中的跳板机连接目标主机
+    fn handle_jump_picker_event(
+        &mut self,
+        key: KeyCode,
+        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+        hosts: &mut Vec<SshHost>,
+        selected: &mut usize,
+        table_state: &mut TableState,
+    ) -> io::Result<()> {
+        let picker = &mut self.state.jump_picker;
+        match key {
+            KeyCode::Down | KeyCode::Char('j')
+                if picker.selection + 1 < picker.candidates.len() =>
+            {
+                picker.selection += 1;
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                picker.selection = picker.selection.saturating_sub(1);
+            }
+            KeyCode::Enter => {
+                let picker = std::mem::take(&mut self.state.jump_picker);
+                if let (Some(target), Some(jump)) =
+                    (picker.target, picker.candidates.get(picker.selection))
+                {
+                    self.exit_and_connect(
+                        &target,
+                        SessionKind::Shell,
+                        Some(jump),
+                        None,
+                        terminal,
+                        hosts,
+                        selected,
+                        table_state,
+                    )?;
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.state.jump_picker = JumpPickerState::default();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// 关闭并清空快速连接输入框
+    fn reset_quick_connect(&mut self) {
+        self.state.quick_connect.show = false;
+        self.state.quick_connect.input.clear();
+        self.state.quick_connect.message = None;
+    }
+
+    /// 更新搜索结果
+    fn update_search_results(
+        &mut self,
+        hosts: &mut Vec<SshHost>,
+        selected: &mut usize,
+        table_state: &mut TableState,
+    ) -> io::Result<()> {
+        let query = self.state.search.input.trim();
+        if query.is_empty() {
+            self.state.search.query = None;
+            *hosts = self.config_manager.get_hosts()?.clone();
+        } else {
+            self.state.search.query = Some(query.to_string());
+            *hosts = self.config_manager.search_hosts_ranked(query)?;
+        }
+        self.restore_statuses(hosts);
+        self.sort_hosts(hosts);
+        *selected = 0;
+        if !hosts.is_empty() {
+            table_state.select(Some(*selected));
+        } else {
+            table_state.select(None);
+        }
+        Ok(())
+    }
+
+    /// 处理删除确认事件
+    fn handle_delete_confirm_event(
+        &mut self,
+        key: KeyCode,
+        hosts: &mut Vec<SshHost>,
+        selected: &mut usize,
+        table_state: &mut TableState,
+    ) -> io::Result<bool> {
+        match key {
+            KeyCode::Enter => {
+                if self.state.delete_confirm.input.trim().to_lowercase() == "yes"
+                    && let Some(host_to_delete) = &self.state.delete_confirm.host
+                {
+                    let _ = self.config_manager.delete_host(host_to_delete);
+                    self.reset_delete_confirm();
+                    self.reload_hosts(hosts, selected, table_state)?;
+                }
                 Ok(true)
             }
             KeyCode::Esc => {
@@ -933,6 +2617,8 @@ impl UiManager {
     ) -> io::Result<()> {
         self.config_manager.clear_cache();
         *hosts = self.config_manager.get_hosts()?.clone();
+        self.restore_statuses(hosts);
+        self.sort_hosts(hosts);
         if *selected >= hosts.len() && !hosts.is_empty() {
             *selected = hosts.len() - 1;
         }
@@ -944,15 +2630,71 @@ impl UiManager {
         Ok(())
     }
 
+    /// 手动从磁盘重新加载配置（`R`/F5），保持当前的搜索条件并按名称重新选中之前的主机
+    fn reload_from_disk(
+        &mut self,
+        hosts: &mut Vec<SshHost>,
+        selected: &mut usize,
+        table_state: &mut TableState,
+    ) -> io::Result<()> {
+        let previous = hosts.get(*selected).map(|h| h.host.clone());
+        self.config_manager.clear_cache();
+        let reloaded = match &self.state.search.query {
+            Some(query) => self.config_manager.search_hosts_ranked(query),
+            None => self.config_manager.get_hosts().cloned(),
+        };
+        match reloaded {
+            Ok(reloaded) => *hosts = reloaded,
+            Err(e) => return self.show_error_message(&e.localized_message()),
+        }
+        self.restore_statuses(hosts);
+        self.sort_hosts(hosts);
+
+        let index = reselect_by_name(hosts, previous.as_deref(), *selected);
+        *selected = index.unwrap_or(0);
+        table_state.select(index);
+        self.state.notice = Some(t("ui.reloaded").replace("{}", &hosts.len().to_string()));
+        Ok(())
+    }
+
     /// 处理表单事件
     fn handle_form_event(
         &mut self,
-        key: KeyCode,
+        key: KeyEvent,
         hosts: &mut Vec<SshHost>,
         selected: &mut usize,
         table_state: &mut TableState,
     ) -> io::Result<bool> {
-        match key {
+        if self.state.form.diff_preview.is_some() {
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Enter => {
+                    self.state.form.diff_preview = None;
+                    self.state.form.diff_confirmed = true;
+                    if self.save_form_data(hosts, selected, table_state)? {
+                        self.reset_form();
+                    }
+                }
+                KeyCode::Char('n') | KeyCode::Esc | KeyCode::Char('q') => {
+                    // 回到表单继续修改
+                    self.state.form.diff_preview = None;
+                }
+                _ => {}
+            }
+            return Ok(true);
+        }
+
+        match key.code {
+            KeyCode::Char('k')
+                if key.modifiers.contains(KeyModifiers::CONTROL)
+                    && self.state.form.editing_field =>
+            {
+                self.handle_form_clear();
+                Ok(true)
+            }
+            KeyCode::Delete if self.state.form.editing_field => {
+                self.handle_form_clear();
+                Ok(true)
+            }
             KeyCode::Esc => {
                 if self.state.form.editing_field {
                     self.state.form.editing_field = false;
@@ -981,6 +2723,12 @@ impl UiManager {
                 self.move_form_focus_up();
                 Ok(true)
             }
+            KeyCode::Left | KeyCode::Right if !self.state.form.editing_field => {
+                if let Some(field) = self.focused_select_field() {
+                    field.cycle_option(key.code == KeyCode::Right);
+                }
+                Ok(true)
+            }
             KeyCode::Enter => {
                 self.handle_form_enter();
                 Ok(true)
@@ -1020,6 +2768,8 @@ impl UiManager {
         self.state.form.editing_field = false;
         self.state.form.edit_host_original = None;
         self.state.form.error_field_index = None;
+        self.state.form.diff_preview = None;
+        self.state.form.diff_confirmed = false;
     }
 
     /// 移动表单焦点到下一个字段
@@ -1052,8 +2802,24 @@ impl UiManager {
         }
     }
 
+    /// 当前焦点所在的选择字段
+    fn focused_select_field(&mut self) -> Option<&mut FormField> {
+        self.state
+            .form
+            .fields
+            .get_mut(self.state.form.focus_index)
+            .filter(|f| matches!(f.field_type, FormFieldType::Select(_)))
+    }
+
     /// 处理表单Enter键
     fn handle_form_enter(&mut self) {
+        // 选择字段不进入编辑状态，Enter直接切换到下一个选项
+        if let Some(field) = self.focused_select_field() {
+            field.cycle_option(true);
+            self.state.form.editing_field = false;
+            return;
+        }
+
         if self.state.form.editing_field {
             self.state.form.editing_field = false;
             if self.state.form.focus_index + 1 < self.state.form.fields.len() {
@@ -1095,6 +2861,17 @@ impl UiManager {
         }
     }
 
+    /// 清空当前字段（Ctrl+K / Delete），编辑表单中只读的Host字段不受影响
+    fn handle_form_clear(&mut self) {
+        if self.state.form.focus_index < self.state.form.fields.len()
+            && !(self.state.form.show_edit && self.state.form.focus_index == 0)
+        {
+            self.state.form.fields[self.state.form.focus_index]
+                .value
+                .clear();
+        }
+    }
+
     /// 处理主机密钥确认事件
     fn handle_host_key_event(
         &mut self,
@@ -1106,10 +2883,18 @@ impl UiManager {
     ) -> io::Result<bool> {
         match key {
             KeyCode::Enter => {
-                if let Some(host) = self.state.host_key_confirm.host.clone() {
-                    if self.state.host_key_confirm.selection == 0 {
-                        self.handle_host_key_accept(&host, terminal, hosts, selected, table_state)?;
-                    }
+                if let Some(host) = self.state.host_key_confirm.host.clone()
+                    && self.state.host_key_confirm.selection == 0
+                {
+                    let session = self.state.host_key_confirm.session;
+                    self.handle_host_key_accept(
+                        &host,
+                        session,
+                        terminal,
+                        hosts,
+                        selected,
+                        table_state,
+                    )?;
                 }
                 self.reset_host_key_confirm();
                 Ok(true)
@@ -1128,7 +2913,15 @@ impl UiManager {
             }
             KeyCode::Char('y') | KeyCode::Char('Y') => {
                 if let Some(host) = self.state.host_key_confirm.host.clone() {
-                    self.handle_host_key_accept(&host, terminal, hosts, selected, table_state)?;
+                    let session = self.state.host_key_confirm.session;
+                    self.handle_host_key_accept(
+                        &host,
+                        session,
+                        terminal,
+                        hosts,
+                        selected,
+                        table_state,
+                    )?;
                 }
                 self.reset_host_key_confirm();
                 Ok(true)
@@ -1146,32 +2939,33 @@ impl UiManager {
         self.state.host_key_confirm.show = false;
         self.state.host_key_confirm.host = None;
         self.state.host_key_confirm.selection = 0;
+        self.state.host_key_confirm.session = SessionKind::Shell;
     }
 
     /// 处理主机密钥接受
     fn handle_host_key_accept(
         &mut self,
         host: &str,
+        session: SessionKind,
         terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
         hosts: &mut Vec<SshHost>,
         selected: &mut usize,
         table_state: &mut TableState,
     ) -> io::Result<()> {
         // 1. 退出TUI模式，恢复正常终端
-        disable_raw_mode()?;
-        execute!(io::stdout(), LeaveAlternateScreen)?;
+        restore_terminal()?;
 
         // 2. 使用TUI专用的主机密钥处理方法
-        let result = self
-            .config_manager
-            .handle_host_key_verification_failed_for_tui(host);
+        let result = with_foreground_child(|| {
+            self.config_manager
+                .handle_host_key_verification_failed_for_tui(host, session)
+        });
 
         // 3. 等待系统稳定，防止终端状态混乱
         std::thread::sleep(std::time::Duration::from_millis(300));
 
         // 4. 重新初始化终端环境 - 增强版
-        enable_raw_mode()?;
-        execute!(io::stdout(), EnterAlternateScreen)?;
+        enter_terminal_mode()?;
 
         // 5. 强制清理终端，确保主机密钥处理后状态完全正常
         execute!(
@@ -1218,27 +3012,38 @@ impl UiManager {
     /// 2. 执行SSH连接
     /// 3. 重新进入TUI模式
     /// 4. 刷新界面数据并强制重新渲染
+    ///
+    /// 指定 `jump_host` 或 `user` 时经该跳板机、以该用户建立shell连接
+    #[allow(clippy::too_many_arguments)]
     fn exit_and_connect(
         &mut self,
         host: &str,
+        session: SessionKind,
+        jump_host: Option<&str>,
+        user: Option<&str>,
         terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
         hosts: &mut Vec<SshHost>,
         selected: &mut usize,
         table_state: &mut TableState,
     ) -> io::Result<()> {
         // 1. 退出TUI模式，恢复正常终端
-        disable_raw_mode()?;
-        execute!(io::stdout(), LeaveAlternateScreen)?;
+        restore_terminal()?;
 
-        // 2. 执行SSH连接
-        let connection_result = self.config_manager.connect_host_for_tui(host);
+        // 2. 执行SSH连接（或SFTP会话）
+        let connection_result = with_foreground_child(|| {
+            if jump_host.is_some() || user.is_some() {
+                self.config_manager
+                    .connect_host_via_for_tui(host, jump_host, user)
+            } else {
+                self.config_manager.open_session_for_tui(host, session)
+            }
+        });
 
         // 3. 等待系统稳定，防止终端状态混乱
         std::thread::sleep(std::time::Duration::from_millis(200));
 
         // 4. 重新初始化终端环境 - 增强版
-        enable_raw_mode()?;
-        execute!(io::stdout(), EnterAlternateScreen)?;
+        enter_terminal_mode()?;
 
         // 5. 强制清理终端，确保SSH连接后状态完全正常
         execute!(
@@ -1282,37 +3087,13 @@ impl UiManager {
         selected: &mut usize,
         table_state: &mut TableState,
     ) -> io::Result<()> {
-        // 1. 强化终端状态恢复 - 确保终端设置完全正确
-        use std::process::Command;
-
-        // 执行多重终端修复，确保彻底恢复正常状态
-        let restore_commands = [
-            vec!["stty", "sane"],                             // 重置到安全状态
-            vec!["stty", "echo", "icanon", "onlcr", "icrnl"], // 恢复标准设置
-            vec!["tput", "sgr0"],                             // 重置所有终端属性
-            vec!["tput", "cnorm"],                            // 恢复光标显示
-            vec!["tput", "clear"],                            // 清屏
-        ];
-
-        for cmd_args in restore_commands.iter() {
-            let _ = Command::new(cmd_args[0]).args(&cmd_args[1..]).status();
-        }
-
-        // 2. 等待终端状态稳定
-        std::thread::sleep(std::time::Duration::from_millis(100));
+        // 1-4. 重置颜色并清屏，重新初始化raw mode确保按键捕获正常，清除残留的事件
+        run_recovery(&mut CrosstermOps(io::stdout()), AFTER_SESSION_STEPS);
 
-        // 3. 强制重新初始化终端模式，确保按键捕获正常
-        disable_raw_mode()?;
-        std::thread::sleep(std::time::Duration::from_millis(50));
-        enable_raw_mode()?;
-
-        // 4. 清除任何可能残留的事件
-        while event::poll(std::time::Duration::from_millis(1))? {
-            let _ = event::read()?;
-        }
-
-        // 5. 重新初始化所有UI状态
+        // 5. 重新初始化所有UI状态；连接时可能向ssh-agent载入了密钥，重新检查
         self.reset_all_ui_state();
+        self.agent_status = None;
+        self.key_statuses.clear();
 
         // 6. 强制重新初始化事件系统，确保按键响应正常
         self.reinitialize_event_system()?;
@@ -1320,7 +3101,7 @@ impl UiManager {
         // 6. 重新加载服务器列表数据
         if let Some(query) = &self.state.search.query {
             // 如果当前有搜索查询，重新执行搜索
-            if let Ok(search_results) = self.config_manager.search_hosts(query) {
+            if let Ok(search_results) = self.config_manager.search_hosts_ranked(query) {
                 *hosts = search_results;
             }
         } else {
@@ -1329,6 +3110,8 @@ impl UiManager {
                 *hosts = all_hosts.clone();
             }
         }
+        self.restore_statuses(hosts);
+        self.sort_hosts(hosts);
 
         // 确保选中索引有效
         if *selected >= hosts.len() && !hosts.is_empty() {
@@ -1359,22 +3142,7 @@ impl UiManager {
         table_state: &mut TableState,
     ) -> io::Result<()> {
         // 强制重新渲染界面，确保SSH连接后界面正确显示
-        terminal.draw(|f| {
-            let size = f.area();
-
-            // 渲染搜索输入框
-            let y_offset = self.render_search_popup(f, size);
-
-            // 渲染主表格
-            self.render_main_table(f, size, y_offset, hosts, table_state);
-
-            // 渲染各种弹窗
-            self.render_delete_confirm_popup(f, size);
-            self.render_form_popup(f, size);
-            self.render_error_modal(f, size);
-            self.render_host_key_confirm(f, size);
-        })?;
-        Ok(())
+        self.render_ui(terminal, hosts, table_state)
     }
 
     /// 重置所有UI状态
@@ -1385,6 +3153,11 @@ impl UiManager {
         self.state.search.show_popup = false;
         self.state.search.input.clear();
 
+        self.reset_quick_connect();
+        self.state.user_override = UserOverrideState::default();
+        self.state.connect_confirm = ConnectConfirmState::default();
+        self.state.jump_picker = JumpPickerState::default();
+
         self.state.delete_confirm.show = false;
         self.state.delete_confirm.host = None;
         self.state.delete_confirm.input.clear();
@@ -1406,22 +3179,46 @@ impl UiManager {
     }
 
     /// 检查并更新连接测试结果
-    fn update_connection_test_results(&mut self, hosts: &mut [SshHost]) {
-        if let Ok(mut pending_tests) = self.pending_connection_tests.lock() {
-            let mut completed_indices = Vec::new();
-
-            for (i, (host_index, status_opt)) in pending_tests.iter().enumerate() {
-                if let Some(status) = status_opt {
-                    if *host_index < hosts.len() {
-                        hosts[*host_index].connection_status = status.clone();
-                        completed_indices.push(i);
-                    }
-                }
+    ///
+    /// 结果按主机名回填，测试期间主机列表被过滤、排序或重新加载也不会错位；
+    /// 不在当前列表中的主机只记录结果，重新出现时由 [`Self::restore_statuses`] 恢复
+    ///
+    /// 返回是否收到了新的结果
+    fn update_connection_test_results(&mut self, hosts: &mut [SshHost]) -> bool {
+        let mut updated = false;
+        while let Ok((name, status)) = self.test_results_rx.try_recv() {
+            updated = true;
+            self.pending_tests.remove(&name);
+            if let Some(host) = hosts.iter_mut().find(|h| h.host == name) {
+                host.connection_status = status.clone();
             }
+            self.test_times.insert(name.clone(), Instant::now());
+            self.host_statuses.insert(name, status);
+        }
+        updated
+    }
+
+    /// 取出已完成的认证方式探测结果，返回是否有新结果
+    fn update_auth_probe_results(&mut self) -> bool {
+        let mut updated = false;
+        while let Ok((name, result)) = self.auth_results_rx.try_recv() {
+            updated = true;
+            let state = match result {
+                Ok(methods) => AuthProbeState::Done(methods),
+                Err(reason) => AuthProbeState::Failed(reason),
+            };
+            self.auth_probes.insert(name, state);
+        }
+        updated
+    }
 
-            // 移除已完成的测试（从后往前移除以避免索引问题）
-            for &i in completed_indices.iter().rev() {
-                pending_tests.remove(i);
+    /// 把已知的连接状态复制到重新加载的主机上：测试中的主机显示为连接中，其余取最近一次的结果
+    fn restore_statuses(&self, hosts: &mut [SshHost]) {
+        for host in hosts.iter_mut() {
+            if self.pending_tests.contains(&host.host) {
+                host.connection_status = ConnectionStatus::Connecting;
+            } else if let Some(status) = self.host_statuses.get(&host.host) {
+                host.connection_status = status.clone();
             }
         }
     }
@@ -1454,28 +3251,56 @@ impl UiManager {
             KeyCode::Enter => {
                 if !hosts.is_empty() {
                     let host = hosts[*selected].host.clone();
-                    self.handle_connect_request(&host, terminal, hosts, selected, table_state)?;
+                    self.handle_connect_request(
+                        &host,
+                        SessionKind::Shell,
+                        terminal,
+                        hosts,
+                        selected,
+                        table_state,
+                    )?;
+                }
+                Ok(false)
+            }
+            KeyCode::Char('f') => {
+                if !hosts.is_empty() {
+                    let host = hosts[*selected].host.clone();
+                    self.handle_connect_request(
+                        &host,
+                        SessionKind::Sftp,
+                        terminal,
+                        hosts,
+                        selected,
+                        table_state,
+                    )?;
                 }
                 Ok(false)
             }
-            KeyCode::Char('a') => {
-                self.show_add_form();
+            KeyCode::Char('a') | KeyCode::Char('e') | KeyCode::Char('d') => {
+                self.handle_modify_shortcut(key, hosts, *selected);
                 Ok(false)
             }
-            KeyCode::Char('e') => {
+            KeyCode::Char('s') | KeyCode::Char('/') => {
+                self.show_search_popup();
+                Ok(false)
+            }
+            KeyCode::Char('J') => {
                 if !hosts.is_empty() {
-                    self.show_edit_form(&hosts[*selected]);
+                    let target = hosts[*selected].host.clone();
+                    self.show_jump_picker(&target)?;
                 }
                 Ok(false)
             }
-            KeyCode::Char('d') => {
+            KeyCode::Char('g') => {
                 if !hosts.is_empty() {
-                    self.show_delete_confirm(&hosts[*selected].host);
+                    self.state.quick_connect.show = true;
                 }
                 Ok(false)
             }
-            KeyCode::Char('s') | KeyCode::Char('/') => {
-                self.show_search_popup();
+            KeyCode::Char('u') => {
+                if let Some(host) = hosts.get(*selected) {
+                    self.show_user_override(&host.clone());
+                }
                 Ok(false)
             }
             KeyCode::Char('t') => {
@@ -1484,20 +3309,213 @@ impl UiManager {
                 }
                 Ok(false)
             }
+            KeyCode::Char('C') => {
+                if !hosts.is_empty() {
+                    self.checked_connect = Some(hosts[*selected].host.clone());
+                    self.start_connection_test(hosts, *selected);
+                }
+                Ok(false)
+            }
+            KeyCode::Char('p') => {
+                if !hosts.is_empty() {
+                    self.start_ssh_probe(hosts, *selected);
+                }
+                Ok(false)
+            }
             KeyCode::Char('T') => {
                 if !hosts.is_empty() {
                     self.test_all_connections(hosts);
                 }
                 Ok(false)
             }
+            KeyCode::Char('A') => {
+                if let Some(host) = hosts.get(*selected) {
+                    self.start_auth_probe(&host.clone());
+                }
+                Ok(false)
+            }
+            KeyCode::Char('y') => {
+                if !hosts.is_empty() {
+                    self.copy_ssh_command(&hosts[*selected])?;
+                }
+                Ok(false)
+            }
+            KeyCode::Char('o') => {
+                self.toggle_sort(hosts, selected, table_state);
+                Ok(false)
+            }
+            KeyCode::Char('c') => {
+                self.toggle_compact();
+                Ok(false)
+            }
+            KeyCode::Char('b') => {
+                self.toggle_color_blind();
+                Ok(false)
+            }
+            KeyCode::Char('R') | KeyCode::F(5) => {
+                self.reload_from_disk(hosts, selected, table_state)?;
+                Ok(false)
+            }
+            KeyCode::Char('P') => {
+                self.switch_to_next_profile(hosts, selected, table_state)?;
+                Ok(false)
+            }
+            KeyCode::Char('w') => {
+                if !hosts.is_empty() {
+                    let host = hosts[*selected].host.clone();
+                    self.open_in_new_terminal(&host)?;
+                }
+                Ok(false)
+            }
             _ => Ok(false),
         }
     }
 
-    /// 处理连接请求
-    fn handle_connect_request(
+    /// 处理新增/编辑/删除快捷键，只读模式下不做任何操作
+    fn handle_modify_shortcut(&mut self, key: KeyCode, hosts: &[SshHost], selected: usize) {
+        if self.read_only {
+            return;
+        }
+
+        match key {
+            KeyCode::Char('a') => self.show_add_form(),
+            KeyCode::Char('e') => {
+                if let Some(host) = hosts.get(selected) {
+                    self.show_edit_form(host);
+                }
+            }
+            KeyCode::Char('d') => {
+                if let Some(host) = hosts.get(selected) {
+                    self.show_delete_confirm(&host.host);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// 按当前排序方式排列主机列表
+    ///
+    /// 未开启按名称排序时保持加载时的顺序（配置文件顺序或搜索相关度）
+    fn sort_hosts(&mut self, hosts: &mut [SshHost]) {
+        if self.state.sort_by_name {
+            hosts.sort_by(|a, b| crate::utils::natural_cmp(&a.host, &b.host));
+        }
+    }
+
+    /// 恢复默认顺序：有搜索词时按相关度，否则按配置文件顺序
+    fn restore_default_order(&mut self, hosts: &mut [SshHost]) {
+        let ordered = match &self.state.search.query {
+            Some(query) => self.config_manager.search_hosts_ranked(&query.clone()),
+            None => self.config_manager.get_hosts().cloned(),
+        };
+        if let Ok(ordered) = ordered {
+            let position = |name: &str| ordered.iter().position(|h| h.host == name);
+            hosts.sort_by_key(|h| position(&h.host));
+        }
+    }
+
+    /// 切换按主机名排序，保持当前选中的主机不变
+    fn toggle_sort(
+        &mut self,
+        hosts: &mut [SshHost],
+        selected: &mut usize,
+        table_state: &mut TableState,
+    ) {
+        let current = hosts.get(*selected).map(|h| h.host.clone());
+        self.state.sort_by_name = !self.state.sort_by_name;
+        if self.state.sort_by_name {
+            self.sort_hosts(hosts);
+        } else {
+            self.restore_default_order(hosts);
+        }
+
+        if let Some(name) = current
+            && let Some(index) = hosts.iter().position(|h| h.host == name)
+        {
+            *selected = index;
+            table_state.select(Some(index));
+        }
+
+        self.state.notice = Some(if self.state.sort_by_name {
+            t("ui.sorted_by_name")
+        } else {
+            t("ui.sorted_by_config")
+        });
+    }
+
+    /// 切换到下一个配置档案并重新加载主机列表
+    fn switch_to_next_profile(
+        &mut self,
+        hosts: &mut Vec<SshHost>,
+        selected: &mut usize,
+        table_state: &mut TableState,
+    ) -> io::Result<()> {
+        // 不同档案中可能有同名主机，测试进行中切换会把结果记到新档案的主机上
+        if !self.pending_tests.is_empty() {
+            self.state.notice = Some(t("ui.profile_unavailable_testing"));
+            return Ok(());
+        }
+
+        let Some(next) = self
+            .config_manager
+            .settings()
+            .next_profile()
+            .map(str::to_string)
+        else {
+            self.state.notice = Some(t("ui.profile_none"));
+            return Ok(());
+        };
+
+        if let Err(e) = self.config_manager.switch_profile(&next) {
+            return self.show_error_message(&e.localized_message());
+        }
+        self.state.search.query = None;
+        self.host_statuses.clear();
+        self.test_times.clear();
+        *selected = 0;
+        self.reload_hosts(hosts, selected, table_state)?;
+        self.state.notice = Some(t("ui.profile_switched").replacen("{}", &next, 1).replacen(
+            "{}",
+            self.config_manager.config_path(),
+            1,
+        ));
+        Ok(())
+    }
+
+    /// 在新的终端窗口中连接主机，TUI保持运行
+    fn open_in_new_terminal(&mut self, host: &str) -> io::Result<()> {
+        match self.config_manager.open_in_terminal(host) {
+            Ok(()) => {
+                self.state.notice = Some(t("ui.terminal_opened").replace("{}", host));
+                Ok(())
+            }
+            Err(e) => self.show_error_message(&e.localized_message()),
+        }
+    }
+
+    /// 复制完整的ssh命令到剪贴板，存储的密码不会出现在命令中
+    fn copy_ssh_command(&mut self, host: &SshHost) -> io::Result<()> {
+        let command = self
+            .config_manager
+            .build_ssh_command(host, &CommandMode::Connect)
+            .to_command_line();
+        match crate::utils::copy_to_clipboard(&command) {
+            Ok(()) => {
+                self.state.notice = Some(t("ui.ssh_command_copied").replace("{}", &command));
+            }
+            Err(e) => {
+                log::warn!("Failed to copy ssh command: {}", e);
+                self.show_error_message(&t("clipboard_unavailable"))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 处理连接请求
+    fn handle_connect_request(
         &mut self,
         host: &str,
+        session: SessionKind,
         terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
         hosts: &mut Vec<SshHost>,
         selected: &mut usize,
@@ -1509,6 +3527,7 @@ impl UiManager {
             self.state.host_key_confirm.show = true;
             self.state.host_key_confirm.host = Some(host.to_string());
             self.state.host_key_confirm.selection = 0;
+            self.state.host_key_confirm.session = session;
         } else if !success {
             if let Some(err_msg) = error_message {
                 self.show_error_message(&format!("{}: {}", t("error.connection_failed"), err_msg))?;
@@ -1517,7 +3536,16 @@ impl UiManager {
             }
         } else {
             // 连接测试成功，进行实际的SSH连接
-            self.exit_and_connect(host, terminal, hosts, selected, table_state)?;
+            self.exit_and_connect(
+                host,
+                session,
+                None,
+                None,
+                terminal,
+                hosts,
+                selected,
+                table_state,
+            )?;
         }
         Ok(())
     }
@@ -1532,8 +3560,29 @@ impl UiManager {
             FormField::new(t("form.port"), ""),
             FormField::new(t("form.proxy_command"), ""),
             FormField::new(t("form.identity_file"), ""),
+            FormField::new(t("form.remote_command"), ""),
+            FormField::new(t("form.request_tty"), ""),
+            FormField::new(t("form.set_env"), ""),
+            FormField::new(t("form.address_family"), "")
+                .with_type(FormFieldType::Select(address_family_options())),
+            FormField::new(t("form.bind_address"), ""),
+            FormField::new(t("form.permit_local_command"), "")
+                .with_type(FormFieldType::Select(permit_local_command_options())),
+            FormField::new(t("form.local_command"), ""),
             FormField::new(t("form.password"), ""),
         ];
+        let templates: Vec<String> = self
+            .config_manager
+            .settings()
+            .templates
+            .keys()
+            .cloned()
+            .collect();
+        if !templates.is_empty() {
+            self.state.form.fields.push(
+                FormField::new(t("form.template"), "").with_type(FormFieldType::Select(templates)),
+            );
+        }
         self.state.form.focus_index = 0;
         self.state.form.editing_field = false;
     }
@@ -1558,6 +3607,39 @@ impl UiManager {
                 t("form.identity_file"),
                 host.identity_file.clone().unwrap_or_default(),
             ),
+            FormField::new(
+                t("form.remote_command"),
+                host.remote_command.clone().unwrap_or_default(),
+            ),
+            FormField::new(
+                t("form.request_tty"),
+                host.request_tty.clone().unwrap_or_default(),
+            ),
+            FormField::new(t("form.set_env"), host.set_env_string()),
+            FormField::new(
+                t("form.address_family"),
+                host.address_family
+                    .as_deref()
+                    .map(str::to_ascii_lowercase)
+                    .unwrap_or_default(),
+            )
+            .with_type(FormFieldType::Select(address_family_options())),
+            FormField::new(
+                t("form.bind_address"),
+                host.bind_address.clone().unwrap_or_default(),
+            ),
+            FormField::new(
+                t("form.permit_local_command"),
+                host.permit_local_command
+                    .as_deref()
+                    .map(str::to_ascii_lowercase)
+                    .unwrap_or_default(),
+            )
+            .with_type(FormFieldType::Select(permit_local_command_options())),
+            FormField::new(
+                t("form.local_command"),
+                host.local_command.clone().unwrap_or_default(),
+            ),
             FormField::new(t("form.password"), ""),
         ];
         self.state.form.focus_index = 1; // 编辑模式下，初始焦点设在第二个字段
@@ -1589,109 +3671,117 @@ impl UiManager {
 
         // 设置状态为连接中
         hosts[selected].connection_status = ConnectionStatus::Connecting;
+        self.spawn_connection_test(hosts[selected].clone());
+    }
 
-        // 克隆必要的数据
-        let mut host = hosts[selected].clone();
-        let pending_tests = self.pending_connection_tests.clone();
-
-        // 添加到待处理列表
-        if let Ok(mut pending) = pending_tests.lock() {
-            pending.push((selected, None));
-        }
+    /// 在独立线程中运行TCP连接测试，完成后通过通道发回结果
+    ///
+    /// 测试使用合并了 `Host *` 默认选项的配置（如其中的Port、ConnectTimeout）
+    fn spawn_connection_test(&mut self, host: SshHost) {
+        let mut host = self.config_manager.effective_host(&host);
+        let min_connecting =
+            Duration::from_millis(self.config_manager.settings().ui.min_connecting_ms);
+        let tx = self.test_results_tx.clone();
+        self.pending_tests.insert(host.host.clone());
 
-        // 在独立线程中运行连接测试
         thread::spawn(move || {
             // 创建运行时并执行测试
-            let rt = match tokio::runtime::Runtime::new() {
-                Ok(rt) => rt,
+            let status = match tokio::runtime::Runtime::new() {
+                Ok(rt) => {
+                    // 测试结果记录在 connection_status 中，失败同样如此
+                    let started = Instant::now();
+                    let _ = rt.block_on(host.test_connection());
+                    // 让🟡状态至少显示设置的时间，测试本身不做额外等待
+                    if let Some(remaining) =
+                        remaining_connecting_time(min_connecting, started.elapsed())
+                    {
+                        thread::sleep(remaining);
+                    }
+                    host.connection_status.clone()
+                }
                 Err(e) => {
                     log::error!("Failed to create async runtime: {}", e);
-                    let error_status = ConnectionStatus::Failed("Runtime error".to_string());
-                    if let Ok(mut pending) = pending_tests.lock() {
-                        if let Some(entry) = pending.iter_mut().find(|(idx, _)| *idx == selected) {
-                            entry.1 = Some(error_status);
-                        }
-                    }
-                    return;
+                    ConnectionStatus::Failed("Runtime error".to_string())
                 }
             };
-
-            // 执行连接测试
-            let result_status = rt.block_on(async {
-                match host.test_connection().await {
-                    Ok(_) => host.connection_status.clone(),
-                    Err(_) => host.connection_status.clone(),
-                }
-            });
-
-            // 更新结果
-            if let Ok(mut pending) = pending_tests.lock() {
-                if let Some(entry) = pending.iter_mut().find(|(idx, _)| *idx == selected) {
-                    entry.1 = Some(result_status);
-                }
-            }
-
-            log::info!(
+            log::debug!(
                 "Connection test completed for {}: {}",
                 host.host,
-                host.connection_status.detail_string()
+                status.detail_string()
             );
+            // 界面已退出时接收端已关闭，结果直接丢弃
+            let _ = tx.send((host.host, status));
         });
     }
 
-    /// 批量测试所有主机连接
-    fn test_all_connections(&mut self, hosts: &mut [SshHost]) {
-        // 设置所有主机状态为连接中
-        for (index, host) in hosts.iter_mut().enumerate() {
-            host.connection_status = ConnectionStatus::Connecting;
-
-            // 克隆必要的数据
-            let mut host_clone = host.clone();
-            let pending_tests = self.pending_connection_tests.clone();
+    /// 启动经由ssh的连通性探测
+    ///
+    /// 与 `t` 的TCP探测不同，会使用配置中的ProxyJump/ProxyCommand，适合只能经跳板机访问的主机
+    fn start_ssh_probe(&mut self, hosts: &mut [SshHost], selected: usize) {
+        if selected >= hosts.len() {
+            return;
+        }
 
-            // 添加到待处理列表
-            if let Ok(mut pending) = pending_tests.lock() {
-                pending.push((index, None));
-            }
+        hosts[selected].connection_status = ConnectionStatus::Connecting;
+        let host = hosts[selected].host.clone();
+        let argv = self
+            .config_manager
+            .build_ssh_command(&hosts[selected], &CommandMode::Probe)
+            .argv();
+        let tx = self.test_results_tx.clone();
+        self.pending_tests.insert(host.clone());
 
-            // 在独立线程中运行连接测试
-            thread::spawn(move || {
-                // 创建运行时并执行测试
-                let rt = match tokio::runtime::Runtime::new() {
-                    Ok(rt) => rt,
-                    Err(e) => {
-                        log::error!("Failed to create async runtime: {}", e);
-                        let error_status = ConnectionStatus::Failed("Runtime error".to_string());
-                        if let Ok(mut pending) = pending_tests.lock() {
-                            if let Some(entry) = pending.iter_mut().find(|(idx, _)| *idx == index) {
-                                entry.1 = Some(error_status);
-                            }
-                        }
-                        return;
-                    }
-                };
+        thread::spawn(move || {
+            let status = match crate::network::ssh_probe(&argv) {
+                Ok(duration) => ConnectionStatus::SshReachable(duration),
+                Err(SshConnError::Connection(message)) => ConnectionStatus::Failed(message),
+                Err(e) => ConnectionStatus::Failed(e.to_string()),
+            };
+            log::info!(
+                "SSH probe completed for {}: {}",
+                host,
+                status.detail_string()
+            );
 
-                // 执行连接测试
-                let result_status = rt.block_on(async {
-                    match host_clone.test_connection().await {
-                        Ok(_) => host_clone.connection_status.clone(),
-                        Err(_) => host_clone.connection_status.clone(),
-                    }
-                });
+            let _ = tx.send((host, status));
+        });
+    }
 
-                // 更新结果
-                if let Ok(mut pending) = pending_tests.lock() {
-                    if let Some(entry) = pending.iter_mut().find(|(idx, _)| *idx == index) {
-                        entry.1 = Some(result_status);
-                    }
-                }
+    /// 在独立线程中探测服务器接受的认证方式（`ssh -o PreferredAuthentications=none`）
+    fn start_auth_probe(&mut self, host: &SshHost) {
+        if self.auth_probes.get(&host.host) == Some(&AuthProbeState::Probing) {
+            return;
+        }
+        let host = self.config_manager.effective_host(host);
+        let argv = self
+            .config_manager
+            .build_ssh_command(&host, &CommandMode::AuthProbe)
+            .argv();
+        let probe = crate::network::NetworkProbe::for_auth_probe(&host);
+        let tx = self.auth_results_tx.clone();
+        self.auth_probes
+            .insert(host.host.clone(), AuthProbeState::Probing);
 
-                log::debug!(
-                    "Connection test completed for {}: {}",
-                    host_clone.host,
-                    host_clone.connection_status.detail_string()
-                );
+        thread::spawn(move || {
+            let result = match tokio::runtime::Runtime::new() {
+                Ok(rt) => rt.block_on(probe.probe_auth_methods(&argv)),
+                Err(e) => Err(SshConnError::Io(e)),
+            };
+            let result = result.map_err(|e| match e {
+                SshConnError::Connection(message) => message,
+                other => other.to_string(),
             });
+            log::info!("Auth probe completed for {}: {:?}", host.host, result);
+            let _ = tx.send((host.host, result));
+        });
+    }
+
+    /// 批量测试所有主机连接
+    fn test_all_connections(&mut self, hosts: &mut [SshHost]) {
+        // 设置所有主机状态为连接中
+        for host in hosts.iter_mut() {
+            host.connection_status = ConnectionStatus::Connecting;
+            self.spawn_connection_test(host.clone());
         }
 
         log::info!("Started batch connection test for {} hosts", hosts.len());
@@ -1723,20 +3813,941 @@ impl UiManager {
     ///
     /// 在发生意外情况时尝试恢复终端到可用状态
     fn emergency_terminal_recovery(&self) -> io::Result<()> {
-        use std::process::Command;
-
-        // 尝试多种终端恢复方法
-        let recovery_commands = [
-            vec!["stty", "sane"],
-            vec!["reset"],
-            vec!["tput", "cnorm"], // 恢复光标
-            vec!["tput", "sgr0"],  // 重置属性
+        // 每一步独立执行，前面的步骤失败不影响后面的步骤
+        run_recovery(&mut CrosstermOps(io::stdout()), EMERGENCY_STEPS);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::password::PasswordManager;
+    use crate::settings::Settings;
+
+    fn ui_manager(dir: &tempfile::TempDir) -> UiManager {
+        let password_manager = PasswordManager::with_path(dir.path().join("passwords.db")).unwrap();
+        UiManager::new(
+            ConfigManager::with_path(dir.path().join("config"), password_manager).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_edit_form_is_prepopulated_from_host() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("config"),
+            "Host web\n    HostName 10.0.0.5\n\nHost db\n    HostName 10.0.0.7\n    User root\n    Port 2222\n    SetEnv LANG=C\n    AddressFamily INET\n    BindAddress eth0\n",
+        )
+        .unwrap();
+
+        let mut ui = ui_manager(&dir);
+        let (hosts, index) = ui.open_edit_form("db").unwrap();
+        assert_eq!(hosts[index].host, "db");
+        assert!(ui.state.form.show_edit);
+        let values: Vec<&str> = ui
+            .state
+            .form
+            .fields
+            .iter()
+            .map(|f| f.value.as_str())
+            .collect();
+        assert_eq!(
+            values,
+            [
+                "db", "10.0.0.7", "root", "2222", "", "", "", "", "LANG=C", "inet", "eth0", "", "",
+                ""
+            ]
+        );
+        // Host名称只读，焦点从HostName开始
+        assert_eq!(ui.state.form.focus_index, 1);
+
+        let mut ui = ui_manager(&dir);
+        assert!(matches!(
+            ui.open_edit_form("missing"),
+            Err(SshConnError::HostNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_edit_form_confirms_diff_before_saving() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = "Host web\n    HostName 10.0.0.5\n    User deploy\n";
+        std::fs::write(dir.path().join("config"), config).unwrap();
+        let mut ui = ui_manager(&dir);
+        let (mut hosts, mut selected) = ui.open_edit_form("web").unwrap();
+        let mut table_state = TableState::default();
+        let mut press = |ui: &mut UiManager, code: KeyCode| {
+            ui.handle_form_event(
+                KeyEvent::from(code),
+                &mut hosts,
+                &mut selected,
+                &mut table_state,
+            )
+            .unwrap();
+        };
+
+        ui.state.form.fields[2].value = "admin".to_string();
+        press(&mut ui, KeyCode::Char('s'));
+        let diff = ui.state.form.diff_preview.clone().unwrap();
+        assert!(
+            diff.contains("-    User deploy\n+    User admin\n"),
+            "{diff}"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("config")).unwrap(),
+            config
+        );
+
+        // n 返回表单，不写入
+        press(&mut ui, KeyCode::Char('n'));
+        assert!(ui.state.form.diff_preview.is_none());
+        assert!(ui.state.form.show_edit);
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("config")).unwrap(),
+            config
+        );
+
+        press(&mut ui, KeyCode::Char('s'));
+        press(&mut ui, KeyCode::Char('y'));
+        assert!(!ui.state.form.show_edit);
+        assert!(
+            std::fs::read_to_string(dir.path().join("config"))
+                .unwrap()
+                .contains("User admin")
+        );
+        assert!(!ui.state.form.show_edit);
+    }
+
+    #[test]
+    fn test_reload_from_disk_keeps_selection_by_name() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = dir.path().join("config");
+        std::fs::write(
+            &config,
+            "Host web\n    HostName 10.0.0.5\n\nHost db\n    HostName 10.0.0.7\n",
+        )
+        .unwrap();
+
+        let mut ui = ui_manager(&dir);
+        let mut hosts = ui.config_manager.get_hosts().unwrap().clone();
+        let mut selected = hosts.iter().position(|h| h.host == "db").unwrap();
+        let mut table_state = TableState::default();
+        table_state.select(Some(selected));
+
+        // 外部修改：在前面插入新主机，db的位置随之改变
+        std::fs::write(
+            &config,
+            "Host cache\n    HostName 10.0.0.9\n\nHost web\n    HostName 10.0.0.5\n\nHost db\n    HostName 10.0.0.8\n",
+        )
+        .unwrap();
+        ui.reload_from_disk(&mut hosts, &mut selected, &mut table_state)
+            .unwrap();
+        assert_eq!(hosts.len(), 3);
+        assert_eq!(hosts[selected].host, "db");
+        assert_eq!(hosts[selected].hostname.as_deref(), Some("10.0.0.8"));
+        assert_eq!(table_state.selected(), Some(selected));
+
+        // 之前选中的主机被删除时保持原位置
+        std::fs::write(&config, "Host web\n    HostName 10.0.0.5\n").unwrap();
+        ui.reload_from_disk(&mut hosts, &mut selected, &mut table_state)
+            .unwrap();
+        assert_eq!(selected, 0);
+        assert_eq!(hosts[0].host, "web");
+
+        assert_eq!(reselect_by_name(&[], Some("web"), 3), None);
+    }
+
+    #[test]
+    fn test_connection_results_follow_host_names() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("config"),
+            "Host web\n    HostName 10.0.0.5\n\nHost db\n    HostName 10.0.0.7\n",
+        )
+        .unwrap();
+
+        let mut ui = ui_manager(&dir);
+        let mut hosts = ui.config_manager.get_hosts().unwrap().clone();
+        let connected = ConnectionStatus::Connected(std::time::Duration::from_millis(15));
+
+        // 测试web期间列表顺序改变，结果仍然落在web上
+        ui.pending_tests.insert("web".to_string());
+        hosts.reverse();
+        assert_eq!(hosts[0].host, "db");
+        ui.test_results_tx
+            .send(("web".to_string(), connected.clone()))
+            .unwrap();
+        ui.update_connection_test_results(&mut hosts);
+        assert_eq!(hosts[1].host, "web");
+        assert_eq!(hosts[1].connection_status, connected);
+        assert_eq!(hosts[0].connection_status, ConnectionStatus::Unknown);
+        assert!(ui.pending_tests.is_empty());
+
+        // 结果在过滤后重新加载的列表中保留，测试中的主机显示为连接中
+        ui.pending_tests.insert("db".to_string());
+        let mut reloaded = ui.config_manager.get_hosts().unwrap().clone();
+        ui.restore_statuses(&mut reloaded);
+        let status = |name: &str| {
+            reloaded
+                .iter()
+                .find(|h| h.host == name)
+                .map(|h| h.connection_status.clone())
+        };
+        assert_eq!(status("web"), Some(connected));
+        assert_eq!(status("db"), Some(ConnectionStatus::Connecting));
+    }
+
+    /// 记录raw mode和事件队列操作的模拟终端，控制序列写入内存
+    #[derive(Default)]
+    struct MockTerminal {
+        calls: Vec<String>,
+        output: Vec<u8>,
+        fail_raw_mode: bool,
+    }
+
+    impl TerminalOps for MockTerminal {
+        fn set_raw_mode(&mut self, enabled: bool) -> io::Result<()> {
+            self.calls.push(format!("raw_mode({})", enabled));
+            if self.fail_raw_mode {
+                return Err(io::Error::other("not a terminal"));
+            }
+            Ok(())
+        }
+
+        fn drain_events(&mut self) -> io::Result<()> {
+            self.calls.push("drain_events".to_string());
+            Ok(())
+        }
+
+        fn writer(&mut self) -> &mut dyn io::Write {
+            self.calls.push(format!("write@{}", self.output.len()));
+            &mut self.output
+        }
+    }
+
+    #[test]
+    fn test_recovery_steps_run_in_order() {
+        let mut terminal = MockTerminal::default();
+        assert_eq!(run_recovery(&mut terminal, EMERGENCY_STEPS), 0);
+        let writes = terminal
+            .calls
+            .iter()
+            .filter(|c| c.starts_with("write"))
+            .count();
+        assert_eq!(writes, EMERGENCY_STEPS.len() - 3);
+        assert_eq!(terminal.calls.first().unwrap(), "raw_mode(false)");
+        assert_eq!(terminal.calls[terminal.calls.len() - 2], "raw_mode(true)");
+        assert_eq!(terminal.calls.last().unwrap(), "drain_events");
+
+        // 离开备用屏幕、显示光标、完全重置后再重新进入备用屏幕
+        let output = String::from_utf8(terminal.output).unwrap();
+        let position = |sequence: &str| output.find(sequence).unwrap();
+        assert!(position("\x1b[?1049l") < position("\x1b[?25h"));
+        assert!(position("\x1b[?25h") < position("\x1bc"));
+        assert!(position("\x1bc") < position("\x1b[?1049h"));
+        assert!(position("\x1b[?1049h") < position("\x1b[2J"));
+    }
+
+    #[test]
+    fn test_recovery_continues_after_failed_step() {
+        let mut terminal = MockTerminal {
+            fail_raw_mode: true,
+            ..MockTerminal::default()
+        };
+        assert_eq!(run_recovery(&mut terminal, AFTER_SESSION_STEPS), 2);
+        // raw mode失败后仍然清屏并清空事件队列
+        assert!(String::from_utf8_lossy(&terminal.output).contains("\x1b[2J"));
+        assert_eq!(terminal.calls.last().unwrap(), "drain_events");
+
+        let mut terminal = MockTerminal::default();
+        run_recovery(&mut terminal, EXIT_STEPS);
+        assert!(!terminal.output.contains(&b'c'));
+    }
+
+    #[test]
+    fn test_auth_probe_results_shown_in_footer() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("config"),
+            "Host web\n    HostName 10.0.0.5\n\nHost db\n    HostName 10.0.0.7\n",
+        )
+        .unwrap();
+        let mut ui = ui_manager(&dir);
+        let hosts = ui.config_manager.get_hosts().unwrap().clone();
+        let footer = |ui: &UiManager, index: usize| {
+            ui.selected_host_footer(&hosts, Some(index))
+                .map(|line| line.to_string())
+                .unwrap_or_default()
+        };
+
+        ui.auth_probes
+            .insert("web".to_string(), AuthProbeState::Probing);
+        assert!(footer(&ui, 0).contains(&t("ui.auth_probing")));
+
+        ui.auth_results_tx
+            .send((
+                "web".to_string(),
+                Ok(AuthMethods::Offered(vec![
+                    "publickey".to_string(),
+                    "password".to_string(),
+                ])),
+            ))
+            .unwrap();
+        ui.auth_results_tx
+            .send(("db".to_string(), Err("Connection refused".to_string())))
+            .unwrap();
+        assert!(ui.update_auth_probe_results());
+        assert!(!ui.update_auth_probe_results());
+        assert!(footer(&ui, 0).contains(&t("ui.auth_methods").replace("{}", "publickey,password")));
+        assert!(
+            footer(&ui, 1).contains(&t("ui.auth_probe_failed").replace("{}", "Connection refused"))
+        );
+    }
+
+    #[test]
+    fn test_checked_connect_decision() {
+        let ms = Duration::from_millis;
+        let fast = ConnectionStatus::Connected(ms(40));
+        let slow = ConnectionStatus::SshReachable(ms(300));
+
+        // 未设置阈值时总是确认
+        assert_eq!(
+            checked_connect_decision(&fast, None),
+            Some(CheckedConnect::Confirm)
+        );
+        assert_eq!(
+            checked_connect_decision(&fast, Some(ms(100))),
+            Some(CheckedConnect::Connect)
+        );
+        assert_eq!(
+            checked_connect_decision(&ConnectionStatus::Connected(ms(100)), Some(ms(100))),
+            Some(CheckedConnect::Connect)
+        );
+        assert_eq!(
+            checked_connect_decision(&slow, Some(ms(100))),
+            Some(CheckedConnect::Confirm)
+        );
+        assert_eq!(
+            checked_connect_decision(&ConnectionStatus::Failed("refused".into()), Some(ms(100))),
+            Some(CheckedConnect::Abort)
+        );
+        assert_eq!(
+            checked_connect_decision(&ConnectionStatus::Connecting, Some(ms(100))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_redraw_state() {
+        let start = Instant::now();
+        let mut redraw = RedrawState::default();
+        // 第一帧总是绘制
+        assert!(redraw.should_draw(start, None));
+        redraw.drawn(start);
+
+        // 空闲且没有随时间变化的内容时不重绘
+        let later = start + Duration::from_secs(60);
+        assert!(!redraw.should_draw(later, None));
+
+        // 有变化时立即重绘
+        redraw.mark();
+        assert!(redraw.should_draw(start, None));
+        redraw.drawn(start);
+
+        // 显示测试时长时按刷新间隔重绘
+        let refresh = Some(TEST_AGE_REFRESH);
+        assert!(!redraw.should_draw(start + Duration::from_millis(500), refresh));
+        assert!(redraw.should_draw(start + TEST_AGE_REFRESH, refresh));
+    }
+
+    #[test]
+    fn test_connection_results_from_many_threads() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut ui = ui_manager(&dir);
+        let mut hosts: Vec<SshHost> = (0..500)
+            .map(|i| SshHost::new(format!("host{}", i)))
+            .collect();
+
+        let workers: Vec<_> = hosts
+            .iter()
+            .enumerate()
+            .map(|(i, host)| {
+                ui.pending_tests.insert(host.host.clone());
+                let tx = ui.test_results_tx.clone();
+                let name = host.host.clone();
+                thread::spawn(move || {
+                    let latency = std::time::Duration::from_millis(i as u64);
+                    tx.send((name, ConnectionStatus::Connected(latency)))
+                        .unwrap();
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        assert!(ui.update_connection_test_results(&mut hosts));
+        assert!(!ui.update_connection_test_results(&mut hosts));
+        assert!(ui.pending_tests.is_empty());
+        for (i, host) in hosts.iter().enumerate() {
+            assert_eq!(
+                host.connection_status,
+                ConnectionStatus::Connected(std::time::Duration::from_millis(i as u64))
+            );
+        }
+    }
+
+    #[test]
+    fn test_modify_shortcuts_are_noops_in_read_only_mode() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let hosts = vec![SshHost::new("web".to_string())];
+
+        let mut ui = ui_manager(&dir);
+        ui.set_read_only(true);
+        for key in ['a', 'e', 'd'] {
+            ui.handle_modify_shortcut(KeyCode::Char(key), &hosts, 0);
+        }
+        assert!(!ui.state.form.show_add);
+        assert!(!ui.state.form.show_edit);
+        assert!(!ui.state.delete_confirm.show);
+        assert!(ui.help_text().find("a:").is_none());
+
+        // 非只读模式下同样的按键会打开对应界面
+        let mut ui = ui_manager(&dir);
+        ui.handle_modify_shortcut(KeyCode::Char('d'), &hosts, 0);
+        assert!(ui.state.delete_confirm.show);
+        ui.handle_modify_shortcut(KeyCode::Char('a'), &hosts, 0);
+        assert!(ui.state.form.show_add);
+    }
+
+    #[test]
+    fn test_display_toggles_stay_in_memory_in_read_only_mode() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut ui = ui_manager(&dir);
+        ui.set_read_only(true);
+
+        assert!(ui.save_ui_toggle("ui.compact", true).is_ok());
+        ui.toggle_compact();
+        assert!(ui.compact);
+        assert_eq!(
+            ui.state.notice.as_deref(),
+            Some(t("ui.compact_on").as_str())
+        );
+        ui.toggle_color_blind();
+        assert_eq!(ui.palette, StatusPalette::ColorBlind);
+        assert_eq!(
+            ui.state.notice.as_deref(),
+            Some(t("ui.color_blind_on").as_str())
+        );
+    }
+
+    fn sample_hosts() -> Vec<SshHost> {
+        let mut web = SshHost::new("web".to_string());
+        web.hostname = Some("web.example.com".to_string());
+        web.user = Some("deploy".to_string());
+        web.port = Some("2222".to_string());
+        web.identity_file = Some("~/.ssh/web".to_string());
+        web.connection_status = ConnectionStatus::Connected(std::time::Duration::from_millis(15));
+
+        let mut db = SshHost::new("db".to_string());
+        db.hostname = Some("10.0.0.5".to_string());
+        db.proxy_command = Some("ssh -W %h:%p bastion".to_string());
+        vec![web, db]
+    }
+
+    #[test]
+    fn test_form_clear_respects_readonly_host_field() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut manager = ui_manager(&dir);
+        let mut hosts = sample_hosts();
+        let mut selected = 0;
+        let mut table_state = TableState::default();
+        let ctrl_k = KeyEvent::new(KeyCode::Char('k'), KeyModifiers::CONTROL);
+
+        manager.show_edit_form(&hosts[0].clone());
+        manager.state.form.editing_field = true;
+
+        // 编辑模式下Host字段只读，清空无效
+        manager.state.form.focus_index = 0;
+        manager
+            .handle_form_event(ctrl_k, &mut hosts, &mut selected, &mut table_state)
+            .unwrap();
+        assert_eq!(manager.state.form.fields[0].value, "web");
+
+        manager.state.form.focus_index = 1;
+        manager
+            .handle_form_event(ctrl_k, &mut hosts, &mut selected, &mut table_state)
+            .unwrap();
+        assert_eq!(manager.state.form.fields[1].value, "");
+
+        manager.state.form.focus_index = 2;
+        manager
+            .handle_form_event(
+                KeyEvent::from(KeyCode::Delete),
+                &mut hosts,
+                &mut selected,
+                &mut table_state,
+            )
+            .unwrap();
+        assert_eq!(manager.state.form.fields[2].value, "");
+        assert_eq!(manager.state.form.fields[3].value, "2222");
+    }
+
+    #[test]
+    fn test_validation_error_focuses_field() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut manager = ui_manager(&dir);
+        let mut hosts = Vec::new();
+        let mut selected = 0;
+        let mut table_state = TableState::default();
+
+        manager.show_add_form();
+        manager.state.form.fields[0].value = "web".to_string();
+        manager.state.form.fields[1].value = "bad host".to_string();
+        let saved = manager
+            .save_form_data(&mut hosts, &mut selected, &mut table_state)
+            .unwrap();
+
+        assert!(!saved);
+        assert_eq!(manager.state.form.focus_index, 1);
+        assert_eq!(manager.state.form.error_field_index, Some(1));
+        assert!(manager.state.form.editing_field);
+    }
+
+    #[test]
+    fn test_host_table_rows() {
+        let rows = host_table_rows(
+            &sample_hosts(),
+            &HostColumn::DEFAULT,
+            &|host| host == "db",
+            StatusPalette::Default,
+        );
+        assert_eq!(
+            rows,
+            [
+                vec![
+                    "1",
+                    "web",
+                    "web.example.com",
+                    "deploy",
+                    "2222",
+                    "🟢 15ms",
+                    "🔑",
+                    "",
+                    "~/.ssh/web"
+                ],
+                vec![
+                    "2",
+                    "db",
+                    "10.0.0.5",
+                    "",
+                    "",
+                    "⚪",
+                    "🔒",
+                    "ssh -W %h:%p bastion",
+                    ""
+                ],
+            ]
+        );
+
+        // 列的选择和顺序决定单元格内容
+        let rows = host_table_rows(
+            &sample_hosts(),
+            &[HostColumn::User, HostColumn::Host, HostColumn::Index],
+            &|_| false,
+            StatusPalette::Default,
+        );
+        assert_eq!(rows, [["deploy", "web", "1"], ["", "db", "2"]]);
+        assert!(
+            host_table_rows(
+                &[],
+                &HostColumn::DEFAULT,
+                &|_| false,
+                StatusPalette::Default
+            )
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_color_blind_status_symbols_differ() {
+        let ms = Duration::from_millis;
+        let statuses = [
+            ConnectionStatus::Unknown,
+            ConnectionStatus::Connecting,
+            ConnectionStatus::Connected(ms(15)),
+            ConnectionStatus::Failed("refused".to_string()),
         ];
+        // 每种状态的符号各不相同，不依赖颜色区分
+        let symbols: Vec<String> = statuses
+            .iter()
+            .map(|s| s.display_string_with(StatusPalette::ColorBlind))
+            .map(|text| text.split(' ').next().unwrap().to_string())
+            .collect();
+        assert_eq!(symbols, ["?", "~", "✓", "✗"]);
+        assert_eq!(
+            ConnectionStatus::SshReachable(ms(20)).display_string_with(StatusPalette::ColorBlind),
+            "✓ ssh 20ms"
+        );
+        assert_eq!(
+            statuses[2].display_string_with(StatusPalette::Default),
+            statuses[2].display_string()
+        );
+
+        // 可用和失败使用蓝色/橙色，默认显示方式不另加颜色
+        let up = status_style(&statuses[2], StatusPalette::ColorBlind);
+        let failed = status_style(&statuses[3], StatusPalette::ColorBlind);
+        assert_eq!(up.fg, Some(Color::Blue));
+        assert_eq!(failed.fg, Some(Color::Rgb(230, 159, 0)));
+        assert_eq!(
+            status_style(&statuses[3], StatusPalette::Default),
+            Style::default()
+        );
+
+        let rows = host_table_rows(
+            &sample_hosts(),
+            &[HostColumn::Status],
+            &|_| false,
+            StatusPalette::ColorBlind,
+        );
+        assert_eq!(rows, [["✓ 15ms"], ["?"]]);
+    }
+
+    #[test]
+    fn test_auth_indicator_follows_stored_password() {
+        assert_eq!(auth_indicator(true), "🔒");
+        assert_eq!(auth_indicator(false), "🔑");
+
+        // 保存或删除密码后，表格中的标记随之变化
+        let settings = Settings {
+            password_db_path: Some(std::path::PathBuf::from(":memory:")),
+            ..Settings::default()
+        };
+        let mut passwords = PasswordManager::new(&settings).unwrap();
+        let hosts = sample_hosts();
+        let auth_column = |passwords: &PasswordManager| {
+            host_table_rows(
+                &hosts,
+                &[HostColumn::Auth],
+                &|host| passwords.has_password(host),
+                StatusPalette::Default,
+            )
+        };
+        assert_eq!(auth_column(&passwords), [["🔑"], ["🔑"]]);
+
+        passwords.save_password("web", "secret").unwrap();
+        assert_eq!(auth_column(&passwords), [["🔒"], ["🔑"]]);
+        passwords.delete_password("web").unwrap();
+        assert_eq!(auth_column(&passwords), [["🔑"], ["🔑"]]);
+    }
 
-        for cmd_args in recovery_commands.iter() {
-            let _ = Command::new(cmd_args[0]).args(&cmd_args[1..]).output(); // 使用output而不是status，避免输出干扰
+    #[test]
+    fn test_format_test_age_units_and_staleness() {
+        let stale_after = Duration::from_secs(600);
+        let age = |secs| format_test_age(Duration::from_secs(secs), stale_after);
+
+        let (text, stale) = age(42);
+        assert!(text.contains("42s") && !stale);
+        let (text, stale) = age(125);
+        assert!(text.contains("2m") && !stale);
+        // 到达阈值即视为过期，并附带过期标记
+        let (text, stale) = age(600);
+        assert!(text.contains("10m") && stale);
+        assert!(text.ends_with(')'));
+        assert!(age(7300).0.contains("2h"));
+        assert!(age(3 * 86400).0.contains("3d"));
+    }
+
+    #[test]
+    fn test_build_host_table_renders_rows() {
+        use ratatui::backend::TestBackend;
+
+        let hosts = sample_hosts();
+        let mut terminal = Terminal::new(TestBackend::new(140, 6)).unwrap();
+        terminal
+            .draw(|f| {
+                let table = build_host_table(
+                    &hosts,
+                    &HostColumn::DEFAULT,
+                    &|_| false,
+                    "servers".to_string(),
+                    Some(Line::from("notice")),
+                    false,
+                    StatusPalette::Default,
+                );
+                f.render_widget(table, f.area());
+            })
+            .unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let lines: Vec<String> = (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect()
+            })
+            .collect();
+        assert!(lines[0].contains("servers"));
+        assert!(lines[1].contains("Host") && lines[1].contains("IdentityFile"));
+        assert!(lines[2].contains("web.example.com") && lines[2].contains("deploy"));
+        assert!(lines[3].contains("ssh -W %h:%p bastion"));
+        assert!(lines[5].contains("notice"));
+    }
+
+    #[test]
+    fn test_build_host_table_custom_column_order() {
+        use ratatui::backend::TestBackend;
+
+        let names = ["Status", "host", "user"].map(String::from);
+        let columns = parse_columns(&names).unwrap();
+        assert_eq!(
+            columns,
+            [HostColumn::Status, HostColumn::Host, HostColumn::User]
+        );
+
+        let hosts = sample_hosts();
+        let mut terminal = Terminal::new(TestBackend::new(80, 5)).unwrap();
+        terminal
+            .draw(|f| {
+                let table = build_host_table(
+                    &hosts,
+                    &columns,
+                    &|_| false,
+                    "servers".to_string(),
+                    None,
+                    false,
+                    StatusPalette::Default,
+                );
+                f.render_widget(table, f.area());
+            })
+            .unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let header: String = (0..buffer.area.width)
+            .map(|x| buffer[(x, 1)].symbol())
+            .collect();
+        let status = header.find("Status").unwrap();
+        let host = header.find("Host").unwrap();
+        let user = header.find("User").unwrap();
+        assert!(status < host && host < user);
+        assert!(!header.contains("HostName") && !header.contains('#'));
+    }
+
+    #[test]
+    fn test_fleet_summary() {
+        let statuses = [
+            ConnectionStatus::Connected(Duration::from_millis(40)),
+            ConnectionStatus::SshReachable(Duration::from_millis(50)),
+            ConnectionStatus::Failed("refused".to_string()),
+            ConnectionStatus::Unknown,
+            ConnectionStatus::Connecting,
+        ];
+        let summary = FleetSummary::from_statuses(&statuses);
+        assert_eq!(
+            summary,
+            FleetSummary {
+                total: 5,
+                up: 2,
+                down: 1,
+                unknown: 2,
+                avg_latency: Some(Duration::from_millis(45)),
+            }
+        );
+        let expected = [
+            t("ui.summary_hosts").replacen("{}", "5", 1),
+            t("ui.summary_up").replacen("{}", "2", 1),
+            t("ui.summary_down").replacen("{}", "1", 1),
+            t("ui.summary_unknown").replacen("{}", "2", 1),
+            t("ui.summary_avg").replacen("{}", "45", 1),
+        ];
+        assert_eq!(summary.format(), expected.join(" · "));
+
+        // 没有可达主机时不显示平均延迟
+        let summary = FleetSummary::from_statuses(&[ConnectionStatus::Unknown]);
+        assert_eq!(summary.avg_latency, None);
+        assert!(!summary.format().contains("ms"));
+        assert_eq!(FleetSummary::from_statuses(&[]).total, 0);
+    }
+
+    #[test]
+    fn test_compact_mode_constraints_and_columns() {
+        use ratatui::backend::TestBackend;
+
+        let columns = [HostColumn::Index, HostColumn::Host, HostColumn::Status];
+        assert_eq!(
+            column_constraints(&columns, false),
+            [
+                Constraint::Length(4),
+                Constraint::Min(15),
+                Constraint::Length(12)
+            ]
+        );
+        assert_eq!(
+            column_constraints(&columns, true),
+            [
+                Constraint::Length(3),
+                Constraint::Min(10),
+                Constraint::Length(10)
+            ]
+        );
+        // 紧凑模式下每列都不比标准模式宽
+        for column in HostColumn::DEFAULT {
+            let width = |c: Constraint| match c {
+                Constraint::Length(n) | Constraint::Min(n) => n,
+                other => panic!("unexpected constraint {other:?}"),
+            };
+            assert!(width(column.constraint(true)) <= width(column.constraint(false)));
         }
 
-        Ok(())
+        // 未配置列时紧凑模式只显示主要的列
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut ui = ui_manager(&dir);
+        assert_eq!(ui.host_columns(), HostColumn::DEFAULT);
+        ui.set_compact(true);
+        assert_eq!(ui.host_columns(), HostColumn::COMPACT);
+
+        // 不显示选中行前的标记
+        let hosts = sample_hosts();
+        let render = |compact: bool| {
+            let mut terminal = Terminal::new(TestBackend::new(80, 5)).unwrap();
+            let mut state = TableState::default().with_selected(Some(0));
+            terminal
+                .draw(|f| {
+                    let table = build_host_table(
+                        &hosts,
+                        &HostColumn::COMPACT,
+                        &|_| false,
+                        "servers".to_string(),
+                        None,
+                        compact,
+                        StatusPalette::Default,
+                    );
+                    f.render_stateful_widget(table, f.area(), &mut state);
+                })
+                .unwrap();
+            let buffer = terminal.backend().buffer();
+            (0..buffer.area.width)
+                .map(|x| buffer[(x, 2)].symbol())
+                .collect::<String>()
+        };
+        assert!(render(false).contains('▍'));
+        assert!(!render(true).contains('▍'));
+    }
+
+    #[test]
+    fn test_parse_columns_rejects_invalid_lists() {
+        assert!(parse_columns(&[]).is_err());
+        let err = parse_columns(&["host".to_string(), "latency".to_string()]).unwrap_err();
+        assert!(err.contains("latency"));
+        let err = parse_columns(&["port".to_string(), "PORT".to_string()]).unwrap_err();
+        assert!(err.contains("more than once"));
+    }
+
+    #[test]
+    fn test_resolve_quick_connect() {
+        let hosts: Vec<SshHost> = ["web1", "web2", "db", "Dev"]
+            .iter()
+            .map(|name| SshHost::new(name.to_string()))
+            .collect();
+
+        // 序号从1开始，与列表中显示的一致
+        assert_eq!(
+            resolve_quick_connect(&hosts, "1"),
+            QuickConnectMatch::Found(0)
+        );
+        assert_eq!(
+            resolve_quick_connect(&hosts, " 4 "),
+            QuickConnectMatch::Found(3)
+        );
+        assert_eq!(
+            resolve_quick_connect(&hosts, "d"),
+            QuickConnectMatch::Ambiguous(vec!["db".to_string(), "Dev".to_string()])
+        );
+        assert_eq!(
+            resolve_quick_connect(&hosts, "de"),
+            QuickConnectMatch::Found(3)
+        );
+        assert_eq!(
+            resolve_quick_connect(&hosts, "web"),
+            QuickConnectMatch::Ambiguous(vec!["web1".to_string(), "web2".to_string()])
+        );
+        assert_eq!(
+            resolve_quick_connect(&hosts, "web2"),
+            QuickConnectMatch::Found(1)
+        );
+        assert_eq!(
+            resolve_quick_connect(&hosts, "5"),
+            QuickConnectMatch::NotFound
+        );
+        assert_eq!(
+            resolve_quick_connect(&hosts, "0"),
+            QuickConnectMatch::NotFound
+        );
+        assert_eq!(
+            resolve_quick_connect(&hosts, ""),
+            QuickConnectMatch::NotFound
+        );
+        assert_eq!(
+            resolve_quick_connect(&hosts, "cache"),
+            QuickConnectMatch::NotFound
+        );
+    }
+
+    #[test]
+    fn test_resolve_quick_connect_prefers_exact_host() {
+        let hosts: Vec<SshHost> = ["web", "web-staging"]
+            .iter()
+            .map(|name| SshHost::new(name.to_string()))
+            .collect();
+        assert_eq!(
+            resolve_quick_connect(&hosts, "web"),
+            QuickConnectMatch::Found(0)
+        );
+    }
+
+    #[test]
+    fn test_restore_terminal_is_idempotent() {
+        // 未进入TUI模式时直接调用不做任何操作
+        assert!(restore_terminal().is_ok());
+        assert!(!TERMINAL_ACTIVE.load(Ordering::SeqCst));
+
+        // 进入TUI模式后只有第一次调用会执行恢复
+        TERMINAL_ACTIVE.store(true, Ordering::SeqCst);
+        let _ = restore_terminal();
+        assert!(!TERMINAL_ACTIVE.load(Ordering::SeqCst));
+        assert!(restore_terminal().is_ok());
+    }
+
+    #[test]
+    fn test_remaining_connecting_time() {
+        let ms = Duration::from_millis;
+        // 0 表示不等待
+        assert_eq!(remaining_connecting_time(Duration::ZERO, ms(0)), None);
+        assert_eq!(remaining_connecting_time(Duration::ZERO, ms(40)), None);
+        // N 是显示时间的下限：测试更快时补足剩余时间，更慢时不再等待
+        assert_eq!(remaining_connecting_time(ms(300), ms(0)), Some(ms(300)));
+        assert_eq!(remaining_connecting_time(ms(300), ms(120)), Some(ms(180)));
+        assert_eq!(remaining_connecting_time(ms(300), ms(300)), None);
+        assert_eq!(remaining_connecting_time(ms(300), ms(500)), None);
+        for elapsed in [0, 1, 150, 299, 300, 1000].map(ms) {
+            let wait = remaining_connecting_time(ms(300), elapsed).unwrap_or_default();
+            assert!(elapsed + wait >= ms(300));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_sigint_is_ignored_while_child_runs() {
+        use signal_hook::consts::{SIGINT, SIGTERM};
+
+        assert!(should_exit_on_signal(SIGINT, false));
+        assert!(!should_exit_on_signal(SIGINT, true));
+        assert!(should_exit_on_signal(SIGTERM, true));
+
+        assert!(with_foreground_child(
+            || CHILD_RUNNING.load(Ordering::SeqCst)
+        ));
+        assert!(!CHILD_RUNNING.load(Ordering::SeqCst));
     }
 }
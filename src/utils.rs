@@ -11,12 +11,26 @@ pub fn get_ssh_config_path() -> Result<PathBuf> {
 
     let ssh_dir = home_dir.join(".ssh");
     if !ssh_dir.exists() {
-        std::fs::create_dir_all(&ssh_dir)?;
+        crate::permissions::create_private_dir_all(&ssh_dir)?;
     }
 
     Ok(ssh_dir.join("config"))
 }
 
+/// 获取 `~/.ssh` 目录路径，不会创建目录
+pub fn get_ssh_dir() -> Result<PathBuf> {
+    let home_dir = dirs::home_dir()
+        .ok_or_else(|| SshConnError::ConfigParse(t("error_home_dir").to_string()))?;
+    Ok(home_dir.join(".ssh"))
+}
+
+/// 获取 `~/.ssh/known_hosts` 路径
+pub fn get_known_hosts_path() -> Result<PathBuf> {
+    let home_dir = dirs::home_dir()
+        .ok_or_else(|| SshConnError::ConfigParse(t("error_home_dir").to_string()))?;
+    Ok(home_dir.join(".ssh").join("known_hosts"))
+}
+
 /// 获取密码数据库路径
 pub fn get_password_db_path() -> Result<PathBuf> {
     use crate::i18n::t;
@@ -25,65 +39,123 @@ pub fn get_password_db_path() -> Result<PathBuf> {
 
     let ssh_dir = home_dir.join(".ssh");
     if !ssh_dir.exists() {
-        std::fs::create_dir_all(&ssh_dir)?;
+        crate::permissions::create_private_dir_all(&ssh_dir)?;
     }
 
     Ok(ssh_dir.join("ssh_conn_passwords.db"))
 }
 
-/// 验证端口号
-pub fn validate_port(port_str: &str) -> Result<u16> {
-    if port_str.is_empty() {
-        return Err(SshConnError::InvalidPort {
-            port: port_str.to_string(),
-        });
-    }
+/// 获取日志文件目录：`$XDG_DATA_HOME/ssh-conn/logs`，通常为 `~/.local/share/ssh-conn/logs`
+pub fn get_log_dir() -> Result<PathBuf> {
+    let data_dir = dirs::data_local_dir()
+        .ok_or_else(|| SshConnError::ConfigParse(t("error_home_dir").to_string()))?;
+    Ok(data_dir.join("ssh-conn").join("logs"))
+}
 
-    let port = port_str
-        .parse::<u16>()
-        .map_err(|_| SshConnError::InvalidPort {
-            port: port_str.to_string(),
-        })?;
+/// 获取应用设置目录
+///
+/// 优先使用 `SSH_CONN_CONFIG_DIR`，其次为 `$XDG_CONFIG_HOME/ssh-conn`，
+/// 最后回退到 `~/.config/ssh-conn`
+pub fn get_settings_dir() -> Result<PathBuf> {
+    if let Some(dir) = std::env::var_os("SSH_CONN_CONFIG_DIR").filter(|d| !d.is_empty()) {
+        return Ok(PathBuf::from(dir));
+    }
 
-    if port == 0 {
-        return Err(SshConnError::InvalidPort {
-            port: port_str.to_string(),
-        });
+    if let Some(dir) = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|d| d.is_absolute())
+    {
+        return Ok(dir.join("ssh-conn"));
     }
 
-    Ok(port)
+    let home_dir = dirs::home_dir()
+        .ok_or_else(|| SshConnError::ConfigParse(t("error_home_dir").to_string()))?;
+    Ok(home_dir.join(".config").join("ssh-conn"))
+}
+
+/// 获取应用设置文件路径
+pub fn get_settings_path() -> Result<PathBuf> {
+    Ok(get_settings_dir()?.join("settings.toml"))
+}
+
+/// 验证端口号
+pub fn validate_port(port_str: &str) -> Result<u16> {
+    match port_str.parse::<u16>() {
+        Ok(port) if port != 0 => Ok(port),
+        _ => Err(SshConnError::validation(
+            "port",
+            format!("{}: {}", t("error.error_invalid_port"), port_str),
+        )),
+    }
 }
 
 /// 验证SSH主机名称
 pub fn validate_hostname(hostname: &str) -> Result<()> {
-    use crate::i18n::t;
+    let invalid = |key: &str| {
+        Err(SshConnError::InvalidHostname {
+            value: hostname.to_string(),
+            reason: t(key),
+        })
+    };
 
     if hostname.is_empty() {
-        return Err(SshConnError::ConfigParse(t("validation.hostname_empty")));
+        return invalid("validation.hostname_empty");
     }
 
     if hostname.trim() != hostname {
-        return Err(SshConnError::ConfigParse(t(
-            "validation.hostname_whitespace",
-        )));
+        return invalid("validation.hostname_whitespace");
     }
 
     if hostname.contains(' ') {
-        return Err(SshConnError::ConfigParse(t("validation.hostname_spaces")));
+        return invalid("validation.hostname_spaces");
+    }
+
+    // IPv6地址可以带方括号和区域ID，如 [fe80::1%eth0]
+    let bracketed = hostname.starts_with('[') || hostname.ends_with(']');
+    if bracketed || hostname.contains(':') {
+        let inner = match hostname.strip_prefix('[').and_then(|h| h.strip_suffix(']')) {
+            Some(inner) => inner,
+            None if bracketed => return invalid("validation.hostname_invalid_ipv6"),
+            None => hostname,
+        };
+        let (address, zone) = match inner.split_once('%') {
+            Some((address, zone)) => (address, Some(zone)),
+            None => (inner, None),
+        };
+        if address.parse::<std::net::Ipv6Addr>().is_err() || zone.is_some_and(str::is_empty) {
+            return invalid("validation.hostname_invalid_ipv6");
+        }
+        return Ok(());
+    }
+
+    if hostname.parse::<std::net::Ipv4Addr>().is_ok() {
+        return Ok(());
     }
 
     // 检查连续的点号
     if hostname.contains("..") {
-        return Err(SshConnError::ConfigParse(t(
-            "validation.hostname_consecutive_dots",
-        )));
+        return invalid("validation.hostname_consecutive_dots");
     }
 
     // 检查以点号开始或结束
     if hostname.starts_with('.') || hostname.ends_with('.') {
-        return Err(SshConnError::ConfigParse(t(
-            "validation.hostname_starts_or_ends_with_dot",
-        )));
+        return invalid("validation.hostname_starts_or_ends_with_dot");
+    }
+
+    // DNS名称（RFC 1035/1123）：总长不超过253，每段1~63个字母、数字或 -，且不以 - 开头或结尾
+    if hostname.len() > 253 {
+        return invalid("validation.hostname_too_long");
+    }
+    for label in hostname.split('.') {
+        if label.len() > 63 {
+            return invalid("validation.hostname_label_too_long");
+        }
+        if !label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return invalid("validation.hostname_invalid_chars");
+        }
+        if label.starts_with('-') || label.ends_with('-') {
+            return invalid("validation.hostname_label_hyphen");
+        }
     }
 
     Ok(())
@@ -92,18 +164,35 @@ pub fn validate_hostname(hostname: &str) -> Result<()> {
 /// 验证SSH配置Host字段
 pub fn validate_host(host: &str) -> Result<()> {
     if host.is_empty() {
-        return Err(SshConnError::ConfigParse(t("host_name_empty").to_string()));
+        return Err(SshConnError::validation("host", t("error.host_name_empty")));
     }
 
     if host.contains(' ') || host.contains('\t') {
-        return Err(SshConnError::ConfigParse(
-            t("host_name_no_spaces").to_string(),
+        return Err(SshConnError::validation(
+            "host",
+            t("error.host_name_no_spaces"),
+        ));
+    }
+
+    // 以 - 开头的别名会被ssh当作命令行选项
+    if host.starts_with('-') {
+        return Err(SshConnError::validation(
+            "host",
+            t("error.host_name_leading_dash"),
+        ));
+    }
+
+    // 只由通配符组成的别名匹配所有主机，解析配置时会被跳过
+    if host.chars().all(|c| c == '*' || c == '?') {
+        return Err(SshConnError::validation(
+            "host",
+            t("error.host_name_wildcard_only"),
         ));
     }
 
-    // 检查是否包含通配符（在某些情况下可能不合适）
-    if host.contains('*') || host.contains('?') {
-        log::warn!("{}", t("host_name_wildcard_warning"));
+    // 通配符、否定和 % 转义在ssh配置中有特殊含义（在某些情况下可能不合适）
+    if host.contains(['*', '?', '!', '%', '"']) {
+        log::warn!("{}", t("error.host_name_wildcard_warning"));
     }
 
     Ok(())
@@ -111,24 +200,741 @@ pub fn validate_host(host: &str) -> Result<()> {
 
 /// 验证用户名
 pub fn validate_username(username: &str) -> Result<()> {
-    if username.is_empty() {
-        return Err(SshConnError::ConfigParse(t("username_empty").to_string()));
+    // 具体原因由 SshConnError::InvalidUsername 的消息根据值给出
+    if username.is_empty() || username.contains([' ', '\t']) || username.contains(['@', ':']) {
+        return Err(SshConnError::InvalidUsername {
+            value: username.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+/// 按ssh配置文件的规则拆分参数：以空白分隔，双引号内的空白保留，引号本身去除
+pub fn split_config_args(value: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_token = false;
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_token = true;
+            }
+            '\\' if in_quotes => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_token {
+                    args.push(std::mem::take(&mut current));
+                    has_token = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_token = true;
+            }
+        }
     }
 
-    if username.contains(' ') || username.contains('\t') {
-        return Err(SshConnError::ConfigParse(
-            t("username_no_spaces").to_string(),
+    if has_token {
+        args.push(current);
+    }
+    args
+}
+
+/// 验证 `KEY=value` 形式的环境变量
+///
+/// 变量名只能包含字母、数字和下划线且不能以数字开头，值可以为空
+pub fn validate_env_var(entry: &str) -> Result<(String, String)> {
+    let valid_key = |key: &str| {
+        key.chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    };
+
+    match entry.split_once('=') {
+        Some((key, value)) if valid_key(key) => Ok((key.to_string(), value.to_string())),
+        _ => Err(SshConnError::validation(
+            "set_env",
+            t("error.env_var_invalid").replace("{}", entry),
+        )),
+    }
+}
+
+/// RequestTTY允许的取值
+pub const REQUEST_TTY_VALUES: &[&str] = &["auto", "yes", "force", "no"];
+
+/// 验证RequestTTY取值（auto/yes/force/no，不区分大小写），返回小写形式
+pub fn validate_request_tty(value: &str) -> Result<String> {
+    let normalized = value.trim().to_ascii_lowercase();
+    if REQUEST_TTY_VALUES.contains(&normalized.as_str()) {
+        Ok(normalized)
+    } else {
+        Err(SshConnError::validation(
+            "request_tty",
+            t("error.request_tty_invalid").replace("{}", value),
+        ))
+    }
+}
+
+/// AddressFamily允许的取值
+pub const ADDRESS_FAMILY_VALUES: &[&str] = &["any", "inet", "inet6"];
+
+/// 验证AddressFamily取值（any/inet/inet6，不区分大小写），返回小写形式
+pub fn validate_address_family(value: &str) -> Result<String> {
+    let normalized = value.trim().to_ascii_lowercase();
+    if ADDRESS_FAMILY_VALUES.contains(&normalized.as_str()) {
+        Ok(normalized)
+    } else {
+        Err(SshConnError::validation(
+            "address_family",
+            t("error.address_family_invalid").replace("{}", value),
+        ))
+    }
+}
+
+/// 验证BindAddress：IPv4/IPv6地址，或网卡名（字母、数字和 `.`、`-`、`_`，最长15个字符）
+pub fn validate_bind_address(value: &str) -> Result<()> {
+    let value = value.trim();
+    let is_interface = !value.is_empty()
+        && value.len() <= 15
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+        // 全是数字和点的必须是合法的IPv4地址
+        && !value.chars().all(|c| c.is_ascii_digit() || c == '.');
+    if is_interface || value.parse::<std::net::IpAddr>().is_ok() {
+        Ok(())
+    } else {
+        Err(SshConnError::validation(
+            "bind_address",
+            t("error.bind_address_invalid").replace("{}", value),
+        ))
+    }
+}
+
+/// 验证PermitLocalCommand取值（yes/no，不区分大小写），返回小写形式
+pub fn validate_permit_local_command(value: &str) -> Result<String> {
+    let normalized = value.trim().to_ascii_lowercase();
+    if matches!(normalized.as_str(), "yes" | "no") {
+        Ok(normalized)
+    } else {
+        Err(SshConnError::validation(
+            "permit_local_command",
+            t("error.permit_local_command_invalid").replace("{}", value),
+        ))
+    }
+}
+
+/// 验证LocalCommand：非空，且不能包含换行（配置文件中一条指令只占一行）
+pub fn validate_local_command(value: &str) -> Result<()> {
+    if value.trim().is_empty() || value.contains(['\n', '\r']) {
+        Err(SshConnError::validation(
+            "local_command",
+            t("error.local_command_invalid"),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// 已知不安全、只应为兼容老旧设备而启用的算法
+const WEAK_ALGORITHMS: &[&str] = &[
+    "ssh-rsa",
+    "ssh-dss",
+    "ssh-rsa-cert-v01@openssh.com",
+    "ssh-dss-cert-v01@openssh.com",
+    "3des-cbc",
+    "aes128-cbc",
+    "aes192-cbc",
+    "aes256-cbc",
+    "blowfish-cbc",
+    "cast128-cbc",
+    "arcfour",
+    "arcfour128",
+    "arcfour256",
+    "rijndael-cbc@lysator.liu.se",
+    "hmac-md5",
+    "hmac-md5-96",
+    "hmac-md5-etm@openssh.com",
+    "hmac-md5-96-etm@openssh.com",
+    "hmac-sha1-96",
+    "hmac-sha1-96-etm@openssh.com",
+    "hmac-ripemd160",
+];
+
+/// 找出算法列表（如 `+ssh-rsa,rsa-sha2-256`）中启用的弱算法
+///
+/// 以 `+`/`^` 开头的列表表示追加到默认值，同样会启用其中的算法；
+/// 以 `-` 开头的列表表示从默认值中移除，不会启用任何算法
+pub fn weak_algorithms(value: &str) -> Vec<String> {
+    let value = value.trim();
+    if value.starts_with('-') {
+        return Vec::new();
+    }
+
+    value
+        .trim_start_matches(['+', '^'])
+        .split(',')
+        .map(str::trim)
+        .filter(|alg| WEAK_ALGORITHMS.contains(&alg.to_ascii_lowercase().as_str()))
+        .map(str::to_string)
+        .collect()
+}
+
+/// 解析SetEnv的值（可包含多个以空白分隔的 `KEY=value`）
+pub fn parse_set_env(value: &str) -> Result<Vec<(String, String)>> {
+    split_config_args(value)
+        .iter()
+        .map(|entry| validate_env_var(entry))
+        .collect()
+}
+
+/// 格式化单个SetEnv条目，值包含空白或引号时加双引号
+pub fn format_env_var(key: &str, value: &str) -> String {
+    if value
+        .chars()
+        .any(|c| c.is_whitespace() || c == '"' || c == '\\')
+    {
+        format!(
+            "{}=\"{}\"",
+            key,
+            value.replace('\\', "\\\\").replace('"', "\\\"")
+        )
+    } else {
+        format!("{}={}", key, value)
+    }
+}
+
+/// 为shell命令行转义参数
+///
+/// 仅包含安全字符的参数原样返回（保留 `~` 以便shell展开家目录），
+/// 其他参数使用单引号包裹
+pub fn shell_quote(arg: &str) -> String {
+    let is_safe = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "@%+=:,./_-~".contains(c));
+
+    if is_safe {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// 自然排序比较（不区分大小写）
+///
+/// 连续的数字按数值比较，使 `web2` 排在 `web10` 之前；数值相同时前导零较少的在前，
+/// 其余都相同时按原始字符串比较，保证结果稳定
+pub fn natural_cmp(a: &str, b: &str) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+
+    let mut left = a.chars().peekable();
+    let mut right = b.chars().peekable();
+    let mut leading_zeros = Ordering::Equal;
+
+    loop {
+        let (l, r) = match (left.peek(), right.peek()) {
+            (None, None) => break,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(&l), Some(&r)) => (l, r),
+        };
+
+        if l.is_ascii_digit() && r.is_ascii_digit() {
+            let left_digits = take_digits(&mut left);
+            let right_digits = take_digits(&mut right);
+            let left_value = left_digits.trim_start_matches('0');
+            let right_value = right_digits.trim_start_matches('0');
+
+            // 先比较有效位数，再逐位比较，避免大数溢出
+            let ordering = left_value
+                .len()
+                .cmp(&right_value.len())
+                .then_with(|| left_value.cmp(right_value));
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+            if leading_zeros == Ordering::Equal {
+                leading_zeros = left_digits.len().cmp(&right_digits.len());
+            }
+            continue;
+        }
+
+        let ordering = l.to_lowercase().cmp(r.to_lowercase());
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+        left.next();
+        right.next();
+    }
+
+    leading_zeros.then_with(|| a.cmp(b))
+}
+
+/// 通配符匹配，`*` 匹配任意个字符，`?` 匹配单个字符，区分大小写
+///
+/// 用于按模式选择配置中的主机名，不做路径或shell展开
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // 最近一个 `*` 的位置及其当前匹配到的文本位置，失配时回溯到这里多吞一个字符
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// 区间展开得到的名称数量上限，防止 `node[1-99999999]` 之类的输入耗尽内存
+pub const MAX_RANGE_EXPANSION: usize = 4096;
+
+/// 展开名称中的数字区间，如 `node[01-03]` → `node01`、`node02`、`node03`
+///
+/// 起始值带前导零时按起始值的宽度补零；多个区间按笛卡尔积展开。
+/// 没有区间或区间格式不正确（非数字、起始大于结束）时原样返回，
+/// 展开数量超过 [`MAX_RANGE_EXPANSION`] 时同样原样返回，需要报告错误时使用 [`try_expand_range`]
+pub fn expand_range(pattern: &str) -> Vec<String> {
+    try_expand_range(pattern, MAX_RANGE_EXPANSION).unwrap_or_else(|| vec![pattern.to_string()])
+}
+
+/// 与 [`expand_range`] 相同，但展开数量超过 `limit` 时在生成名称之前返回 `None`
+pub fn try_expand_range(pattern: &str, limit: usize) -> Option<Vec<String>> {
+    let Some((prefix, start, end, suffix)) = parse_range(pattern) else {
+        return Some(vec![pattern.to_string()]);
+    };
+    let width = if start.len() > 1 && start.starts_with('0') {
+        start.len()
+    } else {
+        0
+    };
+    let (Ok(start), Ok(end)) = (start.parse::<u64>(), end.parse::<u64>()) else {
+        return Some(vec![pattern.to_string()]);
+    };
+    if start > end {
+        return Some(vec![pattern.to_string()]);
+    }
+
+    let rests = try_expand_range(suffix, limit)?;
+    let count = (end - start)
+        .checked_add(1)
+        .and_then(|n| n.checked_mul(rests.len() as u64))?;
+    if count > limit as u64 {
+        return None;
+    }
+    Some(
+        (start..=end)
+            .flat_map(|n| {
+                rests
+                    .iter()
+                    .map(move |rest| format!("{}{:0width$}{}", prefix, n, rest, width = width))
+            })
+            .collect(),
+    )
+}
+
+/// 拆出第一个 `[起始-结束]` 区间，返回 (前缀, 起始, 结束, 后缀)
+fn parse_range(pattern: &str) -> Option<(&str, &str, &str, &str)> {
+    let open = pattern.find('[')?;
+    let close = open + pattern[open..].find(']')?;
+    let (start, end) = pattern[open + 1..close].split_once('-')?;
+    let is_number = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+    (is_number(start) && is_number(end))
+        .then(|| (&pattern[..open], start, end, &pattern[close + 1..]))
+}
+
+/// 逐行比较的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffLine<'a> {
+    /// 两边相同的行
+    Same(&'a str),
+    /// 仅在旧文本中的行
+    Removed(&'a str),
+    /// 仅在新文本中的行
+    Added(&'a str),
+}
+
+/// 基于最长公共子序列计算两段文本的逐行差异，同一位置先删除后新增
+pub fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<DiffLine<'a>> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+
+    // lcs[i][j] 为 a[i..] 与 b[j..] 的最长公共子序列长度
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut lines = Vec::with_capacity(a.len().max(b.len()));
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            lines.push(DiffLine::Same(a[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            lines.push(DiffLine::Removed(a[i]));
+            i += 1;
+        } else {
+            lines.push(DiffLine::Added(b[j]));
+            j += 1;
+        }
+    }
+    lines.extend(a[i..].iter().map(|l| DiffLine::Removed(l)));
+    lines.extend(b[j..].iter().map(|l| DiffLine::Added(l)));
+    lines
+}
+
+/// 生成统一格式（unified diff）的差异文本，变更前后各保留 `context` 行上下文
+///
+/// 两段文本相同时返回空字符串
+pub fn unified_diff(
+    old: &str,
+    new: &str,
+    old_label: &str,
+    new_label: &str,
+    context: usize,
+) -> String {
+    let lines = diff_lines(old, new);
+
+    // 把相邻（上下文重叠）的变更合并为同一个块
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for (idx, _) in lines
+        .iter()
+        .enumerate()
+        .filter(|(_, l)| !matches!(l, DiffLine::Same(_)))
+    {
+        let start = idx.saturating_sub(context);
+        let end = (idx + context + 1).min(lines.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    if hunks.is_empty() {
+        return String::new();
+    }
+
+    let is_old = |l: &DiffLine| !matches!(l, DiffLine::Added(_));
+    let is_new = |l: &DiffLine| !matches!(l, DiffLine::Removed(_));
+    let range = |before: usize, count: usize| match count {
+        0 => format!("{},0", before),
+        1 => (before + 1).to_string(),
+        _ => format!("{},{}", before + 1, count),
+    };
+
+    let mut out = format!("--- {}\n+++ {}\n", old_label, new_label);
+    for (start, end) in hunks {
+        let old_before = lines[..start].iter().filter(|l| is_old(l)).count();
+        let new_before = lines[..start].iter().filter(|l| is_new(l)).count();
+        let hunk = &lines[start..end];
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            range(old_before, hunk.iter().filter(|l| is_old(l)).count()),
+            range(new_before, hunk.iter().filter(|l| is_new(l)).count())
         ));
+        for line in hunk {
+            let (sign, text) = match line {
+                DiffLine::Same(text) => (' ', text),
+                DiffLine::Removed(text) => ('-', text),
+                DiffLine::Added(text) => ('+', text),
+            };
+            out.push(sign);
+            out.push_str(text);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// 提取HostName的域名部分（可注册域名），用于分组
+///
+/// 一般取最后两段，如 `db1.eu.example.com` → `example.com`；`co.uk`、`com.cn` 这类
+/// 二级公共后缀取最后三段。IP地址、单段主机名返回None，结果统一为小写
+pub fn host_domain(hostname: &str) -> Option<String> {
+    const SECOND_LEVEL: [&str; 7] = ["co", "com", "net", "org", "gov", "edu", "ac"];
+
+    let hostname = hostname.trim().trim_end_matches('.').to_lowercase();
+    let bare = hostname.trim_start_matches('[').trim_end_matches(']');
+    if bare.parse::<std::net::IpAddr>().is_ok() {
+        return None;
+    }
+
+    let labels: Vec<&str> = hostname.split('.').collect();
+    if labels.len() < 2 || labels.iter().any(|l| l.is_empty()) {
+        return None;
     }
 
-    // 检查是否包含非法字符
-    if username.contains('@') || username.contains(':') {
-        return Err(SshConnError::ConfigParse(
-            t("username_invalid_chars").to_string(),
+    let tld = labels[labels.len() - 1];
+    let second = labels[labels.len() - 2];
+    let take = if tld.len() == 2 && SECOND_LEVEL.contains(&second) && labels.len() > 2 {
+        3
+    } else {
+        2
+    };
+    Some(labels[labels.len() - take..].join("."))
+}
+
+/// 按 `key` 将主机分组，组名按自然顺序排列，无法分组（key返回None）的主机放在最后一组
+///
+/// 组内保持输入顺序
+pub fn group_hosts<F>(
+    hosts: &[crate::models::SshHost],
+    key: F,
+) -> Vec<(Option<String>, Vec<&crate::models::SshHost>)>
+where
+    F: Fn(&crate::models::SshHost) -> Option<String>,
+{
+    let mut groups: Vec<(Option<String>, Vec<&crate::models::SshHost>)> = Vec::new();
+    for host in hosts {
+        let name = key(host);
+        match groups.iter_mut().find(|(group, _)| *group == name) {
+            Some((_, members)) => members.push(host),
+            None => groups.push((name, vec![host])),
+        }
+    }
+
+    groups.sort_by(|(a, _), (b, _)| match (a, b) {
+        (Some(a), Some(b)) => natural_cmp(a, b),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+    groups
+}
+
+/// 读取连续的ASCII数字
+fn take_digits(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> String {
+    let mut digits = String::new();
+    while let Some(&c) = chars.peek() {
+        if !c.is_ascii_digit() {
+            break;
+        }
+        digits.push(c);
+        chars.next();
+    }
+    digits
+}
+
+/// 未设置 `terminal_command` 时依次检测的终端，`{cmd}` 为连接命令
+#[cfg(not(target_os = "macos"))]
+const TERMINAL_CANDIDATES: &[(&str, &str)] = &[
+    ("x-terminal-emulator", "x-terminal-emulator -e {cmd}"),
+    ("gnome-terminal", "gnome-terminal -- {cmd}"),
+    ("konsole", "konsole -e {cmd}"),
+    ("alacritty", "alacritty -e {cmd}"),
+    ("kitty", "kitty {cmd}"),
+    ("wezterm", "wezterm start -- {cmd}"),
+    ("xterm", "xterm -e {cmd}"),
+];
+
+/// 检测可用的终端，返回 `terminal_command` 模板
+///
+/// macOS 使用 Terminal.app，其他系统在 PATH 中查找常见终端
+pub fn default_terminal_command() -> Option<String> {
+    #[cfg(target_os = "macos")]
+    {
+        Some(r#"osascript -e "tell application \"Terminal\" to do script \"{cmd}\"""#.to_string())
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        TERMINAL_CANDIDATES
+            .iter()
+            .find(|(program, _)| find_program(program).is_some())
+            .map(|(_, template)| template.to_string())
+    }
+}
+
+/// 展开用户输入的路径：开头的 `~`、`~user`（仅Unix）以及其中的 `$VAR`、`${VAR}`（Windows上还有 `%VAR%`）
+///
+/// 未设置的环境变量、找不到的用户原样保留，不需要展开的路径直接返回
+pub fn expand_path(path: &str) -> PathBuf {
+    if let Some(rest) = path.strip_prefix('~') {
+        let (user, tail) = rest.split_at(rest.find(std::path::is_separator).unwrap_or(rest.len()));
+        if let Some(home) = home_dir_of(user) {
+            let tail = expand_env_vars(tail);
+            let tail = tail.trim_start_matches(std::path::is_separator);
+            return if tail.is_empty() {
+                home
+            } else {
+                home.join(tail)
+            };
+        }
+    }
+    PathBuf::from(expand_env_vars(path))
+}
+
+/// `~user` 中用户的主目录，`user` 为空时为当前用户
+fn home_dir_of(user: &str) -> Option<PathBuf> {
+    if user.is_empty() {
+        return dirs::home_dir();
+    }
+    #[cfg(unix)]
+    {
+        // /etc/passwd 每行为 name:password:uid:gid:gecos:home:shell
+        let passwd = std::fs::read_to_string("/etc/passwd").ok()?;
+        passwd.lines().find_map(|line| {
+            let fields: Vec<&str> = line.split(':').collect();
+            (fields.len() >= 6 && fields[0] == user).then(|| PathBuf::from(fields[5]))
+        })
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+/// 替换 `$VAR`、`${VAR}`（Windows上还有 `%VAR%`）为环境变量的值，未设置的变量原样保留
+fn expand_env_vars(value: &str) -> String {
+    let is_start = |c: char| c == '$' || (cfg!(windows) && c == '%');
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find(is_start) {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        // (变量名, 变量名及其后的括号或 % 占用的长度)
+        let (name, len) = if rest[start..].starts_with('%') {
+            match after.find('%') {
+                Some(end) => (&after[..end], end + 1),
+                None => ("", 0),
+            }
+        } else if let Some(braced) = after.strip_prefix('{') {
+            match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0),
+            }
+        } else {
+            let end = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            (&after[..end], end)
+        };
+        let token = &rest[start..start + 1 + len];
+        match std::env::var(name).ok().filter(|_| !name.is_empty()) {
+            Some(var) => expanded.push_str(&var),
+            None => expanded.push_str(token),
+        }
+        rest = &rest[start + 1 + len..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// IdentityFile展开后不存在时记录警告；密钥可能之后才创建，因此不作为错误
+pub fn warn_missing_identity_file(identity_file: &str) {
+    if !expand_path(identity_file).exists() {
+        log::warn!("{}", t("path_not_exists").replace("{}", identity_file));
+    }
+}
+
+/// 在 PATH 中查找可执行程序
+pub fn find_program(program: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(program))
+        .find(|candidate| candidate.is_file())
+}
+
+/// 用连接命令展开 `terminal_command` 模板，得到启动终端的argv
+///
+/// 单独的 `{cmd}` 展开为多个参数；嵌在其他文本中时（如 osascript 脚本）替换为shell转义后的命令行
+pub fn expand_terminal_command(template: &str, command: &[String]) -> Result<Vec<String>> {
+    let words = split_config_args(template);
+    if !words.iter().any(|w| w.contains("{cmd}")) {
+        return Err(SshConnError::InvalidArgument(
+            t("error.terminal_command_invalid").replace("{}", template),
         ));
     }
 
-    Ok(())
+    let command_line = command
+        .iter()
+        .map(|arg| shell_quote(arg))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut argv = Vec::new();
+    for word in words {
+        if word == "{cmd}" {
+            argv.extend(command.iter().cloned());
+        } else {
+            argv.push(word.replace("{cmd}", &command_line));
+        }
+    }
+    Ok(argv)
+}
+
+/// 复制文本到系统剪贴板
+///
+/// 依次尝试各平台常见的剪贴板命令（pbcopy、clip、wl-copy、xclip、xsel）
+pub fn copy_to_clipboard(text: &str) -> Result<()> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let candidates: &[(&str, &[&str])] = &[
+        ("pbcopy", &[]),
+        ("clip", &[]),
+        ("wl-copy", &[]),
+        ("xclip", &["-selection", "clipboard"]),
+        ("xsel", &["--clipboard", "--input"]),
+    ];
+
+    for (program, args) in candidates {
+        let child = Command::new(program)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+
+        let Ok(mut child) = child else {
+            continue;
+        };
+
+        if let Some(stdin) = child.stdin.as_mut() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        drop(child.stdin.take());
+
+        if child.wait()?.success() {
+            return Ok(());
+        }
+    }
+
+    Err(SshConnError::TuiError(t("clipboard_unavailable")))
 }
 
 /// 格式化SSH连接信息用于显示
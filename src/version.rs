@@ -0,0 +1,104 @@
+//! `version --verbose` 输出的版本和构建信息，便于提交问题报告
+//!
+//! git提交、rustc版本和目标平台由构建脚本在编译时记录，`ssh`/`sshpass` 是否可用在运行时从PATH中查找。
+
+use std::path::PathBuf;
+
+use crate::i18n::t;
+use crate::utils::find_program;
+
+/// 版本和构建信息
+#[derive(Debug, Clone)]
+pub struct VersionInfo {
+    /// crate版本
+    pub version: &'static str,
+    /// 构建时的git提交，不在git仓库中构建时为 `unknown`
+    pub git_commit: &'static str,
+    /// 编译所用的rustc版本
+    pub rustc_version: &'static str,
+    /// 目标平台，如 `x86_64-unknown-linux-gnu`
+    pub target: &'static str,
+    /// PATH中的 `ssh`
+    pub ssh: Option<PathBuf>,
+    /// PATH中的 `sshpass`
+    pub sshpass: Option<PathBuf>,
+}
+
+impl VersionInfo {
+    /// 收集编译时记录的信息，并在PATH中查找 `ssh` 和 `sshpass`
+    pub fn collect() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            git_commit: env!("SSH_CONN_GIT_COMMIT"),
+            rustc_version: env!("SSH_CONN_RUSTC_VERSION"),
+            target: env!("SSH_CONN_TARGET"),
+            ssh: find_program(program_name("ssh").as_str()),
+            sshpass: find_program(program_name("sshpass").as_str()),
+        }
+    }
+
+    /// `version` 的输出：只有版本号，`verbose` 时附带构建信息和外部程序的位置
+    pub fn render(&self, verbose: bool) -> String {
+        let mut out = format!("ssh-conn {}\n", self.version);
+        if !verbose {
+            return out;
+        }
+        let tool = |path: &Option<PathBuf>| match path {
+            Some(path) => path.display().to_string(),
+            None => t("version_not_found"),
+        };
+        let rows = [
+            (t("version_commit"), self.git_commit.to_string()),
+            (t("version_rustc"), self.rustc_version.to_string()),
+            (t("version_target"), self.target.to_string()),
+            ("ssh".to_string(), tool(&self.ssh)),
+            ("sshpass".to_string(), tool(&self.sshpass)),
+        ];
+        for (label, value) in rows {
+            out.push_str(&format!("{}: {}\n", label, value));
+        }
+        out
+    }
+}
+
+/// Windows上可执行文件带 `.exe` 扩展名
+fn program_name(name: &str) -> String {
+    format!("{}{}", name, std::env::consts::EXE_SUFFIX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_info_is_populated() {
+        let info = VersionInfo::collect();
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        assert!(!info.version.is_empty());
+        assert!(!info.git_commit.is_empty());
+        assert!(info.rustc_version.starts_with("rustc ") || info.rustc_version == "unknown");
+        assert!(!info.target.is_empty());
+    }
+
+    #[test]
+    fn test_render() {
+        let info = VersionInfo {
+            version: "1.2.3",
+            git_commit: "abc123",
+            rustc_version: "rustc 1.90.0",
+            target: "x86_64-unknown-linux-gnu",
+            ssh: Some(PathBuf::from("/usr/bin/ssh")),
+            sshpass: None,
+        };
+        assert_eq!(info.render(false), "ssh-conn 1.2.3\n");
+
+        let verbose = info.render(true);
+        assert!(verbose.starts_with("ssh-conn 1.2.3\n"));
+        assert!(verbose.contains("abc123"));
+        assert!(verbose.contains("rustc 1.90.0"));
+        assert!(verbose.contains("x86_64-unknown-linux-gnu"));
+        assert!(verbose.contains("/usr/bin/ssh"));
+        assert!(verbose.contains(&t("version_not_found")));
+        assert_eq!(verbose.lines().count(), 6);
+    }
+}
@@ -0,0 +1,362 @@
+//! 主机可用性持续监测（`watch` 子命令）
+//!
+//! 按固定间隔并发测试选中的主机，只在主机状态从可用变为不可用（或反过来）时输出一行，
+//! 并可调用设置中的通知命令。
+
+use std::process::Command;
+use std::time::Duration;
+
+use futures::StreamExt;
+
+use crate::error::SshConnError;
+use crate::i18n::t;
+use crate::models::{ConnectionStatus, SshHost};
+use crate::network::NetworkProbe;
+
+/// 主机可用性
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Availability {
+    Up,
+    Down,
+}
+
+impl Availability {
+    /// 由连接测试结果得到可用性，未完成的测试返回None
+    pub fn from_status(status: &ConnectionStatus) -> Option<Self> {
+        match status {
+            ConnectionStatus::Connected(_) | ConnectionStatus::SshReachable(_) => {
+                Some(Availability::Up)
+            }
+            ConnectionStatus::Failed(_) => Some(Availability::Down),
+            ConnectionStatus::Unknown | ConnectionStatus::Connecting => None,
+        }
+    }
+
+    /// 输出和通知命令中使用的名称
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Availability::Up => "up",
+            Availability::Down => "down",
+        }
+    }
+}
+
+/// 一次状态变化，`from` 为None表示首次检测
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusChange {
+    pub host: String,
+    pub from: Option<Availability>,
+    pub to: Availability,
+    /// 延迟或失败原因
+    pub detail: String,
+}
+
+impl StatusChange {
+    /// 是否为真正的状态切换（首次检测不算）
+    pub fn is_transition(&self) -> bool {
+        self.from.is_some()
+    }
+
+    /// 输出的一行，如 `[2025-01-01 12:00:00] web: up → down (Connection refused)`
+    pub fn format_line(&self, timestamp: &str) -> String {
+        let from = self.from.map_or("?", Availability::as_str);
+        format!(
+            "[{}] {}: {} → {} ({})",
+            timestamp,
+            self.host,
+            from,
+            self.to.as_str(),
+            self.detail
+        )
+    }
+}
+
+/// 单台主机的监测统计
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HostWatchStats {
+    /// 测试次数
+    pub checks: u32,
+    /// 不可用的次数
+    pub failures: u32,
+    /// 状态切换次数（不含首次检测）
+    pub transitions: u32,
+    /// 最近一次的可用性
+    pub current: Option<Availability>,
+}
+
+/// 状态变化检测：记录每台主机上一次的可用性，只在变化时返回事件
+#[derive(Debug, Default)]
+pub struct StatusTracker {
+    /// 按首次出现的顺序保存，便于输出稳定的汇总
+    hosts: Vec<(String, HostWatchStats)>,
+}
+
+impl StatusTracker {
+    /// 记录一次测试结果，状态与上一次不同（或首次检测）时返回变化
+    pub fn observe(&mut self, host: &str, status: &ConnectionStatus) -> Option<StatusChange> {
+        let to = Availability::from_status(status)?;
+        let index = match self.hosts.iter().position(|(name, _)| name == host) {
+            Some(index) => index,
+            None => {
+                self.hosts
+                    .push((host.to_string(), HostWatchStats::default()));
+                self.hosts.len() - 1
+            }
+        };
+        let stats = &mut self.hosts[index].1;
+        stats.checks += 1;
+        if to == Availability::Down {
+            stats.failures += 1;
+        }
+
+        let from = stats.current.replace(to);
+        if from == Some(to) {
+            return None;
+        }
+        if from.is_some() {
+            stats.transitions += 1;
+        }
+        let detail = match status {
+            ConnectionStatus::Connected(d) | ConnectionStatus::SshReachable(d) => {
+                format!("{}ms", d.as_millis())
+            }
+            ConnectionStatus::Failed(error) => error.clone(),
+            ConnectionStatus::Unknown | ConnectionStatus::Connecting => String::new(),
+        };
+        Some(StatusChange {
+            host: host.to_string(),
+            from,
+            to,
+            detail,
+        })
+    }
+
+    /// 各主机的统计，按首次检测的顺序
+    pub fn stats(&self) -> &[(String, HostWatchStats)] {
+        &self.hosts
+    }
+
+    /// 退出时输出的汇总，每台主机一行
+    pub fn summary_lines(&self) -> Vec<String> {
+        self.hosts
+            .iter()
+            .map(|(host, stats)| {
+                t("watch_summary_line")
+                    .replacen("{}", host, 1)
+                    .replacen("{}", stats.current.map_or("?", Availability::as_str), 1)
+                    .replacen("{}", &stats.checks.to_string(), 1)
+                    .replacen("{}", &stats.failures.to_string(), 1)
+                    .replacen("{}", &stats.transitions.to_string(), 1)
+            })
+            .collect()
+    }
+}
+
+/// 构建状态切换时执行的通知命令：通过 `sh -c` 执行，
+/// 环境变量 `HOST`、`STATUS`（up/down）、`PREVIOUS_STATUS` 和 `DETAIL` 描述这次变化
+pub fn hook_command(hook: &str, change: &StatusChange) -> Command {
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.args(["/C", hook]);
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.args(["-c", hook]);
+        command
+    };
+    command
+        .env("HOST", &change.host)
+        .env("STATUS", change.to.as_str())
+        .env(
+            "PREVIOUS_STATUS",
+            change.from.map_or("", Availability::as_str),
+        )
+        .env("DETAIL", &change.detail);
+    command
+}
+
+/// 执行通知命令，失败只记录日志，不影响监测
+pub fn run_hook(hook: &str, change: &StatusChange) {
+    match hook_command(hook, change).status() {
+        Ok(status) if !status.success() => {
+            log::warn!("Watch hook exited with {} for {}", status, change.host)
+        }
+        Ok(_) => {}
+        Err(e) => log::warn!("Failed to run watch hook for {}: {}", change.host, e),
+    }
+}
+
+/// 并发测试一轮主机（最多 `concurrency` 个同时进行），按主机顺序返回结果
+pub async fn probe_round(hosts: &[SshHost], concurrency: usize) -> Vec<ConnectionStatus> {
+    let probe = NetworkProbe::new();
+    let probe = &probe;
+    let mut results: Vec<(usize, ConnectionStatus)> =
+        futures::stream::iter(hosts.iter().enumerate())
+            .map(|(index, host)| async move {
+                let (hostname, port) = host.get_host_and_port();
+                let timeout = host.connect_timeout.as_ref().and_then(|t| t.parse().ok());
+                let status = match probe.test_connection(&hostname, port, timeout).await {
+                    Ok(duration) => ConnectionStatus::Connected(duration),
+                    Err(SshConnError::Connection(reason)) => ConnectionStatus::Failed(reason),
+                    Err(e) => ConnectionStatus::Failed(e.to_string()),
+                };
+                (index, status)
+            })
+            .buffer_unordered(concurrency.max(1))
+            .collect()
+            .await;
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, status)| status).collect()
+}
+
+/// 等待下一轮；期间 `stop` 被置位（收到Ctrl-C）时提前返回false
+pub fn wait_interval(interval: Duration, stop: &std::sync::atomic::AtomicBool) -> bool {
+    use std::sync::atomic::Ordering;
+
+    let step = Duration::from_millis(100);
+    let mut waited = Duration::ZERO;
+    while waited < interval {
+        if stop.load(Ordering::SeqCst) {
+            return false;
+        }
+        std::thread::sleep(step);
+        waited += step;
+    }
+    !stop.load(Ordering::SeqCst)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn up(ms: u64) -> ConnectionStatus {
+        ConnectionStatus::Connected(Duration::from_millis(ms))
+    }
+
+    fn down(reason: &str) -> ConnectionStatus {
+        ConnectionStatus::Failed(reason.to_string())
+    }
+
+    #[test]
+    fn test_tracker_reports_only_changes() {
+        let mut tracker = StatusTracker::default();
+        let script = [
+            ("web", up(12)),
+            ("db", down("refused")),
+            ("web", up(15)),
+            ("db", down("refused")),
+            ("web", down("timeout")),
+            ("db", ConnectionStatus::Connecting),
+            ("db", up(3)),
+            ("web", down("timeout")),
+            ("web", up(9)),
+        ];
+        let changes: Vec<(String, Option<Availability>, Availability)> = script
+            .iter()
+            .filter_map(|(host, status)| tracker.observe(host, status))
+            .map(|c| (c.host, c.from, c.to))
+            .collect();
+
+        use Availability::*;
+        assert_eq!(
+            changes,
+            [
+                ("web".to_string(), None, Up),
+                ("db".to_string(), None, Down),
+                ("web".to_string(), Some(Up), Down),
+                ("db".to_string(), Some(Down), Up),
+                ("web".to_string(), Some(Down), Up),
+            ]
+        );
+
+        // 未完成的测试不计入次数
+        assert_eq!(
+            tracker.stats(),
+            [
+                (
+                    "web".to_string(),
+                    HostWatchStats {
+                        checks: 5,
+                        failures: 2,
+                        transitions: 2,
+                        current: Some(Up),
+                    }
+                ),
+                (
+                    "db".to_string(),
+                    HostWatchStats {
+                        checks: 3,
+                        failures: 2,
+                        transitions: 1,
+                        current: Some(Up),
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_change_line_and_detail() {
+        let mut tracker = StatusTracker::default();
+        let first = tracker.observe("web", &up(42)).unwrap();
+        assert!(!first.is_transition());
+        assert_eq!(first.format_line("t0"), "[t0] web: ? → up (42ms)");
+
+        let change = tracker.observe("web", &down("Connection refused")).unwrap();
+        assert!(change.is_transition());
+        assert_eq!(
+            change.format_line("t1"),
+            "[t1] web: up → down (Connection refused)"
+        );
+    }
+
+    #[test]
+    fn test_hook_command_env() {
+        let change = StatusChange {
+            host: "web".to_string(),
+            from: Some(Availability::Up),
+            to: Availability::Down,
+            detail: "timeout".to_string(),
+        };
+        let command = hook_command("notify-send \"$HOST is $STATUS\"", &change);
+        let envs: Vec<(String, String)> = command
+            .get_envs()
+            .map(|(k, v)| {
+                (
+                    k.to_string_lossy().to_string(),
+                    v.unwrap().to_string_lossy().to_string(),
+                )
+            })
+            .collect();
+        for expected in [
+            ("HOST", "web"),
+            ("STATUS", "down"),
+            ("PREVIOUS_STATUS", "up"),
+            ("DETAIL", "timeout"),
+        ] {
+            assert!(
+                envs.contains(&(expected.0.to_string(), expected.1.to_string())),
+                "missing {:?}",
+                expected
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_hook_receives_host_and_status() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let log = dir.path().join("hook.log");
+        let hook = format!("echo \"$HOST $STATUS\" >> '{}'", log.display());
+
+        let mut tracker = StatusTracker::default();
+        for status in [up(1), down("refused"), down("refused"), up(2)] {
+            if let Some(change) = tracker.observe("db", &status)
+                && change.is_transition()
+            {
+                run_hook(&hook, &change);
+            }
+        }
+        assert_eq!(std::fs::read_to_string(&log).unwrap(), "db down\ndb up\n");
+    }
+}
@@ -0,0 +1,234 @@
+//! 启用 `async` 特性时，异步API与同步API对同一份配置的行为一致
+//!
+//! 运行：`cargo test --features async --test async_api`
+#![cfg(feature = "async")]
+
+use std::net::TcpListener;
+use std::path::PathBuf;
+
+use ssh_conn::config::ConfigManager;
+use ssh_conn::models::{OptionalField, SshHost};
+use ssh_conn::password::PasswordManager;
+use tempfile::TempDir;
+
+const CONFIG: &str = "# 个人服务器\nHost web\n    HostName 10.0.0.5\n    User deploy\n    Port 2222\n\nHost db\n    HostName 10.0.0.7\n\nHost *\n    ServerAliveInterval 30\n";
+
+/// 两份相同的配置，分别交给同步和异步API
+fn fixture(config: &str) -> (TempDir, PathBuf, PathBuf) {
+    let dir = TempDir::new().unwrap();
+    let sync_config = dir.path().join("sync").join("config");
+    let async_config = dir.path().join("async").join("config");
+    for path in [&sync_config, &async_config] {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, config).unwrap();
+    }
+    (dir, sync_config, async_config)
+}
+
+fn manager(config: &PathBuf) -> ConfigManager {
+    let passwords = PasswordManager::with_path(config.with_file_name("passwords.db")).unwrap();
+    ConfigManager::with_path(config, passwords).unwrap()
+}
+
+fn read(path: &PathBuf) -> String {
+    std::fs::read_to_string(path).unwrap()
+}
+
+fn assert_send<T: Send>(_: &T) {}
+
+#[tokio::test]
+async fn get_hosts_matches_sync() {
+    let (_dir, sync_config, async_config) = fixture(CONFIG);
+    let mut sync = manager(&sync_config);
+    let mut asynchronous = manager(&async_config);
+
+    let sync_hosts: Vec<SshHost> = sync
+        .get_hosts()
+        .unwrap()
+        .iter()
+        .map(|h| SshHost {
+            source_file: None,
+            ..h.clone()
+        })
+        .collect();
+    let future = asynchronous.get_hosts_async();
+    assert_send(&future);
+    let async_hosts: Vec<SshHost> = future
+        .await
+        .unwrap()
+        .iter()
+        .map(|h| SshHost {
+            source_file: None,
+            ..h.clone()
+        })
+        .collect();
+    assert_eq!(sync_hosts, async_hosts);
+    assert_eq!(sync.wildcard_defaults(), asynchronous.wildcard_defaults());
+
+    // 配置文件不存在时同样返回空列表
+    std::fs::remove_file(&async_config).unwrap();
+    asynchronous.clear_cache();
+    assert!(asynchronous.get_hosts_async().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn writes_produce_identical_files() {
+    let (_dir, sync_config, async_config) = fixture(CONFIG);
+    let mut sync = manager(&sync_config);
+    let mut asynchronous = manager(&async_config);
+
+    let mut cache = SshHost::new("cache".to_string());
+    cache.hostname = Some("10.0.0.8".to_string());
+    cache.request_tty = Some("FORCE".to_string());
+    sync.add_host_entry(&cache, Some("secret")).unwrap();
+    asynchronous
+        .add_host_entry_async(&cache, Some("secret"))
+        .await
+        .unwrap();
+    assert_eq!(read(&sync_config), read(&async_config));
+    assert!(asynchronous.has_stored_password("cache"));
+
+    let set_env = [("LANG".to_string(), "C".to_string())];
+    sync.edit_host(
+        "web",
+        None,
+        Some("admin"),
+        None,
+        None,
+        None,
+        None,
+        None,
+        Some(&set_env),
+        None,
+        &[OptionalField::Port],
+    )
+    .unwrap();
+    asynchronous
+        .edit_host_async(
+            "web",
+            None,
+            Some("admin"),
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(&set_env),
+            None,
+            &[OptionalField::Port],
+        )
+        .await
+        .unwrap();
+    assert_eq!(read(&sync_config), read(&async_config));
+
+    sync.delete_host("cache").unwrap();
+    asynchronous.delete_host_async("cache").await.unwrap();
+    assert_eq!(read(&sync_config), read(&async_config));
+    assert!(!asynchronous.has_stored_password("cache"));
+
+    // 写入前都做了一次自动备份，内容为原始配置
+    assert_eq!(sync.auto_backups().len(), 1);
+    assert_eq!(asynchronous.auto_backups().len(), 1);
+    assert_eq!(read(&asynchronous.auto_backups()[0]), CONFIG);
+}
+
+#[tokio::test]
+async fn errors_match_sync() {
+    let (_dir, sync_config, async_config) = fixture(CONFIG);
+    let mut sync = manager(&sync_config);
+    let mut asynchronous = manager(&async_config);
+
+    let mut existing = SshHost::new("web".to_string());
+    existing.hostname = Some("10.0.0.9".to_string());
+    let mut invalid = SshHost::new("bad".to_string());
+    invalid.hostname = Some("10.0.0.9".to_string());
+    invalid.port = Some("70000".to_string());
+    for entry in [&existing, &invalid] {
+        assert_eq!(
+            sync.add_host_entry(entry, None).unwrap_err().to_string(),
+            asynchronous
+                .add_host_entry_async(entry, None)
+                .await
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    for host in ["missing", "bad host"] {
+        assert_eq!(
+            sync.edit_host(
+                host,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &[]
+            )
+            .unwrap_err()
+            .to_string(),
+            asynchronous
+                .edit_host_async(
+                    host,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    &[]
+                )
+                .await
+                .unwrap_err()
+                .to_string()
+        );
+        assert_eq!(
+            sync.delete_host(host).unwrap_err().to_string(),
+            asynchronous
+                .delete_host_async(host)
+                .await
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    // 失败的操作不修改配置
+    assert_eq!(read(&sync_config), CONFIG);
+    assert_eq!(read(&async_config), CONFIG);
+}
+
+#[tokio::test]
+async fn try_connect_matches_sync() {
+    // 绑定后立即关闭，得到一个没有监听的端口
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let config = format!("Host local\n    HostName 127.0.0.1\n    Port {}\n", port);
+    let (_dir, sync_config, async_config) = fixture(&config);
+    let mut sync = manager(&sync_config);
+    let mut asynchronous = manager(&async_config);
+    sync.get_hosts().unwrap();
+    asynchronous.get_hosts_async().await.unwrap();
+
+    assert_eq!(
+        sync.try_connect_host("missing"),
+        asynchronous.try_connect_host_async("missing").await
+    );
+
+    let (success, host_key, _) = sync.try_connect_host("local");
+    let future = asynchronous.try_connect_host_async("local");
+    assert_send(&future);
+    let (async_success, async_host_key, message) = future.await;
+    assert_eq!((success, host_key), (async_success, async_host_key));
+    assert!(!async_success);
+    assert!(message.is_some());
+}
@@ -0,0 +1,57 @@
+//! `bench` 子命令：多轮测量DNS解析和TCP连接耗时
+
+mod common;
+
+use std::net::TcpListener;
+
+use common::ssh_conn;
+use tempfile::TempDir;
+
+fn setup(listener: &TcpListener) -> TempDir {
+    let port = listener.local_addr().unwrap().port();
+    common::setup(&format!(
+        "Host a\n    HostName 127.0.0.1\n    Port {port}\n\nHost b\n    HostName localhost\n    Port {port}\n    AddressFamily inet\n"
+    ))
+}
+
+#[test]
+fn bench_reports_json_stats_per_phase() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let dir = setup(&listener);
+
+    let output = ssh_conn(&dir)
+        .args(["-q", "bench", "a", "--rounds", "3", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let host = &value["hosts"][0];
+    assert_eq!(host["host"], "a");
+    assert_eq!(host["rounds"], 3);
+    let phases = host["phases"].as_array().unwrap();
+    assert_eq!(phases.len(), 2);
+    for (phase, name) in phases.iter().zip(["dns", "tcp"]) {
+        assert_eq!(phase["phase"], name);
+        assert_eq!(phase["failures"], 0);
+        assert!(phase["p95_ms"].is_number());
+    }
+}
+
+#[test]
+fn bench_compares_two_hosts_side_by_side() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let dir = setup(&listener);
+
+    let output = ssh_conn(&dir)
+        .args(["bench", "a", "b", "--rounds", "2"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let header = stdout.lines().find(|l| l.starts_with("Phase")).unwrap();
+    assert!(header.contains('a') && header.contains('b'));
+    assert!(stdout.lines().any(|l| l.starts_with("TCP p95")));
+
+    let output = ssh_conn(&dir).args(["bench", "missing"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(3));
+}
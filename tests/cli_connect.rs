@@ -0,0 +1,62 @@
+//! `connect --print-only` 只打印连接信息，不实际连接
+
+mod common;
+
+use common::ssh_conn;
+use tempfile::TempDir;
+
+fn setup() -> TempDir {
+    common::setup(
+        "Host web\n    HostName 10.0.0.5\n    User admin\n    Port 2222\n    ProxyJump bastion\n",
+    )
+}
+
+#[test]
+fn print_only_full_prints_the_command_used_to_connect() {
+    let dir = setup();
+    let output = ssh_conn(&dir)
+        .args(["connect", "web", "--print-only", "--full"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = stdout.trim_end();
+    // 与实际连接一样经由配置文件解析别名，ProxyJump等选项由ssh读取
+    assert!(line.starts_with("ssh "), "{}", line);
+    assert!(line.contains(&dir.path().join("config").display().to_string()));
+    assert!(line.ends_with(" web"), "{}", line);
+    assert!(!line.contains("sshpass"), "{}", line);
+}
+
+#[test]
+fn print_only_includes_remote_command() {
+    let dir = setup();
+    let output = ssh_conn(&dir)
+        .args([
+            "connect",
+            "web",
+            "--print-only",
+            "--remote-command",
+            "uptime -p",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("-o 'RemoteCommand=uptime -p'"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.trim_end().ends_with(" web"), "{}", stdout);
+
+    // 不带远程命令时仍只打印连接字符串
+    let output = ssh_conn(&dir)
+        .args(["connect", "web", "--print-only"])
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "admin@10.0.0.5:2222\n"
+    );
+}
@@ -0,0 +1,62 @@
+//! `dedupe` 合并同名的Host块：先备份，`--dry-run` 只报告
+
+mod common;
+
+use tempfile::TempDir;
+
+const CONFIG: &str = "Host web\n    HostName 10.0.0.5\n    Port 2222\n\nHost db\n    HostName 10.0.0.7\n\nHost web\n    User admin\n";
+
+fn setup() -> TempDir {
+    common::setup(CONFIG)
+}
+
+fn dedupe(dir: &TempDir, extra: &[&str]) -> std::process::Output {
+    common::ssh_conn(dir)
+        .arg("dedupe")
+        .args(extra)
+        .output()
+        .unwrap()
+}
+
+fn backups(dir: &TempDir) -> usize {
+    std::fs::read_dir(dir.path())
+        .unwrap()
+        .filter(|entry| {
+            let name = entry.as_ref().unwrap().file_name();
+            name.to_string_lossy().starts_with("config.backup.")
+        })
+        .count()
+}
+
+#[test]
+fn dedupe_merges_blocks_after_backup() {
+    let dir = setup();
+    let output = dedupe(&dir, &[]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("web: merged 2 blocks"));
+
+    let config = std::fs::read_to_string(dir.path().join("config")).unwrap();
+    assert_eq!(
+        config,
+        "Host web\n    HostName 10.0.0.5\n    Port 2222\n    User admin\n\nHost db\n    HostName 10.0.0.7\n"
+    );
+    assert_eq!(backups(&dir), 1);
+
+    // 再次运行没有可合并的块，也不再备份
+    let output = dedupe(&dir, &[]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("No duplicate Host blocks"));
+    assert_eq!(backups(&dir), 1);
+}
+
+#[test]
+fn dedupe_dry_run_leaves_config_unchanged() {
+    let dir = setup();
+    let output = dedupe(&dir, &["--dry-run"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("web: merged 2 blocks"));
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("config")).unwrap(),
+        CONFIG
+    );
+    assert_eq!(backups(&dir), 0);
+}
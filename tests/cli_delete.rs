@@ -0,0 +1,170 @@
+//! `delete` 子命令的确认行为
+
+mod common;
+
+use common::ssh_conn;
+use rusqlite::Connection;
+use tempfile::TempDir;
+
+const CONFIG: &str =
+    "Host web\n    HostName 10.0.0.5\n\nHost db\n    HostName 10.0.0.7\n    User root\n";
+
+/// 在临时目录中准备SSH配置、设置文件和存有 `db` 密码的数据库
+fn setup() -> TempDir {
+    let dir = common::setup(CONFIG);
+
+    let conn = Connection::open(dir.path().join("passwords.db")).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE passwords (host TEXT PRIMARY KEY, password TEXT);
+         INSERT INTO passwords VALUES ('db', 'hunter2');",
+    )
+    .unwrap();
+    dir
+}
+
+fn config(dir: &TempDir) -> String {
+    std::fs::read_to_string(dir.path().join("config")).unwrap()
+}
+
+fn stored_password(dir: &TempDir, host: &str) -> Option<String> {
+    let conn = Connection::open(dir.path().join("passwords.db")).unwrap();
+    conn.query_row(
+        "SELECT password FROM passwords WHERE host = ?1",
+        [host],
+        |row| row.get(0),
+    )
+    .ok()
+}
+
+#[test]
+fn delete_without_yes_fails_when_stdin_is_not_a_terminal() {
+    let dir = setup();
+    let output = ssh_conn(&dir)
+        .args(["delete", "db"])
+        .write_stdin("y\n")
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--yes"));
+    assert_eq!(config(&dir), CONFIG);
+    assert_eq!(stored_password(&dir, "db").as_deref(), Some("hunter2"));
+}
+
+#[test]
+fn delete_with_yes_removes_host_and_password() {
+    let dir = setup();
+    ssh_conn(&dir)
+        .args(["delete", "db", "--yes"])
+        .assert()
+        .success();
+
+    assert_eq!(config(&dir), "Host web\n    HostName 10.0.0.5\n\n");
+    assert_eq!(stored_password(&dir, "db"), None);
+}
+
+#[test]
+fn delete_with_keep_password_preserves_credential() {
+    let dir = setup();
+    ssh_conn(&dir)
+        .args(["delete", "-y", "--keep-password", "db"])
+        .assert()
+        .success();
+
+    assert!(!config(&dir).contains("Host db"));
+    assert_eq!(stored_password(&dir, "db").as_deref(), Some("hunter2"));
+}
+
+#[test]
+fn delete_unknown_host_fails() {
+    let dir = setup();
+    ssh_conn(&dir)
+        .args(["delete", "missing", "--yes"])
+        .assert()
+        .code(3);
+    assert_eq!(config(&dir), CONFIG);
+}
+
+#[test]
+fn delete_many_reports_mixed_results() {
+    let dir = setup();
+    let output = ssh_conn(&dir)
+        .args(["delete", "web", "missing", "db", "--yes"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("✓ web"));
+    assert!(stdout.contains("✓ db"));
+    assert!(stdout.contains("Deleted 2 host(s), 1 failed"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("✗ missing"));
+
+    assert_eq!(config(&dir), "");
+    assert_eq!(stored_password(&dir, "db"), None);
+    // 删除前只备份一次
+    let backups = std::fs::read_dir(dir.path())
+        .unwrap()
+        .filter(|e| {
+            e.as_ref()
+                .unwrap()
+                .file_name()
+                .to_string_lossy()
+                .starts_with("config.backup.")
+                && !e
+                    .as_ref()
+                    .unwrap()
+                    .file_name()
+                    .to_string_lossy()
+                    .ends_with(".auto")
+        })
+        .count();
+    assert_eq!(backups, 1);
+}
+
+#[test]
+fn delete_query_with_no_match_refuses() {
+    let dir = setup();
+    let output = ssh_conn(&dir)
+        .args(["delete", "--query", "nothing-here", "--yes"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Nothing matched"));
+    assert_eq!(config(&dir), CONFIG);
+}
+
+#[test]
+fn delete_query_removes_matching_hosts() {
+    let dir = setup();
+    ssh_conn(&dir)
+        .args(["delete", "--query", "10.0.0.7", "--yes"])
+        .assert()
+        .success();
+
+    assert_eq!(config(&dir), "Host web\n    HostName 10.0.0.5\n\n");
+}
+
+#[test]
+fn delete_glob_removes_matching_hosts() {
+    let dir = setup();
+    let output = ssh_conn(&dir)
+        .args(["delete", "d?", "--glob", "--yes"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("1 host(s) will be deleted"));
+    assert_eq!(config(&dir), "Host web\n    HostName 10.0.0.5\n\n");
+}
+
+#[test]
+fn delete_glob_without_match_refuses() {
+    let dir = setup();
+    ssh_conn(&dir)
+        .args(["delete", "staging-*", "--glob", "--yes"])
+        .assert()
+        .code(2);
+    assert_eq!(config(&dir), CONFIG);
+}
@@ -0,0 +1,66 @@
+//! `diff` 子命令：与备份或其他文件比较配置
+
+mod common;
+
+use common::ssh_conn;
+use tempfile::TempDir;
+
+const CONFIG: &str =
+    "Host jump\n    HostName 10.0.0.1\n    Port 2222\n\nHost new-db\n    HostName 10.0.0.7\n";
+
+/// 备份中 `jump` 的端口不同，`new-db` 名为 `old-db`
+const BACKUP: &str =
+    "Host jump\n    HostName 10.0.0.1\n    Port 22\n\nHost old-db\n    HostName 10.0.0.7\n";
+
+/// 在临时目录中准备SSH配置、一份手动备份和设置文件
+fn setup() -> TempDir {
+    let dir = common::setup(CONFIG);
+    std::fs::write(dir.path().join("config.backup.20250101_120000"), BACKUP).unwrap();
+    dir
+}
+
+#[test]
+fn diff_defaults_to_newest_backup() {
+    let dir = setup();
+    let output = ssh_conn(&dir).arg("diff").output().unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("+1 host(s), -1 host(s), 1 modified"));
+    assert!(stdout.contains("  + new-db") && stdout.contains("  - old-db"));
+    assert!(stdout.contains("Port: 22 → 2222"));
+    assert!(stdout.contains("-    Port 22\n+    Port 2222\n"));
+}
+
+#[test]
+fn diff_json_lists_per_option_changes() {
+    let dir = setup();
+    let other = dir.path().join("other");
+    std::fs::write(&other, CONFIG.replace("2222", "2200")).unwrap();
+
+    let output = ssh_conn(&dir)
+        .args(["diff", "--json"])
+        .arg(&other)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(value["added"], serde_json::json!([]));
+    assert_eq!(value["removed"], serde_json::json!([]));
+    assert_eq!(
+        value["modified"],
+        serde_json::json!([{
+            "host": "jump",
+            "changes": [{ "option": "Port", "before": ["2200"], "after": ["2222"] }],
+        }])
+    );
+}
+
+#[test]
+fn diff_without_backup_is_an_argument_error() {
+    let dir = setup();
+    std::fs::remove_file(dir.path().join("config.backup.20250101_120000")).unwrap();
+    let output = ssh_conn(&dir).arg("diff").output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+}
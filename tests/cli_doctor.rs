@@ -0,0 +1,126 @@
+//! `doctor` 报告SSH配置文件和ssh-agent的状态
+
+mod common;
+
+use tempfile::TempDir;
+
+fn setup(config: Option<&str>) -> TempDir {
+    let dir = TempDir::new().unwrap();
+    if let Some(config) = config {
+        std::fs::write(dir.path().join("config"), config).unwrap();
+    }
+    common::write_settings(&dir);
+    dir
+}
+
+fn doctor(dir: &TempDir) -> String {
+    let output = common::ssh_conn(dir)
+        .env_remove("SSH_AUTH_SOCK")
+        .arg("doctor")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn doctor_reports_config_and_missing_agent() {
+    let dir = setup(Some(
+        "Host web\n    HostName 10.0.0.5\n\nHost db\n    HostName 10.0.0.7\n",
+    ));
+    let stdout = doctor(&dir);
+    assert!(stdout.contains("(2 host(s))"), "{stdout}");
+    assert!(
+        stdout.contains("ssh-agent: SSH_AUTH_SOCK is not set"),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn doctor_reports_missing_config() {
+    let dir = setup(None);
+    let stdout = doctor(&dir);
+    assert!(stdout.contains("does not exist yet"), "{stdout}");
+}
+
+#[cfg(unix)]
+mod permissions {
+    use super::*;
+    use assert_cmd::Command;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+
+    fn mode(path: &Path) -> u32 {
+        std::fs::metadata(path).unwrap().permissions().mode() & 0o777
+    }
+
+    fn set_mode(path: &Path, mode: u32) {
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).unwrap();
+    }
+
+    /// 主目录指向临时目录，其中的 `.ssh` 权限过宽
+    fn ssh_conn(dir: &TempDir) -> Command {
+        let home = dir.path().join("home");
+        if !home.exists() {
+            std::fs::create_dir_all(home.join(".ssh")).unwrap();
+            set_mode(&home.join(".ssh"), 0o755);
+        }
+        let mut cmd = common::ssh_conn(dir);
+        cmd.env("HOME", &home).env_remove("SSH_AUTH_SOCK");
+        cmd
+    }
+
+    #[test]
+    fn created_config_and_password_db_are_private() {
+        let dir = setup(None);
+        let output = ssh_conn(&dir)
+            .args(["add", "web", "10.0.0.5"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(mode(&dir.path().join("config")), 0o600);
+        assert_eq!(mode(&dir.path().join("passwords.db")), 0o600);
+    }
+
+    #[test]
+    fn doctor_reports_and_fixes_loose_permissions() {
+        let dir = setup(Some("Host web\n    HostName 10.0.0.5\n"));
+        let config = dir.path().join("config");
+        set_mode(&config, 0o644);
+        let ssh_dir = dir.path().join("home").join(".ssh");
+
+        // 其他命令在标准错误中警告
+        let output = ssh_conn(&dir).arg("list").output().unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("has permissions 644"), "{stderr}");
+        assert!(stderr.contains("has permissions 755"), "{stderr}");
+
+        let output = ssh_conn(&dir).arg("doctor").output().unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("doctor --fix"), "{stdout}");
+        assert_eq!(mode(&config), 0o644);
+
+        // 标准输入不是终端时需要 --yes
+        let output = ssh_conn(&dir).args(["doctor", "--fix"]).output().unwrap();
+        assert!(!output.status.success());
+        assert_eq!(mode(&config), 0o644);
+
+        let output = ssh_conn(&dir)
+            .args(["doctor", "--fix", "--yes"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(mode(&config), 0o600);
+        assert_eq!(mode(&ssh_dir), 0o700);
+
+        let output = ssh_conn(&dir).arg("doctor").output().unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("are fine"), "{stdout}");
+        let output = ssh_conn(&dir).arg("list").output().unwrap();
+        assert!(
+            output.stderr.is_empty(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
@@ -0,0 +1,88 @@
+//! `import` 遇到已存在的主机：默认跳过，`--overwrite` 时确认一次、备份后覆盖
+
+mod common;
+
+use tempfile::TempDir;
+
+const CONFIG: &str = "Host db\n    HostName 10.0.0.7\n    User root\n    ForwardAgent yes\n";
+
+/// `db` 与配置中的主机重名，`web` 是新主机
+const REG: &str = r#"Windows Registry Editor Version 5.00
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\db]
+"HostName"="admin@db.internal"
+"Protocol"="ssh"
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\web]
+"HostName"="web.internal"
+"Protocol"="ssh"
+"#;
+
+fn setup() -> TempDir {
+    let dir = common::setup(CONFIG);
+    std::fs::write(dir.path().join("sessions.reg"), REG).unwrap();
+    dir
+}
+
+fn import(dir: &TempDir, extra: &[&str]) -> std::process::Output {
+    common::ssh_conn(dir)
+        .args(["import", "--format", "putty"])
+        .args(extra)
+        .arg(dir.path().join("sessions.reg"))
+        .output()
+        .unwrap()
+}
+
+/// 手动备份的数量（不含写入时的自动备份）
+fn backups(dir: &TempDir) -> usize {
+    std::fs::read_dir(dir.path())
+        .unwrap()
+        .filter(|entry| {
+            let name = entry.as_ref().unwrap().file_name();
+            let name = name.to_string_lossy();
+            name.starts_with("config.backup.") && !name.ends_with(".auto")
+        })
+        .count()
+}
+
+#[test]
+fn import_skips_existing_hosts_by_default() {
+    let dir = setup();
+    let output = import(&dir, &[]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("db already exists, skipped"));
+
+    let config = std::fs::read_to_string(dir.path().join("config")).unwrap();
+    assert!(config.contains("HostName 10.0.0.7"));
+    assert!(config.contains("Host web\n"));
+    assert_eq!(backups(&dir), 0);
+}
+
+#[test]
+fn import_overwrite_replaces_existing_hosts_after_backup() {
+    let dir = setup();
+    let output = import(&dir, &["--overwrite", "--yes"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Overwrote 1 existing host(s)"));
+
+    let config = std::fs::read_to_string(dir.path().join("config")).unwrap();
+    assert!(config.contains("HostName db.internal"));
+    assert!(config.contains("User admin"));
+    assert!(!config.contains("10.0.0.7"));
+    // 导入中没有的选项保持不变
+    assert!(config.contains("ForwardAgent yes"));
+    assert!(config.contains("Host web\n"));
+    assert_eq!(backups(&dir), 1);
+}
+
+#[test]
+fn import_overwrite_without_terminal_requires_yes() {
+    let dir = setup();
+    let output = import(&dir, &["--overwrite"]);
+    assert_eq!(output.status.code(), Some(2));
+
+    // 没有写入任何内容
+    let config = std::fs::read_to_string(dir.path().join("config")).unwrap();
+    assert_eq!(config, CONFIG);
+    assert_eq!(backups(&dir), 0);
+}
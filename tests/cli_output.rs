@@ -0,0 +1,95 @@
+//! 全局 `-q/--quiet`、`-v/-vv` 和 `--log-file` 对输出的影响
+
+mod common;
+
+use std::net::TcpListener;
+
+use common::{setup, ssh_conn};
+
+#[test]
+fn quiet_suppresses_success_message_but_not_results_or_errors() {
+    let dir = setup("Host web\n    HostName 10.0.0.5\n");
+
+    let output = ssh_conn(&dir)
+        .args(["edit", "web", "--user", "admin"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("✓"));
+
+    let output = ssh_conn(&dir)
+        .args(["-q", "edit", "web", "--user", "root"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "");
+    let config = std::fs::read_to_string(dir.path().join("config")).unwrap();
+    assert!(config.contains("User root"));
+
+    // 查询结果照常输出
+    let output = ssh_conn(&dir).args(["--quiet", "list"]).output().unwrap();
+    assert!(String::from_utf8_lossy(&output.stdout).contains("web"));
+
+    // 错误照常输出到标准错误
+    let output = ssh_conn(&dir)
+        .args(["-q", "edit", "missing", "--user", "root"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(!output.stderr.is_empty());
+}
+
+#[test]
+fn verbose_enables_info_and_debug_logs() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let dir = setup(&format!(
+        "Host local\n    HostName 127.0.0.1\n    Port {}\n",
+        listener.local_addr().unwrap().port()
+    ));
+
+    let output = ssh_conn(&dir)
+        .args(["edit", "local", "--user", "admin"])
+        .output()
+        .unwrap();
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("INFO"));
+
+    let output = ssh_conn(&dir)
+        .args(["-v", "edit", "local", "--user", "root"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("INFO"), "{stderr}");
+    assert!(!stderr.contains("DEBUG"), "{stderr}");
+
+    let output = ssh_conn(&dir).args(["-vv", "test-all"]).output().unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("DEBUG"));
+}
+
+#[test]
+fn quiet_and_verbose_conflict() {
+    let dir = setup("");
+    let output = ssh_conn(&dir).args(["-q", "-v", "list"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn log_file_receives_logs_instead_of_stderr() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let dir = setup(&format!(
+        "Host local\n    HostName 127.0.0.1\n    Port {}\n",
+        listener.local_addr().unwrap().port()
+    ));
+    let log = dir.path().join("logs").join("ssh-conn.log");
+
+    let output = ssh_conn(&dir)
+        .arg("--log-file")
+        .arg(&log)
+        .args(["edit", "local", "--user", "root"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("INFO"));
+    assert!(std::fs::read_to_string(&log).unwrap().contains("INFO"));
+}
@@ -0,0 +1,48 @@
+//! `password` 子命令管理私钥口令；作为 `SSH_ASKPASS` 运行时只输出口令
+
+mod common;
+
+use common::ssh_conn;
+use tempfile::TempDir;
+
+fn setup() -> TempDir {
+    let dir = common::setup("");
+    std::fs::write(dir.path().join("id_ed25519"), "key").unwrap();
+    dir
+}
+
+#[test]
+fn askpass_mode_prints_passphrase() {
+    let dir = setup();
+    let output = ssh_conn(&dir)
+        .env("SSH_CONN_ASKPASS_PASSPHRASE", "s3cret")
+        .arg("Enter passphrase for /home/me/.ssh/id_ed25519: ")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "s3cret\n");
+}
+
+#[test]
+fn set_keyphrase_is_listed_separately() {
+    let dir = setup();
+    let key = dir.path().join("id_ed25519");
+    let output = ssh_conn(&dir)
+        .env("SSH_CONN_KEY_PASSPHRASE", "s3cret")
+        .args(["password", "set-keyphrase"])
+        .arg(&key)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+
+    let output = ssh_conn(&dir).args(["password", "list"]).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout,
+        format!(
+            "Host passwords:\n  (none)\nKey passphrases:\n  {}\n",
+            key.display()
+        )
+    );
+    assert!(!stdout.contains("s3cret"));
+}
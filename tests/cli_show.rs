@@ -0,0 +1,55 @@
+//! `show <host>` 输出可直接粘贴的配置块，`--json`/`--yaml` 输出结构化数据，均不含密码
+
+mod common;
+
+use tempfile::TempDir;
+
+const CONFIG: &str = "Host web\n    HostName 10.0.0.5\n    User deploy\n    Port 2222\n\nHost db\n    HostName 10.0.0.7\n";
+
+fn setup() -> TempDir {
+    common::setup(CONFIG)
+}
+
+fn show(dir: &TempDir, args: &[&str]) -> std::process::Output {
+    common::ssh_conn(dir)
+        .arg("show")
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn show_prints_single_config_block() {
+    let dir = setup();
+    let output = show(&dir, &["web"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("# "), "{stdout}");
+    assert!(stdout.contains("Host web\n    HostName 10.0.0.5\n    User deploy\n    Port 2222"));
+    assert!(!stdout.contains("db"));
+}
+
+#[test]
+fn show_json_and_yaml() {
+    let dir = setup();
+    let output = show(&dir, &["web", "--json"]);
+    assert!(output.status.success());
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(value["hostname"], "10.0.0.5");
+    assert_eq!(value["password_stored"], false);
+
+    let output = show(&dir, &["web", "--yaml"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("password_stored: false"));
+
+    assert_eq!(
+        show(&dir, &["web", "--json", "--yaml"]).status.code(),
+        Some(2)
+    );
+}
+
+#[test]
+fn show_missing_host_fails() {
+    let dir = setup();
+    assert_eq!(show(&dir, &["missing"]).status.code(), Some(3));
+}
@@ -0,0 +1,108 @@
+//! `test` 子命令：测试单个主机的连通性和认证方式
+
+mod common;
+
+use std::net::TcpListener;
+
+use common::ssh_conn;
+use tempfile::TempDir;
+
+/// `up` 指向本机监听的端口，`down` 指向已关闭的端口
+fn setup(listener: &TcpListener) -> TempDir {
+    let closed = TcpListener::bind("127.0.0.1:0").unwrap();
+    let closed_port = closed.local_addr().unwrap().port();
+    drop(closed);
+
+    common::setup(&format!(
+        "Host up\n    HostName 127.0.0.1\n    Port {}\n\nHost down\n    HostName 127.0.0.1\n    Port {}\n    ConnectTimeout 1\n",
+        listener.local_addr().unwrap().port(),
+        closed_port,
+    ))
+}
+
+#[test]
+fn test_reports_reachable_and_unreachable_hosts() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let dir = setup(&listener);
+
+    let output = ssh_conn(&dir).args(["test", "up"]).output().unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("✓ up  "));
+
+    let output = ssh_conn(&dir).args(["test", "down"]).output().unwrap();
+    assert!(!output.status.success());
+
+    let output = ssh_conn(&dir).args(["test", "missing"]).output().unwrap();
+    assert!(!output.status.success());
+}
+
+/// 在PATH最前面放一个假的ssh，按环境变量输出标准错误和退出状态
+#[cfg(unix)]
+fn fake_ssh(dir: &TempDir) -> String {
+    use std::os::unix::fs::PermissionsExt;
+
+    let bin = dir.path().join("bin");
+    std::fs::create_dir(&bin).unwrap();
+    let script = bin.join("ssh");
+    std::fs::write(
+        &script,
+        "#!/bin/sh\nprintf '%s\\n' \"$FAKE_SSH_STDERR\" >&2\nexit \"$FAKE_SSH_EXIT\"\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    format!(
+        "{}:{}",
+        bin.display(),
+        std::env::var("PATH").unwrap_or_default()
+    )
+}
+
+#[cfg(unix)]
+#[test]
+fn test_auth_lists_offered_methods() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let dir = setup(&listener);
+    let path = fake_ssh(&dir);
+
+    let output = ssh_conn(&dir)
+        .args(["test", "up", "--auth"])
+        .env("PATH", &path)
+        .env(
+            "FAKE_SSH_STDERR",
+            "me@127.0.0.1: Permission denied (publickey,password).",
+        )
+        .env("FAKE_SSH_EXIT", "255")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "up offers: publickey, password\n"
+    );
+
+    let output = ssh_conn(&dir)
+        .args(["test", "up", "--auth"])
+        .env("PATH", &path)
+        .env("FAKE_SSH_STDERR", "")
+        .env("FAKE_SSH_EXIT", "0")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("accepts logins without authentication")
+    );
+
+    // 没连到sshd时报告ssh的错误
+    let output = ssh_conn(&dir)
+        .args(["test", "up", "--auth"])
+        .env("PATH", &path)
+        .env(
+            "FAKE_SSH_STDERR",
+            "ssh: connect to host 127.0.0.1 port 22: Connection refused",
+        )
+        .env("FAKE_SSH_EXIT", "255")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Connection refused"));
+}
@@ -0,0 +1,71 @@
+//! `test-all` 子命令：批量测试并写出指标文件
+
+mod common;
+
+use std::net::TcpListener;
+
+use common::ssh_conn;
+use tempfile::TempDir;
+
+/// `up` 指向本机监听的端口，`down` 指向已关闭的端口
+fn setup(listener: &TcpListener) -> TempDir {
+    let closed = TcpListener::bind("127.0.0.1:0").unwrap();
+    let closed_port = closed.local_addr().unwrap().port();
+    drop(closed);
+
+    common::setup(&format!(
+        "Host up\n    HostName 127.0.0.1\n    Port {}\n\nHost down\n    HostName 127.0.0.1\n    Port {}\n    ConnectTimeout 1\n",
+        listener.local_addr().unwrap().port(),
+        closed_port,
+    ))
+}
+
+#[test]
+fn test_all_writes_prometheus_metrics() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let dir = setup(&listener);
+    let metrics = dir.path().join("ssh_conn.prom");
+
+    let output = ssh_conn(&dir)
+        .args(["test-all", "--metrics-out"])
+        .arg(&metrics)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("1 of 2 host(s) reachable"));
+
+    let content = std::fs::read_to_string(&metrics).unwrap();
+    assert!(content.contains("ssh_conn_host_up{host=\"up\"} 1\n"));
+    assert!(content.contains("ssh_conn_host_up{host=\"down\"} 0\n"));
+    assert!(content.contains("ssh_conn_host_latency_ms{host=\"up\"} "));
+    assert!(!content.contains("ssh_conn_host_latency_ms{host=\"down\"}"));
+}
+
+#[test]
+fn test_all_writes_json_metrics_for_matching_hosts() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let dir = setup(&listener);
+    let metrics = dir.path().join("ssh_conn.json");
+
+    let output = ssh_conn(&dir)
+        .args([
+            "test-all",
+            "--query",
+            "down",
+            "--metrics-format",
+            "json",
+            "--metrics-out",
+        ])
+        .arg(&metrics)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let value: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&metrics).unwrap()).unwrap();
+    assert_eq!(
+        value,
+        serde_json::json!({ "hosts": [{ "host": "down", "up": false, "latency_ms": null }] })
+    );
+}
@@ -0,0 +1,50 @@
+//! `version` 输出版本号，`--verbose` 时附带构建信息
+
+use assert_cmd::Command;
+
+fn version(args: &[&str]) -> String {
+    let output = Command::cargo_bin("ssh-conn")
+        .unwrap()
+        .env("SSH_CONN_LANG", "en")
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn version_prints_crate_version() {
+    assert_eq!(
+        version(&["version"]),
+        format!("ssh-conn {}\n", env!("CARGO_PKG_VERSION"))
+    );
+}
+
+#[test]
+fn verbose_version_includes_build_info() {
+    let stdout = version(&["version", "--verbose"]);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0], format!("ssh-conn {}", env!("CARGO_PKG_VERSION")));
+    for label in ["commit:", "rustc:", "target:", "ssh:", "sshpass:"] {
+        assert!(
+            lines.iter().any(|line| line.starts_with(label)),
+            "missing {} in {}",
+            label,
+            stdout
+        );
+    }
+}
+
+#[test]
+fn version_ignores_invalid_settings() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(dir.path().join("settings.toml"), "not_a_setting = 1\n").unwrap();
+    let output = Command::cargo_bin("ssh-conn")
+        .unwrap()
+        .env("SSH_CONN_CONFIG_DIR", dir.path())
+        .arg("version")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+}
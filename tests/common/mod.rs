@@ -0,0 +1,36 @@
+//! 集成测试共用的辅助函数：临时配置目录和运行 `ssh-conn` 的命令
+#![allow(dead_code)]
+
+use assert_cmd::Command;
+use tempfile::TempDir;
+
+/// 在临时目录中写入settings.toml，让SSH配置文件和密码数据库都位于该目录
+pub fn write_settings(dir: &TempDir) {
+    std::fs::write(
+        dir.path().join("settings.toml"),
+        format!(
+            "ssh_config_path = {:?}\npassword_db_path = {:?}\n",
+            dir.path().join("config"),
+            dir.path().join("passwords.db"),
+        ),
+    )
+    .unwrap();
+}
+
+/// 创建临时配置目录，写入SSH配置文件和settings.toml
+pub fn setup(config: &str) -> TempDir {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(dir.path().join("config"), config).unwrap();
+    write_settings(&dir);
+    dir
+}
+
+/// 以临时目录为配置目录、英文界面运行 `ssh-conn`，不受外部日志和askpass环境变量影响
+pub fn ssh_conn(dir: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("ssh-conn").unwrap();
+    cmd.env("SSH_CONN_CONFIG_DIR", dir.path())
+        .env("SSH_CONN_LANG", "en")
+        .env_remove("RUST_LOG")
+        .env_remove("SSH_CONN_ASKPASS_PASSPHRASE");
+    cmd
+}
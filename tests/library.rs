@@ -0,0 +1,42 @@
+//! 作为库使用：`ConfigManager::with_path` 和 `PasswordManager::with_path` 只操作指定的文件
+
+use ssh_conn::config::ConfigManager;
+use ssh_conn::password::PasswordManager;
+use tempfile::TempDir;
+
+#[test]
+fn config_manager_operates_on_given_files() {
+    let dir = TempDir::new().unwrap();
+    let config = dir.path().join("config");
+    std::fs::write(
+        &config,
+        "Host web\n    HostName 10.0.0.5\n    User deploy\n",
+    )
+    .unwrap();
+
+    let passwords = PasswordManager::with_path(dir.path().join("passwords.db")).unwrap();
+    let mut manager = ConfigManager::with_path(&config, passwords).unwrap();
+    let web = manager.get_host("web").unwrap().unwrap();
+    assert_eq!(web.hostname.as_deref(), Some("10.0.0.5"));
+
+    manager
+        .edit_host(
+            "web",
+            None,
+            Some("admin"),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            &[],
+        )
+        .unwrap();
+    let content = std::fs::read_to_string(&config).unwrap();
+    assert!(content.contains("User admin"));
+
+    let spec = manager.connect_command("web", None, None).unwrap();
+    assert!(spec.args.contains(&config.to_string_lossy().to_string()));
+}